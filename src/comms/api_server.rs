@@ -1,33 +1,64 @@
 //! Query API Server - Safe, read-only API for Admin Server
 //! Core Principle: Admin only sees IDs, Agent owns filesystem truth
 
-use super::protocol::{ AdminRequest, AgentResponse, ErrorCode, DriveInfo, NodeInfo, StatsInfo };
-use crate::filesystem_scanner::FileSystemScanner;
-use crate::query_interface::{ QueryInterface, QueryResponse, SystemStats, ScanState };
+use super::protocol::{
+    AdminRequest, AgentResponse, ErrorCode, DriveInfo, NodeInfo, PageRequest, SearchCoverage, StatsInfo,
+};
+use crate::discovery::ContentDiscovery;
+use crate::index_search::SearchRequest;
+use crate::filesystem_scanner::{FileSystemScanner, ScanConfig};
+use crate::fs_index::ChildCursor;
+use crate::query_interface::{
+    QueryInterface, QueryResponse, SafeNodeInfo, DEFAULT_PAGE_SIZE,
+};
 use std::sync::Arc;
 
+/// One page of children, ready for a response
+struct ChildPage {
+    children: Vec<NodeInfo>,
+    total: usize,
+    offset: usize,
+    next_cursor: Option<String>,
+    has_more: bool,
+}
+
 /// Query API Server - processes Admin requests
 pub struct QueryApiServer {
     scanner: Arc<FileSystemScanner>,
     query: Arc<QueryInterface>,
+    discovery: Arc<ContentDiscovery>,
 }
 
 impl QueryApiServer {
     /// Create new API server
     pub fn new(scanner: Arc<FileSystemScanner>, query: Arc<QueryInterface>) -> Self {
-        QueryApiServer { scanner, query }
+        let discovery = Arc::new(ContentDiscovery::new(scanner.index().clone(), scanner.clone()));
+        QueryApiServer { scanner, query, discovery }
+    }
+
+    /// Get scanner (for internal use)
+    pub fn scanner(&self) -> &Arc<FileSystemScanner> {
+        &self.scanner
+    }
+
+    /// Get content discovery (for internal use)
+    pub fn discovery(&self) -> &Arc<ContentDiscovery> {
+        &self.discovery
     }
 
     /// Process Admin request and return Agent response - NOW ASYNC
     pub async fn handle_request(&self, request: AdminRequest) -> AgentResponse {
         match request {
             AdminRequest::GetDrives => self.handle_get_drives().await,
-            AdminRequest::ListChildren { node_id } => self.handle_list_children(node_id).await,
+            AdminRequest::ListChildren { node_id, page } => self.handle_list_children(node_id, page).await,
             AdminRequest::GetNode { node_id } => self.handle_get_node(node_id).await,
             AdminRequest::SearchLocal { parent_id, query, limit } => {
                 self.handle_search_local(parent_id, &query, limit).await
             }
-            AdminRequest::ExpandNode { node_id } => self.handle_expand_node(node_id).await,
+            AdminRequest::Search { request } => self.handle_search(request).await,
+            AdminRequest::ExpandNode { node_id, scan_config, page } =>
+                self.handle_expand_node(node_id, scan_config, page).await,
+            AdminRequest::IdentifyContentType { node_id } => self.handle_identify_content_type(node_id).await,
             AdminRequest::CollapseNode { node_id } => self.handle_collapse_node(node_id).await,
             AdminRequest::GetStats => self.handle_get_stats().await,
             AdminRequest::Ping => self.handle_ping().await,
@@ -55,6 +86,10 @@ impl QueryApiServer {
                                 id: drive.node_id,
                                 name: drive.display_name,
                                 drive_letter: drive.drive_letter,
+                                drive_kind: drive.kind.as_str().to_string(),
+                                device_class: drive.device.as_ref().map(|device| device.class.as_str().to_string()),
+                                label: drive.device.as_ref().and_then(|device| device.label.clone()),
+                                serial: drive.device.and_then(|device| device.serial),
                                 has_children,
                                 is_accessible: true, // Drives are always accessible if listed
                                 node_type: "drive".to_string(),
@@ -88,7 +123,7 @@ impl QueryApiServer {
     }
 
     /// Handle: List children of a node
-    async fn handle_list_children(&self, node_id: u64) -> AgentResponse {
+    async fn handle_list_children(&self, node_id: u64, page: PageRequest) -> AgentResponse {
         let query_clone = self.query.clone();
 
         // Run both operations in a single spawn_blocking
@@ -112,43 +147,18 @@ impl QueryApiServer {
                 }
             };
 
-            // List children
-            match query_clone.list_children(node_id) {
-                QueryResponse::Nodes(nodes) => {
-                    let child_infos: Vec<NodeInfo> = nodes
-                        .into_iter()
-                        .map(|node| {
-                            NodeInfo {
-                                id: node.id,
-                                name: node.name,
-                                node_type: node.entry_type.to_lowercase(),
-                                size: node.size,
-                                modified_time: node.modified_time,
-                                created_time: node.created_time,
-                                has_children: node.has_children,
-                                is_expanded: node.is_expanded,
-                                is_accessible: node.is_accessible,
-                                full_path: Some(node.display_path.clone()),
-                            }
-                        })
-                        .collect();
-
-                    let child_count = child_infos.len();
-
-                    AgentResponse::Children {
-                        parent_id: node_id,
-                        parent_name,
-                        children: child_infos,
-                        total_children: child_count,
-                    }
-                }
-                QueryResponse::Error(e) => AgentResponse::error(ErrorCode::SystemError, &e, None),
-                _ =>
-                    AgentResponse::error(
-                        ErrorCode::SystemError,
-                        "Unexpected response type from list_children",
-                        None
-                    ),
+            // List one page of children
+            match Self::children_page(&query_clone, node_id, &page) {
+                Ok(page) => AgentResponse::Children {
+                    parent_id: node_id,
+                    parent_name,
+                    children: page.children,
+                    total_children: page.total,
+                    offset: page.offset,
+                    next_cursor: page.next_cursor,
+                    has_more: page.has_more,
+                },
+                Err(response) => *response,
             }
         }).await;
 
@@ -166,7 +176,14 @@ impl QueryApiServer {
     /// Handle: Get specific node info
     async fn handle_get_node(&self, node_id: u64) -> AgentResponse {
         let query_clone = self.query.clone();
-        let result = tokio::task::spawn_blocking(move || { query_clone.get_node(node_id) }).await;
+        let scanner = self.scanner.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            // Stable IDs stay valid across restarts: reload the node on demand
+            if let Err(e) = scanner.ensure_node_loaded(node_id) {
+                println!("⚠️ Node {} not loadable: {}", node_id, e);
+            }
+            query_clone.get_node(node_id)
+        }).await;
 
         match result {
             Ok(QueryResponse::Node(node)) => {
@@ -181,6 +198,7 @@ impl QueryApiServer {
                     is_expanded: node.is_expanded,
                     is_accessible: node.is_accessible,
                     full_path: Some(node.display_path.clone()),
+                    content_type: node.content_type.map(|content_type| content_type.as_str().to_string()),
                 };
 
                 AgentResponse::Node { node: node_info }
@@ -237,8 +255,8 @@ impl QueryApiServer {
                             has_children: node.has_children,
                             is_expanded: node.is_expanded,
                             is_accessible: node.is_accessible,
-                            full_path: Some(node.display_path.clone())
-                           
+                            full_path: Some(node.display_path.clone()),
+                            content_type: node.content_type.map(|content_type| content_type.as_str().to_string()),
                         }
                     })
                     .collect();
@@ -269,6 +287,47 @@ impl QueryApiServer {
         }
     }
 
+    /// Handle: Global search over everything indexed
+    async fn handle_search(&self, request: SearchRequest) -> AgentResponse {
+        let query_clone = self.query.clone();
+        let crawl_in_progress = self.scanner.running_crawls() > 0;
+        let subtree = request.root_id.is_some();
+
+        let result = tokio::task::spawn_blocking(move || query_clone.search(&request)).await;
+
+        match result {
+            Ok(QueryResponse::SearchPage(page)) => AgentResponse::SearchResults {
+                results: page.nodes.into_iter().map(Self::node_info).collect(),
+                total_matches: page.total,
+                offset: page.offset,
+                has_more: page.has_more,
+                coverage: SearchCoverage {
+                    scope: if subtree { "subtree" } else { "full_index" }.to_string(),
+                    partial: subtree || crawl_in_progress,
+                    nodes_searched: page.nodes_searched,
+                    total_indexed: page.total_indexed,
+                    crawl_in_progress,
+                },
+            },
+            Ok(QueryResponse::Error(e)) => {
+                let code = if e.contains("not found") { ErrorCode::NodeNotFound } else { ErrorCode::InvalidRequest };
+                AgentResponse::error(code, &e, None)
+            }
+            Ok(_) =>
+                AgentResponse::error(
+                    ErrorCode::SystemError,
+                    "Unexpected response type during search",
+                    None
+                ),
+            Err(e) =>
+                AgentResponse::error(
+                    ErrorCode::SystemError,
+                    &format!("Task execution failed: {}", e),
+                    None
+                ),
+        }
+    }
+
     /// Handle: Expand a directory (load children) - FIXED: Check for already expanded
     // async fn handle_expand_node(&self, node_id: u64) -> AgentResponse {
     //     let scanner_clone = self.scanner.clone();
@@ -362,7 +421,12 @@ impl QueryApiServer {
     // }
 
     /// Handle: Expand a node (Drive or Directory)
-    async fn handle_expand_node(&self, node_id: u64) -> AgentResponse {
+    async fn handle_expand_node(
+        &self,
+        node_id: u64,
+        scan_config: Option<ScanConfig>,
+        page: PageRequest,
+    ) -> AgentResponse {
         let scanner = self.scanner.clone();
         let query = self.query.clone();
 
@@ -385,8 +449,14 @@ impl QueryApiServer {
             // Normalize entry type
             let entry_type = node.entry_type.to_lowercase();
 
-            // 2️⃣ Already expanded check
-            if node.is_expanded {
+            // The admin has this folder open now: the memory ceiling must not collapse it
+            let opens_folder = matches!(entry_type.as_str(), "directory" | "drive" | "symlink" | "junction");
+            if opens_folder && node.is_expanded {
+                scanner.index().mark_opened(node_id);
+            }
+
+            // 2️⃣ Already expanded check (unless re-reading with another config)
+            if node.is_expanded && scan_config.is_none() {
                 return AgentResponse::error(
                     ErrorCode::AlreadyExpanded,
                     &format!("Node '{}' is already expanded", node.name),
@@ -396,8 +466,11 @@ impl QueryApiServer {
 
             // 3️⃣ Expand based on node type
             let expand_result = match entry_type.as_str() {
-                "directory" => scanner.expand_directory(node_id),
-                "drive" => scanner.expand_drive(node_id),
+                "directory" | "drive" | "symlink" | "junction" => match scan_config {
+                    Some(config) => scanner.expand_directory_with(node_id, config),
+                    None if entry_type == "drive" => scanner.expand_drive(node_id),
+                    None => scanner.expand_directory(node_id),
+                },
                 _ => {
                     return AgentResponse::error(
                         ErrorCode::NotADirectory,
@@ -410,44 +483,19 @@ impl QueryApiServer {
             // 4️⃣ Handle expansion result
             match expand_result {
                 Ok(_) => {
-                    match query.list_children(node_id) {
-                        QueryResponse::Nodes(children) => {
-                            let child_infos: Vec<NodeInfo> = children
-                                .into_iter()
-                                .map(|child| NodeInfo {
-                                    id: child.id,
-                                    name: child.name,
-                                    node_type: child.entry_type.to_lowercase(),
-                                    size: child.size,
-                                    modified_time: child.modified_time,
-                                    created_time: child.created_time,
-                                    has_children: child.has_children,
-                                    is_expanded: child.is_expanded,
-                                    is_accessible: child.is_accessible,
-                                    full_path: Some(child.display_path.clone()), // ✅ FIXED
-                                })
-                                .collect();
-
-                            // ✅ FIX: compute length BEFORE move
-                            let total_children = child_infos.len();
-
-                            AgentResponse::Expanded {
-                                node_id,
-                                node_name: node.name.clone(),
-                                children: child_infos, // moved only once
-                                total_children,
-                            }
-                        }
-                        QueryResponse::Error(e) => {
-                            AgentResponse::error(ErrorCode::SystemError, &e, None)
-                        }
-                        _ => {
-                            AgentResponse::error(
-                                ErrorCode::SystemError,
-                                "Failed to list children after expansion",
-                                None
-                            )
-                        }
+                    scanner.index().mark_opened(node_id);
+                    // Return the first requested page; the rest via ListChildren
+                    match Self::children_page(&query, node_id, &page) {
+                        Ok(page) => AgentResponse::Expanded {
+                            node_id,
+                            node_name: node.name.clone(),
+                            children: page.children,
+                            total_children: page.total,
+                            offset: page.offset,
+                            next_cursor: page.next_cursor,
+                            has_more: page.has_more,
+                        },
+                        Err(response) => *response,
                     }
                 }
                 Err(e) => { AgentResponse::error(ErrorCode::SystemError, &e, None) }
@@ -574,6 +622,38 @@ impl QueryApiServer {
         }
     }
 
+    /// Handle: Identify content type (reads the file header, returns the updated node)
+    async fn handle_identify_content_type(&self, node_id: u64) -> AgentResponse {
+        let scanner = self.scanner.clone();
+        let query = self.query.clone();
+
+        let result = tokio::task::spawn_blocking(move || {
+            if let Err(e) = scanner.identify_content_type(node_id) {
+                let code = if scanner.index().get_node(node_id).is_none() {
+                    ErrorCode::NodeNotFound
+                } else {
+                    ErrorCode::InvalidRequest
+                };
+                return AgentResponse::error(code, &e, None);
+            }
+            match query.get_node(node_id) {
+                QueryResponse::Node(node) => AgentResponse::Node { node: Self::node_info(node) },
+                QueryResponse::Error(e) => AgentResponse::error(ErrorCode::NodeNotFound, &e, None),
+                _ => AgentResponse::error(ErrorCode::SystemError, "Invalid response for node lookup", None),
+            }
+        }).await;
+
+        match result {
+            Ok(response) => response,
+            Err(e) =>
+                AgentResponse::error(
+                    ErrorCode::SystemError,
+                    &format!("Task execution failed: {}", e),
+                    None
+                ),
+        }
+    }
+
     /// Handle: Get system statistics
     async fn handle_get_stats(&self) -> AgentResponse {
         let query_clone = self.query.clone();
@@ -586,6 +666,8 @@ impl QueryApiServer {
                     total_drives: stats.total_drives,
                     expanded_nodes: stats.expanded_nodes,
                     memory_usage_bytes: stats.memory_usage_bytes,
+                    memory_ceiling_bytes: stats.memory_ceiling_bytes,
+                    identity_memory_bytes: stats.identity_memory_bytes,
                     scan_state: stats.scan_state.as_str().to_string(),
                 };
 
@@ -614,4 +696,60 @@ impl QueryApiServer {
             version: env!("CARGO_PKG_VERSION").to_string(),
         }
     }
+
+    /// Fetch one sorted page of already-loaded children
+    fn children_page(
+        query: &QueryInterface,
+        node_id: u64,
+        page: &PageRequest,
+    ) -> Result<ChildPage, Box<AgentResponse>> {
+        let start_after = match page.cursor.as_deref() {
+            Some(cursor) => Some(ChildCursor::parse(cursor).ok_or_else(|| {
+                Box::new(AgentResponse::error(ErrorCode::InvalidRequest, "Malformed page cursor", None))
+            })?),
+            None => None,
+        };
+
+        match query.list_children_page(
+            node_id,
+            page.sort,
+            page.descending,
+            start_after.as_ref(),
+            page.offset.unwrap_or(0),
+            page.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+        ) {
+            QueryResponse::Page(page) => {
+                Ok(ChildPage {
+                    children: page.nodes.into_iter().map(Self::node_info).collect(),
+                    total: page.total,
+                    offset: page.offset,
+                    next_cursor: page.next_cursor.map(|cursor| cursor.token()),
+                    has_more: page.has_more,
+                })
+            }
+            QueryResponse::Error(e) => Err(Box::new(AgentResponse::error(ErrorCode::InvalidRequest, &e, None))),
+            _ => Err(Box::new(AgentResponse::error(
+                ErrorCode::SystemError,
+                "Unexpected response type from list_children_page",
+                None
+            ))),
+        }
+    }
+
+    /// Convert safe node info to its wire form
+    fn node_info(node: SafeNodeInfo) -> NodeInfo {
+        NodeInfo {
+            id: node.id,
+            name: node.name,
+            node_type: node.entry_type.to_lowercase(),
+            size: node.size,
+            modified_time: node.modified_time,
+            created_time: node.created_time,
+            has_children: node.has_children,
+            is_expanded: node.is_expanded,
+            is_accessible: node.is_accessible,
+            full_path: Some(node.display_path),
+            content_type: node.content_type.map(|content_type| content_type.as_str().to_string()),
+        }
+    }
 }
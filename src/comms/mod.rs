@@ -6,7 +6,7 @@ mod protocol;
 mod api_server;
 mod transport;
 
-pub use protocol::{AdminRequest, AgentResponse, ErrorCode, DriveInfo, NodeInfo, StatsInfo};
+pub use protocol::{AdminRequest, AgentResponse, ErrorCode, DriveInfo, NodeInfo, PageRequest, StatsInfo};
 pub use api_server::QueryApiServer;
 pub use transport::{TransportServer, TransportConfig};
 
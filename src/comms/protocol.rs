@@ -3,6 +3,10 @@
 
 use serde::{Serialize, Deserialize};
 
+use crate::filesystem_scanner::ScanConfig;
+use crate::fs_index::ChildSort;
+use crate::index_search::SearchRequest;
+
 // ========================
 // Admin → Agent Requests
 // ========================
@@ -16,6 +20,8 @@ pub enum AdminRequest {
     /// List children of a node
     ListChildren {
         node_id: u64,
+        #[serde(default)]
+        page: PageRequest,
     },
     
     /// Get metadata for specific node
@@ -32,9 +38,25 @@ pub enum AdminRequest {
         limit: Option<usize>,
     },
     
+    /// Search everything indexed (expanded or crawled), with filters
+    Search {
+        #[serde(default)]
+        request: SearchRequest,
+    },
+    
     /// Expand a directory (load children if not already loaded)
+    /// A scan config re-reads the children with those filters.
     ExpandNode {
         node_id: u64,
+        #[serde(default)]
+        scan_config: Option<ScanConfig>,
+        #[serde(default)]
+        page: PageRequest,
+    },
+    
+    /// Identify a file's content type from its header
+    IdentifyContentType {
+        node_id: u64,
     },
     
     /// Collapse a directory (unload children)
@@ -49,6 +71,18 @@ pub enum AdminRequest {
     Ping,
 }
 
+/// Paging and sort options for child listings
+/// `cursor` (from a previous `next_cursor`) takes precedence over `offset`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageRequest {
+    pub offset: Option<usize>,
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+    pub sort: ChildSort,
+    pub descending: bool,
+}
+
 // ========================
 // Agent → Admin Responses
 // ========================
@@ -57,7 +91,11 @@ pub enum AdminRequest {
 pub struct DriveInfo {
     pub id: u64,
     pub name: String,
-    pub drive_letter: String,
+    pub drive_letter: String,   // Root: "C:", "\\server\share" or "\\?\volume{...}"
+    pub drive_kind: String,     // "local", "network", "mounted_folder", "volume"
+    pub device_class: Option<String>, // "fixed", "removable", "network", "cdrom", "ram_disk", "unknown"
+    pub label: Option<String>,        // Volume label
+    pub serial: Option<String>,       // Volume serial number, "1A2B-3C4D"
     pub has_children: bool,
     pub is_accessible: bool,
     pub node_type: String,  // "drive"
@@ -76,6 +114,7 @@ pub struct NodeInfo {
     pub is_expanded: bool,
     pub is_accessible: bool,
     pub full_path: Option<String>,
+    pub content_type: Option<String>,  // From magic bytes, e.g. "xlsx" (null until identified)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -84,9 +123,23 @@ pub struct StatsInfo {
     pub total_drives: usize,
     pub expanded_nodes: usize,
     pub memory_usage_bytes: usize,
+    pub memory_ceiling_bytes: usize,
+    pub identity_memory_bytes: usize,
     pub scan_state: String,
 }
 
+/// What part of the index a search looked at
+/// The index only holds expanded or crawled folders, so even a full-index
+/// search does not cover unvisited parts of the disk.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchCoverage {
+    pub scope: String,                 // "full_index" or "subtree"
+    pub partial: bool,                 // Subtree only, or a crawl is still filling the index
+    pub nodes_searched: usize,
+    pub total_indexed: usize,
+    pub crawl_in_progress: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AgentResponse {
@@ -101,6 +154,9 @@ pub enum AgentResponse {
         parent_name: String,
         children: Vec<NodeInfo>,
         total_children: usize,
+        offset: usize,
+        next_cursor: Option<String>,
+        has_more: bool,
     },
     
     /// Single node info
@@ -117,12 +173,24 @@ pub enum AgentResponse {
         scope: String, // "local" - important to indicate limited scope
     },
     
+    /// Global search results (one ranked page)
+    SearchResults {
+        results: Vec<NodeInfo>,
+        total_matches: usize,
+        offset: usize,
+        has_more: bool,
+        coverage: SearchCoverage,
+    },
+    
     /// Expand operation result
     Expanded {
         node_id: u64,
         node_name: String,
        children: Vec<NodeInfo>,
         total_children: usize,
+        offset: usize,
+        next_cursor: Option<String>,
+        has_more: bool,
     },
     
     /// Collapse operation result
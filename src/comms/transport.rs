@@ -66,6 +66,7 @@ impl TransportServer {
         println!("   GET  /api/v1/nodes/:id/children - List children");
         println!("   POST /api/v1/nodes/:id/expand  - Expand directory");
         println!("   POST /api/v1/nodes/:id/collapse - Collapse directory");
+        println!("   GET  /api/v1/search?q=...&mode=... - Global search over the index");
         println!("   GET  /api/v1/search/local?parent=...&q=... - Local search");
         println!("   GET  /api/v1/stats            - Get statistics");
         println!("   GET  /api/v1/ping             - Health check");
@@ -77,8 +78,7 @@ impl TransportServer {
         println!("   • Read-only access only");
         println!();
         println!("⚠️  Important Limitations:");
-        println!("   • Search is LOCAL only (expanded nodes)");
-        println!("   • No global filesystem search yet");
+        println!("   • Search only covers expanded or crawled folders");
         println!("   • No actual networking in STEP 2");
         println!("   • Mock clients belong to tests, not runtime");
     }
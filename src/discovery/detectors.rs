@@ -0,0 +1,319 @@
+//! Content Detectors
+//! Each detector counts validated matches in a text sample. Matched values
+//! are never returned or stored - only how many were found.
+
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Kind of sensitive data a detector looks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingCategory {
+    Pci,        // Payment card data
+    Pii,        // Personal data
+    Secret,     // Credentials and keys
+}
+
+/// Pluggable content detector
+pub trait ContentDetector: Send + Sync {
+    /// Stable detector name (used in findings and request filters)
+    fn name(&self) -> &'static str;
+
+    fn category(&self) -> FindingCategory;
+
+    /// Number of validated matches in `text`
+    fn count_matches(&self, text: &str) -> usize;
+}
+
+/// Built-in detectors
+pub fn default_detectors() -> Vec<Arc<dyn ContentDetector>> {
+    vec![
+        Arc::new(CreditCardDetector::new()),
+        Arc::new(IbanDetector::new()),
+        Arc::new(NationalIdDetector::new()),
+        Arc::new(EmailDetector::new()),
+        Arc::new(ApiKeyDetector::new()),
+        Arc::new(PrivateKeyDetector::new()),
+    ]
+}
+
+/// Patterns below are constants, so a failed compile is a programming error
+fn pattern(source: &str) -> Regex {
+    Regex::new(source).expect("built-in detector pattern")
+}
+
+/// Payment card numbers (13-19 digits, optional space/dash groups, Luhn-valid)
+pub struct CreditCardDetector {
+    pattern: Regex,
+}
+
+impl CreditCardDetector {
+    pub fn new() -> Self {
+        CreditCardDetector { pattern: pattern(r"\b\d(?:[ -]?\d){12,18}\b") }
+    }
+
+    fn luhn_valid(digits: &[u8]) -> bool {
+        let sum: u32 = digits.iter()
+            .rev()
+            .enumerate()
+            .map(|(position, &digit)| {
+                let digit = digit as u32;
+                if position % 2 == 1 {
+                    let doubled = digit * 2;
+                    if doubled > 9 { doubled - 9 } else { doubled }
+                } else {
+                    digit
+                }
+            })
+            .sum();
+        sum.is_multiple_of(10)
+    }
+}
+
+impl ContentDetector for CreditCardDetector {
+    fn name(&self) -> &'static str {
+        "credit_card"
+    }
+
+    fn category(&self) -> FindingCategory {
+        FindingCategory::Pci
+    }
+
+    fn count_matches(&self, text: &str) -> usize {
+        self.pattern.find_iter(text)
+            .filter(|candidate| {
+                let digits: Vec<u8> = candidate.as_str().bytes()
+                    .filter(u8::is_ascii_digit)
+                    .map(|byte| byte - b'0')
+                    .collect();
+                // Card numbers start with 2-6; a run of one digit is filler, not a card
+                (13..=19).contains(&digits.len())
+                    && (2..=6).contains(&digits[0])
+                    && digits.iter().any(|&digit| digit != digits[0])
+                    && Self::luhn_valid(&digits)
+            })
+            .count()
+    }
+}
+
+/// International bank account numbers (ISO 13616 mod-97 check)
+pub struct IbanDetector {
+    pattern: Regex,
+}
+
+impl IbanDetector {
+    pub fn new() -> Self {
+        IbanDetector { pattern: pattern(r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]){11,30}\b") }
+    }
+
+    fn checksum_valid(iban: &str) -> bool {
+        // Move the country code and check digits to the end, letters → 10..35
+        let rearranged = iban[4..].chars().chain(iban[..4].chars());
+        let mut remainder: u32 = 0;
+        for c in rearranged {
+            let value = match c.to_digit(36) {
+                Some(value) => value,
+                None => return false,
+            };
+            remainder = if value >= 10 {
+                (remainder * 100 + value) % 97
+            } else {
+                (remainder * 10 + value) % 97
+            };
+        }
+        remainder == 1
+    }
+}
+
+impl ContentDetector for IbanDetector {
+    fn name(&self) -> &'static str {
+        "iban"
+    }
+
+    fn category(&self) -> FindingCategory {
+        FindingCategory::Pci
+    }
+
+    fn count_matches(&self, text: &str) -> usize {
+        self.pattern.find_iter(text)
+            .filter(|candidate| {
+                let compact: String = candidate.as_str().chars().filter(|c| *c != ' ').collect();
+                (15..=34).contains(&compact.len()) && Self::checksum_valid(&compact)
+            })
+            .count()
+    }
+}
+
+/// National ID formats: US Social Security and UK National Insurance numbers
+pub struct NationalIdDetector {
+    ssn: Regex,
+    nino: Regex,
+}
+
+impl NationalIdDetector {
+    pub fn new() -> Self {
+        NationalIdDetector {
+            ssn: pattern(r"\b(\d{3})-(\d{2})-(\d{4})\b"),
+            nino: pattern(r"\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?\d{2} ?\d{2} ?\d{2} ?[A-D]\b"),
+        }
+    }
+
+    /// Area 000, 666 and 900-999, group 00 and serial 0000 are never issued
+    fn ssn_valid(area: &str, group: &str, serial: &str) -> bool {
+        area != "000" && area != "666" && !area.starts_with('9') && group != "00" && serial != "0000"
+    }
+}
+
+impl ContentDetector for NationalIdDetector {
+    fn name(&self) -> &'static str {
+        "national_id"
+    }
+
+    fn category(&self) -> FindingCategory {
+        FindingCategory::Pii
+    }
+
+    fn count_matches(&self, text: &str) -> usize {
+        let ssns = self.ssn.captures_iter(text)
+            .filter(|captures| Self::ssn_valid(&captures[1], &captures[2], &captures[3]))
+            .count();
+        ssns + self.nino.find_iter(text).count()
+    }
+}
+
+/// Email addresses
+pub struct EmailDetector {
+    pattern: Regex,
+}
+
+impl EmailDetector {
+    pub fn new() -> Self {
+        EmailDetector { pattern: pattern(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b") }
+    }
+}
+
+impl ContentDetector for EmailDetector {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn category(&self) -> FindingCategory {
+        FindingCategory::Pii
+    }
+
+    fn count_matches(&self, text: &str) -> usize {
+        self.pattern.find_iter(text).count()
+    }
+}
+
+/// Well-known API key formats (AWS, GitHub, Slack, Google, Stripe)
+pub struct ApiKeyDetector {
+    pattern: Regex,
+}
+
+impl ApiKeyDetector {
+    pub fn new() -> Self {
+        ApiKeyDetector {
+            pattern: pattern(concat!(
+                r"\b(?:",
+                r"(?:AKIA|ASIA)[0-9A-Z]{16}",          // AWS access key ID
+                r"|gh[pousr]_[A-Za-z0-9]{36}",         // GitHub token
+                r"|xox[abposr]-[A-Za-z0-9-]{10,}",     // Slack token
+                r"|AIza[0-9A-Za-z_-]{35}",             // Google API key
+                r"|[sr]k_live_[0-9A-Za-z]{24,}",       // Stripe live key
+                r")\b",
+            )),
+        }
+    }
+}
+
+impl ContentDetector for ApiKeyDetector {
+    fn name(&self) -> &'static str {
+        "api_key"
+    }
+
+    fn category(&self) -> FindingCategory {
+        FindingCategory::Secret
+    }
+
+    fn count_matches(&self, text: &str) -> usize {
+        self.pattern.find_iter(text).count()
+    }
+}
+
+/// PEM private keys (RSA, EC, DSA, OpenSSH, PKCS#8, encrypted)
+pub struct PrivateKeyDetector {
+    pattern: Regex,
+}
+
+impl PrivateKeyDetector {
+    pub fn new() -> Self {
+        PrivateKeyDetector { pattern: pattern(r"-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY-----") }
+    }
+}
+
+impl ContentDetector for PrivateKeyDetector {
+    fn name(&self) -> &'static str {
+        "private_key"
+    }
+
+    fn category(&self) -> FindingCategory {
+        FindingCategory::Secret
+    }
+
+    fn count_matches(&self, text: &str) -> usize {
+        self.pattern.find_iter(text).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(number: &str) -> Vec<u8> {
+        number.bytes().map(|byte| byte - b'0').collect()
+    }
+
+    #[test]
+    fn luhn_accepts_valid_card_numbers_only() {
+        assert!(CreditCardDetector::luhn_valid(&digits("4111111111111111")));
+        assert!(CreditCardDetector::luhn_valid(&digits("5500005555555559")));
+        assert!(CreditCardDetector::luhn_valid(&digits("378282246310005")));
+        assert!(!CreditCardDetector::luhn_valid(&digits("4111111111111112")));
+        assert!(!CreditCardDetector::luhn_valid(&digits("5500005555555555")));
+
+        let detector = CreditCardDetector::new();
+        assert_eq!(detector.count_matches("card 4111 1111 1111 1111, exp 12/29"), 1);
+        assert_eq!(detector.count_matches("card 4111-1111-1111-1111 and 5500005555555559"), 2);
+        // Bad check digit, wrong leading digit, one repeated digit, too short
+        assert_eq!(detector.count_matches("4111111111111112 1234567812345670 0000000000000000 4111111111"), 0);
+    }
+
+    #[test]
+    fn iban_checks_mod_97() {
+        assert!(IbanDetector::checksum_valid("GB82WEST12345698765432"));
+        assert!(IbanDetector::checksum_valid("DE89370400440532013000"));
+        assert!(!IbanDetector::checksum_valid("GB82WEST12345698765433"));
+        assert!(!IbanDetector::checksum_valid("GB82WEST1234569876543!"));
+
+        let detector = IbanDetector::new();
+        assert_eq!(detector.count_matches("Pay to GB82 WEST 1234 5698 7654 32 by Friday"), 1);
+        assert_eq!(detector.count_matches("DE89370400440532013000 / GB82WEST12345698765433"), 1);
+    }
+
+    #[test]
+    fn ssn_rejects_never_issued_numbers() {
+        assert!(NationalIdDetector::ssn_valid("123", "45", "6789"));
+        assert!(!NationalIdDetector::ssn_valid("000", "45", "6789"));
+        assert!(!NationalIdDetector::ssn_valid("666", "45", "6789"));
+        assert!(!NationalIdDetector::ssn_valid("912", "45", "6789"));
+        assert!(!NationalIdDetector::ssn_valid("123", "00", "6789"));
+        assert!(!NationalIdDetector::ssn_valid("123", "45", "0000"));
+
+        let detector = NationalIdDetector::new();
+        assert_eq!(detector.count_matches("SSN 123-45-6789, test 666-12-3456, 123-45-0000"), 1);
+        assert_eq!(detector.count_matches("NI number AB 12 34 56 C"), 1);
+    }
+}
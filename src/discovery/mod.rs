@@ -0,0 +1,652 @@
+//! Content Discovery - which files actually contain sensitive data
+//! Core Principle: Classify INTERNALLY, report counts by node ID
+//! Files under a node are read (whole, or sampled when large), run through
+//! pluggable detectors, and the per-detector match counts are attached to the
+//! file's node. Matched values never leave this module.
+//! UTF-16 text is decoded first. Compressed containers (OOXML and other ZIPs,
+//! archives, PDFs with filtered streams) cannot be read as text: they are
+//! counted as unsupported and left unclassified, never reported clean.
+
+mod detectors;
+
+pub use detectors::{ContentDetector, FindingCategory};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::file_type::{self, ContentType};
+use crate::filesystem_scanner::{CrawlOptions, CrawlStatus, FileSystemScanner, ScanConfig};
+use crate::fs_index::{EntryType, FileSystemNode, FilesystemIndex};
+
+/// Environment variable for the largest file that is classified (MiB)
+pub const DISCOVERY_MAX_FILE_MB_ENV: &str = "AGENT_DISCOVERY_MAX_FILE_MB";
+
+/// Environment variable for the sample read from large files (KiB)
+pub const DISCOVERY_SAMPLE_KB_ENV: &str = "AGENT_DISCOVERY_SAMPLE_KB";
+
+/// Default largest classified file (MiB)
+const DEFAULT_MAX_FILE_MB: u64 = 64;
+
+/// Default sample size (KiB): smaller files are read whole
+const DEFAULT_SAMPLE_KB: u64 = 1024;
+
+/// Finished discovery jobs kept for status queries
+const DISCOVERY_HISTORY: usize = 32;
+
+/// Default number of findings per page
+const DEFAULT_FINDINGS_LIMIT: usize = 100;
+
+/// Matches of one detector in one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectorFinding {
+    pub detector: String,
+    pub category: FindingCategory,
+    pub count: usize,
+}
+
+/// Classification result attached to a file node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentClassification {
+    pub classified_at: u64,
+    pub content_modified_time: u64,   // File mtime the result belongs to
+    pub bytes_scanned: u64,
+    pub sampled: bool,                // Only head/middle/tail of the file were read
+    pub findings: Vec<DetectorFinding>,
+}
+
+impl ContentClassification {
+    /// Still valid for the node's current content?
+    pub fn is_current(&self, node: &FileSystemNode) -> bool {
+        self.content_modified_time == node.modified_time
+    }
+}
+
+/// Options for one discovery scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoveryOptions {
+    pub crawl: bool,                      // Index the subtree first (background crawler)
+    pub scan_config: Option<ScanConfig>,  // Filters for that crawl
+    pub detectors: Option<Vec<String>>,   // Subset of detector names, all when None
+    pub max_file_bytes: Option<u64>,      // AGENT_DISCOVERY_MAX_FILE_MB when None
+    pub sample_bytes: Option<u64>,        // AGENT_DISCOVERY_SAMPLE_KB when None
+    pub rescan: bool,                     // Re-classify files whose result is still current
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        DiscoveryOptions {
+            crawl: true,
+            scan_config: None,
+            detectors: None,
+            max_file_bytes: None,
+            sample_bytes: None,
+            rescan: false,
+        }
+    }
+}
+
+/// Progress of a discovery scan (admin-safe: node IDs and counters only)
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveryProgress {
+    pub job_id: u64,
+    pub root_node_id: u64,
+    pub status: CrawlStatus,
+    pub crawl_job_id: Option<u64>,
+    pub files_total: u64,
+    pub files_classified: u64,
+    pub files_with_findings: u64,
+    pub files_skipped: u64,          // Too large, or result still current
+    pub files_unsupported: u64,      // Compressed content the detectors cannot read (left unclassified)
+    pub bytes_scanned: u64,
+    pub errors: u64,                 // Files that could not be read
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub message: Option<String>,
+}
+
+/// Query parameters for the findings listing
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FindingsQuery {
+    pub root_id: Option<u64>,
+    pub category: Option<FindingCategory>,
+    pub detector: Option<String>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// Findings of one file (admin-safe)
+#[derive(Debug, Clone, Serialize)]
+pub struct FileFindings {
+    pub node_id: u64,
+    pub name: String,
+    pub parent_id: Option<u64>,
+    pub total_matches: usize,
+    pub classified_at: u64,
+    pub sampled: bool,
+    pub findings: Vec<DetectorFinding>,
+}
+
+/// One page of findings, most matches first
+#[derive(Debug, Clone, Serialize)]
+pub struct FindingsPage {
+    pub files: Vec<FileFindings>,
+    pub total: usize,
+    pub offset: usize,
+    pub has_more: bool,
+}
+
+/// Running or finished discovery scan
+struct DiscoveryJob {
+    progress: RwLock<DiscoveryProgress>,
+    cancel: AtomicBool,
+}
+
+/// Size limits resolved for one scan
+#[derive(Debug, Clone, Copy)]
+struct ReadLimits {
+    max_file_bytes: u64,
+    sample_bytes: u64,
+}
+
+/// Content discovery service
+pub struct ContentDiscovery {
+    index: Arc<FilesystemIndex>,
+    scanner: Arc<FileSystemScanner>,
+    detectors: Vec<Arc<dyn ContentDetector>>,
+    jobs: RwLock<HashMap<u64, Arc<DiscoveryJob>>>,
+    next_job_id: AtomicU64,
+}
+
+impl ContentDiscovery {
+    /// Create with the built-in detectors
+    pub fn new(index: Arc<FilesystemIndex>, scanner: Arc<FileSystemScanner>) -> Self {
+        Self::with_detectors(index, scanner, detectors::default_detectors())
+    }
+
+    /// Create with a custom detector set
+    pub fn with_detectors(
+        index: Arc<FilesystemIndex>,
+        scanner: Arc<FileSystemScanner>,
+        detectors: Vec<Arc<dyn ContentDetector>>,
+    ) -> Self {
+        ContentDiscovery {
+            index,
+            scanner,
+            detectors,
+            jobs: RwLock::new(HashMap::new()),
+            next_job_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Names of the available detectors
+    pub fn detector_names(&self) -> Vec<&'static str> {
+        self.detectors.iter().map(|detector| detector.name()).collect()
+    }
+
+    /// Start classifying files below `root_id` in a background thread
+    pub fn start_scan(self: &Arc<Self>, root_id: u64, options: DiscoveryOptions) -> Result<DiscoveryProgress, String> {
+        let root = self.index.get_node(root_id)
+            .ok_or_else(|| format!("Node {} not found", root_id))?;
+        if root.entry_type == EntryType::VirtualRoot {
+            return Err("Discovery needs a drive, directory or file node".to_string());
+        }
+
+        let detectors = self.select_detectors(options.detectors.as_deref())?;
+        let limits = ReadLimits {
+            max_file_bytes: options.max_file_bytes
+                .unwrap_or_else(|| Self::env_u64(DISCOVERY_MAX_FILE_MB_ENV, DEFAULT_MAX_FILE_MB) * 1024 * 1024),
+            sample_bytes: options.sample_bytes
+                .unwrap_or_else(|| Self::env_u64(DISCOVERY_SAMPLE_KB_ENV, DEFAULT_SAMPLE_KB) * 1024)
+                .max(3),
+        };
+
+        let job_id = self.next_job_id.fetch_add(1, Ordering::SeqCst);
+        let job = Arc::new(DiscoveryJob {
+            progress: RwLock::new(DiscoveryProgress {
+                job_id,
+                root_node_id: root_id,
+                status: CrawlStatus::Running,
+                crawl_job_id: None,
+                files_total: 0,
+                files_classified: 0,
+                files_with_findings: 0,
+                files_skipped: 0,
+                files_unsupported: 0,
+                bytes_scanned: 0,
+                errors: 0,
+                started_at: Self::now(),
+                finished_at: None,
+                message: None,
+            }),
+            cancel: AtomicBool::new(false),
+        });
+        {
+            let mut jobs = self.jobs.write();
+            Self::prune_history(&mut jobs);
+            jobs.insert(job_id, job.clone());
+        }
+
+        let discovery = Arc::clone(self);
+        let worker_job = job.clone();
+        let spawned = thread::Builder::new()
+            .name(format!("discovery-{}", job_id))
+            .spawn(move || discovery.run_scan(&worker_job, root, options, detectors, limits));
+        if let Err(e) = spawned {
+            Self::finish(&job, CrawlStatus::Failed, Some(format!("Failed to start discovery: {}", e)));
+            return Err(format!("Failed to start discovery: {}", e));
+        }
+
+        let progress = job.progress.read().clone();
+        Ok(progress)
+    }
+
+    /// Ask a running scan to stop (its crawl is cancelled too)
+    pub fn cancel_scan(&self, job_id: u64) -> Result<DiscoveryProgress, String> {
+        let job = self.jobs.read().get(&job_id).cloned()
+            .ok_or_else(|| format!("Discovery job {} not found", job_id))?;
+
+        let status = job.progress.read().status;
+        if status != CrawlStatus::Running {
+            return Err(format!("Discovery job {} is already {}", job_id, status.as_str()));
+        }
+
+        job.cancel.store(true, Ordering::SeqCst);
+        println!("🛑 Discovery {} cancellation requested", job_id);
+        let progress = job.progress.read().clone();
+        Ok(progress)
+    }
+
+    /// Cancel every running scan (agent shutdown)
+    pub fn cancel_all_scans(&self) -> usize {
+        let jobs = self.jobs.read();
+        let mut cancelled = 0;
+        for job in jobs.values() {
+            if job.progress.read().status == CrawlStatus::Running {
+                job.cancel.store(true, Ordering::SeqCst);
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+
+    /// Progress of one scan
+    pub fn scan_progress(&self, job_id: u64) -> Option<DiscoveryProgress> {
+        self.jobs.read().get(&job_id).map(|job| job.progress.read().clone())
+    }
+
+    /// All known scans (newest first)
+    pub fn list_scans(&self) -> Vec<DiscoveryProgress> {
+        let mut scans: Vec<DiscoveryProgress> = self.jobs.read().values()
+            .map(|job| job.progress.read().clone())
+            .collect();
+        scans.sort_by_key(|progress| std::cmp::Reverse(progress.job_id));
+        scans
+    }
+
+    /// Files with current findings, most matches first
+    pub fn findings(&self, query: &FindingsQuery) -> FindingsPage {
+        let limit = query.limit.unwrap_or(DEFAULT_FINDINGS_LIMIT).max(1);
+
+        let mut files: Vec<FileFindings> = self.index.classified_nodes(query.root_id)
+            .into_iter()
+            .filter_map(|node| {
+                let classification = node.classification.as_ref()?;
+                let findings: Vec<DetectorFinding> = classification.findings.iter()
+                    .filter(|finding| query.category.is_none_or(|category| finding.category == category))
+                    .filter(|finding| query.detector.as_deref().is_none_or(|detector| finding.detector == detector))
+                    .cloned()
+                    .collect();
+                if findings.is_empty() {
+                    return None;
+                }
+                Some(FileFindings {
+                    node_id: node.id,
+                    name: node.name.clone(),
+                    parent_id: node.parent_id,
+                    total_matches: findings.iter().map(|finding| finding.count).sum(),
+                    classified_at: classification.classified_at,
+                    sampled: classification.sampled,
+                    findings,
+                })
+            })
+            .collect();
+        files.sort_by(|a, b| b.total_matches.cmp(&a.total_matches).then(a.node_id.cmp(&b.node_id)));
+
+        let total = files.len();
+        let files: Vec<FileFindings> = files.into_iter().skip(query.offset).take(limit).collect();
+        let has_more = query.offset + files.len() < total;
+        FindingsPage { files, total, offset: query.offset, has_more }
+    }
+
+    /// Scan worker: optional crawl, then classify every indexed file
+    fn run_scan(
+        &self,
+        job: &DiscoveryJob,
+        root: FileSystemNode,
+        options: DiscoveryOptions,
+        detectors: Vec<Arc<dyn ContentDetector>>,
+        limits: ReadLimits,
+    ) {
+        let job_id = job.progress.read().job_id;
+        println!("🔎 Discovery {} started at node {} ({} detectors)", job_id, root.id, detectors.len());
+
+        let mut message = None;
+        if options.crawl && root.entry_type != EntryType::File {
+            message = self.crawl_first(job, root.id, options.scan_config);
+        }
+
+        let files = self.index.files_under(root.id);
+        job.progress.write().files_total = files.len() as u64;
+
+        for node in files {
+            if job.cancel.load(Ordering::SeqCst) {
+                break;
+            }
+            let current = node.classification.as_ref().is_some_and(|c| c.is_current(&node));
+            if (current && !options.rescan) || node.size.unwrap_or(0) > limits.max_file_bytes {
+                job.progress.write().files_skipped += 1;
+                continue;
+            }
+
+            match Self::classify_file(&node, &detectors, limits) {
+                Ok(None) => job.progress.write().files_unsupported += 1,
+                Ok(Some(classification)) => {
+                    let has_findings = !classification.findings.is_empty();
+                    let bytes = classification.bytes_scanned;
+                    self.index.set_classification(node.id, classification);
+
+                    let mut progress = job.progress.write();
+                    progress.files_classified += 1;
+                    progress.bytes_scanned += bytes;
+                    if has_findings {
+                        progress.files_with_findings += 1;
+                    }
+                }
+                Err(_) => job.progress.write().errors += 1,
+            }
+        }
+
+        let status = if job.cancel.load(Ordering::SeqCst) { CrawlStatus::Cancelled } else { CrawlStatus::Completed };
+        Self::finish(job, status, message);
+
+        let progress = job.progress.read();
+        println!("🔎 Discovery {} {}: {} files classified, {} with findings, {} skipped, {} unsupported, {} errors",
+            job_id, status.as_str(), progress.files_classified, progress.files_with_findings,
+            progress.files_skipped, progress.files_unsupported, progress.errors);
+    }
+
+    /// Index the subtree with the background crawler and wait for it
+    /// Returns a note for the job when the crawl did not complete.
+    fn crawl_first(&self, job: &DiscoveryJob, root_id: u64, scan_config: Option<ScanConfig>) -> Option<String> {
+        let options = CrawlOptions { scan_config, ..CrawlOptions::default() };
+        let crawl_id = match self.scanner.start_crawl(root_id, options) {
+            Ok(progress) => progress.job_id,
+            Err(e) => return Some(format!("Crawl not started ({}), only indexed files were classified", e)),
+        };
+        job.progress.write().crawl_job_id = Some(crawl_id);
+
+        loop {
+            if job.cancel.load(Ordering::SeqCst) {
+                let _ = self.scanner.cancel_crawl(crawl_id);
+                return None;
+            }
+            match self.scanner.crawl_progress(crawl_id) {
+                Some(progress) if progress.status == CrawlStatus::Running => {
+                    thread::sleep(Duration::from_millis(200));
+                }
+                Some(progress) if progress.status == CrawlStatus::Completed => return None,
+                Some(progress) => {
+                    return Some(format!("Crawl {}, only indexed files were classified", progress.status.as_str()));
+                }
+                None => return None,
+            }
+        }
+    }
+
+    /// Read the file (or a head/middle/tail sample) and run the detectors
+    /// None when the content is compressed and cannot be read as text.
+    fn classify_file(
+        node: &FileSystemNode,
+        detectors: &[Arc<dyn ContentDetector>],
+        limits: ReadLimits,
+    ) -> Result<Option<ContentClassification>, String> {
+        let (windows, sampled) = Self::read_sample(&node.display_path, limits.sample_bytes)
+            .map_err(|e| format!("Failed to read node {}: {}", node.id, e))?;
+        let text = match Self::sample_text(&windows) {
+            Ok(text) => text,
+            Err(reason) => {
+                println!("🔎 Discovery: node {} not classified ({})", node.id, reason);
+                return Ok(None);
+            }
+        };
+
+        let findings = detectors.iter()
+            .filter_map(|detector| {
+                let count = detector.count_matches(&text);
+                (count > 0).then(|| DetectorFinding {
+                    detector: detector.name().to_string(),
+                    category: detector.category(),
+                    count,
+                })
+            })
+            .collect();
+
+        Ok(Some(ContentClassification {
+            classified_at: Self::now(),
+            content_modified_time: node.modified_time,
+            bytes_scanned: windows.iter().map(|window| window.len() as u64).sum(),
+            sampled,
+            findings,
+        }))
+    }
+
+    /// Whole file when it fits the sample size, else three equal windows
+    /// Windows start at even offsets so UTF-16 code units stay aligned.
+    fn read_sample(path: &str, sample_bytes: u64) -> std::io::Result<(Vec<Vec<u8>>, bool)> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+
+        if size <= sample_bytes {
+            let mut bytes = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut bytes)?;
+            return Ok((vec![bytes], false));
+        }
+
+        let window = (sample_bytes / 3) & !1;
+        let mut windows = Vec::with_capacity(3);
+        for start in [0, ((size - window) / 2) & !1, (size - window) & !1] {
+            let mut bytes = Vec::with_capacity(window as usize);
+            file.seek(SeekFrom::Start(start))?;
+            (&mut file).take(window).read_to_end(&mut bytes)?;
+            windows.push(bytes);
+        }
+        Ok((windows, true))
+    }
+
+    /// Text of the sample windows for the detectors, or why there is none
+    /// The first window (the file head) decides the encoding; windows are
+    /// joined by newlines so matches never span two of them.
+    fn sample_text(windows: &[Vec<u8>]) -> Result<Cow<'_, str>, &'static str> {
+        let head = windows.first().map(Vec::as_slice).unwrap_or_default();
+        match file_type::detect(&head[..head.len().min(file_type::HEADER_BYTES)]) {
+            ContentType::Docx | ContentType::Xlsx | ContentType::Pptx | ContentType::Zip => {
+                return Err("compressed ZIP package");
+            }
+            ContentType::Gzip | ContentType::SevenZip | ContentType::Rar => return Err("compressed archive"),
+            ContentType::Pdf if windows.iter().any(|window| contains(window, b"/Filter")) => {
+                return Err("PDF with filtered streams");
+            }
+            _ => {}
+        }
+
+        match Self::utf16_order(head) {
+            Some(big_endian) => {
+                let text = windows.iter()
+                    .enumerate()
+                    .map(|(position, window)| {
+                        // The BOM only sits at the start of the file
+                        let bom = position == 0 && matches!(window.get(..2), Some([0xFF, 0xFE]) | Some([0xFE, 0xFF]));
+                        Self::decode_utf16(&window[if bom { 2 } else { 0 }..], big_endian)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Ok(Cow::Owned(text))
+            }
+            None if windows.len() == 1 => Ok(String::from_utf8_lossy(head)),
+            None => {
+                let text = windows.iter()
+                    .map(|window| String::from_utf8_lossy(window))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                Ok(Cow::Owned(text))
+            }
+        }
+    }
+
+    /// UTF-16 byte order from a BOM, or from NUL high bytes in the head
+    /// (ASCII-range UTF-16 without a BOM). Some(true) = big endian.
+    fn utf16_order(head: &[u8]) -> Option<bool> {
+        match head.get(..2) {
+            Some([0xFF, 0xFE]) => return Some(false),
+            Some([0xFE, 0xFF]) => return Some(true),
+            _ => {}
+        }
+        let pairs: Vec<&[u8]> = head[..head.len().min(512)].chunks_exact(2).collect();
+        if pairs.len() < 8 {
+            return None;
+        }
+        let nul_low = pairs.iter().filter(|pair| pair[0] == 0 && pair[1] != 0).count();
+        let nul_high = pairs.iter().filter(|pair| pair[1] == 0 && pair[0] != 0).count();
+        if nul_high * 10 >= pairs.len() * 9 {
+            Some(false)
+        } else if nul_low * 10 >= pairs.len() * 9 {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    fn decode_utf16(bytes: &[u8], big_endian: bool) -> String {
+        let units = bytes.chunks_exact(2).map(|pair| {
+            if big_endian { u16::from_be_bytes([pair[0], pair[1]]) } else { u16::from_le_bytes([pair[0], pair[1]]) }
+        });
+        char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    /// Requested detectors (all when None)
+    fn select_detectors(&self, names: Option<&[String]>) -> Result<Vec<Arc<dyn ContentDetector>>, String> {
+        let names = match names {
+            Some(names) if !names.is_empty() => names,
+            _ => return Ok(self.detectors.clone()),
+        };
+
+        names.iter()
+            .map(|name| {
+                self.detectors.iter()
+                    .find(|detector| detector.name() == name.as_str())
+                    .cloned()
+                    .ok_or_else(|| format!("Unknown detector '{}'", name))
+            })
+            .collect()
+    }
+
+    fn finish(job: &DiscoveryJob, status: CrawlStatus, message: Option<String>) {
+        let mut progress = job.progress.write();
+        progress.status = status;
+        progress.finished_at = Some(Self::now());
+        progress.message = message;
+    }
+
+    /// Drop the oldest finished jobs beyond the history limit
+    fn prune_history(jobs: &mut HashMap<u64, Arc<DiscoveryJob>>) {
+        let mut finished: Vec<u64> = jobs.iter()
+            .filter(|(_, job)| job.progress.read().status != CrawlStatus::Running)
+            .map(|(&id, _)| id)
+            .collect();
+        if finished.len() < DISCOVERY_HISTORY {
+            return;
+        }
+        finished.sort_unstable();
+        for id in &finished[..=finished.len() - DISCOVERY_HISTORY] {
+            jobs.remove(id);
+        }
+    }
+
+    fn env_u64(name: &str, default: u64) -> u64 {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, big_endian: bool, bom: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if bom {
+            bytes.extend_from_slice(if big_endian { &[0xFE, 0xFF] } else { &[0xFF, 0xFE] });
+        }
+        for unit in text.encode_utf16() {
+            bytes.extend_from_slice(&if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() });
+        }
+        bytes
+    }
+
+    #[test]
+    fn utf16_text_is_decoded() {
+        let text = "Employee SSN: 123-45-6789, card 4111 1111 1111 1111";
+        for (big_endian, bom) in [(false, true), (true, true), (false, false), (true, false)] {
+            let windows = vec![utf16(text, big_endian, bom)];
+            assert_eq!(ContentDiscovery::sample_text(&windows).unwrap(), text);
+        }
+
+        // Later windows of a sample carry no BOM
+        let windows = vec![utf16("head", false, true), utf16("tail 123-45-6789", false, false)];
+        assert_eq!(ContentDiscovery::sample_text(&windows).unwrap(), "head\ntail 123-45-6789");
+    }
+
+    #[test]
+    fn compressed_content_is_not_classified() {
+        let docx = b"PK\x03\x04\x14\x00\x06\x00\x08\x00\x00\x00!\x00[Content_Types].xml\x85\x92word/document.xml".to_vec();
+        assert!(ContentDiscovery::sample_text(&[docx]).is_err());
+        assert!(ContentDiscovery::sample_text(&[b"\x1F\x8B\x08\x00\x00\x00".to_vec()]).is_err());
+
+        let compressed_pdf = b"%PDF-1.7\n1 0 obj << /Length 42 /Filter /FlateDecode >> stream\nx\x9C".to_vec();
+        assert!(ContentDiscovery::sample_text(&[compressed_pdf]).is_err());
+        let plain_pdf = b"%PDF-1.1\n1 0 obj << /Length 20 >> stream\n(123-45-6789) Tj".to_vec();
+        assert!(ContentDiscovery::sample_text(&[plain_pdf]).unwrap().contains("123-45-6789"));
+    }
+
+    #[test]
+    fn plain_text_windows_are_joined() {
+        let windows = vec![b"123-45-".to_vec(), b"6789".to_vec()];
+        // A number split across two windows must not be matched
+        assert_eq!(ContentDiscovery::sample_text(&windows).unwrap(), "123-45-\n6789");
+    }
+}
@@ -0,0 +1,347 @@
+//! File Type Identification
+//! Content type from magic bytes, so a renamed file keeps its real type
+//! Only the first HEADER_BYTES of a file are read (plus the central directory
+//! at the end of ZIP files); nothing is kept but the type.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Bytes read from the start of a file
+pub const HEADER_BYTES: usize = 4096;
+
+/// Bytes read from the end of a ZIP file (central directory: every part name)
+const ZIP_TAIL_BYTES: u64 = 64 * 1024;
+
+/// Normalized content type of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentType {
+    Docx,           // OOXML word processing
+    Xlsx,           // OOXML spreadsheet
+    Pptx,           // OOXML presentation
+    Zip,            // Other ZIP containers (jar, odt, ...)
+    OleCompound,    // Legacy Office (doc, xls, ppt), msg
+    Pdf,
+    Rtf,
+    Pst,            // Outlook data file (pst, ost)
+    Sqlite,
+    Png,
+    Jpeg,
+    Gif,
+    Bmp,
+    Tiff,
+    Webp,
+    Executable,     // Windows PE (exe, dll, sys)
+    Elf,
+    MachO,
+    Gzip,
+    SevenZip,
+    Rar,
+    Text,           // No known signature, no binary bytes in the header
+    Empty,
+    Unknown,
+}
+
+impl ContentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Docx => "docx",
+            ContentType::Xlsx => "xlsx",
+            ContentType::Pptx => "pptx",
+            ContentType::Zip => "zip",
+            ContentType::OleCompound => "ole_compound",
+            ContentType::Pdf => "pdf",
+            ContentType::Rtf => "rtf",
+            ContentType::Pst => "pst",
+            ContentType::Sqlite => "sqlite",
+            ContentType::Png => "png",
+            ContentType::Jpeg => "jpeg",
+            ContentType::Gif => "gif",
+            ContentType::Bmp => "bmp",
+            ContentType::Tiff => "tiff",
+            ContentType::Webp => "webp",
+            ContentType::Executable => "executable",
+            ContentType::Elf => "elf",
+            ContentType::MachO => "mach_o",
+            ContentType::Gzip => "gzip",
+            ContentType::SevenZip => "seven_zip",
+            ContentType::Rar => "rar",
+            ContentType::Text => "text",
+            ContentType::Empty => "empty",
+            ContentType::Unknown => "unknown",
+        }
+    }
+
+    /// Parse a type name (as in `as_str`, plus a few common aliases)
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let content_type = match name.trim().to_lowercase().as_str() {
+            "docx" => ContentType::Docx,
+            "xlsx" => ContentType::Xlsx,
+            "pptx" => ContentType::Pptx,
+            "zip" => ContentType::Zip,
+            "ole_compound" | "ole" => ContentType::OleCompound,
+            "pdf" => ContentType::Pdf,
+            "rtf" => ContentType::Rtf,
+            "pst" => ContentType::Pst,
+            "sqlite" => ContentType::Sqlite,
+            "png" => ContentType::Png,
+            "jpeg" | "jpg" => ContentType::Jpeg,
+            "gif" => ContentType::Gif,
+            "bmp" => ContentType::Bmp,
+            "tiff" | "tif" => ContentType::Tiff,
+            "webp" => ContentType::Webp,
+            "executable" | "exe" | "pe" => ContentType::Executable,
+            "elf" => ContentType::Elf,
+            "mach_o" | "macho" => ContentType::MachO,
+            "gzip" | "gz" => ContentType::Gzip,
+            "seven_zip" | "7z" => ContentType::SevenZip,
+            "rar" => ContentType::Rar,
+            "text" => ContentType::Text,
+            "empty" => ContentType::Empty,
+            "unknown" => ContentType::Unknown,
+            other => return Err(format!("Unknown content type '{}'", other)),
+        };
+        Ok(content_type)
+    }
+}
+
+/// Identify a file by reading its header
+/// A ZIP the header cannot place is checked again against its central directory.
+pub fn identify(path: &Path) -> std::io::Result<ContentType> {
+    let mut file = File::open(path)?;
+    let mut header = Vec::with_capacity(HEADER_BYTES);
+    (&mut file).take(HEADER_BYTES as u64).read_to_end(&mut header)?;
+    let content_type = detect(&header);
+    if content_type != ContentType::Zip {
+        return Ok(content_type);
+    }
+
+    let size = file.metadata()?.len();
+    let start = size.saturating_sub(ZIP_TAIL_BYTES).max(header.len() as u64);
+    if start >= size {
+        return Ok(content_type);
+    }
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    file.take(ZIP_TAIL_BYTES).read_to_end(&mut tail)?;
+    Ok(detect_zip(&header, &tail))
+}
+
+/// Content type of a file header (the first bytes of the file)
+pub fn detect(header: &[u8]) -> ContentType {
+    if header.is_empty() {
+        return ContentType::Empty;
+    }
+
+    const SIGNATURES: &[(&[u8], ContentType)] = &[
+        (b"%PDF-", ContentType::Pdf),
+        (b"{\\rtf", ContentType::Rtf),
+        (b"!BDN", ContentType::Pst),
+        (b"SQLite format 3\0", ContentType::Sqlite),
+        (b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1", ContentType::OleCompound),
+        (b"\x89PNG\r\n\x1A\n", ContentType::Png),
+        (b"\xFF\xD8\xFF", ContentType::Jpeg),
+        (b"GIF87a", ContentType::Gif),
+        (b"GIF89a", ContentType::Gif),
+        (b"II*\0", ContentType::Tiff),
+        (b"MM\0*", ContentType::Tiff),
+        (b"\x7FELF", ContentType::Elf),
+        (b"\xFE\xED\xFA\xCE", ContentType::MachO),
+        (b"\xFE\xED\xFA\xCF", ContentType::MachO),
+        (b"\xCE\xFA\xED\xFE", ContentType::MachO),
+        (b"\xCF\xFA\xED\xFE", ContentType::MachO),
+        (b"\x1F\x8B", ContentType::Gzip),
+        (b"7z\xBC\xAF\x27\x1C", ContentType::SevenZip),
+        (b"Rar!\x1A\x07", ContentType::Rar),
+    ];
+    if let Some((_, content_type)) = SIGNATURES.iter().find(|(magic, _)| header.starts_with(magic)) {
+        return *content_type;
+    }
+
+    if header.starts_with(b"PK\x03\x04") {
+        return detect_zip(header, &[]);
+    }
+    if header.starts_with(b"MZ") && (has_pe_signature(header) || !is_text(header)) {
+        return ContentType::Executable;
+    }
+    if is_bmp(header) {
+        return ContentType::Bmp;
+    }
+    if header.len() >= 12 && header.starts_with(b"RIFF") && &header[8..12] == b"WEBP" {
+        return ContentType::Webp;
+    }
+
+    if is_text(header) { ContentType::Text } else { ContentType::Unknown }
+}
+
+/// OOXML packages are ZIPs whose part names show the document kind
+/// Names come from local headers in `header` and the central directory in `tail`.
+fn detect_zip(header: &[u8], tail: &[u8]) -> ContentType {
+    let names: Vec<&[u8]> = zip_part_names(header).chain(zip_part_names(tail)).collect();
+    if !names.contains(&&b"[Content_Types].xml"[..]) {
+        return ContentType::Zip;
+    }
+    let parts: [(&[u8], ContentType); 3] = [
+        (b"word/", ContentType::Docx),
+        (b"xl/", ContentType::Xlsx),
+        (b"ppt/", ContentType::Pptx),
+    ];
+    parts.iter()
+        .find(|(folder, _)| names.iter().any(|name| name.starts_with(folder)))
+        .map(|(_, content_type)| *content_type)
+        .unwrap_or(ContentType::Zip)
+}
+
+/// Part names of the local (PK 3 4) and central directory (PK 1 2) headers in `bytes`
+/// Headers cut off at either end are skipped.
+fn zip_part_names(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    (0..bytes.len().saturating_sub(3)).filter_map(move |at| {
+        let (length_at, name_at) = match &bytes[at..at + 4] {
+            b"PK\x03\x04" => (at + 26, at + 30),
+            b"PK\x01\x02" => (at + 28, at + 46),
+            _ => return None,
+        };
+        let length = u16::from_le_bytes([*bytes.get(length_at)?, *bytes.get(length_at + 1)?]) as usize;
+        bytes.get(name_at..name_at + length)
+    })
+}
+
+/// PE images: e_lfanew (offset 0x3C) points at "PE\0\0"
+fn has_pe_signature(header: &[u8]) -> bool {
+    let offset = match header.get(0x3C..0x40) {
+        Some(bytes) => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize,
+        None => return false,
+    };
+    header.get(offset..offset + 4) == Some(b"PE\0\0")
+}
+
+/// BMP: reserved fields zero and a known DIB header size
+fn is_bmp(header: &[u8]) -> bool {
+    if !header.starts_with(b"BM") || header.len() < 18 || header[6..10] != [0, 0, 0, 0] {
+        return false;
+    }
+    let dib_size = u32::from_le_bytes([header[14], header[15], header[16], header[17]]);
+    matches!(dib_size, 12 | 40 | 52 | 56 | 64 | 108 | 124)
+}
+
+/// UTF-16 with a BOM, or no NUL/control bytes besides whitespace
+fn is_text(header: &[u8]) -> bool {
+    if header.starts_with(b"\xFF\xFE") || header.starts_with(b"\xFE\xFF") {
+        return true;
+    }
+    header.iter().all(|&byte| byte >= 0x20 || matches!(byte, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ZIP local file header for `name` followed by `data` (stored)
+    fn local_entry(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut entry = b"PK\x03\x04".to_vec();
+        entry.extend_from_slice(&[0; 22]);
+        entry.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&[0, 0]);
+        entry.extend_from_slice(name.as_bytes());
+        entry.extend_from_slice(data);
+        entry
+    }
+
+    /// ZIP central directory header for `name`
+    fn central_entry(name: &str) -> Vec<u8> {
+        let mut entry = b"PK\x01\x02".to_vec();
+        entry.extend_from_slice(&[0; 24]);
+        entry.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        entry.extend_from_slice(&[0; 16]);
+        entry.extend_from_slice(name.as_bytes());
+        entry
+    }
+
+    fn package(parts: &[&str], padding: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for part in parts {
+            bytes.extend(local_entry(part, &vec![0x5A; padding]));
+        }
+        for part in parts {
+            bytes.extend(central_entry(part));
+        }
+        bytes.extend_from_slice(b"PK\x05\x06");
+        bytes.extend_from_slice(&[0; 18]);
+        bytes
+    }
+
+    #[test]
+    fn signatures_are_recognised() {
+        assert_eq!(detect(b""), ContentType::Empty);
+        assert_eq!(detect(b"%PDF-1.7\n"), ContentType::Pdf);
+        assert_eq!(detect(b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1\0\0"), ContentType::OleCompound);
+        assert_eq!(detect(b"\x89PNG\r\n\x1A\n\0\0\0\rIHDR"), ContentType::Png);
+        assert_eq!(detect(b"\x7FELF\x02\x01\x01"), ContentType::Elf);
+        assert_eq!(detect(b"SQLite format 3\0"), ContentType::Sqlite);
+        assert_eq!(detect(b"name,card\n4111,visa\n"), ContentType::Text);
+        assert_eq!(detect(b"\xFF\xFEh\0i\0"), ContentType::Text);
+        assert_eq!(detect(b"\0\x01\x02\x03binary"), ContentType::Unknown);
+    }
+
+    #[test]
+    fn ooxml_kind_comes_from_part_names() {
+        let docx = package(&["[Content_Types].xml", "_rels/.rels", "word/document.xml"], 16);
+        assert_eq!(detect(&docx), ContentType::Docx);
+        let xlsx = package(&["[Content_Types].xml", "xl/workbook.xml"], 16);
+        assert_eq!(detect(&xlsx), ContentType::Xlsx);
+        let pptx = package(&["[Content_Types].xml", "ppt/presentation.xml"], 16);
+        assert_eq!(detect(&pptx), ContentType::Pptx);
+
+        // No content types part: a plain ZIP, whatever it holds
+        assert_eq!(detect(&package(&["word/document.xml"], 16)), ContentType::Zip);
+        // Folder names only count at the start of a part name
+        let custom = package(&["[Content_Types].xml", "customXml/item1.xml", "docs/xl/notes.txt"], 16);
+        assert_eq!(detect(&custom), ContentType::Zip);
+    }
+
+    #[test]
+    fn ooxml_parts_beyond_the_header_are_found() {
+        // Large leading parts push word/ past the header: only the central directory names it
+        let docx = package(&["[Content_Types].xml", "docProps/thumbnail.jpeg", "word/document.xml"], HEADER_BYTES);
+        assert_eq!(detect(&docx[..HEADER_BYTES]), ContentType::Zip);
+
+        let path = std::env::temp_dir().join(format!("dlp_file_type_{}_{}.docx", std::process::id(),
+            std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos()));
+        std::fs::write(&path, &docx).unwrap();
+        assert_eq!(identify(&path).unwrap(), ContentType::Docx);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn mz_needs_an_executable_header() {
+        assert_eq!(detect(b"MZ Holdings quarterly report\r\n"), ContentType::Text);
+
+        let mut pe = vec![0u8; 0x84];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        assert_eq!(detect(&pe), ContentType::Executable);
+
+        // DOS executables have no PE header but are binary
+        assert_eq!(detect(b"MZ\x90\0\x03\0\0\0\x04\0"), ContentType::Executable);
+    }
+
+    #[test]
+    fn bm_needs_a_bitmap_header() {
+        assert_eq!(detect(b"BMW fleet list 2024\n"), ContentType::Text);
+
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&70u32.to_le_bytes());   // File size
+        bmp.extend_from_slice(&[0; 4]);                // Reserved
+        bmp.extend_from_slice(&54u32.to_le_bytes());   // Pixel data offset
+        bmp.extend_from_slice(&40u32.to_le_bytes());   // BITMAPINFOHEADER
+        assert_eq!(detect(&bmp), ContentType::Bmp);
+
+        // Zero reserved bytes alone are not enough
+        bmp[14..18].copy_from_slice(&7u32.to_le_bytes());
+        assert_eq!(detect(&bmp), ContentType::Unknown);
+    }
+}
@@ -6,17 +6,71 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::time::{SystemTime, UNIX_EPOCH};
 use crate::nt_path_resolver::NtPathResolver;
+use crate::path_canonical;
+use crate::volume_map::{DriveDevice, VolumeMap};
 use crate::policy::PathResolver;
 
-use super::fs_index::{FilesystemIndex, FileSystemNode, EntryType};
+use super::file_type::{self, ContentType};
+use super::fs_index::{FilesystemIndex, FileSystemNode, EntryType, DriveKind};
 use super::path_normalizer::PathNormalizer;
+use super::query_interface::ScanState;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+use windows_sys::Win32::Storage::FileSystem::{FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_SYSTEM};
 
-/// Configuration for scanning
+/// Children inserted into the index per batch while expanding a directory
+const EXPAND_CHUNK_SIZE: usize = 1024;
+
+/// Environment variable limiting how many background crawls run at once
+pub const CRAWL_MAX_JOBS_ENV: &str = "AGENT_CRAWL_MAX_JOBS";
+
+/// Environment variable for the pause after each crawled chunk (IO throttle)
+pub const CRAWL_THROTTLE_MS_ENV: &str = "AGENT_CRAWL_THROTTLE_MS";
+
+/// Environment variable listing UNC shares to index as drives ("\\srv\a;\\srv\b")
+pub const NETWORK_SHARES_ENV: &str = "AGENT_NETWORK_SHARES";
+
+const DEFAULT_CRAWL_MAX_JOBS: usize = 2;
+const DEFAULT_CRAWL_THROTTLE_MS: u64 = 20;
+
+/// Finished crawl jobs kept for status queries
+const CRAWL_HISTORY: usize = 32;
+
+/// A drive-like root found on this machine
+struct DriveRoot {
+    root: String,   // "C:", "\\server\share", "\\?\Volume{GUID}" (no trailing backslash)
+    display_name: String,
+    kind: DriveKind,
+    device: Option<DriveDevice>,
+}
+
+/// Drive nodes added or dropped by a drive refresh (IDs only)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DriveChanges {
+    pub arrived: Vec<u64>,
+    pub removed: Vec<u64>,
+}
+
+impl DriveChanges {
+    pub fn is_empty(&self) -> bool {
+        self.arrived.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Configuration for scanning (settable per expand request)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ScanConfig {
     pub follow_symlinks: bool,
     pub skip_hidden: bool,
     pub skip_system: bool,
+    pub identify_types: bool,   // Read file headers for the content type (costs one open per file)
 }
 
 impl Default for ScanConfig {
@@ -25,95 +79,279 @@ impl Default for ScanConfig {
             follow_symlinks: false,
             skip_hidden: true,
             skip_system: true,
+            identify_types: false,
+        }
+    }
+}
+
+/// Options for one background crawl
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrawlOptions {
+    pub scan_config: Option<ScanConfig>,  // Scanner default when None
+    pub throttle_ms: Option<u64>,         // AGENT_CRAWL_THROTTLE_MS when None
+    pub max_depth: Option<usize>,         // Levels below the root, unlimited when None
+}
+
+/// Lifecycle of a background crawl
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrawlStatus {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl CrawlStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CrawlStatus::Running => "running",
+            CrawlStatus::Completed => "completed",
+            CrawlStatus::Cancelled => "cancelled",
+            CrawlStatus::Failed => "failed",
         }
     }
 }
 
+/// Progress of a background crawl (admin-safe: node IDs and counters only)
+#[derive(Debug, Clone, Serialize)]
+pub struct CrawlProgress {
+    pub job_id: u64,
+    pub root_node_id: u64,
+    pub status: CrawlStatus,
+    pub directories_scanned: u64,
+    pub entries_indexed: u64,
+    pub errors: u64,                   // Entries that could not be read
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub message: Option<String>,
+}
+
+/// Running or finished crawl
+struct CrawlJob {
+    progress: RwLock<CrawlProgress>,
+    cancel: AtomicBool,
+}
+
 /// Main scanner with lazy loading
 pub struct FileSystemScanner {
     index: Arc<FilesystemIndex>,
     path_resolver: Arc<PathResolver>,  // For DOS → NT path conversion
     config: ScanConfig,
+    scan_state: Arc<RwLock<ScanState>>,  // Published to QueryInterface stats
+    active_scans: AtomicUsize,
+    expanded_with: RwLock<HashMap<u64, ScanConfig>>,  // Config each expanded node was read with
+    crawl_jobs: RwLock<HashMap<u64, Arc<CrawlJob>>>,
+    next_crawl_id: AtomicU64,
+    max_crawl_jobs: usize,
 }
 
 impl FileSystemScanner {
     /// Create a new scanner
     pub fn new(index: Arc<FilesystemIndex>,path_resolver: Arc<PathResolver>) -> Self {
-        FileSystemScanner {
-            index,
-            path_resolver,  
-            config: ScanConfig::default(),
-        }
+        Self::with_config(index, path_resolver, ScanConfig::default())
     }
     
     /// Create with custom configuration
     pub fn with_config(index: Arc<FilesystemIndex>, path_resolver: Arc<PathResolver>, config: ScanConfig) -> Self {
-        FileSystemScanner { index, path_resolver, config }
+        FileSystemScanner {
+            index,
+            path_resolver,
+            config,
+            scan_state: Arc::new(RwLock::new(ScanState::Idle)),
+            active_scans: AtomicUsize::new(0),
+            expanded_with: RwLock::new(HashMap::new()),
+            crawl_jobs: RwLock::new(HashMap::new()),
+            next_crawl_id: AtomicU64::new(1),
+            max_crawl_jobs: std::env::var(CRAWL_MAX_JOBS_ENV)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_CRAWL_MAX_JOBS),
+        }
     }
     
     /// Initialize with drives only (NO full scan)
     pub fn initialize_drives(&self) -> Result<usize, String> {
+        self.begin_scan();
+        let result = self.load_drives();
+        self.end_scan(&result);
+        result
+    }
+    
+    /// Detect drives and add them to the index
+    fn load_drives(&self) -> Result<usize, String> {
         println!("🚀 Initializing drives (lazy loading)...");
         
-        // Clear existing index (except root)
-        self.index.clear();
+        // Warm start: keep the tree loaded from a snapshot, otherwise start clean
+        let warm_start = self.index.node_count() > 1;
+        if !warm_start {
+            self.index.clear();
+        }
+        
+        let (drive_count, _) = self.sync_drives();
+        if drive_count == 0 {
+            Err("No accessible drives found".to_string())
+        } else {
+            println!("✅ Initialized {} drives (ready for lazy expansion)", drive_count);
+            Ok(drive_count)
+        }
+    }
+    
+    /// Re-detect drives after volumes arrived or left (USB media plugged in
+    /// or pulled, shares mapped or disconnected) and update the drive nodes
+    pub fn refresh_drives(&self) -> DriveChanges {
+        let (_, changes) = self.sync_drives();
+        if !changes.is_empty() {
+            println!("💽 Drives changed: {} arrived, {} removed", changes.arrived.len(), changes.removed.len());
+        }
+        changes
+    }
+    
+    /// Add every detected drive, drop drive nodes that are gone
+    fn sync_drives(&self) -> (usize, DriveChanges) {
+        let previous_drives: HashMap<String, u64> = self.index.get_drives().into_iter().collect();
+        let previous_ids: HashSet<u64> = previous_drives.values().copied().collect();
+        let mut current_drive_ids = HashSet::new();
+        let mut changes = DriveChanges::default();
         
         // Detect and add drives
         let mut drive_count = 0;
         
+        for drive in self.detect_drive_roots() {
+            // NT device path from the volume map (unmapped drives are skipped)
+            let nt_path = match NtPathResolver::dos_to_nt_path(&drive.root, true) {
+                Ok(path) => path,
+                Err(e) => {
+                    println!("⚠️ Failed to get NT path for {}: {}", drive.root, e);
+                    continue;
+                }
+            };
+            
+            // Stable node IDs are keyed by volume, not drive letter
+            // (GUID and UNC roots carry their identity in the path already)
+            if drive.kind == DriveKind::Local {
+                match NtPathResolver::volume_guid_for_drive(&drive.root) {
+                    Ok(volume_guid) => self.index.register_volume(&drive.root, &volume_guid),
+                    Err(e) => println!("⚠️ No volume GUID for {} (IDs keyed by letter): {}", drive.root, e),
+                }
+            }
+            
+            // Same root, different device: the snapshot subtree is stale
+            let previous = previous_drives.get(&drive.root)
+                .and_then(|&id| self.index.get_node(id));
+            let drive_id = self.index.add_drive(&drive.root, &drive.display_name, &nt_path, drive.kind, drive.device);
+            if let Some(previous) = previous {
+                if previous.id != drive_id {
+                    self.index.remove_subtree(previous.id);
+                } else if previous.nt_path != nt_path {
+                    self.index.collapse_subtree(drive_id, true);
+                }
+            }
+            if !previous_ids.contains(&drive_id) {
+                changes.arrived.push(drive_id);
+                println!("✅ Added drive: {} -> {}", drive.root, drive.display_name);
+            }
+            current_drive_ids.insert(drive_id);
+            drive_count += 1;
+        }
+        
+        // Drives from the snapshot (or the last refresh) that are gone now
+        for (drive_letter, id) in previous_drives {
+            if !current_drive_ids.contains(&id) {
+                let removed = self.index.remove_subtree(id);
+                changes.removed.push(id);
+                println!("🗑️ Drive {} no longer present, dropped {} nodes", drive_letter, removed);
+            }
+        }
+        
+        self.save_identities();
+        changes.arrived.sort();
+        changes.removed.sort();
+        (drive_count, changes)
+    }
+    
+
+    /// Drive letters (local and mapped network drives), volumes mounted into
+    /// folders, volumes without a letter and the UNC shares from NETWORK_SHARES_ENV
+    fn detect_drive_roots(&self) -> Vec<DriveRoot> {
+        let volumes = VolumeMap::global();
+        let mut roots = Vec::new();
+        
         for letter in b'A'..=b'Z' {
             let drive_letter = format!("{}:", letter as char);
             let drive_path = PathBuf::from(&drive_letter);
+            if !drive_path.exists() {
+                continue;
+            }
+            if let Err(e) = fs::metadata(&drive_path) {
+                // Skip inaccessible drives silently
+                if e.kind() != std::io::ErrorKind::PermissionDenied {
+                    println!("⚠️  Drive {}: {}", drive_letter, e);
+                }
+                continue;
+            }
             
-            if drive_path.exists() {
-                match fs::metadata(&drive_path) {
-                    Ok(_) => {
-                        // PLACEHOLDER: Get NT path (would use QueryDosDevice in production)
-                        let nt_path_result  = NtPathResolver::dos_to_nt_path(
-                            &drive_letter, 
-                            true
-                        );
-                        
-                            // Handle the Result
-                        let nt_path = match nt_path_result {
-                            Ok(path) => path,
-                            Err(e) => {
-                                println!("⚠️ Failed to get NT path for {}: {}", drive_letter, e);
-                                // Use a fallback or continue to next drive
-                                continue;
-                            }
-                        };
-                        
-                        let display_name = if letter == b'C' {
-                            "Local Disk (C:)".to_string()
-                        } else {
-                            format!("Local Disk ({})", drive_letter)
-                        };
-                        
-                        self.index.add_drive(&drive_letter, &display_name, &nt_path);
-                        drive_count += 1;
-                        
-                        println!("✅ Added drive: {} -> {}", drive_letter, display_name);
-                    }
-                    Err(e) => {
-                        // Skip inaccessible drives silently
-                        if e.kind() != std::io::ErrorKind::PermissionDenied {
-                            println!("⚠️  Drive {}: {}", drive_letter, e);
-                        }
-                    }
+            let device = volumes.drive_device(&drive_letter);
+            let (kind, display_name) = match volumes.share_for_drive(&drive_letter) {
+                Ok(unc_path) => (DriveKind::Network, format!("Network Drive ({}) {}", drive_letter, unc_path)),
+                Err(_) => (DriveKind::Local, format!("{} ({})", Self::drive_label(device.as_ref()), drive_letter)),
+            };
+            roots.push(DriveRoot { root: drive_letter, display_name, kind, device });
+        }
+        
+        // Volumes with a letter are reached through it; the rest get a root of
+        // their own under their GUID path (a folder mount only shows as a link)
+        for volume in volumes.volumes() {
+            if volume.mount_paths.iter().any(|mount| mount.trim_end_matches('\\').len() == 2) {
+                continue;
+            }
+            let root = VolumeMap::normalize_guid(&volume.volume_guid).trim_end_matches('\\').to_string();
+            let (kind, display_name) = match volume.mount_paths.iter().min_by_key(|mount| mount.len()) {
+                Some(mount) => (DriveKind::MountedFolder, format!("Mounted Volume ({})", mount.trim_end_matches('\\'))),
+                None => {
+                    let guid = root.find('{').map(|start| &root[start..]).unwrap_or(&root);
+                    (DriveKind::Volume, format!("Volume {}", guid))
                 }
+            };
+            if fs::metadata(format!("{}\\", root)).is_err() {
+                continue;   // Recovery/system partitions are usually unreadable
             }
+            let device = volumes.drive_device(&root);
+            roots.push(DriveRoot { root, display_name, kind, device });
         }
         
-        if drive_count == 0 {
-            Err("No accessible drives found".to_string())
-        } else {
-            println!("✅ Initialized {} drives (ready for lazy expansion)", drive_count);
-            Ok(drive_count)
+        let shares = std::env::var(NETWORK_SHARES_ENV).unwrap_or_default();
+        for share in shares.split(';').map(str::trim).filter(|share| !share.is_empty()) {
+            let unc_path = share.replace('/', "\\").trim_end_matches('\\').to_string();
+            if !unc_path.starts_with("\\\\") {
+                println!("⚠️ {} entry is not a UNC path: {}", NETWORK_SHARES_ENV, share);
+                continue;
+            }
+            if let Some(drive) = volumes.drive_for_share(&unc_path) {
+                println!("ℹ️ Network share {} is mapped as {}, indexed there", unc_path, drive);
+                continue;
+            }
+            if let Err(e) = fs::metadata(format!("{}\\", unc_path)) {
+                println!("⚠️ Network share {}: {}", unc_path, e);
+                continue;
+            }
+            let device = volumes.drive_device(&unc_path);
+            roots.push(DriveRoot { display_name: format!("Network Share ({})", unc_path), root: unc_path, kind: DriveKind::Network, device });
+        }
+        
+        roots
+    }
+    
+    /// Volume label, else the Explorer name of the drive class ("Removable Disk")
+    fn drive_label(device: Option<&DriveDevice>) -> &str {
+        match device {
+            Some(DriveDevice { label: Some(label), .. }) => label,
+            Some(device) => device.class.default_label(),
+            None => "Local Disk",
         }
     }
     
-
        /// Expand a drive (same logic as directory)
     pub fn expand_drive(&self, node_id: u64) -> Result<usize, String> {
         // Drives are expanded exactly like directories
@@ -126,22 +364,361 @@ impl FileSystemScanner {
     
     /// Expand a directory (load its children)
     pub fn expand_directory(&self, node_id: u64) -> Result<usize, String> {
-        let node = match self.index.get_node(node_id) {
-            Some(node) => node,
-            None => return Err(format!("Node {} not found", node_id)),
+        self.expand_directory_with(node_id, self.config)
+    }
+    
+    /// Expand a directory with a per-request scan config
+    /// A directory read with different filters is re-read.
+    pub fn expand_directory_with(&self, node_id: u64, config: ScanConfig) -> Result<usize, String> {
+        self.begin_scan();
+        let result = self.load_children(node_id, config);
+        self.end_scan(&result);
+        
+        if result.is_ok() {
+            self.enforce_memory_ceiling(node_id);
+        }
+        result
+    }
+    
+    /// Shared handle to the scan state (for QueryInterface stats)
+    pub fn scan_state_handle(&self) -> Arc<RwLock<ScanState>> {
+        self.scan_state.clone()
+    }
+    
+    /// Publish Expanding while at least one scan runs
+    fn begin_scan(&self) {
+        self.active_scans.fetch_add(1, Ordering::SeqCst);
+        *self.scan_state.write() = ScanState::Expanding;
+    }
+    
+    /// Publish the outcome once the last running scan finishes
+    fn end_scan(&self, result: &Result<usize, String>) {
+        let mut state = self.scan_state.write();
+        if self.active_scans.fetch_sub(1, Ordering::SeqCst) == 1 {
+            *state = match result {
+                Ok(_) => ScanState::Ready,
+                Err(e) => ScanState::Error(e.clone()),
+            };
+            drop(state);
+            if result.is_ok() {
+                self.publish_crawl_state();
+            }
+        }
+    }
+    
+    /// Evict LRU subtrees if the index grew past its memory ceiling
+    fn enforce_memory_ceiling(&self, keep: u64) {
+        let evicted = self.index.enforce_memory_ceiling(keep);
+        if evicted > 0 {
+            println!("🧹 Memory ceiling reached: evicted {} nodes ({} bytes in use)",
+                evicted, self.index.memory_usage_bytes());
+        }
+        
+        let ceiling = self.index.memory_ceiling_bytes();
+        if ceiling > 0 && self.index.memory_usage_bytes() > ceiling {
+            println!("⚠️ Index still above memory ceiling ({} > {} bytes), pinned nodes kept",
+                self.index.memory_usage_bytes(), ceiling);
+        }
+    }
+    
+    /// Start a background crawl of a drive or directory subtree
+    /// Crawled nodes are added without marking anything expanded, so the
+    /// UI's expansion state is left as it was.
+    pub fn start_crawl(self: &Arc<Self>, root_id: u64, options: CrawlOptions) -> Result<CrawlProgress, String> {
+        let root = self.index.get_node(root_id)
+            .ok_or_else(|| format!("Node {} not found", root_id))?;
+        if !matches!(root.entry_type, EntryType::Drive | EntryType::Directory) {
+            return Err(format!("Node {} is not a drive or directory", root_id));
+        }
+        
+        let mut jobs = self.crawl_jobs.write();
+        let running = jobs.values()
+            .filter(|job| job.progress.read().status == CrawlStatus::Running)
+            .count();
+        if running >= self.max_crawl_jobs {
+            return Err(format!("Crawl limit reached ({} running)", running));
+        }
+        Self::prune_crawl_history(&mut jobs);
+        
+        let job_id = self.next_crawl_id.fetch_add(1, Ordering::SeqCst);
+        let job = Arc::new(CrawlJob {
+            progress: RwLock::new(CrawlProgress {
+                job_id,
+                root_node_id: root_id,
+                status: CrawlStatus::Running,
+                directories_scanned: 0,
+                entries_indexed: 0,
+                errors: 0,
+                started_at: Self::now(),
+                finished_at: None,
+                message: None,
+            }),
+            cancel: AtomicBool::new(false),
+        });
+        jobs.insert(job_id, job.clone());
+        drop(jobs);
+        
+        let scanner = Arc::clone(self);
+        let worker_job = job.clone();
+        let spawned = thread::Builder::new()
+            .name(format!("crawl-{}", job_id))
+            .spawn(move || scanner.run_crawl(&worker_job, root, options));
+        if let Err(e) = spawned {
+            Self::finish_crawl(&job, CrawlStatus::Failed, Some(format!("Failed to start crawl: {}", e)));
+            return Err(format!("Failed to start crawl: {}", e));
+        }
+        
+        self.publish_crawl_state();
+        let progress = job.progress.read().clone();
+        Ok(progress)
+    }
+    
+    /// Ask a running crawl to stop (it stops after the current entry)
+    pub fn cancel_crawl(&self, job_id: u64) -> Result<CrawlProgress, String> {
+        let job = self.crawl_jobs.read().get(&job_id).cloned()
+            .ok_or_else(|| format!("Crawl job {} not found", job_id))?;
+        
+        let status = job.progress.read().status;
+        if status != CrawlStatus::Running {
+            return Err(format!("Crawl job {} is already {}", job_id, status.as_str()));
+        }
+        
+        job.cancel.store(true, Ordering::SeqCst);
+        println!("🛑 Crawl {} cancellation requested", job_id);
+        let progress = job.progress.read().clone();
+        Ok(progress)
+    }
+    
+    /// Cancel every running crawl (agent shutdown)
+    pub fn cancel_all_crawls(&self) -> usize {
+        let jobs = self.crawl_jobs.read();
+        let mut cancelled = 0;
+        for job in jobs.values() {
+            if job.progress.read().status == CrawlStatus::Running {
+                job.cancel.store(true, Ordering::SeqCst);
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+    
+    /// Number of crawls still running
+    pub fn running_crawls(&self) -> usize {
+        self.crawl_jobs.read().values()
+            .filter(|job| job.progress.read().status == CrawlStatus::Running)
+            .count()
+    }
+    
+    /// Progress of one crawl
+    pub fn crawl_progress(&self, job_id: u64) -> Option<CrawlProgress> {
+        self.crawl_jobs.read().get(&job_id).map(|job| job.progress.read().clone())
+    }
+    
+    /// All known crawls (newest first)
+    pub fn list_crawls(&self) -> Vec<CrawlProgress> {
+        let mut crawls: Vec<CrawlProgress> = self.crawl_jobs.read().values()
+            .map(|job| job.progress.read().clone())
+            .collect();
+        crawls.sort_by_key(|progress| std::cmp::Reverse(progress.job_id));
+        crawls
+    }
+    
+    /// Crawl worker: walk, record the outcome, publish state
+    fn run_crawl(&self, job: &CrawlJob, root: FileSystemNode, options: CrawlOptions) {
+        let job_id = job.progress.read().job_id;
+        println!("🕷️ Crawl {} started at {} (ID: {})", job_id, root.display_path, root.id);
+        
+        let (status, message) = match self.crawl_subtree(job, &root, options) {
+            Ok(()) if job.cancel.load(Ordering::SeqCst) => (CrawlStatus::Cancelled, None),
+            Ok(()) => (CrawlStatus::Completed, None),
+            Err(e) => (CrawlStatus::Failed, Some(e)),
         };
+        Self::finish_crawl(job, status, message);
+        
+        self.save_identities();
+        self.publish_crawl_state();
         
-        if node.entry_type != EntryType::Directory && node.entry_type != EntryType::Drive {
-            return Err(format!("Node {} is not a directory", node_id));
+        let progress = job.progress.read();
+        println!("🕷️ Crawl {} {}: {} directories, {} entries, {} errors",
+            job_id, status.as_str(), progress.directories_scanned, progress.entries_indexed, progress.errors);
+    }
+    
+    /// Walk the subtree below `root`, inserting nodes in chunks
+    fn crawl_subtree(&self, job: &CrawlJob, root: &FileSystemNode, options: CrawlOptions) -> Result<(), String> {
+        let config = options.scan_config.unwrap_or(self.config);
+        let throttle = Duration::from_millis(options.throttle_ms.unwrap_or_else(|| {
+            std::env::var(CRAWL_THROTTLE_MS_ENV)
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_CRAWL_THROTTLE_MS)
+        }));
+        
+        let root_path = PathBuf::from(&root.display_path);
+        let mut walker = WalkDir::new(&root_path)
+            .min_depth(1)
+            .follow_links(config.follow_symlinks);
+        if let Some(max_depth) = options.max_depth {
+            walker = walker.max_depth(max_depth);
         }
         
-        if node.is_expanded {
-            // Already expanded, just return count
-            let children = self.index.get_children(node_id);
-            return Ok(children.len());
+        // Directories seen so far → (node ID, display path) for their children
+        let mut parents: HashMap<PathBuf, (u64, String)> = HashMap::new();
+        parents.insert(root_path, (root.id, root.display_path.clone()));
+        
+        let mut chunk = Vec::with_capacity(EXPAND_CHUNK_SIZE);
+        let mut directories_scanned = 0;
+        let mut errors = 0;
+        let mut entries = walker.into_iter();
+        
+        while let Some(entry) = entries.next() {
+            if job.cancel.load(Ordering::SeqCst) {
+                break;
+            }
+            
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    if e.loop_ancestor().is_some() {
+                        println!("⚠️ Crawl {}: link loop skipped", job.progress.read().job_id);
+                    }
+                    errors += 1;
+                    continue;
+                }
+            };
+            let (parent_id, parent_display_path) = match entry.path().parent().and_then(|parent| parents.get(parent)) {
+                Some(parent) => parent.clone(),
+                None => continue,
+            };
+            
+            // Link-level metadata, so links are recorded as links even when followed
+            let metadata = if entry.path_is_symlink() {
+                fs::symlink_metadata(entry.path())
+            } else {
+                entry.metadata().map_err(std::io::Error::from)
+            };
+            let node = match metadata
+                .map_err(|e| e.to_string())
+                .and_then(|metadata| self.build_child_node(entry.path(), &metadata, parent_id, &parent_display_path, &config))
+            {
+                Ok(Some(node)) => node,
+                Ok(None) => {
+                    // Filtered out: do not descend either
+                    if entry.file_type().is_dir() {
+                        entries.skip_current_dir();
+                    }
+                    continue;
+                }
+                Err(_) => {
+                    errors += 1;
+                    continue;
+                }
+            };
+            
+            if entry.file_type().is_dir() {
+                parents.insert(entry.path().to_path_buf(), (node.id, node.display_path.clone()));
+                directories_scanned += 1;
+            }
+            chunk.push(node);
+            
+            if chunk.len() >= EXPAND_CHUNK_SIZE {
+                self.flush_crawl_chunk(job, &mut chunk, directories_scanned, errors)?;
+                thread::sleep(throttle);
+            }
         }
         
-        println!("📁 Expanding: {} (ID: {})", node.display_path, node_id);
+        self.flush_crawl_chunk(job, &mut chunk, directories_scanned, errors)
+    }
+    
+    /// Insert a crawled chunk and update progress
+    /// The crawl stops at the memory ceiling instead of evicting expanded folders.
+    fn flush_crawl_chunk(
+        &self,
+        job: &CrawlJob,
+        chunk: &mut Vec<FileSystemNode>,
+        directories_scanned: u64,
+        errors: u64,
+    ) -> Result<(), String> {
+        let added = self.index.add_nodes(std::mem::take(chunk));
+        {
+            let mut progress = job.progress.write();
+            progress.entries_indexed += added as u64;
+            progress.directories_scanned = directories_scanned;
+            progress.errors = errors;
+        }
+        self.publish_crawl_state();
+        
+        let ceiling = self.index.memory_ceiling_bytes();
+        if ceiling > 0 && self.index.memory_usage_bytes() > ceiling {
+            return Err(format!("Index memory ceiling reached ({} bytes), crawl stopped", ceiling));
+        }
+        Ok(())
+    }
+    
+    /// Mark a crawl finished
+    fn finish_crawl(job: &CrawlJob, status: CrawlStatus, message: Option<String>) {
+        let mut progress = job.progress.write();
+        progress.status = status;
+        progress.finished_at = Some(Self::now());
+        progress.message = message;
+    }
+    
+    /// Publish crawl progress as the scan state while no expansion runs
+    fn publish_crawl_state(&self) {
+        let (running, entries_indexed) = self.crawl_jobs.read().values()
+            .map(|job| job.progress.read())
+            .filter(|progress| progress.status == CrawlStatus::Running)
+            .fold((0, 0), |(running, entries), progress| (running + 1, entries + progress.entries_indexed));
+        
+        if self.active_scans.load(Ordering::SeqCst) > 0 {
+            return;
+        }
+        let mut state = self.scan_state.write();
+        if running > 0 {
+            *state = ScanState::Crawling { jobs: running, entries_indexed };
+        } else if matches!(*state, ScanState::Crawling { .. }) {
+            *state = ScanState::Ready;
+        }
+    }
+    
+    /// Drop the oldest finished jobs beyond the history limit
+    fn prune_crawl_history(jobs: &mut HashMap<u64, Arc<CrawlJob>>) {
+        let mut finished: Vec<u64> = jobs.iter()
+            .filter(|(_, job)| job.progress.read().status != CrawlStatus::Running)
+            .map(|(&job_id, _)| job_id)
+            .collect();
+        if finished.len() < CRAWL_HISTORY {
+            return;
+        }
+        finished.sort_unstable();
+        for job_id in &finished[..=finished.len() - CRAWL_HISTORY] {
+            jobs.remove(job_id);
+        }
+    }
+    
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+    
+    /// Read a directory's children into the index
+    fn load_children(&self, node_id: u64, config: ScanConfig) -> Result<usize, String> {
+        let node = match self.index.get_node(node_id) {
+            Some(node) => node,
+            None => return Err(format!("Node {} not found", node_id)),
+        };
+        
+        match node.entry_type {
+            EntryType::Directory | EntryType::Drive => {}
+            EntryType::Symlink | EntryType::Junction => {
+                if !config.follow_symlinks {
+                    return Err(format!("Node {} is a link; set follow_symlinks to expand it", node_id));
+                }
+                self.check_link_loop(&node)?;
+            }
+            _ => return Err(format!("Node {} is not a directory", node_id)),
+        }
         
         // Convert NT path back to DOS path for scanning
         // This is simplified - in production you'd use the stored display_path
@@ -153,10 +730,35 @@ impl FileSystemScanner {
             PathBuf::from(&node.display_path)
         };
         
+        if node.is_expanded {
+            // Children loaded from a snapshot are re-read if the directory changed since,
+            // children read with other filters are re-read too
+            let read_with = self.expanded_config(node_id);
+            let stale = (self.index.take_unverified(node_id)
+                && Self::directory_mtime(&scan_path) != Some(node.modified_time))
+                || read_with != config;
+            if !stale {
+                // Already expanded, just return count
+                self.index.touch_node(node_id);
+                let children = self.index.get_children(node_id);
+                return Ok(children.len());
+            }
+            
+            println!("♻️ Children of {} are stale, re-reading", node.display_path);
+            self.index.collapse_subtree(node_id, true);
+        }
+        
+        println!("📁 Expanding: {} (ID: {})", node.display_path, node_id);
+        
         if !scan_path.exists() {
             return Err(format!("Path does not exist: {}", scan_path.display()));
         }
         
+        // Record the mtime the children are read at (checked after a warm start)
+        if let Some(modified_time) = Self::directory_mtime(&scan_path) {
+            self.index.set_modified_time(node_id, modified_time);
+        }
+        
         // Read directory contents
         let entries = match fs::read_dir(&scan_path) {
             Ok(entries) => entries,
@@ -164,13 +766,19 @@ impl FileSystemScanner {
         };
         
         let mut child_count = 0;
+        let mut chunk = Vec::with_capacity(EXPAND_CHUNK_SIZE);
         
         for entry in entries {
             match entry {
                 Ok(dir_entry) => {
-                    if let Ok(child_node) = self.create_child_node(&dir_entry, node_id) {
-                        self.index.add_node(child_node);
-                        child_count += 1;
+                    if let Ok(Some(child_node)) = self.create_child_node(&dir_entry, node_id, &config) {
+                        chunk.push(child_node);
+                    }
+                    if chunk.len() >= EXPAND_CHUNK_SIZE {
+                        // Large directory: publish what we have so far
+                        child_count += self.index.add_nodes(std::mem::take(&mut chunk));
+                        println!("📥 {} -> {} children loaded so far", node.name, child_count);
+                        self.enforce_memory_ceiling(node_id);
                     }
                 }
                 Err(e) => {
@@ -182,52 +790,245 @@ impl FileSystemScanner {
             }
         }
         
+        child_count += self.index.add_nodes(chunk);
+        
         // Mark as expanded
         self.index.mark_expanded(node_id);
+        self.expanded_with.write().insert(node_id, config);
+        self.save_identities();
         
         println!("✅ Expanded {} -> {} children", node.name, child_count);
         Ok(child_count)
     }
     
-    /// Collapse a directory (remove loaded children)
+    /// Add or refresh one entry of an expanded directory (watcher deltas)
+    /// Uses the filters the directory was expanded with; None if filtered out.
+    pub fn add_entry(&self, path: &Path, parent_id: u64) -> Result<Option<u64>, String> {
+        let parent = self.index.get_node(parent_id)
+            .ok_or_else(|| format!("Parent node {} not found", parent_id))?;
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to get metadata: {}", e)),
+        };
+        let config = self.expanded_config(parent_id);
+        
+        match self.build_child_node(path, &metadata, parent_id, &parent.display_path, &config)? {
+            Some(node) => Ok(Some(self.index.add_node(node))),
+            None => Ok(None),
+        }
+    }
+    
+    /// Resolve a DOS display path (e.g. "D:\Finance\Q1\") to a node ID,
+    /// expanding intermediate directories on demand
+    pub fn resolve_display_path(&self, display_path: &str) -> Result<u64, String> {
+        let mut normalized = PathNormalizer::normalize_display_path(display_path);
+
+        // Below a folder mount point the mounted volume's own root takes over
+        if let Some(volume_path) = VolumeMap::global().volume_path_for(&normalized) {
+            normalized = volume_path;
+        }
+
+        // Longest drive root covering the path ("C:", "\\server\share", "\\?\Volume{...}")
+        let (root, mut current_id) = self.index.get_drives()
+            .into_iter()
+            .filter(|(root, _)| {
+                normalized.len() >= root.len()
+                    && normalized.as_bytes()[..root.len()].eq_ignore_ascii_case(root.as_bytes())
+                    && normalized.as_bytes().get(root.len()).is_none_or(|&byte| byte == b'\\')
+            })
+            .max_by_key(|(root, _)| root.len())
+            .ok_or_else(|| format!("No drive for {} on this machine", display_path))?;
+
+        // Explicit paths may name hidden/system entries the default filters skip
+        let permissive = ScanConfig { skip_hidden: false, skip_system: false, ..self.config };
+        let find_child = |parent_id: u64, component: &str| {
+            self.index.get_children(parent_id)
+                .into_iter()
+                .find(|child| path_canonical::eq_ignore_case(&child.name, component))
+                .map(|child| child.id)
+        };
+
+        for component in normalized[root.len()..].split('\\').filter(|c| !c.is_empty()) {
+            if !self.index.is_expanded(current_id) {
+                self.expand_directory_with(current_id, permissive)?;
+            }
+
+            let found = match find_child(current_id, component) {
+                Some(id) => Some(id),
+                None if self.expanded_config(current_id) != permissive => {
+                    self.expand_directory_with(current_id, permissive)?;
+                    find_child(current_id, component)
+                }
+                None => None,
+            };
+            current_id = found
+                .ok_or_else(|| format!("Path component '{}' not found in {}", component, display_path))?;
+        }
+
+        Ok(current_id)
+    }
+
+    /// Load a node that has a stable ID but is not in the index right now
+    /// (e.g. after a restart), expanding its ancestors on demand
+    pub fn ensure_node_loaded(&self, node_id: u64) -> Result<(), String> {
+        if self.index.get_node(node_id).is_some() {
+            return Ok(());
+        }
+        
+        let display_path = self.index.display_path_for_id(node_id)
+            .ok_or_else(|| format!("Node {} not found", node_id))?;
+        let resolved_id = self.resolve_display_path(&display_path)?;
+        
+        if resolved_id != node_id {
+            return Err(format!("Node {} no longer matches {}", node_id, display_path));
+        }
+        Ok(())
+    }
+    
+    /// Current directory mtime (seconds), None if unavailable
+    fn directory_mtime(path: &Path) -> Option<u64> {
+        fs::metadata(path).ok()?
+            .modified().ok()?
+            .duration_since(UNIX_EPOCH).ok()
+            .map(|d| d.as_secs())
+    }
+    
+    /// Persist newly issued stable IDs (best effort)
+    fn save_identities(&self) {
+        if let Err(e) = self.index.save_identities() {
+            println!("⚠️ Failed to persist node identities: {}", e);
+        }
+    }
+    
+    /// Collapse a directory (evict loaded subtree, keep policy-pinned nodes)
     pub fn collapse_directory(&self, node_id: u64) -> Result<usize, String> {
-        let children = self.index.get_children(node_id);
-        let child_count = children.len();
+        if self.index.get_node(node_id).is_none() {
+            return Err(format!("Node {} not found", node_id));
+        }
         
-        self.index.mark_collapsed(node_id);
+        let child_count = self.index.get_children(node_id).len();
+        let evicted = self.index.collapse_subtree(node_id, true);
+        self.expanded_with.write().remove(&node_id);
         
-        println!("📁 Collapsed: ID {} (removed {} children)", node_id, child_count);
+        println!("📁 Collapsed: ID {} ({} children, {} nodes evicted)", node_id, child_count, evicted);
         Ok(child_count)
     }
     
-    /// Create a child node from directory entry
-    fn create_child_node(&self, dir_entry: &fs::DirEntry, parent_id: u64) -> Result<FileSystemNode, String> {
-        let path = dir_entry.path();
-        let metadata = dir_entry.metadata()
-            .map_err(|e| format!("Failed to get metadata: {}", e))?;
+    /// Config a node's children were read with (scanner default if unknown)
+    fn expanded_config(&self, node_id: u64) -> ScanConfig {
+        self.expanded_with.read().get(&node_id).copied().unwrap_or(self.config)
+    }
+    
+    /// Refuse to follow a link whose target contains the link itself
+    /// (directly or through another followed link), which would recurse forever
+    fn check_link_loop(&self, link: &FileSystemNode) -> Result<(), String> {
+        let target = fs::canonicalize(&link.display_path)
+            .map_err(|e| format!("Link target of node {} is not reachable: {}", link.id, e))?;
         
-        // Get entry type
-        let entry_type = if metadata.is_dir() {
-            EntryType::Directory
-        } else if metadata.is_symlink() {
-            EntryType::File  // Treat symlinks as files for simplicity
-        } else {
-            EntryType::File
-        };
+        let mut current = link.parent_id.and_then(|parent_id| self.index.get_node(parent_id));
+        while let Some(ancestor) = current {
+            if ancestor.entry_type != EntryType::VirtualRoot {
+                if let Ok(real_path) = fs::canonicalize(&ancestor.display_path) {
+                    if real_path.starts_with(&target) {
+                        return Err(format!("Node {} links to one of its own ancestors, not following", link.id));
+                    }
+                }
+            }
+            current = ancestor.parent_id.and_then(|parent_id| self.index.get_node(parent_id));
+        }
+        Ok(())
+    }
+    
+    /// Hidden/system filtering from file attributes
+    fn is_filtered(name: &str, attributes: u32, config: &ScanConfig) -> bool {
+        let hidden = attributes & FILE_ATTRIBUTE_HIDDEN != 0 || (cfg!(not(windows)) && name.starts_with('.'));
+        let system = attributes & FILE_ATTRIBUTE_SYSTEM != 0;
+        (config.skip_hidden && hidden) || (config.skip_system && system)
+    }
+    
+    /// Directory junction (mount point reparse tag) rather than a symlink
+    #[cfg(windows)]
+    fn is_junction(path: &Path) -> bool {
+        use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+        use windows_sys::Win32::Storage::FileSystem::{FindClose, FindFirstFileW, WIN32_FIND_DATAW};
+        use windows_sys::Win32::System::SystemServices::IO_REPARSE_TAG_MOUNT_POINT;
         
+        let wide = PathNormalizer::to_wide_string(&path.to_string_lossy());
+        unsafe {
+            let mut find_data: WIN32_FIND_DATAW = std::mem::zeroed();
+            let handle = FindFirstFileW(wide.as_ptr(), &mut find_data);
+            if handle == INVALID_HANDLE_VALUE {
+                return false;
+            }
+            FindClose(handle);
+            // dwReserved0 holds the reparse tag for reparse points
+            find_data.dwReserved0 == IO_REPARSE_TAG_MOUNT_POINT
+        }
+    }
+    
+    #[cfg(not(windows))]
+    fn is_junction(_path: &Path) -> bool {
+        false
+    }
+    
+    /// Create a child node from directory entry (None if filtered out by config)
+    fn create_child_node(
+        &self,
+        dir_entry: &fs::DirEntry,
+        parent_id: u64,
+        config: &ScanConfig,
+    ) -> Result<Option<FileSystemNode>, String> {
+        // Does not follow links, so links are seen as links
+        let metadata = dir_entry.metadata()
+            .map_err(|e| format!("Failed to get metadata: {}", e))?;
+        let parent_node = self.index.get_node(parent_id)
+            .ok_or("Parent node not found")?;
+        self.build_child_node(&dir_entry.path(), &metadata, parent_id, &parent_node.display_path, config)
+    }
+    
+    /// Build a node from link-level metadata (None if filtered out)
+    /// Takes the parent's display path so crawls can build whole subtrees before inserting.
+    fn build_child_node(
+        &self,
+        path: &Path,
+        metadata: &fs::Metadata,
+        parent_id: u64,
+        parent_display_path: &str,
+        config: &ScanConfig,
+    ) -> Result<Option<FileSystemNode>, String> {
         // Get file name
         let name = match path.file_name() {
             Some(name) => name.to_string_lossy().to_string(),
             None => return Err("No filename".to_string()),
         };
         
+        // Get attributes (Windows)
+        #[cfg(windows)]
+        let attributes = metadata.file_attributes();
+        #[cfg(not(windows))]
+        let attributes = 0;
+        
+        if Self::is_filtered(&name, attributes, config) {
+            return Ok(None);
+        }
+        
+        // Get entry type (target kept internally, never followed here)
+        let (entry_type, link_target) = if metadata.file_type().is_symlink() {
+            let link_type = if Self::is_junction(path) { EntryType::Junction } else { EntryType::Symlink };
+            let target = fs::read_link(path).ok().map(|target| target.to_string_lossy().to_string());
+            (link_type, target)
+        } else if metadata.is_dir() {
+            (EntryType::Directory, None)
+        } else {
+            (EntryType::File, None)
+        };
+        
         // Build display path
-        let parent_node = self.index.get_node(parent_id)
-            .ok_or("Parent node not found")?;
-        let display_path = if parent_node.display_path.ends_with('\\') {
-            format!("{}{}", parent_node.display_path, name)
+        let display_path = if parent_display_path.ends_with('\\') {
+            format!("{}{}", parent_display_path, name)
         } else {
-            format!("{}\\{}", parent_node.display_path, name)
+            format!("{}\\{}", parent_display_path, name)
         };
         
         // For directories, add trailing backslash
@@ -242,7 +1043,11 @@ impl FileSystemScanner {
         //     entry_type == EntryType::Directory);
         // let nt_path = self.path_resolver.dos_to_real_nt_path(&display_path)?;
          // TEMPORARY: Use empty NT path or placeholder
-           let is_folder = entry_type == EntryType::Directory || entry_type == EntryType::Drive;
+           let is_folder = match entry_type {
+               EntryType::Directory | EntryType::Drive | EntryType::Junction => true,
+               EntryType::Symlink => fs::metadata(path).map(|target| target.is_dir()).unwrap_or(false),
+               _ => false,
+           };
             // let nt_path = match self.path_resolver.(&display_path) {
             //     Ok(path) => path,
             //     Err(e) => {
@@ -256,9 +1061,9 @@ impl FileSystemScanner {
             let nt_path = match NtPathResolver::dos_to_nt_path(&display_path, is_folder) {
                 Ok(path) => path,
                 Err(e) => {
+                    // Left unresolved: policies on this node are refused until a rescan resolves it
                     println!("⚠️ Failed to convert DOS to NT path: {}", e);
-                    // Fallback - use display path as NT path (kernel won't match, but won't crash)
-                    display_path.clone()
+                    String::new()
                 }
             };
             
@@ -277,16 +1082,17 @@ impl FileSystemScanner {
         
         // Check if accessible
         let is_accessible = metadata.permissions().readonly() || 
-            std::fs::metadata(&path).is_ok();
+            std::fs::metadata(path).is_ok();
         
-        // Get attributes (Windows)
-        #[cfg(windows)]
-        let attributes = metadata.file_attributes();
-        #[cfg(not(windows))]
-        let attributes = 0;
+        // Content type from the header (unreadable files stay unidentified)
+        let content_type = if config.identify_types && entry_type == EntryType::File {
+            file_type::identify(path).ok()
+        } else {
+            None
+        };
         
-        Ok(FileSystemNode {
-            id: self.index.get_next_id(),
+        Ok(Some(FileSystemNode {
+            id: self.index.id_for_path(&display_path),
             name,
             entry_type,
             parent_id: Some(parent_id),
@@ -299,7 +1105,32 @@ impl FileSystemScanner {
             attributes,
             is_expanded: false,
             is_accessible,
-        })
+            link_target,
+            classification: None,
+            content_type,
+            drive_kind: None,
+            device: None,
+        }))
+    }
+    
+    /// Identify a file's content type from its header now and record it
+    pub fn identify_content_type(&self, node_id: u64) -> Result<ContentType, String> {
+        self.ensure_node_loaded(node_id)?;
+        let node = self.index.get_node(node_id)
+            .ok_or_else(|| format!("Node {} not found", node_id))?;
+        if node.entry_type != EntryType::File {
+            return Err(format!("Node '{}' is not a file", node.name));
+        }
+        
+        let content_type = file_type::identify(Path::new(&node.display_path))
+            .map_err(|e| format!("Failed to read '{}': {}", node.name, e))?;
+        self.index.set_content_type(node_id, content_type);
+        Ok(content_type)
+    }
+    
+    /// Get the index this scanner fills
+    pub fn index(&self) -> &Arc<FilesystemIndex> {
+        &self.index
     }
     
     /// Get scanner configuration
@@ -311,4 +1142,150 @@ impl FileSystemScanner {
     pub fn set_config(&mut self, config: ScanConfig) {
         self.config = config;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        std::env::temp_dir().join(format!("dlp_{}_{}_{}", name, std::process::id(), nanos))
+    }
+
+    fn node(id: u64, parent_id: u64, name: &str, display_path: String, entry_type: EntryType, modified_time: u64) -> FileSystemNode {
+        FileSystemNode {
+            id,
+            name: name.to_string(),
+            entry_type,
+            parent_id: Some(parent_id),
+            children_ids: Vec::new(),
+            nt_path: String::new(),
+            display_path,
+            size: None,
+            modified_time,
+            created_time: 0,
+            attributes: 0,
+            is_expanded: false,
+            is_accessible: true,
+            link_target: None,
+            classification: None,
+            content_type: None,
+            drive_kind: None,
+            device: None,
+        }
+    }
+
+    /// Snapshot a folder whose only loaded child is "old.txt", read at `modified_time`
+    fn snapshot_folder(index: &FilesystemIndex, dir: &Path, modified_time: u64) -> u64 {
+        let display_path = dir.to_string_lossy().into_owned();
+        let folder = index.id_for_path(&display_path);
+        index.add_node(node(folder, 1, "folder", display_path.clone(), EntryType::Directory, modified_time));
+        let old_path = format!("{}\\old.txt", display_path);
+        index.add_node(node(index.id_for_path(&old_path), folder, "old.txt", old_path, EntryType::File, 0));
+        index.mark_expanded(folder);
+        folder
+    }
+
+    fn child_names(index: &FilesystemIndex, node_id: u64) -> Vec<String> {
+        index.get_children(node_id).into_iter().map(|child| child.name).collect()
+    }
+
+    #[test]
+    fn snapshot_directories_are_reread_when_their_mtime_changed() {
+        let changed = temp_path("scanner_changed");
+        let unchanged = temp_path("scanner_unchanged");
+        for dir in [&changed, &unchanged] {
+            fs::create_dir_all(dir).unwrap();
+            fs::write(dir.join("new.txt"), b"new").unwrap();
+        }
+        let snapshot = temp_path("scanner_snapshot.json").to_string_lossy().into_owned();
+
+        let index = FilesystemIndex::new();
+        let changed_id = snapshot_folder(&index, &changed, 1);
+        let unchanged_mtime = FileSystemScanner::directory_mtime(&unchanged).unwrap();
+        let unchanged_id = snapshot_folder(&index, &unchanged, unchanged_mtime);
+        index.save_snapshot(&snapshot).unwrap();
+
+        let restored = Arc::new(FilesystemIndex::new());
+        restored.load_snapshot(&snapshot).unwrap();
+        let scanner = FileSystemScanner::new(restored.clone(), Arc::new(PathResolver::new(restored.clone())));
+
+        // The directory changed since the snapshot: its children are read again
+        assert_eq!(scanner.expand_directory(changed_id).unwrap(), 1);
+        assert_eq!(child_names(&restored, changed_id), vec!["new.txt"]);
+
+        // Same mtime: the snapshot children are trusted
+        assert_eq!(scanner.expand_directory(unchanged_id).unwrap(), 1);
+        assert_eq!(child_names(&restored, unchanged_id), vec!["old.txt"]);
+
+        let _ = fs::remove_file(&snapshot);
+        let _ = fs::remove_dir_all(&changed);
+        let _ = fs::remove_dir_all(&unchanged);
+    }
+
+    #[test]
+    fn hidden_and_system_entries_follow_the_config() {
+        let everything = ScanConfig { skip_hidden: false, skip_system: false, ..ScanConfig::default() };
+        let no_hidden = ScanConfig { skip_hidden: true, skip_system: false, ..ScanConfig::default() };
+        let no_system = ScanConfig { skip_hidden: false, skip_system: true, ..ScanConfig::default() };
+        let neither = ScanConfig::default();
+        let both = FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM;
+
+        for config in [&everything, &no_hidden, &no_system, &neither] {
+            assert!(!FileSystemScanner::is_filtered("report.xlsx", 0, config));
+        }
+        for (attributes, filtered_by) in [
+            (FILE_ATTRIBUTE_HIDDEN, [false, true, false, true]),
+            (FILE_ATTRIBUTE_SYSTEM, [false, false, true, true]),
+            (both, [false, true, true, true]),
+        ] {
+            for (config, filtered) in [&everything, &no_hidden, &no_system, &neither].into_iter().zip(filtered_by) {
+                assert_eq!(FileSystemScanner::is_filtered("pagefile.sys", attributes, config), filtered);
+            }
+        }
+
+        // Dot files count as hidden where there are no attributes
+        assert_eq!(FileSystemScanner::is_filtered(".git", 0, &no_hidden), cfg!(not(windows)));
+        assert!(!FileSystemScanner::is_filtered(".git", 0, &no_system));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_to_an_ancestor_are_not_followed() {
+        let root = temp_path("scanner_links");
+        let outside = temp_path("scanner_links_target");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("a.txt"), b"a").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("sub").join("loop")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("sub").join("out")).unwrap();
+
+        let index = Arc::new(FilesystemIndex::new());
+        let path = |path: PathBuf| path.to_string_lossy().into_owned();
+        let root_id = index.id_for_path(&path(root.clone()));
+        index.add_node(node(root_id, 1, "root", path(root.clone()), EntryType::Directory, 0));
+        let sub_id = index.id_for_path(&path(root.join("sub")));
+        index.add_node(node(sub_id, root_id, "sub", path(root.join("sub")), EntryType::Directory, 0));
+        let loop_id = index.id_for_path(&path(root.join("sub").join("loop")));
+        index.add_node(node(loop_id, sub_id, "loop", path(root.join("sub").join("loop")), EntryType::Symlink, 0));
+        let out_id = index.id_for_path(&path(root.join("sub").join("out")));
+        index.add_node(node(out_id, sub_id, "out", path(root.join("sub").join("out")), EntryType::Symlink, 0));
+        let scanner = FileSystemScanner::new(index.clone(), Arc::new(PathResolver::new(index.clone())));
+
+        assert!(scanner.check_link_loop(&index.get_node(loop_id).unwrap()).unwrap_err().contains("own ancestors"));
+        assert!(scanner.check_link_loop(&index.get_node(out_id).unwrap()).is_ok());
+
+        // Expanding goes through the same check, and links need follow_symlinks at all
+        let follow = ScanConfig { follow_symlinks: true, ..ScanConfig::default() };
+        assert!(scanner.expand_directory(out_id).unwrap_err().contains("follow_symlinks"));
+        assert!(scanner.expand_directory_with(loop_id, follow).is_err());
+        assert!(!index.is_expanded(loop_id));
+        assert_eq!(scanner.expand_directory_with(out_id, follow).unwrap(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_dir_all(&outside);
+    }
+}
@@ -2,26 +2,110 @@
 //! Agent Filesystem Index - ID-based tree model with lazy loading
 
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::ContentClassification;
+use crate::file_type::ContentType;
+use crate::index_search::SearchMatcher;
+use crate::nt_path_trie::NtPathTrie;
+use crate::volume_map::DriveDevice;
+
+/// Environment variable overriding the node identity file location
+pub const NODE_IDENTITY_FILE_ENV: &str = "AGENT_NODE_ID_MAP";
+
+/// Default node identity file
+const DEFAULT_NODE_IDENTITY_FILE: &str = "dlp_node_ids.json";
+
+/// Current node identity file format version
+const NODE_IDENTITY_FORMAT_VERSION: u32 = 1;
+
+/// Journal lines always allowed before the identity map is rewritten
+const IDENTITY_JOURNAL_MIN_COMPACT: usize = 4096;
+
+/// Environment variable overriding the index snapshot location
+pub const INDEX_SNAPSHOT_FILE_ENV: &str = "AGENT_INDEX_SNAPSHOT";
+
+/// Default index snapshot file
+const DEFAULT_INDEX_SNAPSHOT_FILE: &str = "dlp_index_snapshot.json";
+
+/// Current index snapshot format version
+const INDEX_SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Environment variable overriding the index memory ceiling (MiB, 0 = unlimited)
+pub const INDEX_MEMORY_CEILING_ENV: &str = "AGENT_INDEX_MEMORY_MB";
+
+/// Default index memory ceiling (MiB)
+const DEFAULT_INDEX_MEMORY_CEILING_MB: usize = 512;
+
+/// Approximate per-entry overhead of a hash map / trie slot
+const MAP_ENTRY_OVERHEAD: usize = 48;
+
+/// On-disk stable identity map (identity key → node ID)
+#[derive(Serialize, Deserialize)]
+struct NodeIdentityFile {
+    format_version: u32,
+    next_id: u64,
+    identities: HashMap<String, u64>,
+}
+
+/// One identity change, appended to "<identity file>.journal" between rewrites
+#[derive(Serialize, Deserialize)]
+struct IdentityJournalEntry {
+    key: String,
+    id: Option<u64>,    // None = the key no longer names a node (moved away)
+}
 
 /// Filesystem entry types
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EntryType {
     VirtualRoot,  // "This PC"
     Drive,
     Directory,
     File,
+    Symlink,      // Symbolic link (file or directory)
+    Junction,     // NTFS mount point / directory junction
+}
+
+impl EntryType {
+    /// Symlinks and junctions: policies on them would not reach the target
+    pub fn is_link(&self) -> bool {
+        matches!(self, EntryType::Symlink | EntryType::Junction)
+    }
+}
+
+/// What kind of root a Drive node is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriveKind {
+    Local,          // Volume with a drive letter ("C:\")
+    Network,        // Mapped network drive ("Z:\") or UNC share ("\\server\share\")
+    MountedFolder,  // Volume mounted into a folder ("\\?\Volume{GUID}\", named after the folder)
+    Volume,         // Volume with no letter and no mount folder ("\\?\Volume{GUID}\")
+}
+
+impl DriveKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DriveKind::Local => "local",
+            DriveKind::Network => "network",
+            DriveKind::MountedFolder => "mounted_folder",
+            DriveKind::Volume => "volume",
+        }
+    }
 }
 
 /// Filesystem node with lazy loading support
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileSystemNode {
     pub id: u64,
     pub name: String,
     pub entry_type: EntryType,
     pub parent_id: Option<u64>,
-    pub children_ids: Vec<u64>,
+    #[serde(skip)]
+    pub children_ids: Vec<u64>,   // Rebuilt from parent_id when loading a snapshot
     pub nt_path: String,          // INTERNAL ONLY
     pub display_path: String,     // For debugging/admin display
     pub size: Option<u64>,
@@ -30,6 +114,95 @@ pub struct FileSystemNode {
     pub attributes: u32,
     pub is_expanded: bool,        // Has children been loaded?
     pub is_accessible: bool,      // Can we access this path?
+    #[serde(default)]
+    pub link_target: Option<String>, // Reparse target of links (INTERNAL ONLY)
+    #[serde(default)]
+    pub classification: Option<ContentClassification>, // Content discovery result (files)
+    #[serde(default)]
+    pub content_type: Option<ContentType>, // From magic bytes, None until identified (files)
+    #[serde(default)]
+    pub drive_kind: Option<DriveKind>,     // Drive nodes only
+    #[serde(default)]
+    pub device: Option<DriveDevice>,       // Drive nodes only: class, label, serial
+}
+
+/// Sort order for child listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChildSort {
+    #[default]
+    Name,
+    Size,
+    Mtime,
+    Type,
+}
+
+/// Sort key of one child: (primary, lowercase name, id) keeps the order total
+type ChildKey = (u64, String, u64);
+
+/// Children of one parent in ascending key order (shared by concurrent pages)
+type SortedChildren = Arc<Vec<ChildKey>>;
+
+/// Where the next page of children starts: the sort key of the last child returned
+/// Paging resumes after the key itself, so it survives that child being
+/// deleted or renamed between pages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildCursor {
+    pub primary: u64,
+    pub name: String,   // Lowercase
+    pub id: u64,
+}
+
+impl ChildCursor {
+    /// Parse a token from `token()` ("primary:id:name")
+    pub fn parse(token: &str) -> Option<Self> {
+        let mut parts = token.splitn(3, ':');
+        let primary = parts.next()?.parse().ok()?;
+        let id = parts.next()?.parse().ok()?;
+        let name = parts.next()?.to_string();
+        Some(ChildCursor { primary, name, id })
+    }
+    
+    /// Opaque token handed to clients
+    pub fn token(&self) -> String {
+        format!("{}:{}:{}", self.primary, self.id, self.name)
+    }
+    
+    fn from_key((primary, name, id): &ChildKey) -> Self {
+        ChildCursor { primary: *primary, name: name.clone(), id: *id }
+    }
+    
+    fn key(&self) -> ChildKey {
+        (self.primary, self.name.clone(), self.id)
+    }
+}
+
+/// One page of sorted children
+#[derive(Debug, Clone)]
+pub struct ChildrenPage {
+    pub nodes: Vec<FileSystemNode>,
+    pub total: usize,
+    pub offset: usize,
+    pub has_more: bool,
+    pub next_cursor: Option<ChildCursor>,   // Set when has_more
+}
+
+/// One page of ranked search results
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    pub nodes: Vec<FileSystemNode>,
+    pub total: usize,          // Matches across all pages
+    pub nodes_searched: usize,
+    pub offset: usize,
+    pub has_more: bool,
+}
+
+/// On-disk index snapshot (children and path maps are rebuilt on load)
+#[derive(Serialize, Deserialize)]
+struct IndexSnapshot {
+    format_version: u32,
+    saved_at: u64,
+    nodes: Vec<FileSystemNode>,
 }
 
 /// Main filesystem index with lazy loading
@@ -37,6 +210,23 @@ pub struct FilesystemIndex {
     nodes: RwLock<HashMap<u64, FileSystemNode>>,
     path_to_id: RwLock<HashMap<String, u64>>,  // Display path → ID cache
     id_to_path: RwLock<HashMap<u64, String>>,  // ID → Display path cache
+    nt_paths: RwLock<NtPathTrie>,              // NT path → node ID (kernel event attribution)
+    stable_ids: RwLock<HashMap<String, u64>>,  // Identity key (volume + relative path) → ID ever handed out
+    id_to_identity: RwLock<HashMap<u64, String>>, // Reverse of stable_ids
+    volumes: RwLock<HashMap<String, String>>,  // "C:" → volume GUID path
+    identity_file: RwLock<Option<String>>,     // Where stable IDs persist (None = memory only)
+    identity_journal: Mutex<Vec<IdentityJournalEntry>>, // Changes not yet appended to the journal
+    journal_lines: AtomicUsize,                // Lines in the journal file since the last rewrite
+    snapshot_dirty: AtomicBool,                // Tree changed since last snapshot
+    unverified: RwLock<HashSet<u64>>,          // Expanded dirs from a snapshot, mtime not re-checked yet
+    pinned: RwLock<HashMap<u64, usize>>,       // Node ID → active policy count (survives collapse)
+    opened: RwLock<HashSet<u64>>,              // Expanded by an admin in the explorer (never evicted)
+    memory_bytes: AtomicUsize,                 // Approximate heap use of nodes + path maps
+    identity_bytes: AtomicUsize,               // Approximate heap use of the stable identity maps
+    memory_ceiling: AtomicUsize,               // Bytes of node memory, 0 = unlimited
+    access_clock: AtomicU64,
+    last_access: RwLock<HashMap<u64, u64>>,    // Expanded node ID → access tick (LRU eviction)
+    child_order: RwLock<HashMap<(u64, ChildSort), SortedChildren>>, // Dropped when a parent's children change
     next_id: RwLock<u64>,
 }
 
@@ -47,6 +237,23 @@ impl FilesystemIndex {
             nodes: RwLock::new(HashMap::new()),
             path_to_id: RwLock::new(HashMap::new()),
             id_to_path: RwLock::new(HashMap::new()),
+            stable_ids: RwLock::new(HashMap::new()),
+            id_to_identity: RwLock::new(HashMap::new()),
+            volumes: RwLock::new(HashMap::new()),
+            identity_file: RwLock::new(None),
+            identity_journal: Mutex::new(Vec::new()),
+            journal_lines: AtomicUsize::new(0),
+            snapshot_dirty: AtomicBool::new(false),
+            unverified: RwLock::new(HashSet::new()),
+            nt_paths: RwLock::new(NtPathTrie::new()),
+            pinned: RwLock::new(HashMap::new()),
+            opened: RwLock::new(HashSet::new()),
+            memory_bytes: AtomicUsize::new(0),
+            identity_bytes: AtomicUsize::new(0),
+            memory_ceiling: AtomicUsize::new(0),
+            access_clock: AtomicU64::new(0),
+            last_access: RwLock::new(HashMap::new()),
+            child_order: RwLock::new(HashMap::new()),
             next_id: RwLock::new(2),  // Start from 2 (1 is root)
         };
         
@@ -76,44 +283,344 @@ impl FilesystemIndex {
             attributes: 0,
             is_expanded: false,
             is_accessible: true,
+            link_target: None,
+            classification: None,
+            content_type: None,
+            drive_kind: None,
+            device: None,
         };
         
         let mut nodes = self.nodes.write();
+        self.memory_bytes.fetch_add(Self::node_footprint(&root_node), Ordering::SeqCst);
         nodes.insert(1, root_node);
     }
     
     /// Add a new node with proper parent-child linking
     pub fn add_node(&self, node: FileSystemNode) -> u64 {
         let id = node.id;
-        let display_path = node.display_path.clone();
+        self.add_nodes(vec![node]);
+        id
+    }
+    
+    /// Add a batch of nodes under one lock (large directories are loaded in chunks)
+    pub fn add_nodes(&self, batch: Vec<FileSystemNode>) -> usize {
+        let count = batch.len();
+        if count == 0 {
+            return 0;
+        }
         
-        // Update parent's children list
-        if let Some(parent_id) = node.parent_id {
-            let mut nodes = self.nodes.write();
-            if let Some(parent) = nodes.get_mut(&parent_id) {
-                if !parent.children_ids.contains(&id) {
+        let mut nodes = self.nodes.write();
+        let mut path_to_id = self.path_to_id.write();
+        let mut id_to_path = self.id_to_path.write();
+        let mut nt_paths = self.nt_paths.write();
+        let mut known_children: HashMap<u64, HashSet<u64>> = HashMap::new();
+        let mut changed_parents = HashSet::new();
+        
+        for mut node in batch {
+            let id = node.id;
+            changed_parents.extend(node.parent_id);
+            
+            // Update parent's children list
+            if let Some(parent) = node.parent_id.and_then(|parent_id| nodes.get_mut(&parent_id)) {
+                let known = known_children.entry(parent.id)
+                    .or_insert_with(|| parent.children_ids.iter().copied().collect());
+                if known.insert(id) {
                     parent.children_ids.push(id);
                 }
             }
+            
+            // Store the node (a node kept across a collapse keeps its loaded children)
+            // An expanded node keeps the mtime its children were read at (snapshot freshness)
+            if let Some(existing) = nodes.get(&id) {
+                node.children_ids = existing.children_ids.clone();
+                node.is_expanded = existing.is_expanded;
+                if existing.is_expanded {
+                    node.modified_time = existing.modified_time;
+                }
+                if existing.nt_path != node.nt_path {
+                    nt_paths.remove(&existing.nt_path, id);
+                }
+                // A re-read of an unchanged file keeps its discovery result and content type
+                if existing.modified_time == node.modified_time && existing.size == node.size {
+                    if node.classification.is_none() {
+                        node.classification = existing.classification.clone();
+                    }
+                    if node.content_type.is_none() {
+                        node.content_type = existing.content_type;
+                    }
+                }
+            }
+            if !node.nt_path.is_empty() {
+                nt_paths.insert(&node.nt_path, id);
+            }
+            
+            // Update path caches
+            path_to_id.insert(node.display_path.clone(), id);
+            id_to_path.insert(id, node.display_path.clone());
+            
+            self.memory_bytes.fetch_add(Self::node_footprint(&node), Ordering::SeqCst);
+            if let Some(existing) = nodes.insert(id, node) {
+                self.memory_bytes.fetch_sub(Self::node_footprint(&existing), Ordering::SeqCst);
+            }
         }
         
-        // Store the node
-        {
-            let mut nodes = self.nodes.write();
-            nodes.insert(id, node);
+        self.invalidate_child_order(&changed_parents);
+        self.snapshot_dirty.store(true, Ordering::SeqCst);
+        count
+    }
+    
+    /// Get the ID for a display path, reusing the ID it had before if seen already
+    /// IDs are keyed by volume GUID + relative path, so they survive restarts,
+    /// re-scans and drive letter changes.
+    pub fn id_for_path(&self, display_path: &str) -> u64 {
+        let key = self.identity_key(display_path);
+        if let Some(&id) = self.stable_ids.read().get(&key) {
+            return id;
         }
         
-        // Update path caches
-        {
-            let mut path_to_id = self.path_to_id.write();
-            path_to_id.insert(display_path.clone(), id);
-            
-            let mut id_to_path = self.id_to_path.write();
-            id_to_path.insert(id, display_path);
+        let mut stable_ids = self.stable_ids.write();
+        if let Some(&id) = stable_ids.get(&key) {
+            return id;
         }
-        
+        let id = self.get_next_id();
+        stable_ids.insert(key.clone(), id);
+        self.id_to_identity.write().insert(id, key.clone());
+        self.identity_bytes.fetch_add(Self::identity_footprint(&key), Ordering::SeqCst);
+        self.identity_journal.lock().push(IdentityJournalEntry { key, id: Some(id) });
         id
     }
+    
+    /// Record the volume GUID behind a drive letter (e.g. "C:")
+    pub fn register_volume(&self, drive_letter: &str, volume_guid: &str) {
+        self.volumes.write().insert(drive_letter.to_uppercase(), volume_guid.to_lowercase());
+    }
+    
+    /// Identity key: "<volume guid>|<relative path>" (drive letter if GUID unknown)
+    fn identity_key(&self, display_path: &str) -> String {
+        let path = display_path.to_lowercase();
+        if path.len() < 2 || path.as_bytes()[1] != b':' {
+            return path;
+        }
+        
+        let drive = path[0..2].to_uppercase();
+        let volume = self.volumes.read().get(&drive).cloned().unwrap_or(drive);
+        format!("{}|{}", volume, &path[2..])
+    }
+    
+    /// Current display path for an ID, even if the node is not loaded right now
+    pub fn display_path_for_id(&self, id: u64) -> Option<String> {
+        if let Some(path) = self.get_display_path(id) {
+            return Some(path);
+        }
+        
+        let key = self.id_to_identity.read().get(&id).cloned()?;
+        let (volume, relative) = key.split_once('|')?;
+        let drive = if volume.len() == 2 {
+            volume.to_string()
+        } else {
+            self.volumes.read().iter()
+                .find(|(_, guid)| guid.as_str() == volume)
+                .map(|(drive, _)| drive.clone())?
+        };
+        Some(format!("{}{}", drive, relative))
+    }
+    
+    /// Default location of the stable identity file
+    pub fn default_identity_file() -> String {
+        std::env::var(NODE_IDENTITY_FILE_ENV).unwrap_or_else(|_| DEFAULT_NODE_IDENTITY_FILE.to_string())
+    }
+    
+    /// Load stable IDs from disk and persist new ones there from now on
+    /// The map file is the last full rewrite; the journal next to it holds the
+    /// changes made since, replayed in order.
+    pub fn load_identities(&self, path: &str) -> Result<usize, String> {
+        *self.identity_file.write() = Some(path.to_string());
+        let journal_path = Self::identity_journal_path(path);
+        
+        let file = match std::fs::read_to_string(path) {
+            Ok(content) => match serde_json::from_str::<NodeIdentityFile>(&content) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    // Never reuse IDs that may already be in admin hands: set the bad
+                    // file aside and continue numbering far above anything issued so far
+                    let backup = format!("{}.corrupt", path);
+                    let _ = std::fs::rename(path, &backup);
+                    let _ = std::fs::rename(&journal_path, Self::identity_journal_path(&backup));
+                    let floor = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs() * 1000;
+                    let mut next_id = self.next_id.write();
+                    *next_id = (*next_id).max(floor);
+                    return Err(format!("Corrupt identity file moved to {}: {}", backup, e));
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("Failed to read {}: {}", path, e)),
+        };
+        
+        let mut stable_ids = self.stable_ids.write();
+        let mut id_to_identity = self.id_to_identity.write();
+        let mut file_next_id = 0;
+        if let Some(file) = file {
+            file_next_id = file.next_id;
+            for (key, id) in file.identities {
+                id_to_identity.insert(id, key.clone());
+                stable_ids.insert(key, id);
+            }
+        }
+        
+        let replayed = Self::replay_identity_journal(&journal_path, &mut stable_ids, &mut id_to_identity)?;
+        self.journal_lines.store(replayed, Ordering::SeqCst);
+        self.identity_bytes.store(stable_ids.keys().map(|key| Self::identity_footprint(key)).sum(), Ordering::SeqCst);
+        
+        let max_id = stable_ids.values().copied().max().unwrap_or(1);
+        let mut next_id = self.next_id.write();
+        *next_id = (*next_id).max(file_next_id).max(max_id + 1);
+        
+        Ok(stable_ids.len())
+    }
+    
+    /// Apply journal lines on top of the loaded map, returns the number of lines
+    fn replay_identity_journal(
+        journal_path: &str,
+        stable_ids: &mut HashMap<String, u64>,
+        id_to_identity: &mut HashMap<u64, String>,
+    ) -> Result<usize, String> {
+        let content = match std::fs::read_to_string(journal_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("Failed to read {}: {}", journal_path, e)),
+        };
+        
+        let mut lines = 0;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            // A crash mid-append leaves at most a torn last line
+            let entry: IdentityJournalEntry = match serde_json::from_str(line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    println!("⚠️  Identity journal {}: stopped at line {}: {}", journal_path, lines + 1, e);
+                    break;
+                }
+            };
+            lines += 1;
+            match entry.id {
+                Some(id) => {
+                    if let Some(displaced) = stable_ids.insert(entry.key.clone(), id) {
+                        if displaced != id {
+                            id_to_identity.remove(&displaced);
+                        }
+                    }
+                    id_to_identity.insert(id, entry.key);
+                }
+                None => {
+                    if let Some(id) = stable_ids.remove(&entry.key) {
+                        if id_to_identity.get(&id) == Some(&entry.key) {
+                            id_to_identity.remove(&id);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(lines)
+    }
+    
+    fn identity_journal_path(path: &str) -> String {
+        format!("{}.journal", path)
+    }
+    
+    /// Append identity changes made since the last save to the journal
+    /// The full map is only rewritten once the journal outgrows it.
+    pub fn save_identities(&self) -> Result<(), String> {
+        let path = match self.identity_file.read().clone() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let pending = std::mem::take(&mut *self.identity_journal.lock());
+        if pending.is_empty() {
+            return Ok(());
+        }
+        
+        let lines = self.journal_lines.load(Ordering::SeqCst) + pending.len();
+        let result = if lines > self.stable_ids.read().len().max(IDENTITY_JOURNAL_MIN_COMPACT) {
+            self.rewrite_identities(&path)
+        } else {
+            self.append_identity_journal(&path, &pending)
+        };
+        
+        if result.is_err() {
+            // Keep the changes for the next save, in order
+            let mut journal = self.identity_journal.lock();
+            let newer = std::mem::replace(&mut *journal, pending);
+            journal.extend(newer);
+        }
+        result
+    }
+    
+    fn append_identity_journal(&self, path: &str, entries: &[IdentityJournalEntry]) -> Result<(), String> {
+        use std::io::Write;
+        
+        let journal_path = Self::identity_journal_path(path);
+        let mut lines = String::new();
+        for entry in entries {
+            let line = serde_json::to_string(entry)
+                .map_err(|e| format!("Failed to serialize identity: {}", e))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&journal_path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {}", journal_path, e))?;
+        self.journal_lines.fetch_add(entries.len(), Ordering::SeqCst);
+        Ok(())
+    }
+    
+    /// Write the whole map and start an empty journal
+    fn rewrite_identities(&self, path: &str) -> Result<(), String> {
+        let file = NodeIdentityFile {
+            format_version: NODE_IDENTITY_FORMAT_VERSION,
+            next_id: *self.next_id.read(),
+            identities: self.stable_ids.read().clone(),
+        };
+        let json = serde_json::to_string(&file)
+            .map_err(|e| format!("Failed to serialize identities: {}", e))?;
+        
+        // Write-then-rename so a crash never leaves a half-written map
+        let tmp_path = format!("{}.tmp", path);
+        std::fs::write(&tmp_path, json)
+            .and_then(|_| std::fs::rename(&tmp_path, path))
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        
+        let journal_path = Self::identity_journal_path(path);
+        match std::fs::remove_file(&journal_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to reset {}: {}", journal_path, e)),
+        }
+        self.journal_lines.store(0, Ordering::SeqCst);
+        Ok(())
+    }
+    
+    /// Pin a node (it has an active policy) so collapse keeps it
+    pub fn pin_node(&self, node_id: u64) {
+        let mut pinned = self.pinned.write();
+        *pinned.entry(node_id).or_insert(0) += 1;
+    }
+    
+    /// Release one pin on a node
+    pub fn unpin_node(&self, node_id: u64) {
+        let mut pinned = self.pinned.write();
+        if let Some(count) = pinned.get_mut(&node_id) {
+            *count -= 1;
+            if *count == 0 {
+                pinned.remove(&node_id);
+            }
+        }
+    }
+
 
     /// Count expanded nodes
     pub fn count_expanded_nodes(&self) -> usize {
@@ -137,6 +644,16 @@ impl FilesystemIndex {
         nodes.get(&id).cloned()
     }
     
+    /// Display path of the drive a node lives on ("D:\\", "\\\\server\\share\\", "\\\\?\\volume{...}\\")
+    pub fn drive_root(&self, node_id: u64) -> Option<String> {
+        let nodes = self.nodes.read();
+        let mut current = nodes.get(&node_id)?;
+        while current.entry_type != EntryType::Drive {
+            current = nodes.get(&current.parent_id?)?;
+        }
+        Some(current.display_path.clone())
+    }
+    
     /// Get node by display path (internal use)
     pub fn get_node_by_path(&self, display_path: &str) -> Option<FileSystemNode> {
         let path_to_id = self.path_to_id.read();
@@ -150,6 +667,99 @@ impl FilesystemIndex {
         path_to_id.get(display_path).copied()
     }
     
+    /// One sorted page of a node's children
+    /// Resumes after `start_after` (a cursor) when given, else at `offset`.
+    pub fn children_page(
+        &self,
+        parent_id: u64,
+        sort: ChildSort,
+        descending: bool,
+        start_after: Option<&ChildCursor>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<ChildrenPage, String> {
+        let nodes = self.nodes.read();
+        let parent = nodes.get(&parent_id)
+            .ok_or_else(|| format!("Parent node {} not found", parent_id))?;
+        
+        // Keys are ascending; a descending listing walks them from the end
+        let order = self.sorted_children(&nodes, parent, sort);
+        let total = order.len();
+        let start = match start_after {
+            Some(cursor) => {
+                let cursor = cursor.key();
+                if descending {
+                    total - order.partition_point(|key| *key < cursor)
+                } else {
+                    order.partition_point(|key| *key <= cursor)
+                }
+            }
+            None => offset.min(total),
+        };
+        let end = (start + limit).min(total);
+        let key_at = |position: usize| if descending { &order[total - 1 - position] } else { &order[position] };
+        
+        Ok(ChildrenPage {
+            nodes: (start..end)
+                .filter_map(|position| nodes.get(&key_at(position).2).cloned())
+                .collect(),
+            total,
+            offset: start,
+            has_more: end < total,
+            next_cursor: (end < total && end > start).then(|| ChildCursor::from_key(key_at(end - 1))),
+        })
+    }
+    
+    /// Sorted child keys of `parent`, cached per (parent, sort) until its children change
+    /// Callers hold the nodes lock; invalidation happens under its write lock.
+    fn sorted_children(
+        &self,
+        nodes: &HashMap<u64, FileSystemNode>,
+        parent: &FileSystemNode,
+        sort: ChildSort,
+    ) -> SortedChildren {
+        if let Some(order) = self.child_order.read().get(&(parent.id, sort)) {
+            return order.clone();
+        }
+        
+        let mut order: Vec<ChildKey> = parent.children_ids.iter()
+            .filter_map(|id| nodes.get(id))
+            .map(|node| {
+                let primary = match sort {
+                    ChildSort::Name => 0,
+                    ChildSort::Size => node.size.unwrap_or(0),
+                    ChildSort::Mtime => node.modified_time,
+                    ChildSort::Type => Self::type_rank(node.entry_type),
+                };
+                (primary, node.name.to_lowercase(), node.id)
+            })
+            .collect();
+        order.sort_unstable();
+        
+        let order = Arc::new(order);
+        self.child_order.write().insert((parent.id, sort), order.clone());
+        order
+    }
+    
+    /// Drop cached child orders of these parents (children added, removed or re-keyed)
+    fn invalidate_child_order(&self, parent_ids: &HashSet<u64>) {
+        if !parent_ids.is_empty() {
+            self.child_order.write().retain(|(parent_id, _), _| !parent_ids.contains(parent_id));
+        }
+    }
+    
+    /// Folders before links before files when sorting by type
+    fn type_rank(entry_type: EntryType) -> u64 {
+        match entry_type {
+            EntryType::VirtualRoot => 0,
+            EntryType::Drive => 1,
+            EntryType::Directory => 2,
+            EntryType::Junction => 3,
+            EntryType::Symlink => 4,
+            EntryType::File => 5,
+        }
+    }
+    
     /// Get children of a node (already loaded children only)
     pub fn get_children(&self, parent_id: u64) -> Vec<FileSystemNode> {
         let nodes = self.nodes.read();
@@ -177,21 +787,412 @@ impl FilesystemIndex {
         if let Some(node) = nodes.get_mut(&node_id) {
             node.is_expanded = true;
         }
+        drop(nodes);
+        self.touch_node(node_id);
+        self.snapshot_dirty.store(true, Ordering::SeqCst);
+    }
+    
+    /// Record that an admin opened a node in the explorer
+    /// Opened nodes and their ancestors are never evicted by the memory ceiling.
+    pub fn mark_opened(&self, node_id: u64) {
+        self.opened.write().insert(node_id);
+        self.touch_node(node_id);
+    }
+    
+    /// Record an access to an expanded node (LRU order for eviction)
+    pub fn touch_node(&self, node_id: u64) {
+        let tick = self.access_clock.fetch_add(1, Ordering::SeqCst);
+        self.last_access.write().insert(node_id, tick);
     }
     
-    /// Mark node as not expanded (children removed)
-    pub fn mark_collapsed(&self, node_id: u64) {
+    /// Collapse a node: evict all descendants from the index
+    /// With `keep_pinned`, pinned nodes (and the ancestors leading to them)
+    /// stay so policies keep resolving. Returns the number of evicted nodes.
+    pub fn collapse_subtree(&self, node_id: u64, keep_pinned: bool) -> usize {
         let mut nodes = self.nodes.write();
+        let mut path_to_id = self.path_to_id.write();
+        let mut id_to_path = self.id_to_path.write();
+        let pinned = self.pinned.read();
+        
+        let children = match nodes.get(&node_id) {
+            Some(node) => node.children_ids.clone(),
+            None => return 0,
+        };
+        
+        let mut evicted = Vec::new();
+        let kept: Vec<u64> = children.into_iter()
+            .filter(|&child_id| Self::evict_unpinned(&mut nodes, child_id, keep_pinned, &pinned, &mut evicted))
+            .collect();
+        
         if let Some(node) = nodes.get_mut(&node_id) {
             node.is_expanded = false;
-            node.children_ids.clear();
+            node.children_ids = kept;
+        }
+        
+        let mut unverified = self.unverified.write();
+        let mut nt_paths = self.nt_paths.write();
+        let mut last_access = self.last_access.write();
+        let mut opened = self.opened.write();
+        unverified.remove(&node_id);
+        last_access.remove(&node_id);
+        opened.remove(&node_id);
+        let mut changed_parents: HashSet<u64> = evicted.iter().copied().collect();
+        changed_parents.insert(node_id);
+        for id in &evicted {
+            if let Some(node) = nodes.remove(id) {
+                if path_to_id.get(&node.display_path) == Some(id) {
+                    path_to_id.remove(&node.display_path);
+                }
+                nt_paths.remove(&node.nt_path, *id);
+                self.memory_bytes.fetch_sub(Self::node_footprint(&node), Ordering::SeqCst);
+                changed_parents.extend(node.parent_id);
+            }
+            id_to_path.remove(id);
+            unverified.remove(id);
+            last_access.remove(id);
+            opened.remove(id);
         }
+        
+        self.invalidate_child_order(&changed_parents);
+        self.snapshot_dirty.store(true, Ordering::SeqCst);
+        evicted.len()
     }
     
-    /// Add a drive to the index (lazy - no scanning yet)
-    pub fn add_drive(&self, drive_letter: &str, display_name: &str, nt_path: &str) -> u64 {
-        let id = self.get_next_id();
-        let display_path = format!("{}\\", drive_letter);
+    /// Remove a node and its whole subtree (pinned or not)
+    pub fn remove_subtree(&self, node_id: u64) -> usize {
+        if node_id == 1 {
+            return 0;
+        }
+        let evicted = self.collapse_subtree(node_id, false);
+        
+        let mut nodes = self.nodes.write();
+        let node = match nodes.remove(&node_id) {
+            Some(node) => node,
+            None => return evicted,
+        };
+        if let Some(parent) = node.parent_id.and_then(|parent_id| nodes.get_mut(&parent_id)) {
+            parent.children_ids.retain(|&id| id != node_id);
+        }
+        self.invalidate_child_order(&node.parent_id.into_iter().chain([node_id]).collect());
+        
+        let mut path_to_id = self.path_to_id.write();
+        if path_to_id.get(&node.display_path) == Some(&node_id) {
+            path_to_id.remove(&node.display_path);
+        }
+        self.id_to_path.write().remove(&node_id);
+        self.unverified.write().remove(&node_id);
+        self.nt_paths.write().remove(&node.nt_path, node_id);
+        self.memory_bytes.fetch_sub(Self::node_footprint(&node), Ordering::SeqCst);
+        
+        evicted + 1
+    }
+    
+    /// Move or rename a loaded node, keeping its ID and its descendants' IDs
+    /// Display paths, NT paths and identity keys of the subtree follow the node,
+    /// including identities of descendants that are not loaded right now.
+    pub fn rename_node(&self, node_id: u64, new_parent_id: u64, new_name: &str) -> Result<(), String> {
+        let mut nodes = self.nodes.write();
+        let mut path_to_id = self.path_to_id.write();
+        let mut id_to_path = self.id_to_path.write();
+        let mut nt_paths = self.nt_paths.write();
+        
+        let node = nodes.get(&node_id)
+            .ok_or_else(|| format!("Node {} not found", node_id))?;
+        if matches!(node.entry_type, EntryType::VirtualRoot | EntryType::Drive) {
+            return Err(format!("Node {} cannot be renamed", node_id));
+        }
+        let new_parent = nodes.get(&new_parent_id)
+            .ok_or_else(|| format!("Parent node {} not found", new_parent_id))?;
+        
+        let old_display = node.display_path.clone();
+        let old_nt = node.nt_path.clone();
+        let mut new_display = if new_parent.display_path.ends_with('\\') {
+            format!("{}{}", new_parent.display_path, new_name)
+        } else {
+            format!("{}\\{}", new_parent.display_path, new_name)
+        };
+        if old_display.ends_with('\\') {
+            new_display.push('\\');
+        }
+        let mut new_nt = format!("{}\\{}", new_parent.nt_path.trim_end_matches('\\'), new_name);
+        if old_nt.ends_with('\\') {
+            new_nt.push('\\');
+        }
+        
+        // Re-link under the new parent
+        let old_parent_id = node.parent_id;
+        if let Some(old_parent) = old_parent_id.and_then(|parent_id| nodes.get_mut(&parent_id)) {
+            old_parent.children_ids.retain(|&id| id != node_id);
+        }
+        if let Some(parent) = nodes.get_mut(&new_parent_id) {
+            if !parent.children_ids.contains(&node_id) {
+                parent.children_ids.push(node_id);
+            }
+        }
+        if let Some(node) = nodes.get_mut(&node_id) {
+            node.name = new_name.to_string();
+            node.parent_id = Some(new_parent_id);
+        }
+        self.invalidate_child_order(&old_parent_id.into_iter().chain([new_parent_id]).collect());
+        
+        // Rewrite paths of the node and every loaded descendant
+        let mut pending = vec![node_id];
+        while let Some(id) = pending.pop() {
+            let node = match nodes.get_mut(&id) {
+                Some(node) => node,
+                None => continue,
+            };
+            let before = Self::node_footprint(node);
+            
+            if path_to_id.get(&node.display_path) == Some(&id) {
+                path_to_id.remove(&node.display_path);
+            }
+            nt_paths.remove(&node.nt_path, id);
+            
+            node.display_path = match node.display_path.strip_prefix(old_display.as_str()) {
+                Some(rest) => format!("{}{}", new_display, rest),
+                None => new_display.clone(),
+            };
+            if let Some(rest) = node.nt_path.strip_prefix(old_nt.as_str()) {
+                node.nt_path = format!("{}{}", new_nt, rest);
+            }
+            
+            path_to_id.insert(node.display_path.clone(), id);
+            id_to_path.insert(id, node.display_path.clone());
+            if !node.nt_path.is_empty() {
+                nt_paths.insert(&node.nt_path, id);
+            }
+            
+            let after = Self::node_footprint(node);
+            self.memory_bytes.fetch_add(after, Ordering::SeqCst);
+            self.memory_bytes.fetch_sub(before, Ordering::SeqCst);
+            pending.extend(node.children_ids.iter().copied());
+        }
+        drop(nt_paths);
+        drop(id_to_path);
+        drop(path_to_id);
+        drop(nodes);
+        
+        self.rekey_identities(&old_display, &new_display);
+        self.snapshot_dirty.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+    
+    /// Move identity keys at and below `old_display` to `new_display`
+    fn rekey_identities(&self, old_display: &str, new_display: &str) {
+        let old_key = self.identity_key(old_display);
+        let new_key = self.identity_key(new_display);
+        let subtree = old_key.ends_with('\\');
+        
+        let mut stable_ids = self.stable_ids.write();
+        let mut id_to_identity = self.id_to_identity.write();
+        let mut journal = self.identity_journal.lock();
+        let moved: Vec<(String, u64)> = stable_ids.iter()
+            .filter(|(key, _)| key.as_str() == old_key || (subtree && key.starts_with(old_key.as_str())))
+            .map(|(key, &id)| (key.clone(), id))
+            .collect();
+        
+        for (key, id) in moved {
+            stable_ids.remove(&key);
+            self.identity_bytes.fetch_sub(Self::identity_footprint(&key), Ordering::SeqCst);
+            let rekeyed = format!("{}{}", new_key, &key[old_key.len()..]);
+            // A path that existed before under the new name gives up its old ID
+            match stable_ids.insert(rekeyed.clone(), id) {
+                Some(displaced) if displaced != id => {
+                    id_to_identity.remove(&displaced);
+                }
+                Some(_) => {}
+                None => {
+                    self.identity_bytes.fetch_add(Self::identity_footprint(&rekeyed), Ordering::SeqCst);
+                }
+            }
+            id_to_identity.insert(id, rekeyed.clone());
+            journal.push(IdentityJournalEntry { key, id: None });
+            journal.push(IdentityJournalEntry { key: rekeyed, id: Some(id) });
+        }
+    }
+    
+    /// Loaded child of `parent_id` with this name (names compare case-insensitively)
+    pub fn child_by_name(&self, parent_id: u64, name: &str) -> Option<u64> {
+        let nodes = self.nodes.read();
+        let parent = nodes.get(&parent_id)?;
+        let name_lower = name.to_lowercase();
+        parent.children_ids.iter()
+            .copied()
+            .find(|id| nodes.get(id).is_some_and(|child| child.name.to_lowercase() == name_lower))
+    }
+    
+    /// Expanded folders with their display paths (what a watcher should monitor)
+    pub fn expanded_directories(&self) -> Vec<(u64, String)> {
+        let nodes = self.nodes.read();
+        nodes.values()
+            .filter(|node| node.is_expanded && node.entry_type != EntryType::VirtualRoot)
+            .map(|node| (node.id, node.display_path.clone()))
+            .collect()
+    }
+    
+    /// Directories holding protected nodes (ID → display path), expanded or not
+    /// Watching them shows a protected node leaving, even into a collapsed folder.
+    pub fn protected_parent_directories(&self) -> Vec<(u64, String)> {
+        let nodes = self.nodes.read();
+        let pinned = self.pinned.read();
+        let mut parents: HashMap<u64, String> = HashMap::new();
+        for node_id in pinned.keys() {
+            let parent = nodes.get(node_id)
+                .and_then(|node| node.parent_id)
+                .and_then(|parent_id| nodes.get(&parent_id));
+            if let Some(parent) = parent.filter(|parent| parent.entry_type != EntryType::VirtualRoot) {
+                parents.insert(parent.id, parent.display_path.clone());
+            }
+        }
+        parents.into_iter().collect()
+    }
+    
+    /// Record the directory mtime observed when its children were read
+    pub fn set_modified_time(&self, node_id: u64, modified_time: u64) {
+        let mut nodes = self.nodes.write();
+        if let Some(node) = nodes.get_mut(&node_id) {
+            node.modified_time = modified_time;
+            self.invalidate_child_order(&node.parent_id.into_iter().collect());
+        }
+    }
+    
+    /// Check (once) whether an expanded node came from a snapshot and needs its mtime verified
+    pub fn take_unverified(&self, node_id: u64) -> bool {
+        self.unverified.write().remove(&node_id)
+    }
+    
+    /// Default location of the index snapshot
+    pub fn default_snapshot_file() -> String {
+        std::env::var(INDEX_SNAPSHOT_FILE_ENV).unwrap_or_else(|_| DEFAULT_INDEX_SNAPSHOT_FILE.to_string())
+    }
+    
+    /// Save nodes + expansion state (skipped if nothing changed since the last save)
+    pub fn save_snapshot(&self, path: &str) -> Result<usize, String> {
+        if !self.snapshot_dirty.swap(false, Ordering::SeqCst) {
+            return Ok(0);
+        }
+        
+        let snapshot = IndexSnapshot {
+            format_version: INDEX_SNAPSHOT_FORMAT_VERSION,
+            saved_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            nodes: self.nodes.read().values()
+                .filter(|node| node.entry_type != EntryType::VirtualRoot)
+                .cloned()
+                .collect(),
+        };
+        let count = snapshot.nodes.len();
+        let json = serde_json::to_string(&snapshot)
+            .map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+        
+        let tmp_path = format!("{}.tmp", path);
+        std::fs::write(&tmp_path, json)
+            .and_then(|_| std::fs::rename(&tmp_path, path))
+            .map_err(|e| {
+                self.snapshot_dirty.store(true, Ordering::SeqCst);
+                format!("Failed to write {}: {}", path, e)
+            })?;
+        Ok(count)
+    }
+    
+    /// Load a snapshot into an empty index (root only)
+    /// Expanded directories are marked unverified: the scanner compares their
+    /// mtime on next expand and re-reads them if they changed.
+    pub fn load_snapshot(&self, path: &str) -> Result<usize, String> {
+        if self.node_count() > 1 {
+            return Err("Index already populated, snapshot not loaded".to_string());
+        }
+        
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("Failed to read {}: {}", path, e)),
+        };
+        let snapshot: IndexSnapshot = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid snapshot {}: {}", path, e))?;
+        if snapshot.format_version != INDEX_SNAPSHOT_FORMAT_VERSION {
+            return Err(format!("Unsupported snapshot version {}", snapshot.format_version));
+        }
+        
+        let mut snapshot_nodes = snapshot.nodes;
+        snapshot_nodes.sort_by_key(|node| node.id);
+        let max_id = snapshot_nodes.iter().map(|node| node.id).max().unwrap_or(1);
+        
+        {
+            let mut nodes = self.nodes.write();
+            let mut path_to_id = self.path_to_id.write();
+            let mut id_to_path = self.id_to_path.write();
+            let mut unverified = self.unverified.write();
+            let mut nt_paths = self.nt_paths.write();
+            let mut last_access = self.last_access.write();
+            
+            for node in &snapshot_nodes {
+                path_to_id.insert(node.display_path.clone(), node.id);
+                id_to_path.insert(node.id, node.display_path.clone());
+                if !node.nt_path.is_empty() {
+                    nt_paths.insert(&node.nt_path, node.id);
+                }
+                if node.is_expanded {
+                    unverified.insert(node.id);
+                    last_access.insert(node.id, self.access_clock.fetch_add(1, Ordering::SeqCst));
+                }
+            }
+            for node in &snapshot_nodes {
+                self.memory_bytes.fetch_add(Self::node_footprint(node), Ordering::SeqCst);
+                nodes.insert(node.id, node.clone());
+            }
+            for node in &snapshot_nodes {
+                if let Some(parent) = node.parent_id.and_then(|parent_id| nodes.get_mut(&parent_id)) {
+                    parent.children_ids.push(node.id);
+                }
+            }
+            self.child_order.write().clear();
+        }
+        
+        // Snapshot IDs must stay reserved even if the identity map lost them
+        let mut next_id = self.next_id.write();
+        *next_id = (*next_id).max(max_id + 1);
+        
+        Ok(snapshot_nodes.len())
+    }
+    
+    /// Post-order walk: returns true if `node_id` must stay in the index
+    fn evict_unpinned(
+        nodes: &mut HashMap<u64, FileSystemNode>,
+        node_id: u64,
+        keep_pinned: bool,
+        pinned: &HashMap<u64, usize>,
+        evicted: &mut Vec<u64>,
+    ) -> bool {
+        let children = match nodes.get(&node_id) {
+            Some(node) => node.children_ids.clone(),
+            None => return false,
+        };
+        
+        let kept: Vec<u64> = children.into_iter()
+            .filter(|&child_id| Self::evict_unpinned(nodes, child_id, keep_pinned, pinned, evicted))
+            .collect();
+        
+        if (keep_pinned && pinned.contains_key(&node_id)) || !kept.is_empty() {
+            if let Some(node) = nodes.get_mut(&node_id) {
+                node.is_expanded = false;
+                node.children_ids = kept;
+            }
+            true
+        } else {
+            evicted.push(node_id);
+            false
+        }
+    }
+    
+    /// Add a drive-like root to the index (lazy - no scanning yet)
+    /// Roots: "C:", "\\server\share", "\\?\Volume{GUID}"
+    pub fn add_drive(&self, root: &str, display_name: &str, nt_path: &str, kind: DriveKind, device: Option<DriveDevice>) -> u64 {
+        let display_path = format!("{}\\", root.trim_end_matches('\\'));
+        let id = self.id_for_path(&display_path);
         
         let drive_node = FileSystemNode {
             id,
@@ -207,12 +1208,17 @@ impl FilesystemIndex {
             attributes: 0,
             is_expanded: false,
             is_accessible: true,
+            link_target: None,
+            classification: None,
+            content_type: None,
+            drive_kind: Some(kind),
+            device,
         };
         
         self.add_node(drive_node)
     }
     
-    /// Get all drives
+    /// Get all drives (root display path without trailing backslash → ID)
     pub fn get_drives(&self) -> Vec<(String, u64)> {
         let nodes = self.nodes.read();
         let root = match nodes.get(&1) {
@@ -243,6 +1249,7 @@ impl FilesystemIndex {
     }
     
     /// Clear all nodes except root
+    /// Stable IDs and pins are kept, so re-scanned paths get their old IDs back.
     pub fn clear(&self) {
         let mut nodes = self.nodes.write();
         let mut path_to_id = self.path_to_id.write();
@@ -254,12 +1261,20 @@ impl FilesystemIndex {
         path_to_id.clear();
         id_to_path.clear();
         
-        if let Some(root) = root_node {
+        self.memory_bytes.store(0, Ordering::SeqCst);
+        if let Some(mut root) = root_node {
+            root.children_ids.clear();
+            root.is_expanded = false;
+            self.memory_bytes.store(Self::node_footprint(&root), Ordering::SeqCst);
             nodes.insert(1, root);
         }
         
-        // Reset ID counter
-        *self.next_id.write() = 2;
+        self.unverified.write().clear();
+        self.last_access.write().clear();
+        self.opened.write().clear();
+        self.nt_paths.write().clear();
+        self.child_order.write().clear();
+        self.snapshot_dirty.store(true, Ordering::SeqCst);
     }
     
     /// Resolve ID to NT path (INTERNAL - for kernel use only)
@@ -268,6 +1283,200 @@ impl FilesystemIndex {
         nodes.get(&id).map(|node| node.nt_path.clone())
     }
     
+    /// Approximate heap bytes used by nodes and path maps (what the ceiling limits)
+    pub fn memory_usage_bytes(&self) -> usize {
+        self.memory_bytes.load(Ordering::SeqCst)
+    }
+    
+    /// Approximate heap bytes used by stable identities
+    /// Reported on its own: identities outlive evicted nodes (IDs must stay
+    /// stable), so eviction cannot bring this down.
+    pub fn identity_memory_bytes(&self) -> usize {
+        self.identity_bytes.load(Ordering::SeqCst)
+    }
+    
+    /// Current memory ceiling in bytes (0 = unlimited)
+    pub fn memory_ceiling_bytes(&self) -> usize {
+        self.memory_ceiling.load(Ordering::SeqCst)
+    }
+    
+    /// Set the memory ceiling in bytes (0 = unlimited)
+    pub fn set_memory_ceiling(&self, bytes: usize) {
+        self.memory_ceiling.store(bytes, Ordering::SeqCst);
+    }
+    
+    /// Memory ceiling from the environment (bytes)
+    pub fn default_memory_ceiling() -> usize {
+        let megabytes = std::env::var(INDEX_MEMORY_CEILING_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .unwrap_or(DEFAULT_INDEX_MEMORY_CEILING_MB);
+        megabytes * 1024 * 1024
+    }
+    
+    /// Evict least recently used expanded subtrees until under the memory ceiling
+    /// Only subtrees the agent loaded for its own work (crawls, path resolution)
+    /// go: folders an admin has open, their ancestors, `keep` and pinned nodes stay.
+    /// Returns the number of evicted nodes.
+    pub fn enforce_memory_ceiling(&self, keep: u64) -> usize {
+        let ceiling = self.memory_ceiling_bytes();
+        if ceiling == 0 || self.memory_usage_bytes() <= ceiling {
+            return 0;
+        }
+        
+        let mut protected = HashSet::new();
+        {
+            let nodes = self.nodes.read();
+            let opened = self.opened.read();
+            for &start in std::iter::once(&keep).chain(opened.iter()) {
+                let mut current = Some(start);
+                while let Some(id) = current {
+                    if !protected.insert(id) {
+                        break;
+                    }
+                    current = nodes.get(&id).and_then(|node| node.parent_id);
+                }
+            }
+        }
+        
+        let mut candidates: Vec<(u64, u64)> = self.last_access.read().iter()
+            .filter(|(id, _)| !protected.contains(id))
+            .map(|(&id, &tick)| (tick, id))
+            .collect();
+        candidates.sort_unstable();
+        
+        let mut evicted = 0;
+        for (_, id) in candidates {
+            if self.memory_usage_bytes() <= ceiling {
+                break;
+            }
+            // Skip nodes already evicted along with an ancestor
+            if self.is_expanded(id) {
+                evicted += self.collapse_subtree(id, true);
+            }
+        }
+        evicted
+    }
+    
+    /// Approximate heap bytes for one node: the node itself, both path map
+    /// entries, its trie leaf and its slot in the parent's children list
+    fn node_footprint(node: &FileSystemNode) -> usize {
+        std::mem::size_of::<FileSystemNode>()
+            + node.name.len()
+            + node.nt_path.len()
+            + node.display_path.len() * 3
+            + node.nt_path.rsplit('\\').find(|c| !c.is_empty()).map_or(0, str::len)
+            + std::mem::size_of::<u64>()
+            + 4 * MAP_ENTRY_OVERHEAD
+    }
+    
+    /// Approximate heap bytes for one stable identity (key in both maps)
+    fn identity_footprint(key: &str) -> usize {
+        key.len() * 2 + 2 * std::mem::size_of::<u64>() + 2 * MAP_ENTRY_OVERHEAD
+    }
+    
+    /// Nearest node at or above `node_id` that is a symlink or junction
+    pub fn link_ancestor(&self, node_id: u64) -> Option<u64> {
+        let nodes = self.nodes.read();
+        let mut current = nodes.get(&node_id);
+        while let Some(node) = current {
+            if node.entry_type.is_link() {
+                return Some(node.id);
+            }
+            current = node.parent_id.and_then(|parent_id| nodes.get(&parent_id));
+        }
+        None
+    }
+
+    /// Is `node_id` the node `ancestor_id` or loaded somewhere below it?
+    pub fn is_within(&self, node_id: u64, ancestor_id: u64) -> bool {
+        let nodes = self.nodes.read();
+        let mut current = nodes.get(&node_id);
+        while let Some(node) = current {
+            if node.id == ancestor_id {
+                return true;
+            }
+            current = node.parent_id.and_then(|parent_id| nodes.get(&parent_id));
+        }
+        false
+    }
+
+    /// Attach a content discovery result to a node
+    pub fn set_classification(&self, node_id: u64, classification: ContentClassification) -> bool {
+        let mut nodes = self.nodes.write();
+        match nodes.get_mut(&node_id) {
+            Some(node) => {
+                node.classification = Some(classification);
+                self.snapshot_dirty.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record the identified content type of a file
+    pub fn set_content_type(&self, node_id: u64, content_type: ContentType) -> bool {
+        let mut nodes = self.nodes.write();
+        match nodes.get_mut(&node_id) {
+            Some(node) => {
+                node.content_type = Some(content_type);
+                self.snapshot_dirty.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Loaded file nodes at or below `root_id` (links are not followed)
+    pub fn files_under(&self, root_id: u64) -> Vec<FileSystemNode> {
+        let nodes = self.nodes.read();
+        let mut files = Vec::new();
+        let mut pending = vec![root_id];
+        while let Some(id) = pending.pop() {
+            let node = match nodes.get(&id) {
+                Some(node) => node,
+                None => continue,
+            };
+            match node.entry_type {
+                EntryType::File => files.push(node.clone()),
+                EntryType::Symlink | EntryType::Junction => {}
+                _ => pending.extend(node.children_ids.iter().copied()),
+            }
+        }
+        files
+    }
+
+    /// Nodes with a current discovery result that has findings, optionally only below `root_id`
+    pub fn classified_nodes(&self, root_id: Option<u64>) -> Vec<FileSystemNode> {
+        let nodes = self.nodes.read();
+        nodes.values()
+            .filter(|node| node.classification.as_ref()
+                .is_some_and(|classification| classification.is_current(node) && !classification.findings.is_empty()))
+            .filter(|node| root_id.is_none_or(|root_id| {
+                let mut current = Some(*node);
+                while let Some(candidate) = current {
+                    if candidate.id == root_id {
+                        return true;
+                    }
+                    current = candidate.parent_id.and_then(|parent_id| nodes.get(&parent_id));
+                }
+                false
+            }))
+            .cloned()
+            .collect()
+    }
+
+    /// Find the node loaded for exactly this NT path (INTERNAL)
+    pub fn get_id_by_nt_path(&self, nt_path: &str) -> Option<u64> {
+        self.nt_paths.read().get(nt_path)
+    }
+    
+    /// Find the deepest loaded node at or above an NT path (INTERNAL)
+    /// Used to attribute kernel events on paths the index has not loaded.
+    pub fn find_covering_node(&self, nt_path: &str) -> Option<u64> {
+        self.nt_paths.read().longest_prefix(nt_path)
+    }
+    
     /// Get display path for ID
     pub fn get_display_path(&self, id: u64) -> Option<String> {
         let id_to_path = self.id_to_path.read();
@@ -284,6 +1493,8 @@ impl FilesystemIndex {
             EntryType::Drive => "drive",
             EntryType::Directory => "directory",
             EntryType::File => "file",
+            EntryType::Symlink => "symlink",
+            EntryType::Junction => "junction",
         }
     }
     
@@ -293,6 +1504,54 @@ impl FilesystemIndex {
         nodes.values().cloned().collect()
     }
     
+    /// Search every loaded node (optionally one subtree), ranked and paged
+    pub fn search(
+        &self,
+        matcher: &SearchMatcher,
+        root_id: Option<u64>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SearchPage, String> {
+        let nodes = self.nodes.read();
+        if let Some(root_id) = root_id {
+            if !nodes.contains_key(&root_id) {
+                return Err(format!("Node {} not found", root_id));
+            }
+        }
+        
+        let in_scope = |node: &FileSystemNode| match root_id {
+            None => true,
+            Some(root_id) => {
+                let mut current = node.parent_id;
+                while let Some(id) = current {
+                    if id == root_id {
+                        return true;
+                    }
+                    current = nodes.get(&id).and_then(|parent| parent.parent_id);
+                }
+                false
+            }
+        };
+        
+        let mut nodes_searched = 0;
+        let mut matches: Vec<&FileSystemNode> = nodes.values()
+            .filter(|node| in_scope(node))
+            .inspect(|_| nodes_searched += 1)
+            .filter(|node| matcher.matches(node))
+            .collect();
+        matches.sort_by_cached_key(|node| matcher.rank_key(node));
+        
+        let start = offset.min(matches.len());
+        let end = (start + limit).min(matches.len());
+        Ok(SearchPage {
+            nodes: matches[start..end].iter().map(|node| (*node).clone()).collect(),
+            total: matches.len(),
+            nodes_searched,
+            offset: start,
+            has_more: end < matches.len(),
+        })
+    }
+    
     /// Search for nodes by name within expanded nodes only
     pub fn search_local(&self, parent_id: u64, query: &str) -> Vec<FileSystemNode> {
         let nodes = self.nodes.read();
@@ -314,4 +1573,459 @@ impl FilesystemIndex {
             })
             .collect()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> String {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!("dlp_{}_{}_{}.json", name, std::process::id(), nanos))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn cleanup(path: &str) {
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(FilesystemIndex::identity_journal_path(path));
+    }
+
+    #[test]
+    fn identities_are_appended_not_rewritten() {
+        let path = temp_file("node_ids_journal");
+        let index = FilesystemIndex::new();
+        index.load_identities(&path).unwrap();
+
+        let finance = index.id_for_path("D:\\Finance");
+        let report = index.id_for_path("D:\\Finance\\report.xlsx");
+        index.save_identities().unwrap();
+        index.id_for_path("D:\\Legal");
+        index.save_identities().unwrap();
+
+        // Only the journal grew; the full map was never written
+        assert!(!std::path::Path::new(&path).exists());
+        let journal = std::fs::read_to_string(FilesystemIndex::identity_journal_path(&path)).unwrap();
+        assert_eq!(journal.lines().count(), 3);
+
+        let reloaded = FilesystemIndex::new();
+        assert_eq!(reloaded.load_identities(&path).unwrap(), 3);
+        assert_eq!(reloaded.id_for_path("d:\\finance"), finance);
+        assert_eq!(reloaded.id_for_path("D:\\Finance\\report.xlsx"), report);
+        assert!(reloaded.id_for_path("D:\\New") > report);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn moves_replay_and_compact() {
+        let path = temp_file("node_ids_compact");
+        let index = FilesystemIndex::new();
+        index.load_identities(&path).unwrap();
+
+        let folder = index.id_for_path("D:\\Old\\");
+        let files: Vec<u64> = (0..IDENTITY_JOURNAL_MIN_COMPACT)
+            .map(|i| index.id_for_path(&format!("D:\\Old\\file{}.txt", i)))
+            .collect();
+        index.save_identities().unwrap();
+        assert!(!std::path::Path::new(&path).exists());
+
+        // The move doubles the journal past the map size: one rewrite, empty journal
+        index.rekey_identities("D:\\Old\\", "D:\\New\\");
+        index.save_identities().unwrap();
+        assert!(std::path::Path::new(&path).exists());
+        assert!(!std::path::Path::new(&FilesystemIndex::identity_journal_path(&path)).exists());
+
+        // A later move is journaled on top of the rewritten map
+        index.rekey_identities("D:\\New\\file0.txt", "D:\\Moved.txt");
+        index.save_identities().unwrap();
+
+        let reloaded = FilesystemIndex::new();
+        assert_eq!(reloaded.load_identities(&path).unwrap(), files.len() + 1);
+        assert_eq!(reloaded.id_for_path("D:\\New\\"), folder);
+        assert_eq!(reloaded.id_for_path("D:\\Moved.txt"), files[0]);
+        assert_eq!(reloaded.id_for_path("D:\\New\\file1.txt"), files[1]);
+        assert_eq!(reloaded.display_path_for_id(files[1]).as_deref(), Some("D:\\new\\file1.txt"));
+        assert!(reloaded.id_for_path("D:\\Old\\file1.txt") > files[files.len() - 1]);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn torn_journal_line_is_ignored() {
+        let path = temp_file("node_ids_torn");
+        let index = FilesystemIndex::new();
+        index.load_identities(&path).unwrap();
+        let finance = index.id_for_path("D:\\Finance");
+        index.save_identities().unwrap();
+
+        let journal_path = FilesystemIndex::identity_journal_path(&path);
+        let mut journal = std::fs::read_to_string(&journal_path).unwrap();
+        journal.push_str("{\"key\":\"D:|\\\\le");
+        std::fs::write(&journal_path, journal).unwrap();
+
+        let reloaded = FilesystemIndex::new();
+        assert_eq!(reloaded.load_identities(&path).unwrap(), 1);
+        assert_eq!(reloaded.id_for_path("D:\\Finance"), finance);
+        cleanup(&path);
+    }
+
+    fn folder_with_files(index: &FilesystemIndex, parent_id: u64, path: &str, files: usize) -> u64 {
+        let node = |id: u64, parent_id: u64, display_path: String, entry_type: EntryType| FileSystemNode {
+            id,
+            name: display_path.rsplit('\\').next().unwrap_or_default().to_string(),
+            entry_type,
+            parent_id: Some(parent_id),
+            children_ids: Vec::new(),
+            nt_path: display_path.replacen("\\\\", "\\Device\\Mup\\", 1),
+            display_path,
+            size: None,
+            modified_time: 0,
+            created_time: 0,
+            attributes: 0,
+            is_expanded: false,
+            is_accessible: true,
+            link_target: None,
+            classification: None,
+            content_type: None,
+            drive_kind: None,
+            device: None,
+        };
+        let folder_id = index.id_for_path(path);
+        index.add_nodes(vec![node(folder_id, parent_id, path.to_string(), EntryType::Directory)]);
+        let children = (0..files)
+            .map(|i| {
+                let child_path = format!("{}\\file{}.txt", path, i);
+                node(index.id_for_path(&child_path), folder_id, child_path, EntryType::File)
+            })
+            .collect();
+        index.add_nodes(children);
+        index.mark_expanded(folder_id);
+        folder_id
+    }
+
+    #[test]
+    fn ceiling_keeps_folders_the_admin_opened() {
+        let index = FilesystemIndex::new();
+        let drive = index.add_drive("\\\\fs01\\share", "share", "\\Device\\Mup\\fs01\\share\\", DriveKind::Network, None);
+        index.mark_expanded(drive);
+        let opened = folder_with_files(&index, drive, "\\\\fs01\\share\\Opened", 50);
+        index.mark_opened(opened);
+        let crawled = folder_with_files(&index, drive, "\\\\fs01\\share\\Crawled", 50);
+        let current = folder_with_files(&index, drive, "\\\\fs01\\share\\Current", 1);
+
+        // The opened folder is the least recently used, yet only the crawled one goes
+        index.set_memory_ceiling(index.memory_usage_bytes() - 1);
+        assert!(index.enforce_memory_ceiling(current) >= 50);
+        assert!(index.get_node(opened).unwrap().is_expanded);
+        assert!(index.get_node_by_path("\\\\fs01\\share\\Opened\\file0.txt").is_some());
+        assert!(!index.get_node(crawled).unwrap().is_expanded);
+        assert!(index.get_node_by_path("\\\\fs01\\share\\Crawled\\file0.txt").is_none());
+        assert!(index.get_node(current).unwrap().is_expanded);
+
+        // Once the admin collapses it, it is an ordinary eviction candidate
+        index.collapse_subtree(opened, true);
+        index.mark_expanded(opened);
+        index.set_memory_ceiling(1);
+        index.enforce_memory_ceiling(current);
+        assert!(!index.get_node(opened).unwrap().is_expanded);
+    }
+
+    #[test]
+    fn identity_memory_is_tracked_apart_from_nodes() {
+        let index = FilesystemIndex::new();
+        let nodes = index.memory_usage_bytes();
+        let before = index.identity_memory_bytes();
+        index.id_for_path("D:\\Finance\\report.xlsx");
+        let report = index.identity_memory_bytes() - before;
+        assert!(report > "d:\\finance\\report.xlsx".len() * 2);
+        assert_eq!(index.memory_usage_bytes(), nodes);
+
+        // Re-keying swaps the key: a longer name costs more
+        index.rekey_identities("D:\\Finance\\report.xlsx", "D:\\Finance\\report-final.xlsx");
+        assert_eq!(index.identity_memory_bytes() - before, report + "-final".len() * 2);
+
+        // Moving onto an existing identity drops the moved key and keeps the target's
+        let with_other = index.identity_memory_bytes();
+        index.id_for_path("D:\\Other.txt");
+        let other = index.identity_memory_bytes() - with_other;
+        index.rekey_identities("D:\\Finance\\report-final.xlsx", "D:\\Other.txt");
+        assert_eq!(index.identity_memory_bytes() - before, other);
+    }
+
+    #[test]
+    fn identities_alone_do_not_trip_the_ceiling() {
+        let index = FilesystemIndex::new();
+        let drive = index.add_drive("\\\\fs01\\share", "share", "\\Device\\Mup\\fs01\\share\\", DriveKind::Network, None);
+        index.mark_expanded(drive);
+        let crawled = folder_with_files(&index, drive, "\\\\fs01\\share\\Crawled", 20);
+        index.set_memory_ceiling(index.memory_usage_bytes());
+
+        // A big crawl leaves many identities behind after its nodes are evicted
+        for i in 0..1000 {
+            index.id_for_path(&format!("\\\\fs01\\share\\Crawled\\Deep\\file{}.txt", i));
+        }
+        assert!(index.identity_memory_bytes() > index.memory_ceiling_bytes());
+        assert_eq!(index.enforce_memory_ceiling(drive), 0);
+        assert!(index.is_expanded(crawled));
+    }
+
+    #[test]
+    fn protected_parents_are_watched_while_collapsed() {
+        let index = FilesystemIndex::new();
+        let drive = index.add_drive("\\\\fs01\\share", "share", "\\Device\\Mup\\fs01\\share\\", DriveKind::Network, None);
+        index.mark_expanded(drive);
+        let folder = folder_with_files(&index, drive, "\\\\fs01\\share\\Finance", 3);
+        let protected = index.get_node_by_path("\\\\fs01\\share\\Finance\\file1.txt").unwrap().id;
+        index.pin_node(protected);
+        index.pin_node(drive);
+
+        // Collapsing keeps the pinned file, and its folder stays watched
+        index.collapse_subtree(folder, true);
+        assert!(!index.get_node(folder).unwrap().is_expanded);
+        assert_eq!(index.protected_parent_directories(), vec![(folder, "\\\\fs01\\share\\Finance".to_string())]);
+    }
+
+    #[test]
+    fn collapse_keeps_pinned_nodes_and_their_ancestors() {
+        let index = FilesystemIndex::new();
+        let drive = index.add_drive("\\\\fs01\\share", "share", "\\Device\\Mup\\fs01\\share\\", DriveKind::Network, None);
+        index.mark_expanded(drive);
+        let finance = folder_with_files(&index, drive, "\\\\fs01\\share\\Finance", 3);
+        let q1 = folder_with_files(&index, finance, "\\\\fs01\\share\\Finance\\Q1", 2);
+        let pinned = index.get_id_by_path("\\\\fs01\\share\\Finance\\Q1\\file0.txt").unwrap();
+        let evicted: Vec<(u64, String)> = ["Finance\\file0.txt", "Finance\\file2.txt", "Finance\\Q1\\file1.txt"]
+            .iter()
+            .map(|rest| {
+                let path = format!("\\\\fs01\\share\\{}", rest);
+                (index.get_id_by_path(&path).unwrap(), path)
+            })
+            .collect();
+        index.pin_node(pinned);
+
+        assert_eq!(index.collapse_subtree(drive, true), 4);
+
+        // The pinned file and the chain leading to it stay, collapsed
+        for id in [drive, finance, q1] {
+            assert!(!index.is_expanded(id));
+        }
+        assert_eq!(index.get_children(drive).iter().map(|node| node.id).collect::<Vec<_>>(), vec![finance]);
+        assert_eq!(index.get_children(finance).iter().map(|node| node.id).collect::<Vec<_>>(), vec![q1]);
+        assert_eq!(index.get_children(q1).iter().map(|node| node.id).collect::<Vec<_>>(), vec![pinned]);
+        assert_eq!(index.get_id_by_nt_path("\\Device\\Mup\\fs01\\share\\Finance\\Q1\\file0.txt"), Some(pinned));
+        assert_eq!(index.find_covering_node("\\Device\\Mup\\fs01\\share\\Finance\\Q1\\file1.txt"), Some(q1));
+
+        // Evicted nodes leave no trace in the path maps or the trie
+        for (id, path) in &evicted {
+            assert!(index.get_node(*id).is_none());
+            assert!(index.get_id_by_path(path).is_none());
+            assert!(index.get_display_path(*id).is_none());
+            let nt_path = path.replacen("\\\\", "\\Device\\Mup\\", 1);
+            assert!(index.get_id_by_nt_path(&nt_path).is_none());
+        }
+
+        // Reloading the same paths hands out the same IDs
+        assert_eq!(folder_with_files(&index, drive, "\\\\fs01\\share\\Finance", 3), finance);
+        assert_eq!(folder_with_files(&index, finance, "\\\\fs01\\share\\Finance\\Q1", 2), q1);
+        for (id, path) in &evicted {
+            assert_eq!(index.get_id_by_path(path), Some(*id));
+        }
+        assert_eq!(index.get_id_by_path("\\\\fs01\\share\\Finance\\Q1\\file0.txt"), Some(pinned));
+    }
+
+    #[test]
+    fn snapshot_round_trip_restores_the_tree() {
+        let path = temp_file("index_snapshot");
+        let index = FilesystemIndex::new();
+        let drive = index.add_drive("\\\\fs01\\share", "share", "\\Device\\Mup\\fs01\\share\\", DriveKind::Network, None);
+        index.mark_expanded(drive);
+        let finance = folder_with_files(&index, drive, "\\\\fs01\\share\\Finance", 3);
+        let legal = index.id_for_path("\\\\fs01\\share\\Legal");
+        index.add_node(FileSystemNode {
+            id: legal,
+            name: "Legal".to_string(),
+            children_ids: Vec::new(),
+            nt_path: "\\Device\\Mup\\fs01\\share\\Legal".to_string(),
+            display_path: "\\\\fs01\\share\\Legal".to_string(),
+            is_expanded: false,
+            ..index.get_node(finance).unwrap()
+        });
+        assert_eq!(index.save_snapshot(&path).unwrap(), 6);
+        // Nothing changed since: the next save is skipped
+        assert_eq!(index.save_snapshot(&path).unwrap(), 0);
+
+        let restored = FilesystemIndex::new();
+        assert_eq!(restored.load_snapshot(&path).unwrap(), 6);
+        for node in index.get_all_nodes().into_iter().filter(|node| node.id != 1) {
+            let loaded = restored.get_node(node.id).unwrap();
+            assert_eq!(loaded.name, node.name);
+            assert_eq!(loaded.parent_id, node.parent_id);
+            assert_eq!(loaded.is_expanded, node.is_expanded);
+            assert_eq!(restored.get_id_by_path(&node.display_path), Some(node.id));
+            assert_eq!(restored.get_display_path(node.id), Some(node.display_path.clone()));
+            if !node.nt_path.is_empty() {
+                assert_eq!(restored.get_id_by_nt_path(&node.nt_path), Some(node.id));
+            }
+            let mut children: Vec<u64> = restored.get_children(node.id).iter().map(|child| child.id).collect();
+            let mut expected = node.children_ids.clone();
+            children.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(children, expected);
+        }
+        assert_eq!(restored.find_covering_node("\\Device\\Mup\\fs01\\share\\Finance\\new.txt"), Some(finance));
+
+        // Expanded folders must have their mtime checked once; others never
+        assert!(restored.take_unverified(drive));
+        assert!(restored.take_unverified(finance));
+        assert!(!restored.take_unverified(finance));
+        assert!(!restored.take_unverified(legal));
+
+        // Snapshot IDs stay reserved, and a populated index refuses a second load
+        assert!(restored.get_next_id() > index.get_all_nodes().iter().map(|node| node.id).max().unwrap());
+        assert!(restored.load_snapshot(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// Loaded child of `parent_id` with the sort fields set
+    fn child(index: &FilesystemIndex, parent_id: u64, name: &str, entry_type: EntryType, size: Option<u64>, modified_time: u64) -> u64 {
+        let parent = index.get_node(parent_id).unwrap();
+        let display_path = format!("{}{}", parent.display_path, name);
+        let id = index.id_for_path(&display_path);
+        index.add_node(FileSystemNode {
+            id,
+            name: name.to_string(),
+            entry_type,
+            parent_id: Some(parent_id),
+            children_ids: Vec::new(),
+            nt_path: display_path.replacen("\\\\", "\\Device\\Mup\\", 1),
+            display_path,
+            size,
+            modified_time,
+            created_time: 0,
+            attributes: 0,
+            is_expanded: false,
+            is_accessible: true,
+            link_target: None,
+            classification: None,
+            content_type: None,
+            drive_kind: None,
+            device: None,
+        })
+    }
+
+    fn page_names(page: &ChildrenPage) -> Vec<&str> {
+        page.nodes.iter().map(|node| node.name.as_str()).collect()
+    }
+
+    /// Every name, following cursors `limit` at a time
+    fn walk(index: &FilesystemIndex, parent_id: u64, sort: ChildSort, descending: bool, limit: usize) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = index.children_page(parent_id, sort, descending, cursor.as_ref(), 0, limit).unwrap();
+            names.extend(page.nodes.iter().map(|node| node.name.clone()));
+            match page.next_cursor {
+                Some(next) => cursor = Some(ChildCursor::parse(&next.token()).unwrap()),
+                None => return names,
+            }
+        }
+    }
+
+    fn share(index: &FilesystemIndex) -> u64 {
+        let drive = index.add_drive("\\\\fs01\\share", "share", "\\Device\\Mup\\fs01\\share\\", DriveKind::Network, None);
+        index.mark_expanded(drive);
+        drive
+    }
+
+    #[test]
+    fn children_page_by_offset_and_cursor() {
+        let index = FilesystemIndex::new();
+        let drive = share(&index);
+        for name in ["e.txt", "B.txt", "a.txt", "d.txt", "c.txt"] {
+            child(&index, drive, name, EntryType::File, Some(1), 0);
+        }
+
+        let page = index.children_page(drive, ChildSort::Name, false, None, 1, 2).unwrap();
+        assert_eq!(page_names(&page), vec!["B.txt", "c.txt"]);
+        assert_eq!((page.total, page.offset, page.has_more), (5, 1, true));
+        let last = index.children_page(drive, ChildSort::Name, false, None, 4, 2).unwrap();
+        assert_eq!(page_names(&last), vec!["e.txt"]);
+        assert!(!last.has_more && last.next_cursor.is_none());
+        assert!(index.children_page(drive, ChildSort::Name, false, None, 9, 2).unwrap().nodes.is_empty());
+
+        assert_eq!(walk(&index, drive, ChildSort::Name, false, 2), vec!["a.txt", "B.txt", "c.txt", "d.txt", "e.txt"]);
+        assert_eq!(walk(&index, drive, ChildSort::Name, true, 2), vec!["e.txt", "d.txt", "c.txt", "B.txt", "a.txt"]);
+        assert!(ChildCursor::parse("12").is_none());
+        assert!(ChildCursor::parse("x:1:a").is_none());
+        assert_eq!(ChildCursor::parse("0:7:a:b.txt").unwrap().name, "a:b.txt");
+    }
+
+    #[test]
+    fn cursor_survives_its_child_going_away() {
+        let index = FilesystemIndex::new();
+        let drive = share(&index);
+        let mut ids = HashMap::new();
+        for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+            ids.insert(name, child(&index, drive, name, EntryType::File, Some(1), 0));
+        }
+
+        let first = index.children_page(drive, ChildSort::Name, false, None, 0, 2).unwrap();
+        let cursor = first.next_cursor.unwrap();
+        assert_eq!(cursor.id, ids["b.txt"]);
+
+        // The cursor child is deleted, then another is renamed to sort before it
+        index.remove_subtree(ids["b.txt"]);
+        index.rename_node(ids["d.txt"], drive, "aa.txt").unwrap();
+        let next = index.children_page(drive, ChildSort::Name, false, Some(&cursor), 0, 2).unwrap();
+        assert_eq!(page_names(&next), vec!["c.txt"]);
+        assert_eq!(next.offset, 2);
+        assert!(!next.has_more);
+
+        let descending = index.children_page(drive, ChildSort::Name, true, Some(&cursor), 0, 5).unwrap();
+        assert_eq!(page_names(&descending), vec!["aa.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn children_sort_by_type_size_and_mtime_with_ties_by_name() {
+        let index = FilesystemIndex::new();
+        let drive = share(&index);
+        child(&index, drive, "b.txt", EntryType::File, Some(10), 300);
+        child(&index, drive, "A.txt", EntryType::File, Some(10), 100);
+        child(&index, drive, "link", EntryType::Symlink, None, 100);
+        child(&index, drive, "Zeta", EntryType::Directory, None, 200);
+        child(&index, drive, "alpha", EntryType::Directory, None, 300);
+        child(&index, drive, "big.bin", EntryType::File, Some(99), 200);
+
+        // Folders, links, then files; ties in name order regardless of case
+        assert_eq!(walk(&index, drive, ChildSort::Type, false, 4), vec!["alpha", "Zeta", "link", "A.txt", "b.txt", "big.bin"]);
+        // Folders and links have no size and sort as 0
+        assert_eq!(walk(&index, drive, ChildSort::Size, false, 4), vec!["alpha", "link", "Zeta", "A.txt", "b.txt", "big.bin"]);
+        assert_eq!(walk(&index, drive, ChildSort::Size, true, 4), vec!["big.bin", "b.txt", "A.txt", "Zeta", "link", "alpha"]);
+        assert_eq!(walk(&index, drive, ChildSort::Mtime, false, 4), vec!["A.txt", "link", "big.bin", "Zeta", "alpha", "b.txt"]);
+    }
+
+    #[test]
+    fn sorted_children_cache_follows_the_tree() {
+        let index = FilesystemIndex::new();
+        let drive = share(&index);
+        child(&index, drive, "b.txt", EntryType::File, Some(1), 0);
+        child(&index, drive, "d.txt", EntryType::File, Some(1), 0);
+        assert_eq!(walk(&index, drive, ChildSort::Name, false, 10), vec!["b.txt", "d.txt"]);
+        assert!(index.child_order.read().contains_key(&(drive, ChildSort::Name)));
+
+        // New children and re-read sizes show up in the next listing
+        let c = child(&index, drive, "c.txt", EntryType::File, Some(1), 0);
+        assert_eq!(walk(&index, drive, ChildSort::Name, false, 10), vec!["b.txt", "c.txt", "d.txt"]);
+        assert_eq!(walk(&index, drive, ChildSort::Size, false, 10), vec!["b.txt", "c.txt", "d.txt"]);
+        child(&index, drive, "b.txt", EntryType::File, Some(50), 0);
+        assert_eq!(walk(&index, drive, ChildSort::Size, false, 10), vec!["c.txt", "d.txt", "b.txt"]);
+
+        // Collapse keeps only the pinned child
+        index.pin_node(c);
+        index.collapse_subtree(drive, true);
+        assert!(!index.child_order.read().contains_key(&(drive, ChildSort::Name)));
+        assert_eq!(walk(&index, drive, ChildSort::Name, false, 10), vec!["c.txt"]);
+    }
+}
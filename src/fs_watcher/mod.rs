@@ -0,0 +1,267 @@
+//! Filesystem Watcher - keeps expanded directories in sync with the disk
+//! Core Principle: Backends report raw path changes (INTERNAL), the watcher
+//! turns them into index deltas and ID-only change events for the Admin.
+//! Renames move the existing node, so its ID (and any policy on it) survives.
+//! Parents of protected nodes are watched even when collapsed, so a protected
+//! node moved into a collapsed folder is still seen leaving.
+
+mod polling;
+
+pub use polling::PollingBackend;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::filesystem_scanner::FileSystemScanner;
+use crate::fs_index::FilesystemIndex;
+
+/// Environment variable for the watcher poll interval (0 disables the watcher)
+pub const WATCH_INTERVAL_ENV: &str = "AGENT_WATCH_INTERVAL_MS";
+
+/// Default poll interval
+const DEFAULT_WATCH_INTERVAL_MS: u64 = 2000;
+
+/// Raw change reported by a backend (INTERNAL ONLY - real paths)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawChange {
+    Created(PathBuf),
+    Deleted(PathBuf),
+    Modified(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+/// Platform change source for a set of directories
+/// A backend only reports direct children of watched directories, plus a
+/// watched directory itself disappearing.
+pub trait WatchBackend: Send {
+    /// Backend name for logs
+    fn name(&self) -> &'static str;
+
+    /// Start watching a directory (non-recursive)
+    fn watch(&mut self, dir: &Path) -> Result<(), String>;
+
+    /// Stop watching a directory
+    fn unwatch(&mut self, dir: &Path);
+
+    /// Changes since the previous poll
+    fn poll(&mut self) -> Vec<RawChange>;
+}
+
+/// Kind of change applied to the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FsChangeKind {
+    Created,
+    Deleted,
+    Modified,
+    Renamed,
+}
+
+/// Change applied to the index (admin-safe: node IDs only)
+#[derive(Debug, Clone, Serialize)]
+pub struct FsChange {
+    pub node_id: u64,
+    pub kind: FsChangeKind,
+    pub parent_id: Option<u64>,
+    pub previous_parent_id: Option<u64>,   // Renames that moved the node
+}
+
+/// Backend plus the directories it currently watches
+struct WatchState {
+    backend: Box<dyn WatchBackend>,
+    watched: HashMap<u64, PathBuf>,        // Directory node → OS path
+    partial: HashSet<u64>,                 // Watched only for their protected children (not expanded)
+}
+
+/// Watches expanded directories and applies their changes to the index
+pub struct FilesystemWatcher {
+    index: Arc<FilesystemIndex>,
+    scanner: Arc<FileSystemScanner>,
+    state: Mutex<WatchState>,
+}
+
+impl FilesystemWatcher {
+    /// Create a watcher over any backend
+    pub fn new(index: Arc<FilesystemIndex>, scanner: Arc<FileSystemScanner>, backend: Box<dyn WatchBackend>) -> Self {
+        FilesystemWatcher {
+            index,
+            scanner,
+            state: Mutex::new(WatchState {
+                backend,
+                watched: HashMap::new(),
+                partial: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Create a watcher with the portable polling backend
+    pub fn with_polling(index: Arc<FilesystemIndex>, scanner: Arc<FileSystemScanner>) -> Self {
+        Self::new(index, scanner, Box::new(PollingBackend::new()))
+    }
+
+    /// Poll interval from the environment (None = watcher disabled)
+    pub fn configured_interval() -> Option<Duration> {
+        let millis = std::env::var(WATCH_INTERVAL_ENV)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_WATCH_INTERVAL_MS);
+        (millis > 0).then(|| Duration::from_millis(millis))
+    }
+
+    /// Backend name for logs
+    pub fn backend_name(&self) -> &'static str {
+        self.state.lock().backend.name()
+    }
+
+    /// One cycle: follow expansion changes, collect backend changes, apply them
+    pub fn poll_once(&self) -> Vec<FsChange> {
+        let mut state = self.state.lock();
+        self.sync_watches(&mut state);
+
+        let raw_changes = state.backend.poll();
+        if raw_changes.is_empty() {
+            return Vec::new();
+        }
+
+        let dirs: HashMap<PathBuf, u64> = state.watched.iter()
+            .map(|(&id, path)| (path.clone(), id))
+            .collect();
+        let partial = state.partial.clone();
+        drop(state);
+
+        raw_changes.into_iter()
+            .filter_map(|change| self.apply(&dirs, &partial, change))
+            .collect()
+    }
+
+    /// Watch newly expanded directories and parents of protected nodes (and
+    /// renamed ones at their new path), stop watching collapsed or removed ones
+    fn sync_watches(&self, state: &mut WatchState) {
+        let mut wanted: HashMap<u64, PathBuf> = self.index.expanded_directories()
+            .into_iter()
+            .map(|(id, display_path)| (id, PathBuf::from(display_path)))
+            .collect();
+        state.partial.clear();
+        for (id, display_path) in self.index.protected_parent_directories() {
+            if let std::collections::hash_map::Entry::Vacant(entry) = wanted.entry(id) {
+                entry.insert(PathBuf::from(display_path));
+                state.partial.insert(id);
+            }
+        }
+
+        let stale: Vec<u64> = state.watched.iter()
+            .filter(|(id, path)| wanted.get(id) != Some(*path))
+            .map(|(&id, _)| id)
+            .collect();
+        for id in stale {
+            if let Some(path) = state.watched.remove(&id) {
+                state.backend.unwatch(&path);
+            }
+        }
+
+        for (id, path) in wanted {
+            if state.watched.contains_key(&id) {
+                continue;
+            }
+            match state.backend.watch(&path) {
+                Ok(()) => {
+                    state.watched.insert(id, path);
+                }
+                Err(e) => println!("⚠️ Watcher: {}", e),
+            }
+        }
+    }
+
+    /// Apply one raw change to the index
+    /// In a collapsed (partially watched) directory only loaded children count.
+    fn apply(&self, dirs: &HashMap<PathBuf, u64>, partial: &HashSet<u64>, change: RawChange) -> Option<FsChange> {
+        match change {
+            RawChange::Created(path) => {
+                let parent_id = Self::parent_dir(dirs, &path)?;
+                if partial.contains(&parent_id) {
+                    return None;
+                }
+                let node_id = self.add_entry(&path, parent_id)?;
+                Some(FsChange { node_id, kind: FsChangeKind::Created, parent_id: Some(parent_id), previous_parent_id: None })
+            }
+            RawChange::Modified(path) => {
+                let parent_id = Self::parent_dir(dirs, &path)?;
+                self.find_child(parent_id, &path)?;
+                let node_id = self.add_entry(&path, parent_id)?;
+                Some(FsChange { node_id, kind: FsChangeKind::Modified, parent_id: Some(parent_id), previous_parent_id: None })
+            }
+            RawChange::Deleted(path) => {
+                let (node_id, parent_id) = match Self::parent_dir(dirs, &path) {
+                    Some(parent_id) => (self.find_child(parent_id, &path)?, Some(parent_id)),
+                    // A watched directory that vanished while its parent is not watched
+                    None => {
+                        let node_id = *dirs.get(&path)?;
+                        (node_id, self.index.get_node(node_id)?.parent_id)
+                    }
+                };
+                let removed = self.index.remove_subtree(node_id);
+                println!("🗑️ Watcher: node {} deleted ({} nodes removed)", node_id, removed);
+                Some(FsChange { node_id, kind: FsChangeKind::Deleted, parent_id, previous_parent_id: None })
+            }
+            RawChange::Renamed { from, to } => {
+                let old_parent_id = Self::parent_dir(dirs, &from)?;
+                let new_parent_id = Self::parent_dir(dirs, &to)?;
+                let node_id = match self.find_child(old_parent_id, &from) {
+                    Some(node_id) => node_id,
+                    // Never loaded (sibling in a collapsed folder): arrives as a new entry
+                    None => return self.apply(dirs, partial, RawChange::Created(to)),
+                };
+                let new_name = to.file_name()?.to_string_lossy().to_string();
+
+                // Renamed over an existing entry: the replaced node goes away
+                if let Some(replaced) = self.index.child_by_name(new_parent_id, &new_name) {
+                    if replaced != node_id {
+                        self.index.remove_subtree(replaced);
+                    }
+                }
+
+                if let Err(e) = self.index.rename_node(node_id, new_parent_id, &new_name) {
+                    println!("⚠️ Watcher: rename of node {} failed: {}", node_id, e);
+                    return None;
+                }
+                // Size/mtime as seen at the new location
+                self.add_entry(&to, new_parent_id);
+
+                println!("✏️ Watcher: node {} renamed", node_id);
+                Some(FsChange {
+                    node_id,
+                    kind: FsChangeKind::Renamed,
+                    parent_id: Some(new_parent_id),
+                    previous_parent_id: (old_parent_id != new_parent_id).then_some(old_parent_id),
+                })
+            }
+        }
+    }
+
+    /// Add or refresh an entry through the scanner (same filters as expansion)
+    /// None if filtered out or already gone again (short-lived temp files)
+    fn add_entry(&self, path: &Path, parent_id: u64) -> Option<u64> {
+        match self.scanner.add_entry(path, parent_id) {
+            Ok(node_id) => node_id,
+            Err(e) => {
+                println!("⚠️ Watcher: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Node of the watched directory containing `path`
+    fn parent_dir(dirs: &HashMap<PathBuf, u64>, path: &Path) -> Option<u64> {
+        dirs.get(path.parent()?).copied()
+    }
+
+    /// Loaded node for `path` under its parent
+    fn find_child(&self, parent_id: u64, path: &Path) -> Option<u64> {
+        let name = path.file_name()?.to_string_lossy();
+        self.index.child_by_name(parent_id, &name)
+    }
+}
@@ -0,0 +1,300 @@
+//! Polling Watch Backend
+//! Re-reads every watched directory and diffs it against the previous read.
+//! Portable (works wherever std::fs does), at the cost of one read_dir per
+//! watched directory per poll.
+//! Renames are matched by inode on Unix. On Windows std exposes no file ID, so
+//! a rename is matched on type, size and mtime only: two equal files renamed
+//! in the same poll are ambiguous and reported as delete + create.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{RawChange, WatchBackend};
+
+/// What a poll remembers about one directory entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EntryState {
+    file_id: Option<u64>,      // Inode on Unix, None on Windows
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl EntryState {
+    /// Same underlying file? A rename keeps type, size and mtime; a file ID,
+    /// where known, must match too. Files also compare size and mtime with an
+    /// ID, since a file deleted and created in one poll can reuse the inode.
+    fn same_file(&self, other: &EntryState) -> bool {
+        let same_content = self.size == other.size && self.modified == other.modified;
+        match (self.file_id, other.file_id) {
+            (Some(a), Some(b)) => a == b && self.is_dir == other.is_dir && (self.is_dir || same_content),
+            _ => self.is_dir == other.is_dir && same_content,
+        }
+    }
+}
+
+/// Polling backend: directory listings diffed on every poll
+#[derive(Default)]
+pub struct PollingBackend {
+    listings: HashMap<PathBuf, HashMap<OsString, EntryState>>,
+}
+
+impl PollingBackend {
+    pub fn new() -> Self {
+        PollingBackend::default()
+    }
+
+    /// Read a directory listing (link-level metadata, links are not followed)
+    fn read_listing(dir: &Path) -> std::io::Result<HashMap<OsString, EntryState>> {
+        let mut listing = HashMap::new();
+        for entry in fs::read_dir(dir)?.flatten() {
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            listing.insert(entry.file_name(), EntryState {
+                file_id: Self::file_id(&metadata),
+                is_dir: metadata.is_dir(),
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            });
+        }
+        Ok(listing)
+    }
+
+    #[cfg(unix)]
+    fn file_id(metadata: &fs::Metadata) -> Option<u64> {
+        use std::os::unix::fs::MetadataExt;
+        Some(metadata.ino())
+    }
+
+    /// No stable file index in std on Windows (`file_index` is unstable)
+    #[cfg(not(unix))]
+    fn file_id(_metadata: &fs::Metadata) -> Option<u64> {
+        None
+    }
+
+    /// Pair deletions with creations of the same file: renames and moves
+    /// Unmatched and ambiguous entries stay in `deleted` / `created`.
+    fn pair_renames(deleted: &mut Vec<(PathBuf, EntryState)>, created: &mut Vec<(PathBuf, EntryState)>) -> Vec<RawChange> {
+        let mut renames = Vec::new();
+        deleted.retain(|(from, old_state)| {
+            let candidates: Vec<usize> = created.iter()
+                .enumerate()
+                .filter(|(_, (_, new_state))| old_state.same_file(new_state))
+                .map(|(position, _)| position)
+                .collect();
+            match candidates.as_slice() {
+                [position] => {
+                    let (to, _) = created.remove(*position);
+                    renames.push(RawChange::Renamed { from: from.clone(), to });
+                    false
+                }
+                _ => true,     // No match, or ambiguous: keep as delete + create
+            }
+        });
+        renames
+    }
+}
+
+impl WatchBackend for PollingBackend {
+    fn name(&self) -> &'static str {
+        "polling"
+    }
+
+    fn watch(&mut self, dir: &Path) -> Result<(), String> {
+        let listing = Self::read_listing(dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        self.listings.insert(dir.to_path_buf(), listing);
+        Ok(())
+    }
+
+    fn unwatch(&mut self, dir: &Path) {
+        self.listings.remove(dir);
+    }
+
+    fn poll(&mut self) -> Vec<RawChange> {
+        let mut deleted: Vec<(PathBuf, EntryState)> = Vec::new();
+        let mut created: Vec<(PathBuf, EntryState)> = Vec::new();
+        let mut changes = Vec::new();
+        let mut vanished = Vec::new();
+
+        for (dir, previous) in self.listings.iter_mut() {
+            let current = match Self::read_listing(dir) {
+                Ok(current) => current,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    vanished.push(dir.clone());
+                    continue;
+                }
+                Err(_) => continue,    // Transient (locked, access denied): try again next poll
+            };
+
+            for (name, state) in previous.iter() {
+                match current.get(name) {
+                    None => deleted.push((dir.join(name), *state)),
+                    Some(now) if !now.is_dir && (now.size != state.size || now.modified != state.modified) => {
+                        changes.push(RawChange::Modified(dir.join(name)));
+                    }
+                    Some(_) => {}
+                }
+            }
+            for (name, state) in current.iter() {
+                if !previous.contains_key(name) {
+                    created.push((dir.join(name), *state));
+                }
+            }
+            *previous = current;
+        }
+
+        let renames = Self::pair_renames(&mut deleted, &mut created);
+
+        // A watched directory that disappeared is reported itself, unless its
+        // (watched) parent already reported it deleted or renamed
+        for dir in vanished {
+            self.listings.remove(&dir);
+            let reported = deleted.iter().any(|(path, _)| *path == dir)
+                || renames.iter().any(|rename| matches!(rename, RawChange::Renamed { from, .. } if *from == dir));
+            if !reported {
+                changes.push(RawChange::Deleted(dir));
+            }
+        }
+
+        renames.into_iter()
+            .chain(deleted.into_iter().map(|(path, _)| RawChange::Deleted(path)))
+            .chain(created.into_iter().map(|(path, _)| RawChange::Created(path)))
+            .chain(changes)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("dlp_{}_{}_{}", name, std::process::id(), nanos));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sorted(mut changes: Vec<RawChange>) -> Vec<RawChange> {
+        changes.sort_by_key(|change| format!("{:?}", change));
+        changes
+    }
+
+    #[test]
+    fn reports_create_delete_and_modify() {
+        let dir = temp_dir("poll_basic");
+        fs::write(dir.join("keep.txt"), b"one").unwrap();
+        fs::write(dir.join("gone.txt"), b"gone").unwrap();
+        let mut backend = PollingBackend::new();
+        backend.watch(&dir).unwrap();
+        assert!(backend.poll().is_empty());
+
+        fs::write(dir.join("keep.txt"), b"one, then more").unwrap();
+        fs::remove_file(dir.join("gone.txt")).unwrap();
+        fs::write(dir.join("new.txt"), b"fresh content").unwrap();
+        assert_eq!(sorted(backend.poll()), vec![
+            RawChange::Created(dir.join("new.txt")),
+            RawChange::Deleted(dir.join("gone.txt")),
+            RawChange::Modified(dir.join("keep.txt")),
+        ]);
+        assert!(backend.poll().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rename_within_a_folder_is_one_change() {
+        let dir = temp_dir("poll_rename");
+        fs::write(dir.join("draft.docx"), b"report").unwrap();
+        let mut backend = PollingBackend::new();
+        backend.watch(&dir).unwrap();
+
+        fs::rename(dir.join("draft.docx"), dir.join("final.docx")).unwrap();
+        assert_eq!(backend.poll(), vec![RawChange::Renamed {
+            from: dir.join("draft.docx"),
+            to: dir.join("final.docx"),
+        }]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn move_between_watched_folders_is_a_rename() {
+        let dir = temp_dir("poll_move");
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::create_dir_all(dir.join("a").join("Protected")).unwrap();
+        let mut backend = PollingBackend::new();
+        backend.watch(&dir.join("a")).unwrap();
+        backend.watch(&dir.join("b")).unwrap();
+        backend.watch(&dir.join("a").join("Protected")).unwrap();
+
+        // The moved folder was watched itself: reported once, by its parent
+        fs::rename(dir.join("a").join("Protected"), dir.join("b").join("Protected")).unwrap();
+        assert_eq!(backend.poll(), vec![RawChange::Renamed {
+            from: dir.join("a").join("Protected"),
+            to: dir.join("b").join("Protected"),
+        }]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn watched_folder_deleted_with_unwatched_parent_is_reported() {
+        let dir = temp_dir("poll_vanish");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let mut backend = PollingBackend::new();
+        backend.watch(&dir.join("sub")).unwrap();
+
+        fs::remove_dir_all(dir.join("sub")).unwrap();
+        assert_eq!(backend.poll(), vec![RawChange::Deleted(dir.join("sub"))]);
+        assert!(backend.poll().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn renames_without_file_ids_match_on_size_and_mtime() {
+        let modified = Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000));
+        let state = |size| EntryState { file_id: None, is_dir: false, size, modified };
+
+        let mut deleted = vec![(PathBuf::from("old.txt"), state(10))];
+        let mut created = vec![(PathBuf::from("other.txt"), state(20)), (PathBuf::from("new.txt"), state(10))];
+        let renames = PollingBackend::pair_renames(&mut deleted, &mut created);
+        assert_eq!(renames, vec![RawChange::Renamed { from: "old.txt".into(), to: "new.txt".into() }]);
+        assert!(deleted.is_empty());
+        assert_eq!(created, vec![(PathBuf::from("other.txt"), state(20))]);
+    }
+
+    #[test]
+    fn ambiguous_renames_stay_delete_and_create() {
+        let modified = Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000));
+        let state = EntryState { file_id: None, is_dir: false, size: 10, modified };
+
+        // Two equal files renamed at once: no way to tell which went where
+        let mut deleted = vec![(PathBuf::from("a.txt"), state), (PathBuf::from("b.txt"), state)];
+        let mut created = vec![(PathBuf::from("c.txt"), state), (PathBuf::from("d.txt"), state)];
+        assert!(PollingBackend::pair_renames(&mut deleted, &mut created).is_empty());
+        assert_eq!(deleted.len(), 2);
+        assert_eq!(created.len(), 2);
+
+        // File IDs settle it
+        let mut deleted = vec![
+            (PathBuf::from("a.txt"), EntryState { file_id: Some(1), ..state }),
+            (PathBuf::from("b.txt"), EntryState { file_id: Some(2), ..state }),
+        ];
+        let mut created = vec![
+            (PathBuf::from("c.txt"), EntryState { file_id: Some(2), ..state }),
+            (PathBuf::from("d.txt"), EntryState { file_id: Some(1), ..state }),
+        ];
+        assert_eq!(sorted(PollingBackend::pair_renames(&mut deleted, &mut created)), vec![
+            RawChange::Renamed { from: "a.txt".into(), to: "d.txt".into() },
+            RawChange::Renamed { from: "b.txt".into(), to: "c.txt".into() },
+        ]);
+    }
+}
@@ -0,0 +1,363 @@
+//! Global Index Search
+//! Filters and ranking for searches over everything indexed (expanded or crawled)
+//! Only loaded nodes are searched; nothing here touches the disk.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::file_type::ContentType;
+use crate::fs_index::{EntryType, FileSystemNode};
+
+/// Default number of results per page
+pub const DEFAULT_SEARCH_LIMIT: usize = 100;
+
+/// Compiled regex size limit (keeps pathological patterns cheap)
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// How the query text is matched against entry names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    Substring,
+    Glob,       // Whole name: `*` any run, `?` one character
+    Regex,      // Anywhere in the name
+}
+
+/// Global search request (all filters optional, combined with AND)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchRequest {
+    pub q: Option<String>,
+    pub mode: MatchMode,
+    pub ext: Option<String>,             // Comma-separated, e.g. "docx,xlsx"
+    #[serde(rename = "type")]
+    pub entry_types: Option<String>,     // Comma-separated: file, directory, drive, symlink, junction
+    pub content_type: Option<String>,    // Comma-separated, e.g. "xlsx,pdf" (identified files only)
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub modified_after: Option<u64>,     // Unix seconds, inclusive
+    pub modified_before: Option<u64>,    // Unix seconds, exclusive
+    pub root_id: Option<u64>,            // Only this subtree
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// Search request compiled once, then checked against every node
+#[derive(Debug)]
+pub struct SearchMatcher {
+    query_lower: Option<String>,
+    pattern: Option<Regex>,
+    extensions: Vec<String>,
+    entry_types: Vec<EntryType>,
+    content_types: Vec<ContentType>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_after: Option<u64>,
+    modified_before: Option<u64>,
+}
+
+impl SearchMatcher {
+    /// Validate and compile a request
+    pub fn compile(request: &SearchRequest) -> Result<Self, String> {
+        let query = request.q.as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty());
+
+        let pattern = match (query, request.mode) {
+            (Some(q), MatchMode::Glob) => Some(Self::build_regex(&Self::glob_to_regex(q))?),
+            (Some(q), MatchMode::Regex) => Some(Self::build_regex(q)?),
+            _ => None,
+        };
+
+        let extensions: Vec<String> = Self::split_list(request.ext.as_deref())
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .collect();
+
+        let entry_types = Self::split_list(request.entry_types.as_deref())
+            .map(Self::parse_entry_type)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let content_types = Self::split_list(request.content_type.as_deref())
+            .map(ContentType::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let (Some(min), Some(max)) = (request.min_size, request.max_size) {
+            if min > max {
+                return Err(format!("min_size {} is larger than max_size {}", min, max));
+            }
+        }
+        if let (Some(after), Some(before)) = (request.modified_after, request.modified_before) {
+            if after >= before {
+                return Err("modified_after must be before modified_before".to_string());
+            }
+        }
+
+        Ok(SearchMatcher {
+            query_lower: query.map(str::to_lowercase),
+            pattern,
+            extensions,
+            entry_types,
+            content_types,
+            min_size: request.min_size,
+            max_size: request.max_size,
+            modified_after: request.modified_after,
+            modified_before: request.modified_before,
+        })
+    }
+
+    /// Does the node pass every filter?
+    pub fn matches(&self, node: &FileSystemNode) -> bool {
+        if node.entry_type == EntryType::VirtualRoot {
+            return false;
+        }
+        if !self.entry_types.is_empty() && !self.entry_types.contains(&node.entry_type) {
+            return false;
+        }
+
+        if !self.extensions.is_empty() {
+            let extension = match node.name.rsplit_once('.') {
+                Some((_, extension)) if node.entry_type == EntryType::File => extension.to_lowercase(),
+                _ => return false,
+            };
+            if !self.extensions.contains(&extension) {
+                return false;
+            }
+        }
+
+        // Files never identified have no content type and never match
+        if !self.content_types.is_empty()
+            && !node.content_type.is_some_and(|content_type| self.content_types.contains(&content_type))
+        {
+            return false;
+        }
+
+        // Size filters only apply to files (directories have no size)
+        if self.min_size.is_some() || self.max_size.is_some() {
+            let size = match node.size {
+                Some(size) => size,
+                None => return false,
+            };
+            if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+                return false;
+            }
+        }
+
+        if self.modified_after.is_some_and(|after| node.modified_time < after)
+            || self.modified_before.is_some_and(|before| node.modified_time >= before)
+        {
+            return false;
+        }
+
+        match (&self.pattern, &self.query_lower) {
+            (Some(pattern), _) => pattern.is_match(&node.name),
+            (None, Some(query)) => node.name.to_lowercase().contains(query.as_str()),
+            (None, None) => true,
+        }
+    }
+
+    /// Sort key, best match first: exact name, name prefix, other match;
+    /// then shallower paths, then name
+    pub fn rank_key(&self, node: &FileSystemNode) -> (u8, usize, String, u64) {
+        let name_lower = node.name.to_lowercase();
+        let relevance = match &self.query_lower {
+            Some(query) if name_lower == *query => 0,
+            Some(query) if name_lower.starts_with(query.as_str()) => 1,
+            Some(_) => 2,
+            None => 3,
+        };
+        let depth = node.display_path.trim_end_matches('\\').matches('\\').count();
+        (relevance, depth, name_lower, node.id)
+    }
+
+    /// Compile a glob for whole-string, case-insensitive matching (e.g. paths)
+    pub fn compile_glob(glob: &str) -> Result<Regex, String> {
+        Self::build_regex(&Self::glob_to_regex(glob))
+    }
+
+    /// Translate a glob into an anchored regex
+    fn glob_to_regex(glob: &str) -> String {
+        let mut pattern = String::from("^");
+        for c in glob.chars() {
+            match c {
+                '*' => pattern.push_str(".*"),
+                '?' => pattern.push('.'),
+                c => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push('$');
+        pattern
+    }
+
+    /// Case-insensitive, like Windows names
+    fn build_regex(pattern: &str) -> Result<Regex, String> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()
+            .map_err(|e| format!("Invalid pattern: {}", e))
+    }
+
+    fn split_list(list: Option<&str>) -> impl Iterator<Item = &str> {
+        list.unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+    }
+
+    fn parse_entry_type(name: &str) -> Result<EntryType, String> {
+        match name.to_lowercase().as_str() {
+            "file" => Ok(EntryType::File),
+            "directory" | "dir" | "folder" => Ok(EntryType::Directory),
+            "drive" => Ok(EntryType::Drive),
+            "symlink" => Ok(EntryType::Symlink),
+            "junction" => Ok(EntryType::Junction),
+            other => Err(format!("Unknown entry type '{}'", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(display_path: &str, entry_type: EntryType, size: Option<u64>, modified_time: u64) -> FileSystemNode {
+        FileSystemNode {
+            id: 7,
+            name: display_path.trim_end_matches('\\').rsplit('\\').next().unwrap_or_default().to_string(),
+            entry_type,
+            parent_id: Some(1),
+            children_ids: Vec::new(),
+            nt_path: String::new(),
+            display_path: display_path.to_string(),
+            size,
+            modified_time,
+            created_time: 0,
+            attributes: 0,
+            is_expanded: false,
+            is_accessible: true,
+            link_target: None,
+            classification: None,
+            content_type: None,
+            drive_kind: None,
+            device: None,
+        }
+    }
+
+    fn file(display_path: &str) -> FileSystemNode {
+        node(display_path, EntryType::File, Some(100), 1_000)
+    }
+
+    fn matcher(request: SearchRequest) -> SearchMatcher {
+        SearchMatcher::compile(&request).unwrap()
+    }
+
+    fn query(q: &str, mode: MatchMode) -> SearchMatcher {
+        matcher(SearchRequest { q: Some(q.to_string()), mode, ..SearchRequest::default() })
+    }
+
+    #[test]
+    fn glob_matches_the_whole_name() {
+        let xlsx = query("*.xlsx", MatchMode::Glob);
+        assert!(xlsx.matches(&file("D:\\Finance\\a.xlsx")));
+        assert!(xlsx.matches(&file("D:\\Finance\\Budget.XLSX")));
+        assert!(!xlsx.matches(&file("D:\\Finance\\a.xlsx.bak")));
+        assert!(!xlsx.matches(&file("D:\\Finance\\a_xlsx")));
+
+        let single = query("q?.csv", MatchMode::Glob);
+        assert!(single.matches(&file("D:\\q1.csv")));
+        assert!(!single.matches(&file("D:\\q10.csv")));
+        // Regex characters in a glob are literal
+        assert!(query("a+b(1).txt", MatchMode::Glob).matches(&file("D:\\a+b(1).txt")));
+    }
+
+    #[test]
+    fn regex_is_case_insensitive_and_unanchored() {
+        let pattern = query("^report_\\d{4}", MatchMode::Regex);
+        assert!(pattern.matches(&file("D:\\REPORT_2024.docx")));
+        assert!(!pattern.matches(&file("D:\\old_report_2024.docx")));
+        assert!(query("final", MatchMode::Regex).matches(&file("D:\\Budget FINAL.xlsx")));
+        assert!(SearchMatcher::compile(&SearchRequest {
+            q: Some("(".to_string()),
+            mode: MatchMode::Regex,
+            ..SearchRequest::default()
+        }).is_err());
+    }
+
+    #[test]
+    fn extension_and_type_filters() {
+        let docs = matcher(SearchRequest { ext: Some(".DOCX, xlsx".to_string()), ..SearchRequest::default() });
+        assert!(docs.matches(&file("D:\\a.docx")));
+        assert!(docs.matches(&file("D:\\b.XLSX")));
+        assert!(!docs.matches(&file("D:\\c.pdf")));
+        assert!(!docs.matches(&file("D:\\docx")));
+        // Folders have no extension, even with a dot in the name
+        assert!(!docs.matches(&node("D:\\archive.docx\\", EntryType::Directory, None, 0)));
+
+        let folders = matcher(SearchRequest { entry_types: Some("folder,junction".to_string()), ..SearchRequest::default() });
+        assert!(folders.matches(&node("D:\\Finance\\", EntryType::Directory, None, 0)));
+        assert!(folders.matches(&node("D:\\Link\\", EntryType::Junction, None, 0)));
+        assert!(!folders.matches(&file("D:\\a.docx")));
+        assert!(!folders.matches(&node("This PC", EntryType::VirtualRoot, None, 0)));
+        assert!(SearchMatcher::compile(&SearchRequest { entry_types: Some("socket".to_string()), ..SearchRequest::default() }).is_err());
+    }
+
+    #[test]
+    fn size_and_mtime_filters() {
+        let sized = matcher(SearchRequest { min_size: Some(10), max_size: Some(100), ..SearchRequest::default() });
+        assert!(sized.matches(&node("D:\\a.bin", EntryType::File, Some(10), 0)));
+        assert!(sized.matches(&node("D:\\b.bin", EntryType::File, Some(100), 0)));
+        assert!(!sized.matches(&node("D:\\c.bin", EntryType::File, Some(9), 0)));
+        assert!(!sized.matches(&node("D:\\d.bin", EntryType::File, Some(101), 0)));
+        // Directories have no size and never pass a size filter
+        assert!(!sized.matches(&node("D:\\Finance\\", EntryType::Directory, None, 0)));
+        assert!(!matcher(SearchRequest { min_size: Some(0), ..SearchRequest::default() })
+            .matches(&node("D:\\Finance\\", EntryType::Directory, None, 0)));
+
+        let recent = matcher(SearchRequest { modified_after: Some(1_000), modified_before: Some(2_000), ..SearchRequest::default() });
+        assert!(recent.matches(&node("D:\\a.txt", EntryType::File, Some(1), 1_000)));
+        assert!(!recent.matches(&node("D:\\b.txt", EntryType::File, Some(1), 999)));
+        assert!(!recent.matches(&node("D:\\c.txt", EntryType::File, Some(1), 2_000)));
+        assert!(recent.matches(&node("D:\\Finance\\", EntryType::Directory, None, 1_500)));
+    }
+
+    #[test]
+    fn inverted_ranges_are_rejected() {
+        assert!(SearchMatcher::compile(&SearchRequest { min_size: Some(10), max_size: Some(9), ..SearchRequest::default() }).is_err());
+        assert!(SearchMatcher::compile(&SearchRequest { min_size: Some(10), max_size: Some(10), ..SearchRequest::default() }).is_ok());
+        assert!(SearchMatcher::compile(&SearchRequest {
+            modified_after: Some(2_000),
+            modified_before: Some(1_000),
+            ..SearchRequest::default()
+        }).is_err());
+        // The window is half-open, so an empty one is refused too
+        assert!(SearchMatcher::compile(&SearchRequest {
+            modified_after: Some(1_000),
+            modified_before: Some(1_000),
+            ..SearchRequest::default()
+        }).is_err());
+    }
+
+    #[test]
+    fn exact_names_rank_before_prefixes_before_depth() {
+        let budget = query("budget", MatchMode::Substring);
+        let mut nodes = [
+            file("D:\\Old\\Q1\\q1 budget.xlsx"),
+            file("D:\\budget-2023.xlsx"),
+            file("D:\\Finance\\Deep\\Budget"),
+            file("D:\\Finance\\Budget-2024.xlsx"),
+            file("D:\\team budget.xlsx"),
+        ];
+        assert!(nodes.iter().all(|node| budget.matches(node)));
+        nodes.sort_by_cached_key(|node| budget.rank_key(node));
+
+        let ranked: Vec<&str> = nodes.iter().map(|node| node.display_path.as_str()).collect();
+        assert_eq!(ranked, vec![
+            "D:\\Finance\\Deep\\Budget",
+            "D:\\budget-2023.xlsx",
+            "D:\\Finance\\Budget-2024.xlsx",
+            "D:\\team budget.xlsx",
+            "D:\\Old\\Q1\\q1 budget.xlsx",
+        ]);
+    }
+}
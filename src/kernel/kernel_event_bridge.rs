@@ -7,6 +7,8 @@ use tokio::sync::mpsc;
 use serde::{Deserialize, Serialize};
 
 use crate::networking::WebSocketServer;
+use crate::nt_path_resolver::NtPathResolver;
+use crate::policy::PolicyEngine;
 
 /// Kernel event sent from minifilter to Agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +20,8 @@ pub struct KernelEvent {
     pub process_id: u32,           // Process ID
     pub decision: EnforcementDecision, // What happened
     pub timestamp: u64,            // When it happened
+    #[serde(default, skip_serializing)]
+    pub nt_path: Option<String>,   // Path reported by the kernel (INTERNAL ONLY)
 }
 
 /// Filesystem operation types (from kernel)
@@ -45,6 +49,7 @@ pub enum EnforcementDecision {
 pub struct KernelEventBridge {
     ws_server: Arc<WebSocketServer>,
     event_receiver: mpsc::Receiver<KernelEvent>,
+    policy_engine: Option<Arc<PolicyEngine>>,  // Attributes events by NT path
 }
 
 impl KernelEventBridge {
@@ -56,11 +61,18 @@ impl KernelEventBridge {
         let bridge = KernelEventBridge {
             ws_server,
             event_receiver,
+            policy_engine: None,
         };
         
         (bridge, event_sender)
     }
     
+    /// Attribute events that only carry an NT path to node and policy IDs
+    pub fn with_attribution(mut self, policy_engine: Arc<PolicyEngine>) -> Self {
+        self.policy_engine = Some(policy_engine);
+        self
+    }
+    
     /// Start processing kernel events
     pub async fn start(mut self) {
         println!("🔌 KernelEventBridge: Starting...");
@@ -74,6 +86,7 @@ impl KernelEventBridge {
     
     /// Handle a kernel event
     async fn handle_kernel_event(&self, event: KernelEvent) {
+        let event = self.attribute_event(event);
         println!("🔌 KernelEvent: Received from kernel");
         println!("   Node: {}, Policy: {}, Operation: {:?}", 
             event.node_id, event.policy_id, event.operation);
@@ -110,6 +123,31 @@ impl KernelEventBridge {
         }
     }
     
+    /// Fill in missing node/policy IDs from the event's NT path
+    fn attribute_event(&self, mut event: KernelEvent) -> KernelEvent {
+        let (engine, nt_path) = match (&self.policy_engine, &event.nt_path) {
+            (Some(engine), Some(nt_path)) => (engine, nt_path),
+            _ => return event,
+        };
+        
+        match engine.attribute_nt_path(nt_path) {
+            Some(attribution) => {
+                println!("   🎯 Attributed to node {} ({}), policy {:?}",
+                    attribution.node_id,
+                    if attribution.exact { "exact" } else { "covering ancestor" },
+                    attribution.policy_id);
+                if event.node_id == 0 {
+                    event.node_id = attribution.node_id;
+                }
+                if event.policy_id == 0 {
+                    event.policy_id = attribution.policy_id.unwrap_or(0);
+                }
+            }
+            None => println!("   ⚠️ No indexed node covers {}", NtPathResolver::describe_nt_path(nt_path)),
+        }
+        event
+    }
+    
     /// Convert kernel operation to string
     fn operation_to_string(&self, operation: KernelOperation) -> String {
         match operation {
@@ -152,6 +190,7 @@ impl MockKernelEventGenerator {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            nt_path: None,
         };
         
         if let Err(e) = self.event_sender.send(event).await {
@@ -161,4 +200,103 @@ impl MockKernelEventGenerator {
             self.next_event_id += 1;
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{KernelOperations, KernelPolicy, PathMatchType};
+    use proptest::prelude::*;
+
+    /// Agent-side stand-in for the minifilter's rule matching: the decision for
+    /// one operation on `nt_path` and the policy that made it
+    fn evaluate(policies: &[KernelPolicy], nt_path: &str, operation: KernelOperation) -> (EnforcementDecision, Option<u64>) {
+        let op_name = match operation {
+            KernelOperation::Read | KernelOperation::QueryInfo => "read",
+            KernelOperation::Write | KernelOperation::SetInfo => "write",
+            KernelOperation::Delete => "delete",
+            KernelOperation::Rename => "rename",
+            KernelOperation::Create => "create",
+        };
+
+        let mut matched = None;
+        for policy in policies.iter().filter(|policy| policy.matches_nt_path(nt_path)) {
+            if policy.should_block_operation(op_name) {
+                return (EnforcementDecision::Blocked, Some(policy.policy_id));
+            }
+            matched.get_or_insert(policy.policy_id);
+        }
+        match matched {
+            Some(policy_id) => (EnforcementDecision::Allowed, Some(policy_id)),
+            None => (EnforcementDecision::NotProtected, None),
+        }
+    }
+
+    fn rule(policy_id: u64, nt_path: &str, match_type: PathMatchType) -> KernelPolicy {
+        KernelPolicy {
+            policy_id,
+            nt_path: nt_path.to_string(),
+            match_type,
+            blocked_ops: KernelOperations::block_all(),
+            block_all: false,
+            created_by: "test".to_string(),
+            timestamp: 0,
+        }
+    }
+
+    fn blocked_by(policies: &[KernelPolicy], nt_path: &str) -> Option<u64> {
+        match evaluate(policies, nt_path, KernelOperation::Write) {
+            (EnforcementDecision::Blocked, policy_id) => policy_id,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn read_only_rules_let_reads_through() {
+        let policies = [rule(7, "\\Device\\HarddiskVolume3\\Finance\\", PathMatchType::Prefix)];
+        let path = "\\Device\\HarddiskVolume3\\Finance\\q1.xlsx";
+
+        assert_eq!(blocked_by(&policies, path), Some(7));
+        assert!(matches!(
+            evaluate(&policies, path, KernelOperation::Read),
+            (EnforcementDecision::Allowed, Some(7))
+        ));
+        assert!(matches!(
+            evaluate(&policies, "\\Device\\HarddiskVolume3\\Finance2\\q1.xlsx", KernelOperation::Write),
+            (EnforcementDecision::NotProtected, None)
+        ));
+    }
+
+    proptest! {
+        #[test]
+        fn exact_rules_catch_every_spelling(
+            dir in "[A-Za-z][A-Za-z0-9 ]{0,8}[A-Za-z0-9]",
+            file in "[A-Za-z][A-Za-z0-9]{0,8}\\.[a-z]{1,4}",
+            upper in any::<bool>(),
+            padding in "[. ]{0,3}",
+            stream in prop_oneof![Just(""), Just("::$DATA"), Just(":hidden"), Just(":hidden:$DATA")],
+        ) {
+            let policies = [rule(42, &format!("\\Device\\HarddiskVolume3\\{}\\{}", dir, file), PathMatchType::Exact)];
+            let spelled = |s: &str| if upper { s.to_uppercase() } else { s.to_lowercase() };
+            let variant = format!(
+                "\\DEVICE\\harddiskvolume3\\{}{}\\{}{}{}",
+                spelled(&dir), padding, spelled(&file), padding, stream
+            );
+            prop_assert_eq!(blocked_by(&policies, &variant), Some(42), "{}", variant);
+        }
+
+        #[test]
+        fn prefix_rules_catch_every_spelling_below_the_folder(
+            dir in "[A-Za-z][A-Za-z0-9]{0,8}",
+            rest in prop::collection::vec("[A-Za-z0-9]{1,8}", 1..4),
+            padding in "[. ]{0,2}",
+        ) {
+            let policies = [rule(9, &format!("\\Device\\HarddiskVolume3\\{}\\", dir), PathMatchType::Prefix)];
+            let variant = format!("\\Device\\HarddiskVolume3\\{}{}\\{}::$DATA", dir.to_uppercase(), padding, rest.join("\\"));
+            prop_assert_eq!(blocked_by(&policies, &variant), Some(9), "{}", variant);
+
+            let sibling = format!("\\Device\\HarddiskVolume3\\{}x\\{}", dir, rest.join("\\"));
+            prop_assert_eq!(blocked_by(&policies, &sibling), None);
+        }
+    }
+}
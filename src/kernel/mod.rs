@@ -9,7 +9,7 @@ pub use kernel_event_bridge::{
     KernelEvent, 
     KernelOperation, 
     EnforcementDecision,
-    MockKernelEventGenerator
+    MockKernelEventGenerator,
 };
 
 /// Initialize STEP 6 kernel integration
@@ -1963,6 +1963,7 @@
 // main.rs
 mod fs_index;
 mod path_normalizer;
+mod path_canonical;
 mod filesystem_scanner;
 mod query_interface;
 mod comms;
@@ -1972,6 +1973,12 @@ mod policy;
 mod networking;
 mod kernel;
 mod nt_path_resolver;
+mod volume_map;
+mod nt_path_trie;
+mod index_search;
+mod fs_watcher;
+mod discovery;
+mod file_type;
 use fs_index::FilesystemIndex;
 use filesystem_scanner::FileSystemScanner;
 use query_interface::QueryInterface;
@@ -1992,10 +1999,27 @@ async fn main() -> Result<(), String> {
     println!("🧱 STEP 1: Building Filesystem Index...");
 
     let index = Arc::new(FilesystemIndex::new());
+    let identity_file = FilesystemIndex::default_identity_file();
+    match index.load_identities(&identity_file) {
+        Ok(count) => println!("🆔 Loaded {} stable node IDs from {}", count, identity_file),
+        Err(e) => println!("⚠️  Stable node IDs: {}", e),
+    }
+    index.set_memory_ceiling(FilesystemIndex::default_memory_ceiling());
+    match index.memory_ceiling_bytes() {
+        0 => println!("🧠 Index memory ceiling: unlimited"),
+        bytes => println!("🧠 Index memory ceiling: {} MiB (LRU eviction above)", bytes / (1024 * 1024)),
+    }
+    // Warm start from the last snapshot (stale directories re-read on expand)
+    let snapshot_file = FilesystemIndex::default_snapshot_file();
+    match index.load_snapshot(&snapshot_file) {
+        Ok(0) => println!("📸 No index snapshot, cold start"),
+        Ok(count) => println!("📸 Loaded {} nodes from index snapshot {}", count, snapshot_file),
+        Err(e) => println!("⚠️  Index snapshot: {}", e),
+    }
     // Create path resolver
     let path_resolver = Arc::new(PathResolver::new(index.clone()));
     let scanner = FileSystemScanner::new(index.clone(), path_resolver.clone());
-    let query = QueryInterface::new(index.clone());
+    let query = QueryInterface::new(index.clone()).with_scan_state(scanner.scan_state_handle());
 
     // Initialize drives only (no full scan)
     match scanner.initialize_drives() {
@@ -2103,10 +2127,152 @@ async fn main() -> Result<(), String> {
         println!("✅ PolicyEngine updated with kernel event support");
 
         // Start kernel event bridge in background
+        let kernel_event_bridge = kernel_event_bridge.with_attribution(policy_engine.clone());
         let bridge_handle = tokio::spawn(async move {
             kernel_event_bridge.start().await;
         });
 
+    // Save the index snapshot periodically (only writes when the tree changed)
+    let snapshot_index = index.clone();
+    let snapshot_path = snapshot_file.clone();
+    let snapshot_handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let index = snapshot_index.clone();
+            let path = snapshot_path.clone();
+            match tokio::task::spawn_blocking(move || index.save_snapshot(&path)).await {
+                Ok(Ok(0)) => {}
+                Ok(Ok(count)) => println!("📸 Index snapshot saved ({} nodes)", count),
+                Ok(Err(e)) => println!("⚠️  Index snapshot: {}", e),
+                Err(e) => println!("⚠️  Index snapshot task failed: {}", e),
+            }
+        }
+    });
+
+    // Stored device rules cover the drives present now, and drop policies
+    // of drives that left while the agent was down
+    let startup_device_control = server_handle.as_ref().unwrap().device_control();
+    if let Err(e) = tokio::task::spawn_blocking(move || startup_device_control.enforce()).await {
+        println!("⚠️  Device rule enforcement task failed: {}", e);
+    }
+
+    // Track volumes arriving and leaving (drive letter → device map); drive
+    // nodes follow, and device rules cover drives as they arrive
+    let volume_handle = volume_map::VolumeMap::configured_refresh_interval().map(|interval| {
+        let volumes = volume_map::VolumeMap::global();
+        println!("💽 Volume map ({}): {} volumes, {} network drives, refresh every {:?}",
+            volumes.mapper_name(), volumes.volumes().len(), volumes.network_drives().len(), interval);
+        let scanner = api_server.scanner().clone();
+        let device_control = server_handle.as_ref().unwrap().device_control();
+        let drive_events = ws_server.clone();
+        let mut seen_generation = volumes.generation();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let volumes = volume_map::VolumeMap::global();
+                match tokio::task::spawn_blocking(move || volumes.refresh()).await {
+                    Ok(Ok(changes)) if !changes.is_empty() => volumes.log_changes(&changes),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => println!("⚠️  Volume refresh: {}", e),
+                    Err(e) => println!("⚠️  Volume refresh task failed: {}", e),
+                }
+                if volumes.generation() == seen_generation {
+                    continue;
+                }
+                seen_generation = volumes.generation();
+
+                let scanner = scanner.clone();
+                let device_control = device_control.clone();
+                let sync = tokio::task::spawn_blocking(move || {
+                    let changes = scanner.refresh_drives();
+                    let arrived: Vec<_> = changes.arrived.iter()
+                        .filter_map(|&node_id| scanner.index().get_node(node_id))
+                        .collect();
+                    (arrived, changes.removed, device_control.enforce())
+                });
+                match sync.await {
+                    Ok((arrived, removed, report)) => {
+                        for drive in &arrived {
+                            drive_events.broadcast_drive_arrived(drive);
+                        }
+                        for &node_id in &removed {
+                            drive_events.broadcast_drive_removed(node_id);
+                        }
+                        drive_events.broadcast_device_policies(&report);
+                    }
+                    Err(e) => println!("⚠️  Drive refresh task failed: {}", e),
+                }
+            }
+        })
+    });
+
+    // Keep expanded directories in sync with the disk
+    let watch_handle = match fs_watcher::FilesystemWatcher::configured_interval() {
+        Some(interval) => {
+            let watcher = Arc::new(fs_watcher::FilesystemWatcher::with_polling(
+                index.clone(),
+                api_server.scanner().clone(),
+            ));
+            println!("👀 Filesystem watcher started ({} backend, every {:?})", watcher.backend_name(), interval);
+            println!("   • Renamed protected nodes: {}", policy_engine.retarget_mode().as_str());
+            let watch_events = ws_server.clone();
+            let watch_policies = policy_engine.clone();
+            Some(tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    ticker.tick().await;
+                    let watcher = watcher.clone();
+                    let policies = watch_policies.clone();
+                    let poll = tokio::task::spawn_blocking(move || {
+                        let changes = watcher.poll_once();
+                        // Policies on renamed/moved nodes must follow (or knowingly stay)
+                        let retargets: Vec<_> = changes.iter()
+                            .filter(|change| change.kind == fs_watcher::FsChangeKind::Renamed)
+                            .flat_map(|change| policies.retarget_policies(change.node_id))
+                            .collect();
+                        // Protected nodes deleted or moved where the watcher cannot follow
+                        let orphans = if changes.iter().any(|change| change.kind == fs_watcher::FsChangeKind::Deleted) {
+                            policies.orphan_missing_policies()
+                        } else {
+                            Vec::new()
+                        };
+                        // New or rewritten files may be copies of protected content
+                        let written: Vec<u64> = changes.iter()
+                            .filter(|change| matches!(change.kind, fs_watcher::FsChangeKind::Created | fs_watcher::FsChangeKind::Modified))
+                            .map(|change| change.node_id)
+                            .collect();
+                        let copies = if written.is_empty() { Vec::new() } else { policies.find_protected_copies(&written) };
+                        (changes, retargets, orphans, copies)
+                    });
+                    match poll.await {
+                        Ok((changes, retargets, orphans, copies)) => {
+                            for change in &changes {
+                                watch_events.broadcast_filesystem_changed(change);
+                            }
+                            for retarget in &retargets {
+                                watch_events.broadcast_policy_retargeted(retarget);
+                            }
+                            for orphan in &orphans {
+                                watch_events.broadcast_policy_orphaned(orphan);
+                            }
+                            for copy in &copies {
+                                watch_events.broadcast_protected_copy(copy);
+                            }
+                        }
+                        Err(e) => println!("⚠️  Filesystem watcher task failed: {}", e),
+                    }
+                }
+            }))
+        }
+        None => {
+            println!("👀 Filesystem watcher disabled ({}=0)", fs_watcher::WATCH_INTERVAL_ENV);
+            None
+        }
+    };
+
     // Recreate PolicyEngine with kernel events for STEP 6
     println!("🔄 Updating Policy Engine with kernel event support...");
     println!("✅ STEP 6 Complete: Kernel enforcement ready");
@@ -2141,11 +2307,38 @@ async fn main() -> Result<(), String> {
     println!("   GET  /api/v1/nodes/:id/children - List children");
     println!("   POST /api/v1/nodes/:id/expand  - Expand directory");
     println!("   POST /api/v1/nodes/:id/collapse - Collapse directory");
+    println!("   POST /api/v1/nodes/:id/identify - Identify file type (magic bytes)");
+    println!("   GET  /api/v1/search            - Global search (glob/regex, filters)");
     println!("   GET  /api/v1/search/local      - Local search");
     println!("   GET  /api/v1/stats             - System stats");
+    println!("   POST /api/v1/scan              - Start background crawl");
+    println!("   GET  /api/v1/scan/:id          - Crawl progress");
+    println!("   POST /api/v1/scan/:id/cancel   - Cancel crawl");
+    println!("   POST /api/v1/discovery/scan    - Classify file contents (PII/PCI/secrets)");
+    println!("   GET  /api/v1/discovery/scan/:id - Discovery progress");
+    println!("   GET  /api/v1/discovery/findings - Files with sensitive content");
+    println!("   POST /api/v1/discovery/rules   - Add auto-protection rule");
+    println!("   GET  /api/v1/discovery/rules/:id/preview - Dry-run a rule");
+    println!("   GET  /api/v1/fingerprints      - Protected content fingerprints");
+    println!("   POST /api/v1/devices/rules     - Add device rule (class or volume serial)");
+    println!("   GET  /api/v1/devices/rules/:id/drives - Drives a device rule covers");
     println!("   POST /api/v1/policies/apply    - Apply protection");
+    println!("   PUT  /api/v1/policies/:id      - Update protection (new version)");
     println!("   DELETE /api/v1/policies/:id    - Remove protection");
+    println!("   GET  /api/v1/policies/:id/history - Policy version history");
+    println!("   GET  /api/v1/policies/:id/diff - Diff two policy versions");
+    println!("   POST /api/v1/policies/:id/revert/:version - Revert to version");
     println!("   GET  /api/v1/policies          - List all policies");
+    println!("   GET  /api/v1/policies/pending  - High-risk changes awaiting approval");
+    println!("   POST /api/v1/policies/pending/:id/approve - Second admin approves");
+    println!("   POST /api/v1/policies/pending/:id/reject  - Reject pending change");
+    println!("   POST /api/v1/policies/export   - Export signed policy bundle");
+    println!("   POST /api/v1/policies/import   - Import policy bundle (dry-run supported)");
+    println!("   GET  /api/v1/enforcement/status - Break-glass state");
+    println!("   POST /api/v1/enforcement/suspend - Break glass: clear kernel rules");
+    println!("   POST /api/v1/enforcement/resume - Re-push all policies to kernel");
+    println!("   GET  /api/v1/audit/admin       - Admin audit trail (paged)");
+    println!("   GET  /api/v1/audit/admin/verify - Verify audit hash chain");
     println!("   GET  /api/v1/ping              - Health check");
     println!("==================================================");
 
@@ -2154,6 +2347,7 @@ async fn main() -> Result<(), String> {
     println!("   • NT paths never leave the Agent");
     println!("   • Kernel rules enforced locally");
     println!("   • Policy validation at every step");
+    println!("   • Admin actions hash-chained in audit trail");
     println!("==================================================");
 
     println!("\n⏳ Waiting for Admin Server connections...");
@@ -2166,6 +2360,29 @@ async fn main() -> Result<(), String> {
     // Stop kernel event bridge
     bridge_handle.abort();
     println!("✅ Kernel event bridge stopped");
+
+    // Stop the watcher, background crawls and discovery scans before the final snapshot
+    if let Some(handle) = watch_handle {
+        handle.abort();
+    }
+    if let Some(handle) = volume_handle {
+        handle.abort();
+    }
+    let cancelled = api_server.scanner().cancel_all_crawls();
+    if cancelled > 0 {
+        println!("✅ Cancelled {} background crawls", cancelled);
+    }
+    let cancelled = api_server.discovery().cancel_all_scans();
+    if cancelled > 0 {
+        println!("✅ Cancelled {} discovery scans", cancelled);
+    }
+
+    // Final index snapshot for the next warm start
+    snapshot_handle.abort();
+    match index.save_snapshot(&snapshot_file) {
+        Ok(count) => println!("✅ Index snapshot saved ({} nodes)", count),
+        Err(e) => println!("⚠️  Index snapshot: {}", e),
+    }
     
     // Gracefully shutdown networking
     if let Some(handle) = server_handle {
@@ -0,0 +1,534 @@
+//! Admin Action Audit Trail (STEP 5.3)
+//! Core Principle: Every mutating admin call is recorded, and the record is tamper-evident
+//! Each entry carries the hash of the previous one, so editing or deleting any
+//! entry breaks the chain from that point on. Hashes are HMAC-SHA256 under an
+//! agent key, so whoever can write the log cannot recompute the chain after an
+//! edit. The key comes from ADMIN_AUDIT_KEY_ENV, else from a key file created
+//! next to the log on first start (keep it out of reach of the log's writers).
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::RandomState;
+use std::fs::{self, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::policy::policy_bundle::hmac_sha256;
+
+/// Environment variable overriding the audit log location
+pub const ADMIN_AUDIT_LOG_ENV: &str = "AGENT_ADMIN_AUDIT_LOG";
+
+/// Default audit log file (JSON lines, one entry per line)
+const DEFAULT_ADMIN_AUDIT_LOG: &str = "dlp_admin_audit.jsonl";
+
+/// Environment variable holding the chain key (else "<log>.key" is used)
+pub const ADMIN_AUDIT_KEY_ENV: &str = "AGENT_ADMIN_AUDIT_KEY";
+
+/// prev_hash of the first entry
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// One audited admin action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminAuditEntry {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub action: String,                // "apply", "confirm", "update", "remove", ...
+    pub method: String,
+    pub path: String,
+    pub caller: String,                // X-ADMIN-USER header or body identity
+    pub source_address: String,
+    pub request_digest: String,        // hex SHA-256 of the raw request body
+    pub status_code: u16,
+    pub outcome: String,               // "success" or "failure"
+    pub error_code: Option<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub response_truncated: bool,      // Response too large to inspect for an error code
+    pub prev_hash: String,
+    pub entry_hash: String,            // hex HMAC-SHA256 over all fields above
+}
+
+/// Hashed portion of an entry (field order is the canonical order)
+#[derive(Serialize)]
+struct AdminAuditPayload<'a> {
+    sequence: u64,
+    timestamp: u64,
+    action: &'a str,
+    method: &'a str,
+    path: &'a str,
+    caller: &'a str,
+    source_address: &'a str,
+    request_digest: &'a str,
+    status_code: u16,
+    outcome: &'a str,
+    error_code: Option<&'a str>,
+    #[serde(skip_serializing_if = "is_false")]
+    response_truncated: bool,          // Omitted when false so older entries hash the same
+    prev_hash: &'a str,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Details of a completed admin action, before chaining
+#[derive(Debug, Clone)]
+pub struct AdminActionRecord {
+    pub action: String,
+    pub method: String,
+    pub path: String,
+    pub caller: String,
+    pub source_address: String,
+    pub request_digest: String,
+    pub status_code: u16,
+    pub error_code: Option<String>,
+    pub response_truncated: bool,
+}
+
+/// Result of a chain verification
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainVerification {
+    pub valid: bool,
+    pub entries_checked: u64,
+    pub first_invalid_sequence: Option<u64>,
+    pub message: String,
+}
+
+/// Hash-chained, append-only admin audit log
+pub struct AdminAuditLog {
+    log_path: String,
+    key: Vec<u8>,                      // Chain key (HMAC)
+    entries: RwLock<Vec<AdminAuditEntry>>,
+}
+
+impl AdminAuditLog {
+    /// Open the audit log, continuing the chain of an existing file
+    pub fn open() -> Arc<Self> {
+        let log_path = std::env::var(ADMIN_AUDIT_LOG_ENV)
+            .unwrap_or_else(|_| DEFAULT_ADMIN_AUDIT_LOG.to_string());
+        Self::open_at(&log_path)
+    }
+
+    /// Open the audit log at `log_path`
+    /// An unreadable log is moved aside and the new chain starts by recording
+    /// that; if it cannot be moved the agent refuses to start. So does a chain
+    /// key that cannot be read or created.
+    pub fn open_at(log_path: &str) -> Arc<Self> {
+        let key = match Self::chain_key(log_path) {
+            Ok(key) => key,
+            Err(e) => panic!("AdminAudit: No chain key for {}: {}", log_path, e),
+        };
+        let (entries, rotated) = match Self::read_entries(log_path) {
+            Ok(entries) => (entries, None),
+            Err(e) => {
+                let backup = format!("{}.corrupt-{}", log_path, SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs());
+                let content = fs::read(log_path).unwrap_or_default();
+                if let Err(rename_err) = fs::rename(log_path, &backup) {
+                    panic!("AdminAudit: {} is unreadable ({}) and could not be moved aside: {}",
+                        log_path, e, rename_err);
+                }
+                println!("🚨 AdminAudit: {} is unreadable ({}), moved to {}", log_path, e, backup);
+                (Vec::new(), Some((backup, content)))
+            }
+        };
+
+        let log = Arc::new(AdminAuditLog {
+            log_path: log_path.to_string(),
+            key,
+            entries: RwLock::new(entries),
+        });
+
+        if let Some((backup, content)) = rotated {
+            log.record(AdminActionRecord {
+                action: "log_rotated".to_string(),
+                method: "STARTUP".to_string(),
+                path: backup,
+                caller: "agent".to_string(),
+                source_address: "local".to_string(),
+                request_digest: Self::digest(&content),
+                status_code: 500,
+                error_code: Some("AUDIT_LOG_UNREADABLE".to_string()),
+                response_truncated: false,
+            });
+        }
+
+        let verification = log.verify();
+        if verification.valid {
+            println!("🧾 AdminAudit: {} entries loaded from {} (chain intact)",
+                verification.entries_checked, log.log_path);
+        } else {
+            println!("🚨 AdminAudit: CHAIN BROKEN in {}: {}", log.log_path, verification.message);
+        }
+
+        log
+    }
+
+    /// Append an action to the chain and persist it
+    pub fn record(&self, record: AdminActionRecord) -> AdminAuditEntry {
+        let mut entries = self.entries.write();
+
+        let (sequence, prev_hash) = match entries.last() {
+            Some(last) => (last.sequence + 1, last.entry_hash.clone()),
+            None => (1, GENESIS_HASH.to_string()),
+        };
+
+        let outcome = if (200..300).contains(&record.status_code) { "success" } else { "failure" };
+
+        let mut entry = AdminAuditEntry {
+            sequence,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            action: record.action,
+            method: record.method,
+            path: record.path,
+            caller: record.caller,
+            source_address: record.source_address,
+            request_digest: record.request_digest,
+            status_code: record.status_code,
+            outcome: outcome.to_string(),
+            error_code: record.error_code,
+            response_truncated: record.response_truncated,
+            prev_hash,
+            entry_hash: String::new(),
+        };
+        entry.entry_hash = self.compute_hash(&entry);
+
+        if let Err(e) = self.append_to_file(&entry) {
+            println!("⚠️  AdminAudit: Failed to persist entry {}: {}", entry.sequence, e);
+        }
+
+        println!("🧾 AdminAudit #{}: {} {} by {} from {} → {} ({})",
+            entry.sequence, entry.action, entry.path, entry.caller,
+            entry.source_address, entry.status_code, entry.outcome);
+
+        entries.push(entry.clone());
+        entry
+    }
+
+    /// Page through entries (oldest first)
+    pub fn page(&self, offset: usize, limit: usize) -> (Vec<AdminAuditEntry>, usize) {
+        let entries = self.entries.read();
+        let page = entries.iter().skip(offset).take(limit).cloned().collect();
+        (page, entries.len())
+    }
+
+    /// Verify the on-disk chain, and that it still ends where this agent left it
+    pub fn verify(&self) -> ChainVerification {
+        let on_disk = match Self::read_entries(&self.log_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                return ChainVerification {
+                    valid: false,
+                    entries_checked: 0,
+                    first_invalid_sequence: None,
+                    message: format!("Audit log unreadable: {}", e),
+                };
+            }
+        };
+
+        let mut expected_prev = GENESIS_HASH.to_string();
+        for (position, entry) in on_disk.iter().enumerate() {
+            let expected_sequence = position as u64 + 1;
+            let problem = if entry.sequence != expected_sequence {
+                Some(format!("Expected sequence {}, found {}", expected_sequence, entry.sequence))
+            } else if entry.prev_hash != expected_prev {
+                Some(format!("Entry {} does not link to its predecessor", entry.sequence))
+            } else if entry.entry_hash != self.compute_hash(entry) {
+                Some(format!("Entry {} content does not match its hash", entry.sequence))
+            } else {
+                None
+            };
+
+            if let Some(message) = problem {
+                return ChainVerification {
+                    valid: false,
+                    entries_checked: position as u64,
+                    first_invalid_sequence: Some(expected_sequence),
+                    message,
+                };
+            }
+            expected_prev = entry.entry_hash.clone();
+        }
+
+        // Truncating the tail keeps the remaining chain valid, so compare with memory
+        let entries = self.entries.read();
+        if let Some(last) = entries.last() {
+            if on_disk.last().map(|e| &e.entry_hash) != Some(&last.entry_hash) {
+                return ChainVerification {
+                    valid: false,
+                    entries_checked: on_disk.len() as u64,
+                    first_invalid_sequence: Some(on_disk.len() as u64 + 1),
+                    message: format!("Log ends at entry {} but agent recorded {}",
+                        on_disk.len(), last.sequence),
+                };
+            }
+        }
+
+        ChainVerification {
+            valid: true,
+            entries_checked: on_disk.len() as u64,
+            first_invalid_sequence: None,
+            message: "Audit chain intact".to_string(),
+        }
+    }
+
+    /// Hex SHA-256 digest of a request body
+    pub fn digest(body: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(body))
+    }
+
+    /// Chain key: ADMIN_AUDIT_KEY_ENV, else "<log>.key" (created on first start)
+    fn chain_key(log_path: &str) -> Result<Vec<u8>, String> {
+        if let Ok(key) = std::env::var(ADMIN_AUDIT_KEY_ENV) {
+            if !key.trim().is_empty() {
+                return Ok(key.into_bytes());
+            }
+        }
+
+        let key_path = format!("{}.key", log_path);
+        match fs::read_to_string(&key_path) {
+            Ok(key) if !key.trim().is_empty() => return Ok(key.trim().as_bytes().to_vec()),
+            Ok(_) => return Err(format!("{} is empty", key_path)),
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(format!("{}: {}", key_path, e)),
+            Err(_) => {}
+        }
+
+        // RandomState is seeded from the OS random source
+        let mut seed = Sha256::new();
+        for _ in 0..4 {
+            seed.update(RandomState::new().build_hasher().finish().to_le_bytes());
+        }
+        seed.update(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_le_bytes());
+        let key = format!("{:x}", seed.finalize());
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&key_path).map_err(|e| format!("Failed to create {}: {}", key_path, e))?;
+        file.write_all(key.as_bytes()).map_err(|e| format!("Failed to write {}: {}", key_path, e))?;
+        println!("🔑 AdminAudit: Created chain key {} (set {} to keep it elsewhere)", key_path, ADMIN_AUDIT_KEY_ENV);
+        Ok(key.into_bytes())
+    }
+
+    /// Hash an entry over its canonical payload (keyed)
+    fn compute_hash(&self, entry: &AdminAuditEntry) -> String {
+        let payload = AdminAuditPayload {
+            sequence: entry.sequence,
+            timestamp: entry.timestamp,
+            action: &entry.action,
+            method: &entry.method,
+            path: &entry.path,
+            caller: &entry.caller,
+            source_address: &entry.source_address,
+            request_digest: &entry.request_digest,
+            status_code: entry.status_code,
+            outcome: &entry.outcome,
+            error_code: entry.error_code.as_deref(),
+            response_truncated: entry.response_truncated,
+            prev_hash: &entry.prev_hash,
+        };
+        let bytes = serde_json::to_vec(&payload).unwrap_or_default();
+        format!("{:x}", hmac_sha256(&self.key, &bytes))
+    }
+
+    /// Append one JSON line to the log file
+    fn append_to_file(&self, entry: &AdminAuditEntry) -> Result<(), String> {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("Failed to serialize entry: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_path)
+            .map_err(|e| format!("Failed to open {}: {}", self.log_path, e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", self.log_path, e))
+    }
+
+    /// Read all entries from a log file (missing file = empty log)
+    fn read_entries(log_path: &str) -> Result<Vec<AdminAuditEntry>, String> {
+        let content = match fs::read_to_string(log_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| serde_json::from_str(line)
+                .map_err(|e| format!("Line {} is not a valid entry: {}", i + 1, e)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str) -> String {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        std::env::temp_dir()
+            .join(format!("dlp_{}_{}_{}.jsonl", name, std::process::id(), nanos))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn record(action: &str, status_code: u16) -> AdminActionRecord {
+        AdminActionRecord {
+            action: action.to_string(),
+            method: "POST".to_string(),
+            path: "/api/v1/policies/apply".to_string(),
+            caller: "alice".to_string(),
+            source_address: "127.0.0.1:50000".to_string(),
+            request_digest: AdminAuditLog::digest(b"{}"),
+            status_code,
+            error_code: None,
+            response_truncated: false,
+        }
+    }
+
+    fn rewrite_lines(path: &str, edit: impl FnOnce(&mut Vec<String>)) {
+        let mut lines: Vec<String> = fs::read_to_string(path).unwrap().lines().map(String::from).collect();
+        edit(&mut lines);
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    fn cleanup(path: &str) {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(format!("{}.key", path));
+        let dir = std::path::Path::new(path).parent().unwrap();
+        let name = std::path::Path::new(path).file_name().unwrap().to_string_lossy().into_owned();
+        for entry in fs::read_dir(dir).unwrap().flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&format!("{}.corrupt-", name)) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    #[test]
+    fn chain_links_entries_and_survives_reopen() {
+        let path = temp_log("audit_chain");
+        let log = AdminAuditLog::open_at(&path);
+        let first = log.record(record("apply", 200));
+        let second = log.record(record("remove", 404));
+        assert_eq!(first.prev_hash, GENESIS_HASH);
+        assert_eq!(second.prev_hash, first.entry_hash);
+        assert_eq!(second.outcome, "failure");
+        assert!(log.verify().valid);
+
+        let reopened = AdminAuditLog::open_at(&path);
+        assert_eq!(reopened.record(record("update", 200)).sequence, 3);
+        assert!(reopened.verify().valid);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn edited_entry_breaks_the_chain() {
+        let path = temp_log("audit_edit");
+        let log = AdminAuditLog::open_at(&path);
+        for _ in 0..3 {
+            log.record(record("apply", 200));
+        }
+        rewrite_lines(&path, |lines| lines[1] = lines[1].replace("\"alice\"", "\"mallory\""));
+
+        let verification = log.verify();
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_sequence, Some(2));
+        cleanup(&path);
+    }
+
+    #[test]
+    fn deleted_and_truncated_entries_are_detected() {
+        let path = temp_log("audit_delete");
+        let log = AdminAuditLog::open_at(&path);
+        for _ in 0..3 {
+            log.record(record("apply", 200));
+        }
+
+        // Dropping a middle entry breaks the sequence
+        let original = fs::read_to_string(&path).unwrap();
+        rewrite_lines(&path, |lines| { lines.remove(1); });
+        assert_eq!(log.verify().first_invalid_sequence, Some(2));
+
+        // Dropping the tail leaves a valid chain, but not the one the agent wrote
+        fs::write(&path, &original).unwrap();
+        rewrite_lines(&path, |lines| { lines.pop(); });
+        let verification = log.verify();
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_sequence, Some(3));
+        cleanup(&path);
+    }
+
+    #[test]
+    fn truncated_flag_is_hashed_only_when_set() {
+        let path = temp_log("audit_truncated");
+        let log = AdminAuditLog::open_at(&path);
+        let plain = log.record(record("export", 200));
+        let mut large = record("export", 200);
+        large.response_truncated = true;
+        let truncated = log.record(large);
+
+        let lines: Vec<String> = fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+        assert!(!lines[0].contains("response_truncated"));
+        assert!(lines[1].contains("\"response_truncated\":true"));
+        assert!(!plain.response_truncated && truncated.response_truncated);
+
+        // Clearing the flag on disk is tampering like any other edit
+        rewrite_lines(&path, |lines| lines[1] = lines[1].replace("\"response_truncated\":true,", ""));
+        assert_eq!(log.verify().first_invalid_sequence, Some(2));
+        cleanup(&path);
+    }
+
+    #[test]
+    fn unreadable_log_is_moved_aside_and_recorded() {
+        let path = temp_log("audit_corrupt");
+        fs::write(&path, "{not an entry\n").unwrap();
+
+        let log = AdminAuditLog::open_at(&path);
+        let (entries, total) = log.page(0, 10);
+        assert_eq!(total, 1);
+        assert_eq!(entries[0].action, "log_rotated");
+        assert_eq!(entries[0].request_digest, AdminAuditLog::digest(b"{not an entry\n"));
+        assert!(std::path::Path::new(&entries[0].path).exists());
+        assert!(log.verify().valid);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn recomputed_chain_fails_without_the_key() {
+        let path = temp_log("audit_recomputed");
+        let log = AdminAuditLog::open_at(&path);
+        for _ in 0..3 {
+            log.record(record("apply", 200));
+        }
+        assert!(fs::read_to_string(format!("{}.key", path)).is_ok());
+
+        // Edit an entry and rebuild every hash after it, as plain SHA-256 would allow
+        let mut entries = AdminAuditLog::read_entries(&path).unwrap();
+        let forger = AdminAuditLog { log_path: path.clone(), key: b"guessed".to_vec(), entries: RwLock::new(Vec::new()) };
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for entry in entries.iter_mut() {
+            if entry.sequence == 2 {
+                entry.caller = "mallory".to_string();
+            }
+            entry.prev_hash = prev_hash;
+            entry.entry_hash = forger.compute_hash(entry);
+            prev_hash = entry.entry_hash.clone();
+        }
+        let lines: Vec<String> = entries.iter().map(|entry| serde_json::to_string(entry).unwrap()).collect();
+        fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        // A restarted agent has no memory of the old chain, only the key
+        let verification = AdminAuditLog::open_at(&path).verify();
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid_sequence, Some(1));
+        cleanup(&path);
+    }
+}
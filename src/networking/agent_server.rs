@@ -2,8 +2,9 @@
 //! Core Principle: Expose existing APIs over HTTP, NO new logic, NO NT paths in responses
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::HttpBody,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, Method, StatusCode},
     response::IntoResponse,
     Json, Router,
     routing::{get, post, delete},
@@ -12,10 +13,19 @@ use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use crate::{comms::{AdminRequest, AgentResponse, QueryApiServer}, networking::WebSocketServer, policy::{ProtectionAction, ProtectionOperations, ProtectionScope}};
+use crate::comms::PageRequest;
+use crate::discovery::{DiscoveryOptions, FindingsQuery};
+use crate::filesystem_scanner::{CrawlOptions, CrawlStatus, ScanConfig};
+use crate::index_search::SearchRequest;
 use crate::policy::PolicyEngine;
 use crate::policy::PolicyIntent;
 use crate::policy::policy_preview::PolicyPreviewService;
 use crate::policy::policy_store::HealthStatus;
+use crate::policy::PolicyBundle;
+use crate::policy::policy_approval::{ChangeOutcome, PendingApproval};
+use crate::policy::auto_protection::{AutoProtectionEngine, AutoProtectionRule, RuleRunReport};
+use crate::policy::device_control::{DeviceControlEngine, DeviceRule};
+use super::admin_audit::{AdminActionRecord, AdminAuditLog};
 
 /// Server state shared across all handlers
 #[derive(Clone)]
@@ -23,6 +33,9 @@ pub struct ServerState {
     query_api: Arc<QueryApiServer>,
     policy_engine: Arc<PolicyEngine>,
       ws_server: Arc<WebSocketServer>, // Add WebSocket server
+    admin_audit: Arc<AdminAuditLog>,
+    auto_protection: Arc<AutoProtectionEngine>,
+    device_control: Arc<DeviceControlEngine>,
 }
 
 /// Standardized error response
@@ -93,6 +106,105 @@ pub struct PolicyOperations {
 
 }
 
+/// Policy bundle export request
+#[derive(Debug, Deserialize)]
+pub struct ExportBundleRequest {
+    pub exported_by: String,
+}
+
+/// Policy bundle import request
+#[derive(Debug, Deserialize)]
+pub struct ImportBundleRequest {
+    pub bundle: PolicyBundle,
+    pub imported_by: String,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub confirmed: bool,
+}
+
+/// Query parameters for policy removal
+#[derive(Debug, Deserialize)]
+pub struct RemovePolicyQuery {
+    #[serde(default)]
+    pub removed_by: Option<String>,
+}
+
+/// Query parameters for version diff
+#[derive(Debug, Deserialize)]
+pub struct PolicyDiffQuery {
+    pub from: u32,
+    pub to: u32,
+}
+
+/// Policy revert request
+#[derive(Debug, Deserialize)]
+pub struct RevertPolicyRequest {
+    pub reverted_by: String,
+    #[serde(default)]
+    pub confirmed: bool,
+}
+
+/// Second-admin approval request (approver = X-ADMIN-USER)
+#[derive(Debug, Deserialize)]
+pub struct ApprovePendingRequest {
+    #[serde(default)]
+    pub approved_by: String,           // Optional, must match X-ADMIN-USER when given
+    pub confirmation: Option<String>,  // e.g. "CONFIRM_RECURSIVE_BLOCK_ALL"
+}
+
+/// Rejection of a pending approval (rejecter = X-ADMIN-USER)
+#[derive(Debug, Deserialize)]
+pub struct RejectPendingRequest {
+    #[serde(default)]
+    pub rejected_by: String,           // Optional, must match X-ADMIN-USER when given
+    pub reason: String,
+}
+
+/// Break-glass suspend request
+#[derive(Debug, Deserialize)]
+pub struct SuspendEnforcementRequest {
+    #[serde(default)]
+    pub suspended_by: String,          // Must match X-ADMIN-USER when given
+    pub reason: String,                // Mandatory
+    pub timeout_secs: Option<u64>,     // Auto-resume after this many seconds
+}
+
+/// Break-glass resume request
+#[derive(Debug, Deserialize)]
+pub struct ResumeEnforcementRequest {
+    #[serde(default)]
+    pub resumed_by: String,            // Must match X-ADMIN-USER when given
+}
+
+/// Background crawl request
+#[derive(Debug, Deserialize)]
+pub struct StartScanRequest {
+    pub root_node_id: u64,
+    #[serde(default)]
+    pub scan_config: Option<ScanConfig>,
+    #[serde(default)]
+    pub throttle_ms: Option<u64>,      // Pause after each indexed chunk
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+}
+
+/// Content discovery request
+#[derive(Debug, Deserialize)]
+pub struct StartDiscoveryRequest {
+    pub root_node_id: u64,
+    #[serde(flatten)]
+    pub options: DiscoveryOptions,
+}
+
+/// Query parameters for admin audit paging
+#[derive(Debug, Deserialize)]
+pub struct AuditPageQuery {
+    #[serde(default)]
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
 /// Agent HTTP Server
 pub struct AgentServer {
     state: Arc<ServerState>,
@@ -110,13 +222,21 @@ impl AgentServer {
         AgentServer {
             state: Arc::new(ServerState {
                 query_api,
+                auto_protection: AutoProtectionEngine::open(policy_engine.clone(), &AutoProtectionEngine::default_rules_file()),
+                device_control: DeviceControlEngine::open(policy_engine.clone(), &DeviceControlEngine::default_rules_file()),
                 policy_engine,
                   ws_server,
+                admin_audit: AdminAuditLog::open(),
             }),
             bind_address,
         }
     }
     
+    /// Device rules (re-enforced by the agent when drives arrive or leave)
+    pub fn device_control(&self) -> Arc<DeviceControlEngine> {
+        self.state.device_control.clone()
+    }
+    
     /// Start the HTTP server
     pub async fn start(self ,shutdown_rx: tokio::sync::oneshot::Receiver<()>) -> Result<(), String> {
         println!("🌐 Agent HTTP Server starting on {}", self.bind_address);
@@ -130,12 +250,35 @@ impl AgentServer {
             .route("/api/v1/nodes/:id/children", get(get_node_children))
             .route("/api/v1/nodes/:id/expand", post(expand_node))
             .route("/api/v1/nodes/:id/collapse", post(collapse_node))
+            .route("/api/v1/nodes/:id/identify", post(identify_node))
+            .route("/api/v1/search", get(search_global))
             .route("/api/v1/search/local", get(search_local))
             .route("/api/v1/stats", get(get_stats))
 
+            // Background crawler
+            .route("/api/v1/scan", get(list_scans_handler).post(start_scan_handler))
+            .route("/api/v1/scan/:job_id", get(scan_status_handler))
+            .route("/api/v1/scan/:job_id/cancel", post(cancel_scan_handler))
+
+            // Content discovery (PII/PCI/secret classifiers)
+            .route("/api/v1/discovery/scan", get(list_discovery_handler).post(start_discovery_handler))
+            .route("/api/v1/discovery/scan/:job_id", get(discovery_status_handler))
+            .route("/api/v1/discovery/scan/:job_id/cancel", post(cancel_discovery_handler))
+            .route("/api/v1/discovery/findings", get(discovery_findings_handler))
+            .route("/api/v1/fingerprints", get(fingerprints_handler))
+            .route("/api/v1/discovery/rules", get(list_rules_handler).post(add_rule_handler))
+            .route("/api/v1/discovery/rules/:rule_id", delete(remove_rule_handler))
+            .route("/api/v1/discovery/rules/:rule_id/preview", get(preview_rule_handler))
+            .route("/api/v1/discovery/rules/:rule_id/run", post(run_rule_handler))
+
+            // Device control (removable media, device classes, volume serials)
+            .route("/api/v1/devices/rules", get(list_device_rules_handler).post(add_device_rule_handler))
+            .route("/api/v1/devices/rules/:rule_id", delete(remove_device_rule_handler))
+            .route("/api/v1/devices/rules/:rule_id/drives", get(device_rule_drives_handler))
+
             // Policy APIs
             .route("/api/v1/policies/apply", post(apply_policy))
-            .route("/api/v1/policies/:policy_id", delete(remove_policy))
+            .route("/api/v1/policies/:policy_id", delete(remove_policy).put(update_policy))
             .route("/api/v1/policies", get(list_policies))
             .route("/api/v1/policies/node/:node_id", get(get_node_policies))
             
@@ -144,6 +287,25 @@ impl AgentServer {
             .route("/api/v1/policies/dry-run", post(policy_dry_run_handler))
             .route("/api/v1/policies/:id/status", get(policy_status_handler))
             .route("/api/v1/policies/validate", post(policy_validate_handler))
+            .route("/api/v1/policies/pending", get(list_pending_handler))
+            .route("/api/v1/policies/pending/:id/approve", post(approve_pending_handler))
+            .route("/api/v1/policies/pending/:id/reject", post(reject_pending_handler))
+
+            // STEP 8 Policy Portability endpoints
+            .route("/api/v1/policies/export", post(policy_export_handler))
+            .route("/api/v1/policies/import", post(policy_import_handler))
+            .route("/api/v1/policies/:id/history", get(policy_history_handler))
+            .route("/api/v1/policies/:id/diff", get(policy_diff_handler))
+            .route("/api/v1/policies/:id/revert/:version", post(policy_revert_handler))
+
+            // Break-glass enforcement control
+            .route("/api/v1/enforcement/status", get(enforcement_status_handler))
+            .route("/api/v1/enforcement/suspend", post(suspend_enforcement_handler))
+            .route("/api/v1/enforcement/resume", post(resume_enforcement_handler))
+
+            // Admin audit trail
+            .route("/api/v1/audit/admin", get(admin_audit_handler))
+            .route("/api/v1/audit/admin/verify", get(admin_audit_verify_handler))
 
              // WebSocket endpoint
             .route("/api/v1/ws", get(handle_websocket_route))
@@ -154,6 +316,9 @@ impl AgentServer {
              // Add authentication middleware (optional)
             .layer(axum::middleware::from_fn(optional_auth_middleware))
 
+            // Record every mutating admin call in the hash-chained audit trail
+            .layer(axum::middleware::from_fn_with_state(self.state.clone(), admin_audit_middleware))
+
             // Add state
             .with_state(self.state.clone());
         
@@ -165,7 +330,7 @@ impl AgentServer {
         println!("✅ Agent HTTP Server listening on {}", self.bind_address);
         println!("   Ready for Spring Boot Admin connections");
         
-        axum::serve(listener, app)
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(async {
                 shutdown_rx.await.ok();
                 println!("🛑 Received shutdown signal, stopping HTTP server...");
@@ -184,50 +349,11 @@ async fn policy_dry_run_handler(
     println!("   Node ID: {}, Action: {}", request.node_id, request.action);
     
     // Convert HTTP request to PolicyIntent (syntax validation only)
-    let scope = match request.scope.as_str() {
-        "file" => ProtectionScope::File,
-        "folder" => ProtectionScope::Folder,
-        "folder_recursive" => ProtectionScope::FolderRecursive,
-        _ => {
-            let error = ErrorResponse {
-                code: "INVALID_SCOPE".to_string(),
-                message: format!("Invalid scope: {}", request.scope),
-            };
-            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
-        }
-    };
-    
-    let action = match request.action.as_str() {
-        "block" => ProtectionAction::Block,
-        "allow" => ProtectionAction::Allow,
-        "audit" => ProtectionAction::Audit,
-        _ => {
-            let error = ErrorResponse {
-                code: "INVALID_ACTION".to_string(),
-                message: format!("Invalid action: {}", request.action),
-            };
-            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
-        }
-    };
-    
-    // Create intent
-    let operations = ProtectionOperations {
-        read: request.operations.read,
-        write: request.operations.write,
-        delete: request.operations.delete,
-        rename: request.operations.rename,
-        create: request.operations.create,
+    let intent = match intent_from_request(&request) {
+        Ok(intent) => intent,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error))),
     };
     
-    let intent = PolicyIntent::new(
-        request.node_id,
-        scope,
-        action,
-        operations,
-        &request.created_by,
-        request.comment.as_deref(),
-    );
-    
     // Run dry-run through PolicyEngine
     match state.policy_engine.dry_run_policy(&intent) {
         Ok(evaluation) => {
@@ -308,32 +434,133 @@ async fn policy_validate_handler(
     println!("🛡️ POST /api/v1/policies/validate");
     
     // Convert HTTP to PolicyIntent (SYNTAX ONLY)
+    let intent = match intent_from_request(&request) {
+        Ok(intent) => intent,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error))),
+    };
+    
+    // ✅ DELEGATE TO POLICY ENGINE
+    let validation = state.policy_engine.validate_policy_safety(&intent);
+    
+    let response = serde_json::json!({
+        "is_valid": validation.is_valid,
+        "warnings": validation.warnings,
+        "errors": validation.errors,
+        "requires_confirmation": validation.requires_confirmation,
+        "confirmation_message": validation.confirmation_message,
+    });
+    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+}
+
+/// POST /api/v1/policies/export - Export signed policy bundle (STEP 8.1)
+async fn policy_export_handler(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<ExportBundleRequest>,
+) -> impl IntoResponse {
+    println!("📦 POST /api/v1/policies/export (by {})", request.exported_by);
+
+    // The exporter is signed into the bundle
+    request.exported_by = match acting_admin(&headers, &request.exported_by, false) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+
+    match state.policy_engine.export_policy_bundle(&request.exported_by) {
+        Ok(bundle) => {
+            println!("   ✅ Exported {} policies", bundle.entries.len());
+            let response = serde_json::to_value(&bundle).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => {
+            println!("   ❌ Export failed: {}", e);
+            let error = ErrorResponse {
+                code: "BUNDLE_EXPORT_FAILED".to_string(),
+                message: e,
+            };
+            (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// POST /api/v1/policies/import - Import signed policy bundle (STEP 8.1)
+async fn policy_import_handler(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<ImportBundleRequest>,
+) -> impl IntoResponse {
+    println!("📦 POST /api/v1/policies/import (by {}, dry_run={})",
+        request.imported_by, request.dry_run);
+
+    request.imported_by = match acting_admin(&headers, &request.imported_by, request.confirmed && !request.dry_run) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+
+    let engine = state.policy_engine.clone();
+    let scanner = state.query_api.scanner().clone();
+    let result = tokio::task::spawn_blocking(move || {
+        engine.import_policy_bundle(
+            &scanner,
+            &request.bundle,
+            &request.imported_by,
+            request.dry_run,
+            request.confirmed,
+        )
+    })
+    .await;
+
+    match result {
+        Ok(Ok(report)) => {
+            println!("   ✅ Import finished: {} applied, {} resolved, {} failed",
+                report.applied, report.resolved, report.failed);
+            let response = serde_json::to_value(&report).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Ok(Err(e)) => {
+            println!("   ❌ Import rejected: {}", e);
+            let error = ErrorResponse {
+                code: "BUNDLE_IMPORT_FAILED".to_string(),
+                message: e,
+            };
+            (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Import task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// Convert HTTP policy request to PolicyIntent (syntax validation only)
+fn intent_from_request(request: &ApplyPolicyRequest) -> Result<PolicyIntent, ErrorResponse> {
     let scope = match request.scope.as_str() {
         "file" => ProtectionScope::File,
         "folder" => ProtectionScope::Folder,
         "folder_recursive" => ProtectionScope::FolderRecursive,
         _ => {
-            let error = ErrorResponse {
+            return Err(ErrorResponse {
                 code: "INVALID_SCOPE".to_string(),
                 message: format!("Invalid scope: {}", request.scope),
-            };
-            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
+            });
         }
     };
-    
+
     let action = match request.action.as_str() {
         "block" => ProtectionAction::Block,
         "allow" => ProtectionAction::Allow,
         "audit" => ProtectionAction::Audit,
         _ => {
-            let error = ErrorResponse {
+            return Err(ErrorResponse {
                 code: "INVALID_ACTION".to_string(),
                 message: format!("Invalid action: {}", request.action),
-            };
-            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
+            });
         }
     };
-    
+
     let operations = ProtectionOperations {
         read: request.operations.read,
         write: request.operations.write,
@@ -341,27 +568,15 @@ async fn policy_validate_handler(
         rename: request.operations.rename,
         create: request.operations.create,
     };
-    
-    let intent = PolicyIntent::new(
+
+    Ok(PolicyIntent::new(
         request.node_id,
         scope,
         action,
         operations,
         &request.created_by,
         request.comment.as_deref(),
-    );
-    
-    // ✅ DELEGATE TO POLICY ENGINE
-    let validation = state.policy_engine.validate_policy_safety(&intent);
-    
-    let response = serde_json::json!({
-        "is_valid": validation.is_valid,
-        "warnings": validation.warnings,
-        "errors": validation.errors,
-        "requires_confirmation": validation.requires_confirmation,
-        "confirmation_message": validation.confirmation_message,
-    });
-    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+    ))
 }
 
 // ========================================
@@ -430,20 +645,27 @@ async fn get_node(
 }
 
 /// GET /api/v1/nodes/:id/children
+/// Query: offset or cursor, limit, sort (name|size|mtime|type), descending
 async fn get_node_children(
     State(state): State<Arc<ServerState>>,
     Path(node_id): Path<u64>,
+    Query(page): Query<PageRequest>,
 ) -> impl IntoResponse {
     println!("🌐 GET /api/v1/nodes/{}/children", node_id);
     
-    match state.query_api.handle_request(AdminRequest::ListChildren { node_id }).await {
-        AgentResponse::Children { parent_id, parent_name, children, total_children } => {
-            println!("   ✅ Returning {} children", children.len());
+    match state.query_api.handle_request(AdminRequest::ListChildren { node_id, page }).await {
+        AgentResponse::Children {
+            parent_id, parent_name, children, total_children, offset, next_cursor, has_more,
+        } => {
+            println!("   ✅ Returning {} of {} children", children.len(), total_children);
             let response = serde_json::json!({
                 "parent_id": parent_id,
                 "parent_name": parent_name,
                 "children": children,
                 "total_children": total_children,
+                "offset": offset,
+                "next_cursor": next_cursor,
+                "has_more": has_more,
             });
             (StatusCode::OK, Json(StandardApiResponse::success(response)))
         }
@@ -451,6 +673,8 @@ async fn get_node_children(
             println!("   ❌ Error: {} - {}", code, message);
              let status = if code == "NODE_NOT_FOUND" {
                 StatusCode::NOT_FOUND
+            } else if code == "INVALID_REQUEST" {
+                StatusCode::BAD_REQUEST
             } else {
                 StatusCode::INTERNAL_SERVER_ERROR
             };
@@ -469,21 +693,34 @@ async fn get_node_children(
 }
 
 /// POST /api/v1/nodes/:id/expand - NOW RETURNS CHILDREN
+/// Optional JSON body: ScanConfig (skip_hidden, skip_system, follow_symlinks) for this request
+/// Query: same paging options as /children; only the first page is returned
 async fn expand_node(
     State(state): State<Arc<ServerState>>,
     Path(node_id): Path<u64>,
+    Query(page): Query<PageRequest>,
+    scan_config: Option<Json<ScanConfig>>,
 ) -> impl IntoResponse {
     println!("🌐 POST /api/v1/nodes/{}/expand", node_id);
+    let scan_config = scan_config.map(|Json(config)| config);
+    if let Some(config) = &scan_config {
+        println!("   Scan config: {:?}", config);
+    }
     
-    match state.query_api.handle_request(AdminRequest::ExpandNode { node_id }).await {
-        AgentResponse::Expanded { node_id, node_name, children, total_children } => {
-            println!("   ✅ Expanded '{}', {} children loaded", node_name, children.len());
+    match state.query_api.handle_request(AdminRequest::ExpandNode { node_id, scan_config, page }).await {
+        AgentResponse::Expanded {
+            node_id, node_name, children, total_children, offset, next_cursor, has_more,
+        } => {
+            println!("   ✅ Expanded '{}', {} children loaded", node_name, total_children);
             let response = serde_json::json!({
                 "node_id": node_id,
                 "node_name": node_name,
-                "children": children,  // Now returns actual children
+                "children": children,  // First page of children
                 "total_children": total_children,
-                "message": format!("Expanded '{}' with {} children", node_name, children.len()),
+                "offset": offset,
+                "next_cursor": next_cursor,
+                "has_more": has_more,
+                "message": format!("Expanded '{}' with {} children", node_name, total_children),
             });
             (StatusCode::OK, Json(StandardApiResponse::success(response)))
         }
@@ -493,6 +730,8 @@ async fn expand_node(
                 StatusCode::NOT_FOUND
             } else if code == "ALREADY_EXPANDED" {
                 StatusCode::CONFLICT
+            } else if code == "INVALID_REQUEST" {
+                StatusCode::BAD_REQUEST
             } else {
                 StatusCode::INTERNAL_SERVER_ERROR
             };
@@ -549,6 +788,41 @@ async fn collapse_node(
     }
 }
 
+/// POST /api/v1/nodes/:id/identify - Read the file header and record its content type
+async fn identify_node(
+    State(state): State<Arc<ServerState>>,
+    Path(node_id): Path<u64>,
+) -> impl IntoResponse {
+    println!("🌐 POST /api/v1/nodes/{}/identify", node_id);
+    
+    match state.query_api.handle_request(AdminRequest::IdentifyContentType { node_id }).await {
+        AgentResponse::Node { node } => {
+            println!("   ✅ '{}' is {}", node.name, node.content_type.as_deref().unwrap_or("unknown"));
+            (StatusCode::OK, Json(StandardApiResponse::success(node)))
+        }
+        AgentResponse::Error { code, message, .. } => {
+            println!("   ❌ Error: {} - {}", code, message);
+            let status = if code == "NODE_NOT_FOUND" {
+                StatusCode::NOT_FOUND
+            } else if code == "INVALID_REQUEST" {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            let error = ErrorResponse { code, message };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+        _ => {
+            println!("   ❌ Unexpected response type");
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Unexpected response from agent".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
 /// GET /api/v1/search/local - FIXED: parent_id is required
 async fn search_local(
     State(state): State<Arc<ServerState>>,
@@ -592,6 +866,51 @@ async fn search_local(
     }
 }
 
+/// GET /api/v1/search - Global search over everything indexed
+/// Query: q, mode (substring|glob|regex), ext, type, min_size, max_size,
+/// modified_after, modified_before, root_id, offset, limit
+async fn search_global(
+    State(state): State<Arc<ServerState>>,
+    Query(request): Query<SearchRequest>,
+) -> impl IntoResponse {
+    println!("🌐 GET /api/v1/search?q={} ({:?})", request.q.as_deref().unwrap_or(""), request.mode);
+
+    match state.query_api.handle_request(AdminRequest::Search { request }).await {
+        AgentResponse::SearchResults { results, total_matches, offset, has_more, coverage } => {
+            println!("   ✅ {} matches ({} nodes searched, scope: {})",
+                total_matches, coverage.nodes_searched, coverage.scope);
+            let response = serde_json::json!({
+                "results": results,
+                "total_matches": total_matches,
+                "offset": offset,
+                "has_more": has_more,
+                "coverage": coverage,
+            });
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        AgentResponse::Error { code, message, .. } => {
+            println!("   ❌ Error: {} - {}", code, message);
+            let status = if code == "NODE_NOT_FOUND" {
+                StatusCode::NOT_FOUND
+            } else if code == "INVALID_REQUEST" {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            let error = ErrorResponse { code, message };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+        _ => {
+            println!("   ❌ Unexpected response type");
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Unexpected response from agent".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
 /// GET /api/v1/stats
 async fn get_stats(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
     println!("🌐 GET /api/v1/stats");
@@ -624,7 +943,8 @@ async fn get_stats(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
 /// POST /api/v1/policies/apply
 async fn apply_policy(
     State(state): State<Arc<ServerState>>,
-    Json(request): Json<ApplyPolicyRequest>,
+    headers: HeaderMap,
+    Json(mut request): Json<ApplyPolicyRequest>,
 ) -> impl IntoResponse {
     println!("🌐 POST /api/v1/policies/apply");
     println!("   Node ID: {}, Scope: {}, Action: {}", 
@@ -651,59 +971,17 @@ async fn apply_policy(
     //     return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
     // }
     
-    if request.created_by.trim().is_empty() {
-        let error = ErrorResponse {
-            code: "INVALID_REQUEST".to_string(),
-            message: "Creator name cannot be empty".to_string(),
-        };
-        return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
-    }
+    request.created_by = match acting_admin(&headers, &request.created_by, request.confirmed) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
     
     // Convert HTTP request to PolicyIntent
-    let scope = match request.scope.as_str() {
-        "file" => ProtectionScope::File,
-        "folder" => ProtectionScope::Folder,
-        "folder_recursive" => ProtectionScope::FolderRecursive,
-        _ => {
-            let error = ErrorResponse {
-                code: "INVALID_SCOPE".to_string(),
-                message: format!("Invalid scope: {}", request.scope),
-            };
-            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
-        }
+    let intent = match intent_from_request(&request) {
+        Ok(intent) => intent,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error))),
     };
     
-    let action = match request.action.as_str() {
-        "block" => ProtectionAction::Block,
-        "allow" => ProtectionAction::Allow,
-        "audit" => ProtectionAction::Audit,
-        _ => {
-            let error = ErrorResponse {
-                code: "INVALID_ACTION".to_string(),
-                message: format!("Invalid action: {}", request.action),
-            };
-            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
-        }
-    };
-    
-    let operations = ProtectionOperations {
-        read: request.operations.read,
-        write: request.operations.write,
-        delete: request.operations.delete,
-        rename: request.operations.rename,
-        create: request.operations.create,
-       
-    };
-    
-    let intent = PolicyIntent::new(
-        request.node_id,
-        scope,
-        action,
-        operations,
-        &request.created_by,
-        request.comment.as_deref(),
-    );
-    
 
     // match state.policy_engine.apply_protection(intent.clone()) {
     //     Ok(policy_id) => {
@@ -753,7 +1031,8 @@ async fn apply_policy(
         })
         .await;
 match result {
-    Ok(Ok(policy_id)) => {
+    Ok(Ok(ChangeOutcome::PendingApproval(request))) => pending_approval_response(&request),
+    Ok(Ok(ChangeOutcome::Applied { policy_id, .. })) => {
         println!("   ✅ Policy applied successfully (ID: {})", policy_id);
 
         let response = serde_json::json!({
@@ -781,18 +1060,310 @@ match result {
 
 }
 
+/// PUT /api/v1/policies/:policy_id - Update policy (new version, same ID)
+async fn update_policy(
+    State(state): State<Arc<ServerState>>,
+    Path(policy_id): Path<u64>,
+    headers: HeaderMap,
+    Json(mut request): Json<ApplyPolicyRequest>,
+) -> impl IntoResponse {
+    println!("🌐 PUT /api/v1/policies/{}", policy_id);
+    println!("   Node ID: {}, Scope: {}, Action: {}",
+        request.node_id, request.scope, request.action);
+
+    request.created_by = match acting_admin(&headers, &request.created_by, request.confirmed) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+
+    let intent = match intent_from_request(&request) {
+        Ok(intent) => intent,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error))),
+    };
+
+    let engine = state.policy_engine.clone();
+    let changed_by = request.created_by.clone();
+    let confirmed = request.confirmed;
+    let result = tokio::task::spawn_blocking(move || {
+        engine.update_protection(policy_id, intent, &changed_by, confirmed)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(ChangeOutcome::PendingApproval(request))) => pending_approval_response(&request),
+        Ok(Ok(ChangeOutcome::Applied { version, .. })) => {
+            println!("   ✅ Policy {} updated to version {}", policy_id, version);
+            let response = serde_json::json!({
+                "policy_id": policy_id,
+                "version": version,
+                "message": "Policy updated successfully",
+            });
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Ok(Err(e)) => {
+            println!("   ❌ Failed to update policy: {}", e);
+            let (status, code) = if e.contains("not found") {
+                (StatusCode::NOT_FOUND, "POLICY_NOT_FOUND")
+            } else {
+                (StatusCode::BAD_REQUEST, "POLICY_UPDATE_FAILED")
+            };
+            let error = ErrorResponse { code: code.to_string(), message: e };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Kernel task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// GET /api/v1/policies/:id/history - Full version history (STEP 8.2)
+async fn policy_history_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(policy_id): Path<u64>,
+) -> impl IntoResponse {
+    println!("📜 GET /api/v1/policies/{}/history", policy_id);
+
+    let history = state.policy_engine.get_policy_history(policy_id);
+    if history.is_empty() {
+        let error = ErrorResponse {
+            code: "POLICY_NOT_FOUND".to_string(),
+            message: format!("No history for policy ID {}", policy_id),
+        };
+        return (StatusCode::NOT_FOUND, Json(StandardApiResponse::error(error)));
+    }
+
+    println!("   ✅ Returning {} versions", history.len());
+    let versions: Vec<_> = history.iter().map(|version| version.to_info()).collect();
+    let response = serde_json::json!({
+        "policy_id": policy_id,
+        "current_version": versions.last().map(|v| v.version),
+        "is_active": state.policy_engine.policy_store().get_policy(policy_id).is_some(),
+        "versions": versions,
+    });
+    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+}
+
+/// GET /api/v1/policies/:id/diff?from=&to= - Structural diff (STEP 8.2)
+async fn policy_diff_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(policy_id): Path<u64>,
+    Query(params): Query<PolicyDiffQuery>,
+) -> impl IntoResponse {
+    println!("📜 GET /api/v1/policies/{}/diff?from={}&to={}", policy_id, params.from, params.to);
+
+    match state.policy_engine.diff_policy_versions(policy_id, params.from, params.to) {
+        Ok(diff) => {
+            let response = serde_json::to_value(&diff).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => {
+            let error = ErrorResponse {
+                code: "VERSION_NOT_FOUND".to_string(),
+                message: e,
+            };
+            (StatusCode::NOT_FOUND, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// POST /api/v1/policies/:id/revert/:version - Revert to a version (STEP 8.2)
+async fn policy_revert_handler(
+    State(state): State<Arc<ServerState>>,
+    Path((policy_id, version)): Path<(u64, u32)>,
+    headers: HeaderMap,
+    Json(request): Json<RevertPolicyRequest>,
+) -> impl IntoResponse {
+    println!("⏪ POST /api/v1/policies/{}/revert/{} (by {})", policy_id, version, request.reverted_by);
+
+    let reverted_by = match acting_admin(&headers, &request.reverted_by, request.confirmed) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+
+    let engine = state.policy_engine.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        engine.revert_protection(policy_id, version, &reverted_by, request.confirmed)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(ChangeOutcome::PendingApproval(request))) => pending_approval_response(&request),
+        Ok(Ok(ChangeOutcome::Applied { version: new_version, .. })) => {
+            println!("   ✅ Policy {} reverted (new version {})", policy_id, new_version);
+            let response = serde_json::json!({
+                "policy_id": policy_id,
+                "reverted_to": version,
+                "version": new_version,
+                "message": format!("Policy reverted to version {}", version),
+            });
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Ok(Err(e)) => {
+            println!("   ❌ Revert failed: {}", e);
+            let error = ErrorResponse {
+                code: "POLICY_REVERT_FAILED".to_string(),
+                message: e,
+            };
+            (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Kernel task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// 202 response for a change parked in the two-person approval queue
+fn pending_approval_response(request: &PendingApproval) -> (StatusCode, Json<StandardApiResponse<serde_json::Value>>) {
+    println!("   ⏳ High-risk change queued for approval (request {})", request.request_id);
+    let response = serde_json::json!({
+        "status": "pending_approval",
+        "request_id": request.request_id,
+        "expires_at": request.expires_at,
+        "message": "High-risk policy requires approval by a second admin",
+        "approval": request,
+    });
+    (StatusCode::ACCEPTED, Json(StandardApiResponse::success(response)))
+}
+
+/// GET /api/v1/policies/pending - List approval requests (STEP 7.5)
+async fn list_pending_handler(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    println!("⏳ GET /api/v1/policies/pending");
+
+    let requests = state.policy_engine.list_pending_approvals();
+    println!("   ✅ Returning {} approval requests", requests.len());
+    (StatusCode::OK, Json(StandardApiResponse::success(requests)))
+}
+
+/// POST /api/v1/policies/pending/:id/approve - Second admin approves (STEP 7.5)
+async fn approve_pending_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(request_id): Path<u64>,
+    headers: HeaderMap,
+    Json(request): Json<ApprovePendingRequest>,
+) -> impl IntoResponse {
+    // The approver must be authenticated, or one admin could approve under another name
+    let approved_by = match acting_admin(&headers, &request.approved_by, true) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+    println!("✅ POST /api/v1/policies/pending/{}/approve (by {})", request_id, approved_by);
+
+    let engine = state.policy_engine.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        engine.approve_pending(request_id, &approved_by, request.confirmation.as_deref())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(ChangeOutcome::Applied { policy_id, version })) => {
+            println!("   ✅ Request {} approved → policy {} (version {})", request_id, policy_id, version);
+            let response = serde_json::json!({
+                "request_id": request_id,
+                "policy_id": policy_id,
+                "version": version,
+                "message": "Policy approved and applied",
+            });
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Ok(Ok(ChangeOutcome::PendingApproval(pending))) => pending_approval_response(&pending),
+        Ok(Err(e)) => {
+            println!("   ❌ Approval failed: {}", e);
+            let (status, code) = if e.contains("not found") {
+                (StatusCode::NOT_FOUND, "APPROVAL_NOT_FOUND")
+            } else if e.contains("different admin") {
+                (StatusCode::FORBIDDEN, "SECOND_ADMIN_REQUIRED")
+            } else if e.contains("Type '") {
+                (StatusCode::BAD_REQUEST, "CONFIRMATION_PHRASE_MISMATCH")
+            } else if e.starts_with("Approval request") {
+                (StatusCode::CONFLICT, "APPROVAL_NOT_PENDING")
+            } else {
+                (StatusCode::BAD_REQUEST, "POLICY_APPLICATION_FAILED")
+            };
+            let error = ErrorResponse { code: code.to_string(), message: e };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Kernel task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// POST /api/v1/policies/pending/:id/reject - Reject a pending change (STEP 7.5)
+async fn reject_pending_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(request_id): Path<u64>,
+    headers: HeaderMap,
+    Json(request): Json<RejectPendingRequest>,
+) -> impl IntoResponse {
+    let rejected_by = match acting_admin(&headers, &request.rejected_by, true) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+    println!("🚫 POST /api/v1/policies/pending/{}/reject (by {})", request_id, rejected_by);
+
+    if request.reason.trim().is_empty() {
+        let error = ErrorResponse {
+            code: "INVALID_REQUEST".to_string(),
+            message: "reason is required".to_string(),
+        };
+        return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
+    }
+
+    match state.policy_engine.reject_pending(request_id, &rejected_by, &request.reason) {
+        Ok(rejected) => {
+            let response = serde_json::to_value(&rejected).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => {
+            let status = if e.contains("not found") { StatusCode::NOT_FOUND } else { StatusCode::CONFLICT };
+            let error = ErrorResponse {
+                code: "APPROVAL_REJECT_FAILED".to_string(),
+                message: e,
+            };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
 /// DELETE /api/v1/policies/:policy_id
 async fn remove_policy(
     State(state): State<Arc<ServerState>>,
     Path(policy_id): Path<u64>,
+    headers: HeaderMap,
+    Query(params): Query<RemovePolicyQuery>,
 ) -> impl IntoResponse {
     println!("🌐 DELETE /api/v1/policies/{}", policy_id);
     
+    // History must name who removed it
+    let removed_by = match admin_user(&headers)
+        .or(params.removed_by.filter(|user| !user.trim().is_empty()))
+    {
+        Some(removed_by) => removed_by,
+        None => {
+            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(ErrorResponse {
+                code: "REMOVER_REQUIRED".to_string(),
+                message: "Send X-ADMIN-USER or removed_by to remove a policy".to_string(),
+            })));
+        }
+    };
+    
     // Get node_id BEFORE removing policy
     let node_id = state.policy_engine.get_policy_by_id(policy_id)
         .map(|policy| policy.intent.node_id);
     
-    match state.policy_engine.remove_protection(policy_id) {
+    match state.policy_engine.remove_protection(policy_id, &removed_by) {
         Ok(_) => {
             println!("   ✅ Policy removed successfully");
             
@@ -856,9 +1427,12 @@ async fn list_policies(State(state): State<Arc<ServerState>>) -> impl IntoRespon
                 ProtectionAction::Audit => "audit",
             },
             "is_active": policy.is_active,
+            "orphaned": policy.orphaned,
             "created_by": policy.intent.created_by,
             "created_at": policy.created_at,
             "comment": policy.intent.comment,
+            "auto_rule_id": policy.intent.auto_rule_id,
+            "device_rule_id": policy.intent.device_rule_id,
             "note": "NT paths are stored internally only, never exposed",
         })
     }).collect();
@@ -896,9 +1470,12 @@ async fn get_node_policies(
                 ProtectionAction::Audit => "audit",
             },
             "is_active": policy.is_active,
+            "orphaned": policy.orphaned,
             "created_by": policy.intent.created_by,
             "created_at": policy.created_at,
             "comment": policy.intent.comment,
+            "auto_rule_id": policy.intent.auto_rule_id,
+            "device_rule_id": policy.intent.device_rule_id,
         })
     }).collect();
     
@@ -913,49 +1490,11 @@ async fn policy_preview_handler(
     println!("🔍 POST /api/v1/policies/preview");
     
     // Convert HTTP to PolicyIntent (SYNTAX ONLY)
-    let scope = match request.scope.as_str() {
-        "file" => ProtectionScope::File,
-        "folder" => ProtectionScope::Folder,
-        "folder_recursive" => ProtectionScope::FolderRecursive,
-        _ => {
-            let error = ErrorResponse {
-                code: "INVALID_SCOPE".to_string(),
-                message: format!("Invalid scope: {}", request.scope),
-            };
-            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
-        }
-    };
-    
-    let action = match request.action.as_str() {
-        "block" => ProtectionAction::Block,
-        "allow" => ProtectionAction::Allow,
-        "audit" => ProtectionAction::Audit,
-        _ => {
-            let error = ErrorResponse {
-                code: "INVALID_ACTION".to_string(),
-                message: format!("Invalid action: {}", request.action),
-            };
-            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
-        }
-    };
-    
-    let operations = ProtectionOperations {
-        read: request.operations.read,
-        write: request.operations.write,
-        delete: request.operations.delete,
-        rename: request.operations.rename,
-        create: request.operations.create,
+    let intent = match intent_from_request(&request) {
+        Ok(intent) => intent,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error))),
     };
     
-    let intent = PolicyIntent::new(
-        request.node_id,
-        scope,
-        action,
-        operations,
-        &request.created_by,
-        request.comment.as_deref(),
-    );
-    
     // ✅ DELEGATE TO POLICY ENGINE
     match state.policy_engine.preview_policy(&intent) {
         Ok(preview) => {
@@ -1010,32 +1549,924 @@ async fn optional_auth_middleware(
     next.run(request).await
 }
 
-// ========================================
-// Health API
-// ========================================
+/// Maximum request body buffered for audit digests
+const AUDIT_BODY_LIMIT: usize = 16 * 1024 * 1024;
 
-/// GET /api/v1/ping
-async fn ping(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
-    println!("🌐 GET /api/v1/ping");
-    
-    match state.query_api.handle_request(AdminRequest::Ping).await {
-        AgentResponse::Pong { timestamp, version } => {
-            println!("   ✅ Agent alive (v{})", version);
-            let response = serde_json::json!({
-                "status": "alive",
-                "timestamp": timestamp,
-                "version": version,
-                "agent": "DLP Agent",
-                "message": "Ready for Spring Boot Admin connections",
-            });
-            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+/// Map a request to an audited admin action (None = read-only call)
+/// Every non-GET call is audited unless it is known not to change anything.
+fn admin_action_for(method: &Method, path: &str, body: &serde_json::Value) -> Option<String> {
+    if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS {
+        return None;
+    }
+
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let confirmed = body.get("confirmed").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let action = match (method, segments.as_slice()) {
+        // Read-only POSTs (tree view state, previews)
+        (&Method::POST, ["api", "v1", "nodes", _, "expand" | "collapse"]) => return None,
+        (&Method::POST, ["api", "v1", "policies", "preview" | "dry-run" | "validate"]) => return None,
+
+        (&Method::POST, ["api", "v1", "policies", "apply"]) if confirmed => "confirm",
+        (&Method::POST, ["api", "v1", "policies", "apply"]) => "apply",
+        (&Method::PUT, ["api", "v1", "policies", _]) if confirmed => "confirm_update",
+        (&Method::PUT, ["api", "v1", "policies", _]) => "update",
+        (&Method::DELETE, ["api", "v1", "policies", _]) => "remove",
+        (&Method::POST, ["api", "v1", "policies", "import"]) => {
+            if body.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false) {
+                return None;
+            }
+            "import"
         }
-        _ => {
-            println!("   ⚠️  Ping responded unexpectedly");
-            (StatusCode::OK, Json(StandardApiResponse::success(serde_json::json!({
-                "status": "alive",
-                "message": "Agent running",
-            }))))
+        (&Method::POST, ["api", "v1", "policies", _, "revert", _]) => "revert",
+        (&Method::POST, ["api", "v1", "policies", "pending", _, "approve"]) => "approve",
+        (&Method::POST, ["api", "v1", "policies", "pending", _, "reject"]) => "reject",
+        (&Method::POST, ["api", "v1", "enforcement", "suspend"]) => "suspend",
+        (&Method::POST, ["api", "v1", "enforcement", "resume"]) => "resume",
+        (&Method::POST, ["api", "v1", "policies", "export"]) => "export",
+        (&Method::POST, ["api", "v1", "discovery", "rules"]) => "add_rule",
+        (&Method::DELETE, ["api", "v1", "discovery", "rules", _]) => "remove_rule",
+        (&Method::POST, ["api", "v1", "discovery", "rules", _, "run"]) => "run_rule",
+        (&Method::POST, ["api", "v1", "devices", "rules"]) => "add_device_rule",
+        (&Method::DELETE, ["api", "v1", "devices", "rules", _]) => "remove_device_rule",
+        (&Method::POST, ["api", "v1", "scan"]) => "start_scan",
+        (&Method::POST, ["api", "v1", "scan", _, "cancel"]) => "cancel_scan",
+        (&Method::POST, ["api", "v1", "discovery", "scan"]) => "start_discovery",
+        (&Method::POST, ["api", "v1", "discovery", "scan", _, "cancel"]) => "cancel_discovery",
+        (&Method::POST, ["api", "v1", "nodes", _, "identify"]) => "identify",
+        _ => "other",
+    };
+    Some(action.to_string())
+}
+
+/// Admin named by the X-ADMIN-USER header (the identity the audit log trusts)
+fn admin_user(headers: &HeaderMap) -> Option<String> {
+    headers.get("X-ADMIN-USER")
+        .and_then(|h| h.to_str().ok())
+        .map(str::trim)
+        .filter(|user| !user.is_empty())
+        .map(String::from)
+}
+
+/// Admin a change is made under: the X-ADMIN-USER header, which must agree with
+/// the name given in the body. `required` = the header must be present
+/// (approval decisions and confirmed high-risk changes, whose requester and
+/// approver two-person approval compares, and break-glass suspend/resume).
+fn acting_admin(headers: &HeaderMap, claimed: &str, required: bool) -> Result<String, (StatusCode, ErrorResponse)> {
+    let claimed = claimed.trim();
+    match admin_user(headers) {
+        Some(admin) if claimed.is_empty() || claimed.eq_ignore_ascii_case(&admin) => Ok(admin),
+        Some(admin) => Err((StatusCode::FORBIDDEN, ErrorResponse {
+            code: "IDENTITY_MISMATCH".to_string(),
+            message: format!("Request names {} but was sent by {}", claimed, admin),
+        })),
+        None if required => Err((StatusCode::UNAUTHORIZED, ErrorResponse {
+            code: "ADMIN_IDENTITY_REQUIRED".to_string(),
+            message: "X-ADMIN-USER is required for approvals, confirmed changes and break-glass".to_string(),
+        })),
+        None if claimed.is_empty() => Err((StatusCode::BAD_REQUEST, ErrorResponse {
+            code: "INVALID_REQUEST".to_string(),
+            message: "Admin name cannot be empty".to_string(),
+        })),
+        None => Ok(claimed.to_string()),
+    }
+}
+
+/// Caller identity: X-ADMIN-USER header, else the identity named in the body
+fn caller_identity(headers: &HeaderMap, query: Option<&str>, body: &serde_json::Value) -> String {
+    if let Some(user) = admin_user(headers) {
+        return user;
+    }
+
+    for field in ["created_by", "reverted_by", "imported_by", "approved_by", "rejected_by", "suspended_by", "resumed_by"] {
+        if let Some(user) = body.get(field).and_then(|v| v.as_str()) {
+            return format!("{} (unverified)", user);
         }
     }
-}
\ No newline at end of file
+
+    if let Some(user) = query.and_then(|q| q.split('&').find_map(|pair| pair.strip_prefix("removed_by="))) {
+        return format!("{} (unverified)", user);
+    }
+
+    "anonymous".to_string()
+}
+
+/// Admin audit middleware - digests the request, runs it, records the outcome
+async fn admin_audit_middleware(
+    State(state): State<Arc<ServerState>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    if request.method() == Method::GET {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, AUDIT_BODY_LIMIT).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let error = ErrorResponse {
+                code: "REQUEST_TOO_LARGE".to_string(),
+                message: format!("Failed to read request body: {}", e),
+            };
+            return (StatusCode::PAYLOAD_TOO_LARGE, Json(StandardApiResponse::<()>::error(error))).into_response();
+        }
+    };
+
+    let body_json: serde_json::Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let action = match admin_action_for(&parts.method, parts.uri.path(), &body_json) {
+        Some(action) => action,
+        None => {
+            let request = axum::extract::Request::from_parts(parts, axum::body::Body::from(bytes));
+            return next.run(request).await;
+        }
+    };
+
+    let mut record = AdminActionRecord {
+        action,
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        caller: caller_identity(&parts.headers, parts.uri.query(), &body_json),
+        source_address: parts.extensions.get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.to_string())
+            .unwrap_or_else(|| "unknown".to_string()),
+        request_digest: AdminAuditLog::digest(&bytes),
+        status_code: 0,
+        error_code: None,
+        response_truncated: false,
+    };
+
+    let request = axum::extract::Request::from_parts(parts, axum::body::Body::from(bytes));
+    let response = next.run(request).await;
+
+    // Buffer the (small JSON) response to pick up the error code; larger ones
+    // pass through untouched and are recorded as truncated
+    let (parts, body) = response.into_parts();
+    record.status_code = parts.status.as_u16();
+    let fits = body.size_hint().upper().is_some_and(|upper| upper <= AUDIT_BODY_LIMIT as u64);
+    let body = if fits {
+        let bytes = axum::body::to_bytes(body, AUDIT_BODY_LIMIT).await.unwrap_or_default();
+        record.error_code = serde_json::from_slice::<serde_json::Value>(&bytes).ok()
+            .and_then(|v| v.pointer("/error/code").and_then(|c| c.as_str()).map(String::from));
+        axum::body::Body::from(bytes)
+    } else {
+        record.response_truncated = true;
+        body
+    };
+
+    let audit = state.admin_audit.clone();
+    let _ = tokio::task::spawn_blocking(move || audit.record(record)).await;
+
+    axum::response::Response::from_parts(parts, body)
+}
+
+/// GET /api/v1/enforcement/status - Break-glass state (STEP 7.6)
+async fn enforcement_status_handler(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    println!("🚨 GET /api/v1/enforcement/status");
+
+    let suspension = state.policy_engine.enforcement_suspension();
+    let response = serde_json::json!({
+        "suspended": suspension.is_some(),
+        "kernel_connected": state.policy_engine.is_kernel_connected(),
+        "suspension": suspension,
+    });
+    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+}
+
+/// POST /api/v1/enforcement/suspend - Break glass: clear all kernel rules (STEP 7.6)
+async fn suspend_enforcement_handler(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<SuspendEnforcementRequest>,
+) -> impl IntoResponse {
+    println!("🚨 POST /api/v1/enforcement/suspend (by {}: {})", request.suspended_by, request.reason);
+
+    // Clears every kernel rule: only under a verified admin identity
+    request.suspended_by = match acting_admin(&headers, &request.suspended_by, true) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+
+    let engine = state.policy_engine.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        engine.suspend_enforcement(&request.suspended_by, &request.reason, request.timeout_secs)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(suspension)) => {
+            state.ws_server.broadcast_enforcement_suspended(&suspension);
+            schedule_auto_resume(state.clone(), suspension.suspension_id, suspension.resume_at - suspension.suspended_at);
+
+            let response = serde_json::to_value(&suspension).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Ok(Err(e)) => {
+            println!("   ❌ Suspend failed: {}", e);
+            let status = if e.contains("already suspended") { StatusCode::CONFLICT } else { StatusCode::BAD_REQUEST };
+            let error = ErrorResponse {
+                code: "SUSPEND_FAILED".to_string(),
+                message: e,
+            };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Kernel task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// POST /api/v1/enforcement/resume - Re-push all policies to the kernel (STEP 7.6)
+async fn resume_enforcement_handler(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<ResumeEnforcementRequest>,
+) -> impl IntoResponse {
+    println!("🔁 POST /api/v1/enforcement/resume (by {})", request.resumed_by);
+
+    request.resumed_by = match acting_admin(&headers, &request.resumed_by, true) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+
+    let engine = state.policy_engine.clone();
+    let result = tokio::task::spawn_blocking(move || engine.resume_enforcement(&request.resumed_by)).await;
+
+    match result {
+        Ok(Ok(report)) => {
+            state.ws_server.broadcast_enforcement_resumed(&report);
+            let response = serde_json::to_value(&report).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Ok(Err(e)) => {
+            let error = ErrorResponse {
+                code: "NOT_SUSPENDED".to_string(),
+                message: e,
+            };
+            (StatusCode::CONFLICT, Json(StandardApiResponse::error(error)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Kernel task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// Resume enforcement when the suspension times out (unless resumed earlier)
+fn schedule_auto_resume(state: Arc<ServerState>, suspension_id: u64, timeout_secs: u64) {
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(timeout_secs)).await;
+
+        let engine = state.policy_engine.clone();
+        let report = match tokio::task::spawn_blocking(move || engine.auto_resume_enforcement(suspension_id)).await {
+            Ok(Some(report)) => report,
+            _ => return,
+        };
+
+        println!("⏰ Break-glass suspension {} timed out, enforcement resumed", suspension_id);
+        state.ws_server.broadcast_enforcement_resumed(&report);
+
+        // The timer acts on the agent's own authority, record it like an admin call
+        let audit = state.admin_audit.clone();
+        let record = AdminActionRecord {
+            action: "auto_resume".to_string(),
+            method: "TIMER".to_string(),
+            path: "/api/v1/enforcement/resume".to_string(),
+            caller: "agent".to_string(),
+            source_address: "local".to_string(),
+            request_digest: AdminAuditLog::digest(&[]),
+            status_code: if report.failed_policy_ids.is_empty() { 200 } else { 500 },
+            error_code: None,
+            response_truncated: false,
+        };
+        let _ = tokio::task::spawn_blocking(move || audit.record(record)).await;
+    });
+}
+
+/// POST /api/v1/scan - Start a background crawl of a drive or directory
+async fn start_scan_handler(
+    State(state): State<Arc<ServerState>>,
+    Json(request): Json<StartScanRequest>,
+) -> impl IntoResponse {
+    println!("🕷️ POST /api/v1/scan (root {})", request.root_node_id);
+
+    let options = CrawlOptions {
+        scan_config: request.scan_config,
+        throttle_ms: request.throttle_ms,
+        max_depth: request.max_depth,
+    };
+    match state.query_api.scanner().start_crawl(request.root_node_id, options) {
+        Ok(progress) => {
+            state.ws_server.broadcast_scan_progress(&progress);
+            watch_crawl(state.clone(), progress.job_id);
+
+            let response = serde_json::to_value(&progress).unwrap_or_default();
+            (StatusCode::ACCEPTED, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => {
+            println!("   ❌ Crawl not started: {}", e);
+            let (status, code) = if e.contains("not found") {
+                (StatusCode::NOT_FOUND, "NODE_NOT_FOUND")
+            } else if e.contains("limit reached") {
+                (StatusCode::TOO_MANY_REQUESTS, "SCAN_LIMIT_REACHED")
+            } else {
+                (StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+            };
+            let error = ErrorResponse {
+                code: code.to_string(),
+                message: e,
+            };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// GET /api/v1/scan - List background crawls (newest first)
+async fn list_scans_handler(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let crawls = state.query_api.scanner().list_crawls();
+    let response = serde_json::json!({
+        "jobs": crawls,
+        "total": crawls.len(),
+    });
+    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+}
+
+/// GET /api/v1/scan/:job_id - Progress of one crawl
+async fn scan_status_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(job_id): Path<u64>,
+) -> impl IntoResponse {
+    match state.query_api.scanner().crawl_progress(job_id) {
+        Some(progress) => {
+            let response = serde_json::to_value(&progress).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        None => {
+            let error = ErrorResponse {
+                code: "SCAN_NOT_FOUND".to_string(),
+                message: format!("Crawl job {} not found", job_id),
+            };
+            (StatusCode::NOT_FOUND, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// POST /api/v1/scan/:job_id/cancel - Stop a running crawl
+async fn cancel_scan_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(job_id): Path<u64>,
+) -> impl IntoResponse {
+    println!("🛑 POST /api/v1/scan/{}/cancel", job_id);
+
+    match state.query_api.scanner().cancel_crawl(job_id) {
+        Ok(progress) => {
+            let response = serde_json::to_value(&progress).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => {
+            let (status, code) = if e.contains("not found") {
+                (StatusCode::NOT_FOUND, "SCAN_NOT_FOUND")
+            } else {
+                (StatusCode::CONFLICT, "SCAN_NOT_RUNNING")
+            };
+            let error = ErrorResponse {
+                code: code.to_string(),
+                message: e,
+            };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// Push crawl progress over the WebSocket every second until the crawl ends
+fn watch_crawl(state: Arc<ServerState>, job_id: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let progress = match state.query_api.scanner().crawl_progress(job_id) {
+                Some(progress) => progress,
+                None => return,
+            };
+            state.ws_server.broadcast_scan_progress(&progress);
+            if progress.status != CrawlStatus::Running {
+                check_protected_copies(&state, progress.root_node_id).await;
+                return;
+            }
+        }
+    });
+}
+
+/// Match files indexed below `root_id` against protected content
+async fn check_protected_copies(state: &Arc<ServerState>, root_id: u64) {
+    let policy_engine = state.policy_engine.clone();
+    let index = state.query_api.scanner().index().clone();
+    let check = tokio::task::spawn_blocking(move || {
+        let node_ids: Vec<u64> = index.files_under(root_id).iter().map(|node| node.id).collect();
+        policy_engine.find_protected_copies(&node_ids)
+    });
+    match check.await {
+        Ok(copies) => {
+            for copy in &copies {
+                state.ws_server.broadcast_protected_copy(copy);
+            }
+        }
+        Err(e) => println!("⚠️  Protected copy check failed: {}", e),
+    }
+}
+
+/// GET /api/v1/fingerprints - Fingerprint registry state per policy
+async fn fingerprints_handler(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let policy_engine = state.policy_engine.clone();
+    match tokio::task::spawn_blocking(move || policy_engine.fingerprint_summary()).await {
+        Ok((chunking, policies)) => {
+            let response = serde_json::json!({
+                "chunking": chunking,
+                "policies": policies,
+                "total": policies.len(),
+            });
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Fingerprint task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// POST /api/v1/discovery/scan - Classify file contents below a node
+async fn start_discovery_handler(
+    State(state): State<Arc<ServerState>>,
+    Json(request): Json<StartDiscoveryRequest>,
+) -> impl IntoResponse {
+    println!("🔎 POST /api/v1/discovery/scan (root {})", request.root_node_id);
+
+    match state.query_api.discovery().start_scan(request.root_node_id, request.options) {
+        Ok(progress) => {
+            watch_discovery(state.clone(), progress.job_id);
+
+            let response = serde_json::to_value(&progress).unwrap_or_default();
+            (StatusCode::ACCEPTED, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => {
+            println!("   ❌ Discovery not started: {}", e);
+            let (status, code) = if e.contains("not found") {
+                (StatusCode::NOT_FOUND, "NODE_NOT_FOUND")
+            } else {
+                (StatusCode::BAD_REQUEST, "INVALID_REQUEST")
+            };
+            let error = ErrorResponse {
+                code: code.to_string(),
+                message: e,
+            };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// GET /api/v1/discovery/scan - List discovery scans (newest first)
+async fn list_discovery_handler(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let discovery = state.query_api.discovery();
+    let scans = discovery.list_scans();
+    let response = serde_json::json!({
+        "jobs": scans,
+        "total": scans.len(),
+        "detectors": discovery.detector_names(),
+    });
+    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+}
+
+/// GET /api/v1/discovery/scan/:job_id - Progress of one discovery scan
+async fn discovery_status_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(job_id): Path<u64>,
+) -> impl IntoResponse {
+    match state.query_api.discovery().scan_progress(job_id) {
+        Some(progress) => {
+            let response = serde_json::to_value(&progress).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        None => {
+            let error = ErrorResponse {
+                code: "SCAN_NOT_FOUND".to_string(),
+                message: format!("Discovery job {} not found", job_id),
+            };
+            (StatusCode::NOT_FOUND, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// POST /api/v1/discovery/scan/:job_id/cancel - Stop a running discovery scan
+async fn cancel_discovery_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(job_id): Path<u64>,
+) -> impl IntoResponse {
+    println!("🛑 POST /api/v1/discovery/scan/{}/cancel", job_id);
+
+    match state.query_api.discovery().cancel_scan(job_id) {
+        Ok(progress) => {
+            let response = serde_json::to_value(&progress).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => {
+            let (status, code) = if e.contains("not found") {
+                (StatusCode::NOT_FOUND, "SCAN_NOT_FOUND")
+            } else {
+                (StatusCode::CONFLICT, "SCAN_NOT_RUNNING")
+            };
+            let error = ErrorResponse {
+                code: code.to_string(),
+                message: e,
+            };
+            (status, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// GET /api/v1/discovery/findings?root_id=&category=&detector=&offset=&limit=
+/// Files with sensitive content (match counts only, never the matched values)
+async fn discovery_findings_handler(
+    State(state): State<Arc<ServerState>>,
+    Query(query): Query<FindingsQuery>,
+) -> impl IntoResponse {
+    println!("🔎 GET /api/v1/discovery/findings (root {:?})", query.root_id);
+
+    let page = state.query_api.discovery().findings(&query);
+    (StatusCode::OK, Json(StandardApiResponse::success(page)))
+}
+
+/// Run enabled auto-protection rules once a discovery scan completes
+fn watch_discovery(state: Arc<ServerState>, job_id: u64) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            match state.query_api.discovery().scan_progress(job_id) {
+                Some(progress) if progress.status == CrawlStatus::Running => continue,
+                Some(progress) if progress.status == CrawlStatus::Completed => break,
+                _ => return,
+            }
+        }
+
+        let auto_protection = state.auto_protection.clone();
+        match tokio::task::spawn_blocking(move || auto_protection.run_enabled_rules()).await {
+            Ok(reports) => {
+                for report in &reports {
+                    broadcast_rule_policies(&state, report);
+                }
+            }
+            Err(e) => println!("⚠️  Auto-protection task failed: {}", e),
+        }
+    });
+}
+
+/// Announce policies an auto-protection run applied
+fn broadcast_rule_policies(state: &ServerState, report: &RuleRunReport) {
+    for &(node_id, policy_id) in &report.created {
+        let action = state.policy_engine.get_policy_by_id(policy_id)
+            .map(|policy| format!("{:?}", policy.intent.action).to_lowercase())
+            .unwrap_or_default();
+        state.ws_server.broadcast_policy_applied(policy_id, node_id, "file", &action);
+    }
+}
+
+/// GET /api/v1/discovery/rules - List auto-protection rules
+async fn list_rules_handler(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let rules = state.auto_protection.list_rules();
+    let response = serde_json::json!({
+        "rules": rules,
+        "total": rules.len(),
+    });
+    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+}
+
+/// POST /api/v1/discovery/rules - Add an auto-protection rule
+/// Generated policies are created in the name of the admin sending the rule.
+async fn add_rule_handler(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(mut rule): Json<AutoProtectionRule>,
+) -> impl IntoResponse {
+    rule.created_by = match acting_admin(&headers, &rule.created_by, rule.confirmed) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+    println!("🤖 POST /api/v1/discovery/rules ('{}' by {})", rule.name, rule.created_by);
+
+    match state.auto_protection.add_rule(rule) {
+        Ok(rule) => {
+            let response = serde_json::to_value(&rule).unwrap_or_default();
+            (StatusCode::CREATED, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => {
+            println!("   ❌ Rule rejected: {}", e);
+            let error = ErrorResponse {
+                code: "INVALID_REQUEST".to_string(),
+                message: e,
+            };
+            (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// DELETE /api/v1/discovery/rules/:rule_id - Delete a rule (its policies stay)
+async fn remove_rule_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(rule_id): Path<u64>,
+) -> impl IntoResponse {
+    println!("🤖 DELETE /api/v1/discovery/rules/{}", rule_id);
+
+    match state.auto_protection.remove_rule(rule_id) {
+        Ok(rule) => {
+            let response = serde_json::to_value(&rule).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => rule_not_found(e),
+    }
+}
+
+/// GET /api/v1/discovery/rules/:rule_id/preview - Dry run: policies the rule would create
+async fn preview_rule_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(rule_id): Path<u64>,
+) -> impl IntoResponse {
+    println!("🤖 GET /api/v1/discovery/rules/{}/preview", rule_id);
+
+    match state.auto_protection.preview(rule_id) {
+        Ok(preview) => {
+            let response = serde_json::to_value(&preview).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => rule_not_found(e),
+    }
+}
+
+/// POST /api/v1/discovery/rules/:rule_id/run - Apply a rule now
+async fn run_rule_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(rule_id): Path<u64>,
+) -> impl IntoResponse {
+    println!("🤖 POST /api/v1/discovery/rules/{}/run", rule_id);
+
+    let auto_protection = state.auto_protection.clone();
+    match tokio::task::spawn_blocking(move || auto_protection.run_rule(rule_id)).await {
+        Ok(Ok(report)) => {
+            broadcast_rule_policies(&state, &report);
+            let response = serde_json::to_value(&report).unwrap_or_default();
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Ok(Err(e)) => rule_not_found(e),
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Auto-protection task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// GET /api/v1/devices/rules - List device rules
+async fn list_device_rules_handler(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let rules = state.device_control.list_rules();
+    let response = serde_json::json!({
+        "rules": rules,
+        "total": rules.len(),
+    });
+    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+}
+
+/// POST /api/v1/devices/rules - Add a device rule and cover the matching drives
+async fn add_device_rule_handler(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(mut rule): Json<DeviceRule>,
+) -> impl IntoResponse {
+    // Generated policies are created in this admin's name
+    rule.created_by = match acting_admin(&headers, &rule.created_by, false) {
+        Ok(admin) => admin,
+        Err((status, error)) => return (status, Json(StandardApiResponse::error(error))),
+    };
+    println!("🔌 POST /api/v1/devices/rules ('{}' by {})", rule.name, rule.created_by);
+
+    let rule = match state.device_control.add_rule(rule) {
+        Ok(rule) => rule,
+        Err(e) => {
+            println!("   ❌ Device rule rejected: {}", e);
+            let error = ErrorResponse {
+                code: "INVALID_REQUEST".to_string(),
+                message: e,
+            };
+            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(error)));
+        }
+    };
+
+    let device_control = state.device_control.clone();
+    match tokio::task::spawn_blocking(move || device_control.enforce()).await {
+        Ok(report) => {
+            state.ws_server.broadcast_device_policies(&report);
+            let response = serde_json::json!({
+                "rule": rule,
+                "report": report,
+            });
+            (StatusCode::CREATED, Json(StandardApiResponse::success(response)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Device control task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// DELETE /api/v1/devices/rules/:rule_id?removed_by= - Delete a rule and its drive policies
+async fn remove_device_rule_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(rule_id): Path<u64>,
+    headers: HeaderMap,
+    Query(params): Query<RemovePolicyQuery>,
+) -> impl IntoResponse {
+    println!("🔌 DELETE /api/v1/devices/rules/{}", rule_id);
+
+    // History of the removed drive policies must name who removed the rule
+    let removed_by = match admin_user(&headers)
+        .or(params.removed_by.filter(|user| !user.trim().is_empty()))
+    {
+        Some(removed_by) => removed_by,
+        None => {
+            return (StatusCode::BAD_REQUEST, Json(StandardApiResponse::error(ErrorResponse {
+                code: "REMOVER_REQUIRED".to_string(),
+                message: "Send X-ADMIN-USER or removed_by to remove a device rule".to_string(),
+            })));
+        }
+    };
+    let device_control = state.device_control.clone();
+    match tokio::task::spawn_blocking(move || device_control.remove_rule(rule_id, &removed_by)).await {
+        Ok(Ok((rule, report))) => {
+            state.ws_server.broadcast_device_policies(&report);
+            let response = serde_json::json!({
+                "rule": rule,
+                "report": report,
+            });
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Ok(Err(e)) => rule_not_found(e),
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Device control task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+/// GET /api/v1/devices/rules/:rule_id/drives - Drives a rule covers right now
+async fn device_rule_drives_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(rule_id): Path<u64>,
+) -> impl IntoResponse {
+    match state.device_control.matching_drives(rule_id) {
+        Ok(drives) => {
+            let response = serde_json::json!({
+                "rule_id": rule_id,
+                "drives": drives,
+                "total": drives.len(),
+            });
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        Err(e) => rule_not_found(e),
+    }
+}
+
+fn rule_not_found(message: String) -> (StatusCode, Json<StandardApiResponse<serde_json::Value>>) {
+    let error = ErrorResponse {
+        code: "RULE_NOT_FOUND".to_string(),
+        message,
+    };
+    (StatusCode::NOT_FOUND, Json(StandardApiResponse::error(error)))
+}
+
+/// GET /api/v1/audit/admin?offset=&limit= - Page through admin audit trail
+async fn admin_audit_handler(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<AuditPageQuery>,
+) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    println!("🧾 GET /api/v1/audit/admin?offset={}&limit={}", params.offset, limit);
+
+    let (entries, total) = state.admin_audit.page(params.offset, limit);
+    let response = serde_json::json!({
+        "offset": params.offset,
+        "limit": limit,
+        "total": total,
+        "entries": entries,
+    });
+    (StatusCode::OK, Json(StandardApiResponse::success(response)))
+}
+
+/// GET /api/v1/audit/admin/verify - Verify the audit hash chain
+async fn admin_audit_verify_handler(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    println!("🧾 GET /api/v1/audit/admin/verify");
+
+    let audit = state.admin_audit.clone();
+    match tokio::task::spawn_blocking(move || audit.verify()).await {
+        Ok(verification) => {
+            if !verification.valid {
+                println!("   🚨 {}", verification.message);
+            }
+            (StatusCode::OK, Json(StandardApiResponse::success(verification)))
+        }
+        Err(_) => {
+            let error = ErrorResponse {
+                code: "INTERNAL_ERROR".to_string(),
+                message: "Audit verification task panicked".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(StandardApiResponse::error(error)))
+        }
+    }
+}
+
+// ========================================
+// Health API
+// ========================================
+
+/// GET /api/v1/ping
+async fn ping(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    println!("🌐 GET /api/v1/ping");
+    
+    match state.query_api.handle_request(AdminRequest::Ping).await {
+        AgentResponse::Pong { timestamp, version } => {
+            println!("   ✅ Agent alive (v{})", version);
+            let response = serde_json::json!({
+                "status": "alive",
+                "timestamp": timestamp,
+                "version": version,
+                "agent": "DLP Agent",
+                "message": "Ready for Spring Boot Admin connections",
+            });
+            (StatusCode::OK, Json(StandardApiResponse::success(response)))
+        }
+        _ => {
+            println!("   ⚠️  Ping responded unexpectedly");
+            (StatusCode::OK, Json(StandardApiResponse::success(serde_json::json!({
+                "status": "alive",
+                "message": "Agent running",
+            }))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(method: Method, path: &str, body: serde_json::Value) -> Option<String> {
+        admin_action_for(&method, path, &body)
+    }
+
+    #[test]
+    fn every_mutating_call_is_audited_unless_read_only() {
+        let none = serde_json::Value::Null;
+        assert_eq!(action(Method::POST, "/api/v1/policies/apply", serde_json::json!({ "confirmed": true })).as_deref(), Some("confirm"));
+        assert_eq!(action(Method::POST, "/api/v1/discovery/rules", none.clone()).as_deref(), Some("add_rule"));
+        assert_eq!(action(Method::DELETE, "/api/v1/discovery/rules/3", none.clone()).as_deref(), Some("remove_rule"));
+        assert_eq!(action(Method::POST, "/api/v1/discovery/rules/3/run", none.clone()).as_deref(), Some("run_rule"));
+        assert_eq!(action(Method::DELETE, "/api/v1/devices/rules/2", none.clone()).as_deref(), Some("remove_device_rule"));
+        assert_eq!(action(Method::POST, "/api/v1/scan/9/cancel", none.clone()).as_deref(), Some("cancel_scan"));
+        assert_eq!(action(Method::POST, "/api/v1/discovery/scan", none.clone()).as_deref(), Some("start_discovery"));
+        assert_eq!(action(Method::PATCH, "/api/v1/something/new", none.clone()).as_deref(), Some("other"));
+
+        assert_eq!(action(Method::GET, "/api/v1/policies", none.clone()), None);
+        assert_eq!(action(Method::POST, "/api/v1/nodes/5/expand", none.clone()), None);
+        assert_eq!(action(Method::POST, "/api/v1/policies/preview", none.clone()), None);
+        assert_eq!(action(Method::POST, "/api/v1/policies/import", serde_json::json!({ "dry_run": true })), None);
+    }
+
+    #[test]
+    fn acting_admin_comes_from_the_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(acting_admin(&headers, "alice", false).unwrap(), "alice");
+        assert_eq!(acting_admin(&headers, "alice", true).unwrap_err().0, StatusCode::UNAUTHORIZED);
+        assert_eq!(acting_admin(&headers, " ", false).unwrap_err().0, StatusCode::BAD_REQUEST);
+
+        headers.insert("X-ADMIN-USER", "bob".parse().unwrap());
+        assert_eq!(acting_admin(&headers, "", true).unwrap(), "bob");
+        assert_eq!(acting_admin(&headers, "BOB", true).unwrap(), "bob");
+        // Naming someone else in the body is refused, not trusted
+        assert_eq!(acting_admin(&headers, "alice", true).unwrap_err().0, StatusCode::FORBIDDEN);
+    }
+}
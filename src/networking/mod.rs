@@ -3,6 +3,7 @@
 //! Core Principle: Expose Agent APIs over network for Spring Boot Admin
 //! 🔐 SECURITY: No NT paths in network responses, IDs only
 
+mod admin_audit;
 mod agent_server;
 mod websocket_server;
 
@@ -35,6 +36,7 @@ pub async fn init_step5(
         ws_server.clone(),
         bind_address
     );
+    let device_control = server.device_control();
     
     // Create shutdown signal
     let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
@@ -55,6 +57,7 @@ pub async fn init_step5(
         server_handle,
         shutdown_tx,
         ws_server,
+        device_control,
     })
 }
 
@@ -63,6 +66,7 @@ pub struct ServerHandle {
     server_handle: tokio::task::JoinHandle<()>,
     shutdown_tx: tokio::sync::oneshot::Sender<()>,
     ws_server: Arc<WebSocketServer>,
+    device_control: Arc<crate::policy::device_control::DeviceControlEngine>,
 }
 
 impl ServerHandle {
@@ -86,12 +90,14 @@ impl ServerHandle {
         self.ws_server.clone()
     }
 
+    /// Device rules, re-enforced when drives arrive or leave
+    pub fn device_control(&self) -> Arc<crate::policy::device_control::DeviceControlEngine> {
+        self.device_control.clone()
+    }
+
     /// Helper to emit filesystem changed events
-    pub fn emit_filesystem_changed(&self, node_id: u64, change_type: &str) {
-        self.ws_server.broadcast_event(AgentEvent::FilesystemChanged {
-            node_id,
-            change_type: change_type.to_string(),
-        });
+    pub fn emit_filesystem_changed(&self, change: &crate::fs_watcher::FsChange) {
+        self.ws_server.broadcast_filesystem_changed(change);
     }
 
      pub fn emit_kernel_blocked(&self, operation: &str, policy_id: u64, process: &str) {
@@ -7,13 +7,22 @@ use futures::{ sink::SinkExt, stream::StreamExt };
 use serde_json::json;
 use std::sync::Arc;
 use tokio::sync::broadcast;
+use crate::filesystem_scanner::CrawlProgress;
+use crate::fs_index::FileSystemNode;
+use crate::fs_watcher::{FsChange, FsChangeKind};
+use crate::policy::enforcement_suspension::{EnforcementSuspension, ResumeReport};
+use crate::policy::policy_retarget::{PolicyOrphan, PolicyRetarget, RetargetMode};
+use crate::policy::fingerprint_registry::{MatchKind, ProtectedCopy};
+use crate::policy::device_control::DeviceRuleReport;
 
 /// WebSocket events
 #[derive(Debug, Clone, serde::Serialize)]
 pub enum AgentEvent {
     FilesystemChanged {
         node_id: u64,
-        change_type: String,
+        change_type: FsChangeKind,
+        parent_id: Option<u64>,
+        previous_parent_id: Option<u64>,   // Set when a rename moved the node
     },
     PolicyApplied {
         policy_id: u64,
@@ -25,12 +34,66 @@ pub enum AgentEvent {
         policy_id: u64,
         node_id: u64,
     },
+    PolicyRetargeted {
+        policy_id: u64,
+        node_id: u64,
+        mode: RetargetMode,
+        followed: bool,      // false: rules stayed on the old path (by mode, or because follow failed)
+        version: Option<u32>,
+        failed: bool,
+    },
+    PolicyOrphaned {
+        policy_id: u64,
+        node_id: u64,        // Deleted or moved out of view; rules still guard the old path
+    },
+    ProtectedContentDuplicated {
+        policy_id: u64,
+        source_node_id: u64, // Protected file
+        node_id: u64,        // The copy
+        match_kind: MatchKind,
+        similarity: u8,
+    },
     KernelBlocked {
         operation: String, 
         policy_id: u64,      // ✅ Use policy_id, not path
         process: String,
         timestamp: u64,
     },
+    EnforcementSuspended {
+        suspension_id: u64,
+        suspended_by: String,
+        reason: String,
+        policies_suspended: usize,
+        resume_at: u64,
+        priority: String,    // Always "high": every admin must notice break glass
+    },
+    EnforcementResumed {
+        suspension_id: u64,
+        resumed_by: String,
+        automatic: bool,
+        policies_restored: usize,
+        failed_policies: usize,
+        priority: String,
+    },
+    ScanProgress {
+        job_id: u64,
+        root_node_id: u64,
+        status: String,
+        directories_scanned: u64,
+        entries_indexed: u64,
+        errors: u64,
+    },
+    DriveArrived {
+        node_id: u64,
+        name: String,
+        drive_kind: Option<String>,
+        device_class: Option<String>,
+        label: Option<String>,
+        serial: Option<String>,
+    },
+    DriveRemoved {
+        node_id: u64,
+    },
     AgentConnected,
     AgentDisconnected,
     Error {
@@ -174,6 +237,114 @@ impl WebSocketServer {
         });
     }
 
+    /// Broadcast break-glass suspension (high priority)
+    pub fn broadcast_enforcement_suspended(&self, suspension: &EnforcementSuspension) {
+        self.broadcast_event(AgentEvent::EnforcementSuspended {
+            suspension_id: suspension.suspension_id,
+            suspended_by: suspension.suspended_by.clone(),
+            reason: suspension.reason.clone(),
+            policies_suspended: suspension.policies_suspended,
+            resume_at: suspension.resume_at,
+            priority: "high".to_string(),
+        });
+    }
+
+    /// Broadcast enforcement resumed (high priority)
+    pub fn broadcast_enforcement_resumed(&self, report: &ResumeReport) {
+        self.broadcast_event(AgentEvent::EnforcementResumed {
+            suspension_id: report.suspension_id,
+            resumed_by: report.resumed_by.clone(),
+            automatic: report.automatic,
+            policies_restored: report.policies_restored,
+            failed_policies: report.failed_policy_ids.len(),
+            priority: "high".to_string(),
+        });
+    }
+    
+    /// Broadcast a filesystem change applied to the index (safe - IDs only)
+    pub fn broadcast_filesystem_changed(&self, change: &FsChange) {
+        self.broadcast_event(AgentEvent::FilesystemChanged {
+            node_id: change.node_id,
+            change_type: change.kind,
+            parent_id: change.parent_id,
+            previous_parent_id: change.previous_parent_id,
+        });
+    }
+
+    /// Broadcast a policy re-resolved after its node was renamed (safe - IDs only)
+    pub fn broadcast_policy_retargeted(&self, retarget: &PolicyRetarget) {
+        self.broadcast_event(AgentEvent::PolicyRetargeted {
+            policy_id: retarget.policy_id,
+            node_id: retarget.node_id,
+            mode: retarget.mode,
+            followed: retarget.followed,
+            version: retarget.version,
+            failed: retarget.failed,
+        });
+    }
+
+    /// Broadcast a policy whose node was deleted or moved out of view (safe - IDs only)
+    pub fn broadcast_policy_orphaned(&self, orphan: &PolicyOrphan) {
+        self.broadcast_event(AgentEvent::PolicyOrphaned {
+            policy_id: orphan.policy_id,
+            node_id: orphan.node_id,
+        });
+    }
+
+    /// Broadcast a copy of protected content found elsewhere (safe - IDs only)
+    pub fn broadcast_protected_copy(&self, copy: &ProtectedCopy) {
+        self.broadcast_event(AgentEvent::ProtectedContentDuplicated {
+            policy_id: copy.policy_id,
+            source_node_id: copy.source_node_id,
+            node_id: copy.node_id,
+            match_kind: copy.match_kind,
+            similarity: copy.similarity,
+        });
+    }
+
+    /// Broadcast a drive added to the tree (safe - no NT paths)
+    pub fn broadcast_drive_arrived(&self, drive: &FileSystemNode) {
+        let device = drive.device.as_ref();
+        self.broadcast_event(AgentEvent::DriveArrived {
+            node_id: drive.id,
+            name: drive.name.clone(),
+            drive_kind: drive.drive_kind.map(|kind| kind.as_str().to_string()),
+            device_class: device.map(|device| device.class.as_str().to_string()),
+            label: device.and_then(|device| device.label.clone()),
+            serial: device.and_then(|device| device.serial.clone()),
+        });
+    }
+
+    /// Broadcast a drive dropped from the tree (safe - ID only)
+    pub fn broadcast_drive_removed(&self, node_id: u64) {
+        self.broadcast_event(AgentEvent::DriveRemoved { node_id });
+    }
+
+    /// Broadcast policies device rules applied or removed (safe - IDs only)
+    pub fn broadcast_device_policies(&self, report: &DeviceRuleReport) {
+        for policy in report.applied.iter().chain(&report.repointed) {
+            let action = format!("{:?}", policy.action).to_lowercase();
+            self.broadcast_policy_applied(policy.policy_id, policy.node_id, "folder_recursive", &action);
+        }
+        for policy in &report.removed {
+            self.broadcast_event(AgentEvent::PolicyRemoved {
+                policy_id: policy.policy_id,
+                node_id: policy.node_id,
+            });
+        }
+    }
+
+    /// Broadcast background crawl progress (safe - node IDs and counters only)
+    pub fn broadcast_scan_progress(&self, progress: &CrawlProgress) {
+        self.broadcast_event(AgentEvent::ScanProgress {
+            job_id: progress.job_id,
+            root_node_id: progress.root_node_id,
+            status: progress.status.as_str().to_string(),
+            directories_scanned: progress.directories_scanned,
+            entries_indexed: progress.entries_indexed,
+            errors: progress.errors,
+        });
+    }
     
     /// WebSocket handler
     pub async fn handle_websocket(
@@ -0,0 +1,192 @@
+//! NT Path Reverse Index
+//! Maps kernel-reported NT paths back to node IDs (INTERNAL ONLY)
+//! Components compare in canonical form (NTFS case folding, long names, no
+//! stream suffix or trailing dots/spaces), so any spelling of a path is found.
+
+use std::collections::HashMap;
+
+use crate::path_canonical;
+
+/// One path component in the trie
+#[derive(Debug, Default)]
+struct TrieNode {
+    node_id: Option<u64>,
+    children: HashMap<String, TrieNode>,
+}
+
+/// Trie over NT path components (e.g. device, harddiskvolume3, finance)
+#[derive(Debug, Default)]
+pub struct NtPathTrie {
+    root: TrieNode,
+}
+
+impl NtPathTrie {
+    /// Create empty trie
+    pub fn new() -> Self {
+        NtPathTrie::default()
+    }
+
+    /// Map an NT path to a node ID (replaces any previous mapping)
+    pub fn insert(&mut self, nt_path: &str, node_id: u64) {
+        let mut current = &mut self.root;
+        for component in Self::components(nt_path) {
+            current = current.children.entry(component).or_default();
+        }
+        current.node_id = Some(node_id);
+    }
+
+    /// Remove a mapping, only if it still points at `node_id`
+    pub fn remove(&mut self, nt_path: &str, node_id: u64) -> bool {
+        let components = Self::components(nt_path);
+        Self::remove_at(&mut self.root, &components, node_id)
+    }
+
+    /// Exact lookup
+    pub fn get(&self, nt_path: &str) -> Option<u64> {
+        let mut current = &self.root;
+        for component in Self::components(nt_path) {
+            current = current.children.get(&component)?;
+        }
+        current.node_id
+    }
+
+    /// Deepest mapped node that is `nt_path` itself or one of its ancestors
+    pub fn longest_prefix(&self, nt_path: &str) -> Option<u64> {
+        let mut current = &self.root;
+        let mut best = current.node_id;
+        for component in Self::components(nt_path) {
+            current = match current.children.get(&component) {
+                Some(child) => child,
+                None => break,
+            };
+            if current.node_id.is_some() {
+                best = current.node_id;
+            }
+        }
+        best
+    }
+
+    /// Drop all mappings
+    pub fn clear(&mut self) {
+        self.root = TrieNode::default();
+    }
+
+    /// Recursive remove, pruning branches left without mappings
+    fn remove_at(node: &mut TrieNode, components: &[String], node_id: u64) -> bool {
+        let (first, rest) = match components.split_first() {
+            Some(split) => split,
+            None => {
+                if node.node_id == Some(node_id) {
+                    node.node_id = None;
+                    return true;
+                }
+                return false;
+            }
+        };
+
+        let child = match node.children.get_mut(first) {
+            Some(child) => child,
+            None => return false,
+        };
+        let removed = Self::remove_at(child, rest, node_id);
+        if removed && child.node_id.is_none() && child.children.is_empty() {
+            node.children.remove(first);
+        }
+        removed
+    }
+
+    /// Split into canonical components ("\Device\X\a.\" → ["DEVICE", "X", "A"])
+    fn components(nt_path: &str) -> Vec<String> {
+        path_canonical::comparison_key(nt_path)
+            .split('\\')
+            .filter(|component| !component.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINANCE: &str = "\\Device\\HarddiskVolume3\\Finance\\";
+    const REPORT: &str = "\\Device\\HarddiskVolume3\\Finance\\Q1\\report.xlsx";
+
+    #[test]
+    fn get_is_exact() {
+        let mut trie = NtPathTrie::new();
+        trie.insert(FINANCE, 10);
+        trie.insert(REPORT, 12);
+
+        assert_eq!(trie.get(FINANCE), Some(10));
+        assert_eq!(trie.get(REPORT), Some(12));
+        assert_eq!(trie.get("\\Device\\HarddiskVolume3\\Finance\\Q1"), None);
+        assert_eq!(trie.get("\\Device\\HarddiskVolume3\\Finance\\Q1\\other.xlsx"), None);
+        assert_eq!(trie.get("\\Device\\HarddiskVolume3"), None);
+    }
+
+    #[test]
+    fn longest_prefix_finds_the_deepest_mapped_ancestor() {
+        let mut trie = NtPathTrie::new();
+        trie.insert("\\Device\\HarddiskVolume3\\", 2);
+        trie.insert(FINANCE, 10);
+
+        assert_eq!(trie.longest_prefix(REPORT), Some(10));
+        assert_eq!(trie.longest_prefix(FINANCE), Some(10));
+        assert_eq!(trie.longest_prefix("\\Device\\HarddiskVolume3\\Legal\\a.docx"), Some(2));
+        assert_eq!(trie.longest_prefix("\\Device\\HarddiskVolume4\\Finance\\a.docx"), None);
+        assert_eq!(trie.longest_prefix("\\Device\\Mup\\fs01\\share"), None);
+    }
+
+    #[test]
+    fn spellings_of_a_path_resolve_to_the_same_node() {
+        let mut trie = NtPathTrie::new();
+        trie.insert(REPORT, 12);
+
+        assert_eq!(trie.get("\\DEVICE\\harddiskvolume3\\FINANCE\\q1\\Report.XLSX"), Some(12));
+        assert_eq!(trie.get("\\Device\\HarddiskVolume3\\Finance\\Q1\\report.xlsx:Zone.Identifier"), Some(12));
+        assert_eq!(trie.get("\\Device\\HarddiskVolume3\\Finance\\Q1\\report.xlsx:Zone.Identifier:$DATA"), Some(12));
+        assert_eq!(trie.get("\\Device\\HarddiskVolume3\\Finance\\Q1\\report.xlsx. "), Some(12));
+        assert_eq!(trie.get("\\Device\\HarddiskVolume3\\Finance.\\Q1\\report.xlsx"), Some(12));
+        assert_eq!(trie.longest_prefix("\\device\\harddiskvolume3\\finance\\q1\\report.xlsx:x\\"), Some(12));
+    }
+
+    #[test]
+    fn remove_only_drops_its_own_mapping_and_prunes() {
+        let mut trie = NtPathTrie::new();
+        trie.insert(FINANCE, 10);
+        trie.insert(REPORT, 12);
+
+        // The path was taken over by another node: the stale remove is a no-op
+        trie.insert(REPORT, 13);
+        assert!(!trie.remove(REPORT, 12));
+        assert_eq!(trie.get(REPORT), Some(13));
+
+        assert!(trie.remove(REPORT, 13));
+        assert_eq!(trie.get(REPORT), None);
+        assert_eq!(trie.longest_prefix(REPORT), Some(10));
+        assert!(!trie.remove(REPORT, 13));
+
+        // Q1 held no mapping of its own, so it went with its last child
+        let volume = &trie.root.children["DEVICE"].children["HARDDISKVOLUME3"];
+        assert!(volume.children["FINANCE"].children.is_empty());
+
+        assert!(trie.remove(FINANCE, 10));
+        assert!(trie.root.children.is_empty());
+    }
+
+    #[test]
+    fn clear_drops_everything() {
+        let mut trie = NtPathTrie::new();
+        trie.insert(FINANCE, 10);
+        trie.insert(REPORT, 12);
+
+        trie.clear();
+        assert_eq!(trie.get(FINANCE), None);
+        assert_eq!(trie.longest_prefix(REPORT), None);
+        assert!(trie.root.children.is_empty());
+
+        trie.insert(REPORT, 14);
+        assert_eq!(trie.get(REPORT), Some(14));
+    }
+}
@@ -0,0 +1,369 @@
+//! Path Canonicalization - one spelling per file for matching
+//! NTFS opens the same file under many spellings: any casing, 8.3 short names
+//! (FINANC~1), trailing dots/spaces (stripped by Win32) and stream suffixes
+//! ("a.txt::$DATA", "a.txt:notes"). Rules and kernel-reported paths are
+//! compared in canonical form so a variant cannot dodge an exact-match rule.
+//! Works on NT paths ("\Device\...") and DOS paths ("C:\...", "\\server\share\...").
+
+/// Expands 8.3 components of an existing path (None when it cannot)
+pub type LongNameExpander = dyn Fn(&str) -> Option<String>;
+
+/// NTFS upcase of one character: the simple (1:1) uppercase mapping of a
+/// UTF-16 unit, as in the $UpCase table written at format time. Characters
+/// whose uppercase is several characters ("ß") and characters outside the
+/// BMP are left alone, like NTFS does.
+pub fn upcase_char(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_uppercase();
+    }
+    if c as u32 > 0xFFFF {
+        return c;
+    }
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) if u as u32 <= 0xFFFF => u,
+        _ => c,
+    }
+}
+
+/// Upcase a whole string with the NTFS table
+pub fn fold_case(s: &str) -> String {
+    s.chars().map(upcase_char).collect()
+}
+
+/// Case-insensitive equality under the NTFS upcase table
+pub fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars().map(upcase_char).eq(b.chars().map(upcase_char))
+}
+
+/// 8.3 short name with a numeric tail ("FINANC~1", "REPORT~12.XLS")
+pub fn is_short_name(component: &str) -> bool {
+    let (base, ext) = component.split_once('.').unwrap_or((component, ""));
+    let tail = match base.rfind('~') {
+        Some(tilde) if tilde > 0 => &base[tilde + 1..],
+        _ => return false,
+    };
+    base.len() <= 8
+        && ext.len() <= 3
+        && !ext.contains('.')
+        && !tail.is_empty()
+        && tail.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// Spelling-only cleanup (no filesystem access, case kept): '/' → '\',
+/// doubled separators, "." and ".." components, stream suffixes and
+/// trailing dots/spaces of each component. A trailing backslash is kept.
+pub fn clean(path: &str) -> String {
+    let path = path.trim().replace('/', "\\");
+    let leading = path.len() - path.trim_start_matches('\\').len();
+    let prefix = &path[..leading.min(2)];
+
+    // "\Device\X", "\\server\share" and "\\?\C:" roots are never popped by ".."
+    let root_len = if prefix.is_empty() { 1 } else { 2 };
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split('\\').filter(|component| !component.is_empty()) {
+        match component {
+            "." => {}
+            ".." => {
+                if components.len() > root_len {
+                    components.pop();
+                }
+            }
+            _ => {
+                let component = strip_stream(component).trim_end_matches(['.', ' ']);
+                if !component.is_empty() {
+                    components.push(component);
+                }
+            }
+        }
+    }
+
+    let mut cleaned = format!("{}{}", prefix, components.join("\\"));
+    if path.ends_with('\\') && !components.is_empty() {
+        cleaned.push('\\');
+    }
+    cleaned
+}
+
+/// Canonical spelling for storing and sending paths: cleaned, short names
+/// expanded to long names (Win32), case kept
+pub fn canonicalize(path: &str) -> String {
+    canonicalize_with(path, &expand_long_names)
+}
+
+/// `canonicalize` with a given long-name expander
+/// The expander is only asked when a component looks like a short name.
+pub fn canonicalize_with(path: &str, expand: &LongNameExpander) -> String {
+    let cleaned = clean(path);
+    if !cleaned.split('\\').any(is_short_name) {
+        return cleaned;
+    }
+    match expand(&cleaned) {
+        Some(long) => clean(&long),
+        None => cleaned,
+    }
+}
+
+/// Key for comparisons and maps: canonical spelling, upcased
+pub fn comparison_key(path: &str) -> String {
+    fold_case(&canonicalize(path))
+}
+
+/// `comparison_key` with a given long-name expander
+pub fn comparison_key_with(path: &str, expand: &LongNameExpander) -> String {
+    fold_case(&canonicalize_with(path, expand))
+}
+
+/// Two spellings name the same file
+pub fn same_path(a: &str, b: &str) -> bool {
+    same_path_with(a, b, &expand_long_names)
+}
+
+pub fn same_path_with(a: &str, b: &str, expand: &LongNameExpander) -> bool {
+    comparison_key_with(a, expand).trim_end_matches('\\') == comparison_key_with(b, expand).trim_end_matches('\\')
+}
+
+/// `path` is `folder` itself or below it
+pub fn is_within(path: &str, folder: &str) -> bool {
+    is_within_with(path, folder, &expand_long_names)
+}
+
+pub fn is_within_with(path: &str, folder: &str, expand: &LongNameExpander) -> bool {
+    let path = comparison_key_with(path, expand);
+    let folder = comparison_key_with(folder, expand);
+    let folder = folder.trim_end_matches('\\');
+    path.trim_end_matches('\\') == folder
+        || path.strip_prefix(folder).is_some_and(|rest| rest.starts_with('\\'))
+}
+
+/// Component without its stream ("a.txt:notes:$DATA" → "a.txt"); drive specs stay
+fn strip_stream(component: &str) -> &str {
+    let is_drive = component.len() == 2
+        && component.ends_with(':')
+        && component.as_bytes()[0].is_ascii_alphabetic();
+    match component.find(':') {
+        Some(colon) if !is_drive => &component[..colon],
+        _ => component,
+    }
+}
+
+/// Long form of an existing path via GetLongPathName
+/// NT device paths are passed through the GLOBALROOT link.
+#[cfg(windows)]
+pub fn expand_long_names(path: &str) -> Option<String> {
+    use windows_sys::Win32::Storage::FileSystem::GetLongPathNameW;
+
+    const GLOBALROOT: &str = "\\\\?\\GLOBALROOT";
+    let is_nt = path.starts_with("\\Device\\");
+    let win32_path = if is_nt { format!("{}{}", GLOBALROOT, path) } else { path.to_string() };
+    let wide: Vec<u16> = win32_path.encode_utf16().chain(Some(0)).collect();
+
+    let mut buffer = vec![0u16; 1024];
+    let mut len = unsafe { GetLongPathNameW(wide.as_ptr(), buffer.as_mut_ptr(), buffer.len() as u32) } as usize;
+    if len > buffer.len() {
+        buffer = vec![0u16; len];
+        len = unsafe { GetLongPathNameW(wide.as_ptr(), buffer.as_mut_ptr(), buffer.len() as u32) } as usize;
+    }
+    if len == 0 || len > buffer.len() {
+        return None;
+    }
+
+    let long = String::from_utf16_lossy(&buffer[..len]);
+    Some(match long.strip_prefix(GLOBALROOT) {
+        Some(nt_path) if is_nt => nt_path.to_string(),
+        _ => long,
+    })
+}
+
+/// Short (8.3) names only exist on Windows volumes
+#[cfg(not(windows))]
+pub fn expand_long_names(_path: &str) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Short names as a volume would have them (long path → short alias)
+    const SHORT_NAMES: &[(&str, &str)] = &[
+        ("FINANC~1", "Finance Reports"),
+        ("QUARTE~1.XLS", "Quarterly Results.xlsx"),
+        ("PROGRA~1", "Program Files"),
+    ];
+
+    fn mock_expander(path: &str) -> Option<String> {
+        let expanded: Vec<String> = path.split('\\')
+            .map(|component| {
+                SHORT_NAMES.iter()
+                    .find(|(short, _)| short.eq_ignore_ascii_case(component))
+                    .map(|(_, long)| long.to_string())
+                    .unwrap_or_else(|| component.to_string())
+            })
+            .collect();
+        Some(expanded.join("\\"))
+    }
+
+    fn key(path: &str) -> String {
+        comparison_key_with(path, &mock_expander)
+    }
+
+    #[test]
+    fn upcase_follows_ntfs_rules() {
+        assert_eq!(upcase_char('a'), 'A');
+        assert_eq!(upcase_char('é'), 'É');
+        assert_eq!(upcase_char('я'), 'Я');
+        assert_eq!(upcase_char('ß'), 'ß');      // Would be "SS": not a 1:1 mapping
+        assert_eq!(upcase_char('𐐨'), '𐐨');     // Outside the BMP
+        assert!(eq_ignore_case("Финансы\\Отчёт.docx", "ФИНАНСЫ\\ОТЧЁТ.DOCX"));
+    }
+
+    #[test]
+    fn recognizes_short_names() {
+        assert!(is_short_name("FINANC~1"));
+        assert!(!is_short_name("FINANCE~1"));   // 9-character base
+        assert!(is_short_name("QUARTE~1.XLS"));
+        assert!(is_short_name("A~12"));
+        assert!(!is_short_name("~1"));
+        assert!(!is_short_name("Finance~Reports"));
+        assert!(!is_short_name("LONGERNAME~1"));
+        assert!(!is_short_name("FILE~1.XLSX"));
+    }
+
+    #[test]
+    fn cleans_spelling_variants() {
+        assert_eq!(clean("\\Device\\HarddiskVolume3\\Finance.\\q1.xlsx::$DATA"), "\\Device\\HarddiskVolume3\\Finance\\q1.xlsx");
+        assert_eq!(clean("C:/Finance//Q1 . \\a.txt:secret"), "C:\\Finance\\Q1\\a.txt");
+        assert_eq!(clean("C:\\Finance\\..\\..\\..\\a.txt"), "C:\\a.txt");
+        assert_eq!(clean("\\Device\\HarddiskVolume3\\..\\a.txt"), "\\Device\\HarddiskVolume3\\a.txt");
+        assert_eq!(clean("\\\\server\\share\\.\\dir\\"), "\\\\server\\share\\dir\\");
+        assert_eq!(clean("\\Device\\HarddiskVolume3\\dir:$I30:$INDEX_ALLOCATION\\a.txt"), "\\Device\\HarddiskVolume3\\dir\\a.txt");
+    }
+
+    #[test]
+    fn short_names_are_expanded() {
+        assert_eq!(
+            canonicalize_with("C:\\FINANC~1\\QUARTE~1.XLS", &mock_expander),
+            "C:\\Finance Reports\\Quarterly Results.xlsx"
+        );
+        assert!(same_path_with(
+            "\\Device\\HarddiskVolume3\\PROGRA~1\\App\\",
+            "\\device\\harddiskvolume3\\program files\\app",
+            &mock_expander,
+        ));
+        assert!(is_within_with("C:\\financ~1\\q1.xlsx", "C:\\Finance Reports\\", &mock_expander));
+        assert!(!is_within_with("C:\\Finance Reports 2\\q1.xlsx", "C:\\Finance Reports", &mock_expander));
+    }
+
+    /// One path component that survives cleaning unchanged
+    fn component() -> impl Strategy<Value = String> {
+        "[A-Za-z0-9_éÉяЯ][A-Za-z0-9 _.éÉяЯ-]{0,10}[A-Za-z0-9_éÉяЯ]"
+    }
+
+    fn nt_path() -> impl Strategy<Value = String> {
+        (1u8..=9, prop::collection::vec(component(), 1..5))
+            .prop_map(|(volume, components)| format!("\\Device\\HarddiskVolume{}\\{}", volume, components.join("\\")))
+    }
+
+    /// Flip the case of characters chosen by the mask
+    fn recase(path: &str, mask: &[bool]) -> String {
+        path.chars()
+            .zip(mask.iter().cycle())
+            .map(|(c, &flip)| match (flip, c.is_lowercase()) {
+                (false, _) => c,
+                (true, true) => upcase_char(c),
+                (true, false) => c.to_lowercase().next().unwrap_or(c),
+            })
+            .collect()
+    }
+
+    /// Add trailing dots/spaces to components chosen by the mask
+    fn pad_components(path: &str, mask: &[bool], padding: &str) -> String {
+        path.split('\\')
+            .enumerate()
+            .map(|(i, component)| {
+                if i > 2 && mask[i % mask.len()] { format!("{}{}", component, padding) } else { component.to_string() }
+            })
+            .collect::<Vec<_>>()
+            .join("\\")
+    }
+
+    proptest! {
+        #[test]
+        fn canonical_form_is_idempotent(path in nt_path()) {
+            let once = key(&path);
+            prop_assert_eq!(key(&once), once.clone());
+            prop_assert_eq!(clean(&clean(&path)), clean(&path));
+        }
+
+        #[test]
+        fn case_variants_match(path in nt_path(), mask in prop::collection::vec(any::<bool>(), 1..16)) {
+            let variant = recase(&path, &mask);
+            prop_assert!(same_path_with(&path, &variant, &mock_expander), "{} vs {}", path, variant);
+        }
+
+        #[test]
+        fn trailing_dots_and_spaces_match(
+            path in nt_path(),
+            mask in prop::collection::vec(any::<bool>(), 1..8),
+            padding in "[. ]{1,4}",
+        ) {
+            let variant = pad_components(&path, &mask, &padding);
+            prop_assert!(same_path_with(&path, &variant, &mock_expander), "{} vs {}", path, variant);
+        }
+
+        #[test]
+        fn stream_suffixes_match(path in nt_path(), stream in prop_oneof![
+            Just("::$DATA".to_string()),
+            "[a-z]{1,8}".prop_map(|name| format!(":{}", name)),
+            "[a-z]{1,8}".prop_map(|name| format!(":{}:$DATA", name)),
+        ]) {
+            let variant = format!("{}{}", path, stream);
+            prop_assert!(same_path_with(&path, &variant, &mock_expander), "{} vs {}", path, variant);
+        }
+
+        #[test]
+        fn short_name_spellings_match(
+            volume in 1u8..=9,
+            before in prop::collection::vec(component(), 0..3),
+            after in prop::collection::vec(component(), 0..3),
+            alias in 0..SHORT_NAMES.len(),
+        ) {
+            let (short, long) = SHORT_NAMES[alias];
+            let folder = |middle: &str| {
+                let mut components = before.clone();
+                components.push(middle.to_string());
+                format!("\\Device\\HarddiskVolume{}\\{}", volume, components.join("\\"))
+            };
+            let below = |middle: &str| {
+                let mut path = folder(middle);
+                for component in &after {
+                    path.push('\\');
+                    path.push_str(component);
+                }
+                path
+            };
+            prop_assert!(same_path_with(&below(long), &below(short), &mock_expander), "{} vs {}", below(long), below(short));
+            prop_assert!(is_within_with(&below(short), &folder(long), &mock_expander));
+            let short_folder = format!("{}\\", folder(short));
+            prop_assert!(is_within_with(&below(long), &short_folder, &mock_expander));
+        }
+
+        #[test]
+        fn combined_variants_match(
+            path in nt_path(),
+            mask in prop::collection::vec(any::<bool>(), 1..16),
+            padding in "[. ]{1,3}",
+        ) {
+            let variant = format!("{}::$DATA", pad_components(&recase(&path, &mask), &mask, &padding));
+            prop_assert!(same_path_with(&path, &variant, &mock_expander), "{} vs {}", path, variant);
+        }
+
+        #[test]
+        fn different_files_stay_different(a in nt_path(), b in nt_path()) {
+            prop_assume!(!eq_ignore_case(&a, &b));
+            prop_assert!(!same_path_with(&a, &b, &mock_expander));
+        }
+    }
+}
@@ -244,14 +244,16 @@
 
 use std::path::Path;
 use std::ffi::OsStr;
+use crate::path_canonical;
 use std::os::windows::ffi::OsStrExt;
 
 pub struct PathNormalizer;
 
 impl PathNormalizer {
-    /// Convert to display format (for debugging only)
+    /// Convert to display format: canonical spelling (long names, no stream
+    /// suffix or trailing dots/spaces), case kept
     pub fn normalize_display_path(path: &str) -> String {
-        let mut normalized: String = path.replace('/', "\\").trim().to_string();
+        let mut normalized = path_canonical::canonicalize(path);
         
         if !normalized.ends_with('\\') && Path::new(&normalized).is_dir() {
             normalized.push('\\');
@@ -0,0 +1,583 @@
+//! Auto-Protection Rules
+//! Core Principle: Classification results turn into policies without guesswork
+//! A rule matches discovery findings (detector/category, minimum matches),
+//! path patterns and file types, and generates File-scope intents through the
+//! normal assurance path. Every generated policy is tagged with its rule, and
+//! a rule never creates more policies than its cap.
+//! A rule remembers every node it ever protected or requested: a policy an
+//! admin removed (or a request they rejected) is not generated again, and
+//! the cap counts over the rule's lifetime. Rules are persisted to disk.
+
+use parking_lot::{Mutex, RwLock};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::discovery::FindingCategory;
+use crate::fs_index::FileSystemNode;
+use crate::index_search::SearchMatcher;
+
+use super::policy_approval::ChangeOutcome;
+use super::policy_engine::PolicyEngine;
+use super::policy_intent::{PolicyIntent, ProtectionAction, ProtectionOperations, ProtectionScope};
+
+/// Policies one rule may create when the rule does not set a cap
+pub const DEFAULT_RULE_POLICY_CAP: usize = 50;
+
+/// Largest cap a rule may ask for
+pub const MAX_RULE_POLICY_CAP: usize = 1000;
+
+/// Environment variable for the auto-protection rule file
+pub const AUTO_RULES_FILE_ENV: &str = "AGENT_AUTO_RULES";
+
+/// Default rule file (next to the agent)
+const DEFAULT_AUTO_RULES_FILE: &str = "dlp_auto_rules.json";
+
+/// Auto-protection rule (admin-defined)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoProtectionRule {
+    #[serde(default)]
+    pub rule_id: u64,                        // Assigned by the agent
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,                       // Run after every discovery scan
+    #[serde(default)]
+    pub root_id: Option<u64>,                // Only files below this node
+    #[serde(default)]
+    pub path_glob: Option<String>,           // Display path glob, e.g. "D:\Shared\*"
+    #[serde(default)]
+    pub extensions: Vec<String>,             // e.g. ["csv", "xlsx"], any when empty
+    #[serde(default)]
+    pub detector: Option<String>,            // e.g. "credit_card"
+    #[serde(default)]
+    pub category: Option<FindingCategory>,
+    #[serde(default = "default_min_matches")]
+    pub min_matches: usize,                  // Sum of matching findings in one file
+    pub action: ProtectionAction,
+    pub operations: ProtectionOperations,
+    #[serde(default)]
+    pub max_policies: Option<usize>,         // DEFAULT_RULE_POLICY_CAP when None
+    #[serde(default)]
+    pub confirmed: bool,                     // Admin confirmed high-risk intents at rule creation
+    #[serde(default)]
+    pub created_by: String,                  // Authenticated admin; generated policies are created in their name
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub generated_nodes: Vec<u64>,           // Nodes protected or requested so far (agent-maintained, lifetime)
+}
+
+/// On-disk rule file
+#[derive(Default, Serialize, Deserialize)]
+struct AutoRulesFile {
+    next_rule_id: u64,
+    rules: Vec<AutoProtectionRule>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_min_matches() -> usize {
+    1
+}
+
+/// File a rule matches (admin-safe)
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleMatch {
+    pub node_id: u64,
+    pub name: String,
+    pub matches: usize,
+}
+
+/// Dry-run result of a rule
+#[derive(Debug, Clone, Serialize)]
+pub struct RulePreview {
+    pub rule_id: u64,
+    pub matched_files: usize,
+    pub already_protected: usize,        // Generated by this rule before (even if since removed)
+    pub would_create: Vec<RuleMatch>,    // Within the remaining cap
+    pub over_cap: usize,                 // Matches the cap leaves unprotected
+    pub policy_cap: usize,
+    pub policies_created: usize,         // By earlier runs, removed ones included
+}
+
+/// Outcome of running a rule
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuleRunReport {
+    pub rule_id: u64,
+    pub created: Vec<(u64, u64)>,        // (node_id, policy_id)
+    pub pending_approval: Vec<(u64, u64)>, // (node_id, approval request_id)
+    pub failed: Vec<(u64, String)>,      // (node_id, reason)
+    pub already_protected: usize,
+    pub cap_reached: bool,
+}
+
+/// Compiled form of a rule, checked against classified nodes
+#[derive(Clone)]
+struct CompiledRule {
+    rule: AutoProtectionRule,
+    path: Option<Regex>,
+    extensions: Vec<String>,
+}
+
+impl CompiledRule {
+    fn compile(rule: AutoProtectionRule) -> Result<Self, String> {
+        let path = rule.path_glob.as_deref()
+            .map(str::trim)
+            .filter(|glob| !glob.is_empty())
+            .map(SearchMatcher::compile_glob)
+            .transpose()?;
+        let extensions = rule.extensions.iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+        Ok(CompiledRule { rule, path, extensions })
+    }
+
+    /// Matching findings in the file (0 = rule does not apply)
+    fn matches(&self, node: &FileSystemNode) -> usize {
+        if let Some(path) = &self.path {
+            if !path.is_match(&node.display_path) {
+                return 0;
+            }
+        }
+        if !self.extensions.is_empty() {
+            let extension = node.name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+            if !extension.is_some_and(|ext| self.extensions.contains(&ext)) {
+                return 0;
+            }
+        }
+
+        let classification = match &node.classification {
+            Some(classification) if classification.is_current(node) => classification,
+            _ => return 0,
+        };
+        let matches: usize = classification.findings.iter()
+            .filter(|finding| self.rule.detector.as_deref().is_none_or(|detector| finding.detector == detector))
+            .filter(|finding| self.rule.category.is_none_or(|category| finding.category == category))
+            .map(|finding| finding.count)
+            .sum();
+        if matches >= self.rule.min_matches { matches } else { 0 }
+    }
+
+    fn intent(&self, node_id: u64) -> PolicyIntent {
+        let comment = format!("Auto-protection rule {} ({})", self.rule.rule_id, self.rule.name);
+        PolicyIntent::new(
+            node_id,
+            ProtectionScope::File,
+            self.rule.action,
+            self.rule.operations,
+            &self.rule.created_by,
+            Some(&comment),
+        ).with_auto_rule(self.rule.rule_id)
+    }
+
+    fn cap(&self) -> usize {
+        self.rule.max_policies.unwrap_or(DEFAULT_RULE_POLICY_CAP)
+    }
+}
+
+/// Candidate files of one rule, split against what the rule already did
+struct RulePlan {
+    matched: usize,
+    already_protected: usize,
+    created_before: usize,
+    candidates: Vec<RuleMatch>,          // Not protected yet, best matches first
+}
+
+/// Auto-protection rule engine
+pub struct AutoProtectionEngine {
+    policy_engine: Arc<PolicyEngine>,
+    rules: RwLock<HashMap<u64, Arc<CompiledRule>>>,
+    next_rule_id: AtomicU64,
+    run_lock: Mutex<()>,                 // One run at a time: a plan must see earlier runs' nodes
+    rules_file: String,
+}
+
+impl AutoProtectionEngine {
+    /// Engine backed by a rule file (loaded now, rewritten on every change)
+    pub fn open(policy_engine: Arc<PolicyEngine>, path: &str) -> Arc<Self> {
+        let file = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str::<AutoRulesFile>(&content).unwrap_or_else(|e| {
+                // Keep the damaged file for inspection and start empty
+                let backup = format!("{}.corrupt", path);
+                let _ = std::fs::rename(path, &backup);
+                println!("⚠️  AutoProtection: Corrupt rule file moved to {}: {}", backup, e);
+                AutoRulesFile::default()
+            }),
+            Err(_) => AutoRulesFile::default(),
+        };
+
+        let mut rules = HashMap::new();
+        let mut next_rule_id = file.next_rule_id.max(1);
+        for rule in file.rules {
+            next_rule_id = next_rule_id.max(rule.rule_id + 1);
+            match CompiledRule::compile(rule) {
+                Ok(compiled) => {
+                    rules.insert(compiled.rule.rule_id, Arc::new(compiled));
+                }
+                Err(e) => println!("⚠️  AutoProtection: Stored rule skipped: {}", e),
+            }
+        }
+        if !rules.is_empty() {
+            println!("🤖 AutoProtection: {} rules loaded from {}", rules.len(), path);
+        }
+
+        Arc::new(AutoProtectionEngine {
+            policy_engine,
+            rules: RwLock::new(rules),
+            next_rule_id: AtomicU64::new(next_rule_id),
+            run_lock: Mutex::new(()),
+            rules_file: path.to_string(),
+        })
+    }
+
+    /// Rule file from the environment, or the default
+    pub fn default_rules_file() -> String {
+        std::env::var(AUTO_RULES_FILE_ENV).unwrap_or_else(|_| DEFAULT_AUTO_RULES_FILE.to_string())
+    }
+
+    /// Validate and store a rule (the agent assigns the rule ID)
+    pub fn add_rule(&self, mut rule: AutoProtectionRule) -> Result<AutoProtectionRule, String> {
+        if rule.name.trim().is_empty() {
+            return Err("Rule name cannot be empty".to_string());
+        }
+        if rule.min_matches == 0 {
+            return Err("min_matches must be at least 1".to_string());
+        }
+        if rule.max_policies.is_some_and(|cap| cap == 0 || cap > MAX_RULE_POLICY_CAP) {
+            return Err(format!("max_policies must be between 1 and {}", MAX_RULE_POLICY_CAP));
+        }
+
+        if rule.created_by.trim().is_empty() {
+            return Err("Rule needs the admin it is created by".to_string());
+        }
+
+        rule.rule_id = self.next_rule_id.fetch_add(1, Ordering::SeqCst);
+        rule.generated_nodes.clear();
+        rule.created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let compiled = CompiledRule::compile(rule)?;
+        // Same checks a generated intent will face
+        compiled.intent(u64::MAX).validate()?;
+
+        println!("🤖 AutoProtection: Rule {} '{}' added by {}", compiled.rule.rule_id, compiled.rule.name, compiled.rule.created_by);
+        let rule = compiled.rule.clone();
+        let mut rules = self.rules.write();
+        rules.insert(rule.rule_id, Arc::new(compiled));
+        self.save(&rules);
+        Ok(rule)
+    }
+
+    /// Delete a rule (policies it created stay)
+    pub fn remove_rule(&self, rule_id: u64) -> Result<AutoProtectionRule, String> {
+        let mut rules = self.rules.write();
+        let removed = rules.remove(&rule_id)
+            .map(|compiled| compiled.rule.clone())
+            .ok_or_else(|| format!("Rule {} not found", rule_id))?;
+        self.save(&rules);
+        Ok(removed)
+    }
+
+    /// All rules (oldest first)
+    pub fn list_rules(&self) -> Vec<AutoProtectionRule> {
+        let mut rules: Vec<AutoProtectionRule> = self.rules.read().values()
+            .map(|compiled| compiled.rule.clone())
+            .collect();
+        rules.sort_by_key(|rule| rule.rule_id);
+        rules
+    }
+
+    /// Dry run: what the rule would create right now
+    pub fn preview(&self, rule_id: u64) -> Result<RulePreview, String> {
+        let compiled = self.rule(rule_id)?;
+        let plan = self.plan(&compiled);
+        let remaining = compiled.cap().saturating_sub(plan.created_before);
+
+        let over_cap = plan.candidates.len().saturating_sub(remaining);
+        let mut would_create = plan.candidates;
+        would_create.truncate(remaining);
+
+        Ok(RulePreview {
+            rule_id,
+            matched_files: plan.matched,
+            already_protected: plan.already_protected,
+            would_create,
+            over_cap,
+            policy_cap: compiled.cap(),
+            policies_created: plan.created_before,
+        })
+    }
+
+    /// Create policies for every unprotected match, up to the cap
+    pub fn run_rule(&self, rule_id: u64) -> Result<RuleRunReport, String> {
+        let _run = self.run_lock.lock();
+        let compiled = self.rule(rule_id)?;
+        let plan = self.plan(&compiled);
+        let mut remaining = compiled.cap().saturating_sub(plan.created_before);
+
+        let mut report = RuleRunReport {
+            rule_id,
+            already_protected: plan.already_protected,
+            ..RuleRunReport::default()
+        };
+        println!("🤖 AutoProtection: Running rule {} ({} candidates, {} left under cap)",
+            rule_id, plan.candidates.len(), remaining);
+
+        for candidate in plan.candidates {
+            if remaining == 0 {
+                report.cap_reached = true;
+                println!("   ⚠️  Rule {} reached its cap of {} policies", rule_id, compiled.cap());
+                break;
+            }
+            let intent = compiled.intent(candidate.node_id);
+            match self.policy_engine.apply_protection_with_assurance(intent, compiled.rule.confirmed) {
+                Ok(ChangeOutcome::Applied { policy_id, .. }) => {
+                    report.created.push((candidate.node_id, policy_id));
+                    remaining -= 1;
+                }
+                Ok(ChangeOutcome::PendingApproval(request)) => {
+                    report.pending_approval.push((candidate.node_id, request.request_id));
+                    remaining -= 1;
+                }
+                Err(e) => {
+                    println!("   ❌ Rule {} could not protect node {}: {}", rule_id, candidate.node_id, e);
+                    report.failed.push((candidate.node_id, e));
+                }
+            }
+        }
+
+        // Failed nodes are retried next run; the others count against the cap for good
+        let generated: Vec<u64> = report.created.iter()
+            .chain(&report.pending_approval)
+            .map(|&(node_id, _)| node_id)
+            .collect();
+        if !generated.is_empty() {
+            let mut rules = self.rules.write();
+            if let Some(compiled) = rules.get_mut(&rule_id) {
+                Arc::make_mut(compiled).rule.generated_nodes.extend(generated);
+            }
+            self.save(&rules);
+        }
+
+        println!("🤖 AutoProtection: Rule {} created {} policies, {} pending approval, {} failed",
+            rule_id, report.created.len(), report.pending_approval.len(), report.failed.len());
+        Ok(report)
+    }
+
+    /// Run every enabled rule (after a discovery scan)
+    pub fn run_enabled_rules(&self) -> Vec<RuleRunReport> {
+        let rule_ids: Vec<u64> = self.list_rules().into_iter()
+            .filter(|rule| rule.enabled)
+            .map(|rule| rule.rule_id)
+            .collect();
+        rule_ids.into_iter()
+            .filter_map(|rule_id| self.run_rule(rule_id).ok())
+            .collect()
+    }
+
+    fn rule(&self, rule_id: u64) -> Result<Arc<CompiledRule>, String> {
+        self.rules.read().get(&rule_id).cloned()
+            .ok_or_else(|| format!("Rule {} not found", rule_id))
+    }
+
+    /// Write all rules to the rule file (tmp + rename)
+    fn save(&self, rules: &HashMap<u64, Arc<CompiledRule>>) {
+        let path = &self.rules_file;
+        let mut stored: Vec<AutoProtectionRule> = rules.values().map(|compiled| compiled.rule.clone()).collect();
+        stored.sort_by_key(|rule| rule.rule_id);
+        let file = AutoRulesFile { next_rule_id: self.next_rule_id.load(Ordering::SeqCst), rules: stored };
+
+        let tmp_path = format!("{}.tmp", path);
+        let result = serde_json::to_vec_pretty(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&tmp_path, json).map_err(|e| e.to_string()))
+            .and_then(|_| std::fs::rename(&tmp_path, path).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("⚠️  AutoProtection: Failed to save rules to {}: {}", path, e);
+        }
+    }
+
+    /// Match classified files and drop the ones this rule already generated
+    fn plan(&self, compiled: &CompiledRule) -> RulePlan {
+        let protected: HashSet<u64> = compiled.rule.generated_nodes.iter().copied().collect();
+        let created_before = compiled.rule.generated_nodes.len();
+
+        let index = self.policy_engine.path_resolver().index();
+        let mut matched = 0;
+        let mut already_protected = 0;
+        let mut candidates: Vec<RuleMatch> = index.classified_nodes(compiled.rule.root_id)
+            .into_iter()
+            .filter_map(|node| {
+                let matches = compiled.matches(&node);
+                if matches == 0 {
+                    return None;
+                }
+                matched += 1;
+                if protected.contains(&node.id) {
+                    already_protected += 1;
+                    return None;
+                }
+                Some(RuleMatch { node_id: node.id, name: node.name, matches })
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.matches.cmp(&a.matches).then(a.node_id.cmp(&b.node_id)));
+
+        RulePlan { matched, already_protected, created_before, candidates }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::{ContentClassification, DetectorFinding};
+    use crate::fs_index::{DriveKind, EntryType};
+
+    fn temp_file(name: &str) -> String {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!("dlp_{}_{}_{}.json", name, std::process::id(), nanos))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Engine over a UNC share (resolves without the volume map) with classified files
+    fn engine_with_files(files: &[(&str, &str, usize)]) -> (Arc<PolicyEngine>, Vec<u64>) {
+        let engine = Arc::new(PolicyEngine::new_simulated());
+        let index = engine.path_resolver().index().clone();
+        let drive = index.add_drive("\\\\fs01\\finance", "finance", "\\Device\\Mup\\fs01\\finance\\", DriveKind::Network, None);
+        let ids = files.iter()
+            .map(|&(name, detector, count)| {
+                let display_path = format!("\\\\fs01\\finance\\{}", name);
+                let id = index.id_for_path(&display_path);
+                index.add_nodes(vec![FileSystemNode {
+                    id,
+                    name: name.to_string(),
+                    entry_type: EntryType::File,
+                    parent_id: Some(drive),
+                    children_ids: Vec::new(),
+                    nt_path: format!("\\Device\\Mup\\fs01\\finance\\{}", name),
+                    display_path,
+                    size: Some(100),
+                    modified_time: 1_700_000_000,
+                    created_time: 1_700_000_000,
+                    attributes: 0,
+                    is_expanded: false,
+                    is_accessible: true,
+                    link_target: None,
+                    classification: Some(ContentClassification {
+                        classified_at: 1_700_000_100,
+                        content_modified_time: 1_700_000_000,
+                        bytes_scanned: 100,
+                        sampled: false,
+                        findings: vec![DetectorFinding {
+                            detector: detector.to_string(),
+                            category: if detector == "email" { FindingCategory::Pii } else { FindingCategory::Pci },
+                            count,
+                        }],
+                    }),
+                    content_type: None,
+                    drive_kind: None,
+                    device: None,
+                }]);
+                id
+            })
+            .collect();
+        (engine, ids)
+    }
+
+    fn rule(max_policies: Option<usize>) -> AutoProtectionRule {
+        serde_json::from_value(serde_json::json!({
+            "name": "Card data",
+            "detector": "credit_card",
+            "min_matches": 2,
+            "extensions": ["csv"],
+            "action": "block",
+            "operations": { "read": false, "write": true, "delete": true, "rename": false, "create": false },
+            "max_policies": max_policies,
+            "created_by": "alice",
+        })).unwrap()
+    }
+
+    #[test]
+    fn rules_match_detector_threshold_and_extension() {
+        let (engine, ids) = engine_with_files(&[
+            ("cards.csv", "credit_card", 5),
+            ("one_card.csv", "credit_card", 1),
+            ("cards.txt", "credit_card", 9),
+            ("mail.csv", "email", 7),
+        ]);
+        let path = temp_file("auto_rules_match");
+        let auto = AutoProtectionEngine::open(engine, &path);
+        let rule = auto.add_rule(rule(None)).unwrap();
+
+        let preview = auto.preview(rule.rule_id).unwrap();
+        assert_eq!(preview.matched_files, 1);
+        assert_eq!(preview.would_create.len(), 1);
+        assert_eq!(preview.would_create[0].node_id, ids[0]);
+        assert_eq!(preview.would_create[0].matches, 5);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn preview_changes_nothing() {
+        let (engine, _) = engine_with_files(&[("a.csv", "credit_card", 3), ("b.csv", "credit_card", 4)]);
+        let path = temp_file("auto_rules_preview");
+        let auto = AutoProtectionEngine::open(engine.clone(), &path);
+        let rule = auto.add_rule(rule(None)).unwrap();
+
+        let first = auto.preview(rule.rule_id).unwrap();
+        let second = auto.preview(rule.rule_id).unwrap();
+        assert_eq!(first.would_create.len(), 2);
+        assert_eq!(second.would_create.len(), 2);
+        assert_eq!(second.policies_created, 0);
+        assert!(engine.get_active_policies().is_empty());
+        assert!(auto.list_rules()[0].generated_nodes.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn cap_is_a_lifetime_cap_and_removed_policies_stay_removed() {
+        let (engine, ids) = engine_with_files(&[
+            ("a.csv", "credit_card", 9),
+            ("b.csv", "credit_card", 8),
+            ("c.csv", "credit_card", 7),
+        ]);
+        let path = temp_file("auto_rules_cap");
+        let auto = AutoProtectionEngine::open(engine.clone(), &path);
+        let rule = auto.add_rule(rule(Some(2))).unwrap();
+
+        // Best matches first, stopped at the cap
+        let report = auto.run_rule(rule.rule_id).unwrap();
+        assert_eq!(report.created.iter().map(|&(node_id, _)| node_id).collect::<Vec<_>>(), vec![ids[0], ids[1]]);
+        assert!(report.cap_reached);
+
+        // The admin removes one: it is not re-protected, and the cap stays used up
+        engine.remove_protection(report.created[0].1, "bob").unwrap();
+        let report = auto.run_rule(rule.rule_id).unwrap();
+        assert!(report.created.is_empty());
+        assert!(report.cap_reached);
+        let preview = auto.preview(rule.rule_id).unwrap();
+        assert_eq!(preview.already_protected, 2);
+        assert_eq!(preview.policies_created, 2);
+        assert_eq!(preview.over_cap, 1);
+
+        // What the rule generated survives a restart
+        let reopened = AutoProtectionEngine::open(engine, &path);
+        let stored = reopened.list_rules();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].generated_nodes, vec![ids[0], ids[1]]);
+        assert!(reopened.run_rule(rule.rule_id).unwrap().created.is_empty());
+        assert!(reopened.add_rule(self::rule(None)).unwrap().rule_id > rule.rule_id);
+        let _ = std::fs::remove_file(&path);
+    }
+}
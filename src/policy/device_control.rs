@@ -0,0 +1,512 @@
+//! Device Control Rules
+//! Core Principle: Protect a class of media, not a path that comes and goes
+//! A rule targets a device class (every removable drive, every CD drive) or
+//! one volume serial number. Each matching drive in the tree gets a recursive
+//! policy on its drive node through the normal assurance path. Drives that
+//! arrive later are covered when they show up, and their policies go when
+//! they leave (the next medium may get the same device name). Fixed and
+//! network classes cannot be targeted: a rule on them would cover the
+//! system disk or every share. Rules are persisted to disk.
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::fs_index::FileSystemNode;
+use crate::volume_map::{DeviceClass, DriveDevice};
+
+use super::policy_approval::ChangeOutcome;
+use super::policy_engine::PolicyEngine;
+use super::policy_guard::PolicyGuard;
+use super::policy_intent::{PolicyIntent, ProtectionAction, ProtectionOperations, ProtectionScope};
+
+/// Recorded as the remover when a drive leaves or stops matching
+const DEVICE_CONTROL_ACTOR: &str = "device_control";
+
+/// Environment variable for the device rule file
+pub const DEVICE_RULES_FILE_ENV: &str = "AGENT_DEVICE_RULES";
+
+/// Default rule file (next to the agent)
+const DEFAULT_DEVICE_RULES_FILE: &str = "dlp_device_rules.json";
+
+/// Drives a device rule applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeviceTarget {
+    Class { class: DeviceClass },   // e.g. every removable drive
+    Serial { serial: String },      // One volume, "1A2B-3C4D"
+}
+
+impl DeviceTarget {
+    fn matches(&self, device: &DriveDevice) -> bool {
+        match self {
+            DeviceTarget::Class { class } => device.class == *class,
+            DeviceTarget::Serial { serial } => device.serial.as_deref() == Some(serial.as_str()),
+        }
+    }
+}
+
+/// Device rule (admin-defined)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceRule {
+    #[serde(default)]
+    pub rule_id: u64,                        // Assigned by the agent
+    pub name: String,
+    pub target: DeviceTarget,
+    pub action: ProtectionAction,
+    pub operations: ProtectionOperations,    // e.g. write/create/delete/rename against USB exfiltration
+    #[serde(default)]
+    pub created_by: String,                  // Authenticated admin; generated policies are created in their name
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+impl DeviceRule {
+    fn intent(&self, node_id: u64) -> PolicyIntent {
+        let comment = format!("Device rule {} ({})", self.rule_id, self.name);
+        PolicyIntent::new(
+            node_id,
+            ProtectionScope::FolderRecursive,
+            self.action,
+            self.operations,
+            &self.created_by,
+            Some(&comment),
+        ).with_device_rule(self.rule_id)
+    }
+}
+
+/// On-disk rule file
+#[derive(Default, Serialize, Deserialize)]
+struct DeviceRulesFile {
+    next_rule_id: u64,
+    rules: Vec<DeviceRule>,
+}
+
+/// Policy a device rule holds on one drive
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DevicePolicy {
+    pub rule_id: u64,
+    pub node_id: u64,
+    pub policy_id: u64,
+    pub action: ProtectionAction,
+}
+
+/// Drive a rule covers right now (admin-safe)
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceMatch {
+    pub node_id: u64,
+    pub name: String,
+    pub device: DriveDevice,
+    pub policy_id: Option<u64>,
+}
+
+/// Outcome of bringing device policies in line with the drives present
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeviceRuleReport {
+    pub applied: Vec<DevicePolicy>,
+    pub repointed: Vec<DevicePolicy>,        // Drive came back under another device name
+    pub removed: Vec<DevicePolicy>,
+    pub failed: Vec<(u64, String)>,          // (node_id, reason)
+}
+
+/// Device rule engine
+pub struct DeviceControlEngine {
+    policy_engine: Arc<PolicyEngine>,
+    rules: RwLock<HashMap<u64, DeviceRule>>,
+    next_rule_id: AtomicU64,
+    sync_lock: Mutex<()>,                    // One sync at a time (volume task vs. admin calls)
+    rules_file: String,
+}
+
+impl DeviceControlEngine {
+    /// Engine backed by a rule file (loaded now, rewritten on every change)
+    /// Call `enforce` once the drives are in the tree.
+    pub fn open(policy_engine: Arc<PolicyEngine>, path: &str) -> Arc<Self> {
+        let file = match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str::<DeviceRulesFile>(&content).unwrap_or_else(|e| {
+                // Keep the damaged file for inspection and start empty
+                let backup = format!("{}.corrupt", path);
+                let _ = std::fs::rename(path, &backup);
+                println!("⚠️  DeviceControl: Corrupt rule file moved to {}: {}", backup, e);
+                DeviceRulesFile::default()
+            }),
+            Err(_) => DeviceRulesFile::default(),
+        };
+
+        let mut next_rule_id = file.next_rule_id.max(1);
+        let rules: HashMap<u64, DeviceRule> = file.rules.into_iter()
+            .map(|rule| {
+                next_rule_id = next_rule_id.max(rule.rule_id + 1);
+                (rule.rule_id, rule)
+            })
+            .collect();
+        if !rules.is_empty() {
+            println!("🔌 DeviceControl: {} rules loaded from {}", rules.len(), path);
+        }
+
+        Arc::new(DeviceControlEngine {
+            policy_engine,
+            rules: RwLock::new(rules),
+            next_rule_id: AtomicU64::new(next_rule_id),
+            sync_lock: Mutex::new(()),
+            rules_file: path.to_string(),
+        })
+    }
+
+    /// Rule file from the environment, or the default
+    pub fn default_rules_file() -> String {
+        std::env::var(DEVICE_RULES_FILE_ENV).unwrap_or_else(|_| DEFAULT_DEVICE_RULES_FILE.to_string())
+    }
+
+    /// Validate and store a rule (call `enforce` to cover the drives present)
+    pub fn add_rule(&self, mut rule: DeviceRule) -> Result<DeviceRule, String> {
+        if rule.name.trim().is_empty() {
+            return Err("Rule name cannot be empty".to_string());
+        }
+        if rule.created_by.trim().is_empty() {
+            return Err("Rule needs the admin it is created by".to_string());
+        }
+        match &mut rule.target {
+            DeviceTarget::Serial { serial } => {
+                *serial = DriveDevice::normalize_serial(serial)
+                    .ok_or_else(|| format!("Invalid volume serial number: {} (expected 1A2B-3C4D)", serial))?;
+            }
+            DeviceTarget::Class { class: class @ (DeviceClass::Fixed | DeviceClass::Network) } => {
+                return Err(format!(
+                    "Device rules cannot target every {} drive; protect the folders or target one volume serial instead",
+                    class.as_str()
+                ));
+            }
+            DeviceTarget::Class { .. } => {}
+        }
+
+        rule.rule_id = self.next_rule_id.fetch_add(1, Ordering::SeqCst);
+        rule.created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        // Same checks a generated intent will face
+        let intent = rule.intent(u64::MAX);
+        intent.validate()?;
+        // Every arriving drive would wait for a second admin
+        if PolicyGuard::requires_confirmation(&intent) {
+            return Err("Device rules cannot block all access (READ); block write, create, delete and rename instead".to_string());
+        }
+
+        println!("🔌 DeviceControl: Rule {} '{}' added by {}", rule.rule_id, rule.name, rule.created_by);
+        let mut rules = self.rules.write();
+        rules.insert(rule.rule_id, rule.clone());
+        self.save(&rules);
+        Ok(rule)
+    }
+
+    /// Delete a rule and remove the policies it holds
+    pub fn remove_rule(&self, rule_id: u64, removed_by: &str) -> Result<(DeviceRule, DeviceRuleReport), String> {
+        let rule = {
+            let mut rules = self.rules.write();
+            let rule = rules.remove(&rule_id)
+                .ok_or_else(|| format!("Rule {} not found", rule_id))?;
+            self.save(&rules);
+            rule
+        };
+        println!("🔌 DeviceControl: Rule {} '{}' removed by {}", rule_id, rule.name, removed_by);
+        Ok((rule, self.sync(removed_by)))
+    }
+
+    /// All rules (oldest first)
+    pub fn list_rules(&self) -> Vec<DeviceRule> {
+        let mut rules: Vec<DeviceRule> = self.rules.read().values().cloned().collect();
+        rules.sort_by_key(|rule| rule.rule_id);
+        rules
+    }
+
+    /// Drives in the tree the rule covers, with the policy it holds on each
+    pub fn matching_drives(&self, rule_id: u64) -> Result<Vec<DeviceMatch>, String> {
+        let rule = self.rules.read().get(&rule_id).cloned()
+            .ok_or_else(|| format!("Rule {} not found", rule_id))?;
+        let held = self.held_policies();
+        Ok(self.drives().into_iter()
+            .filter_map(|drive| {
+                let device = drive.device.filter(|device| rule.target.matches(device))?;
+                Some(DeviceMatch {
+                    node_id: drive.id,
+                    name: drive.name,
+                    device,
+                    policy_id: held.get(&(rule_id, drive.id)).map(|policy| policy.policy_id),
+                })
+            })
+            .collect())
+    }
+
+    /// Cover every matching drive, remove policies of drives that left
+    /// (run after drives arrive or leave, and after a rule is added)
+    pub fn enforce(&self) -> DeviceRuleReport {
+        self.sync(DEVICE_CONTROL_ACTOR)
+    }
+
+    fn sync(&self, removed_by: &str) -> DeviceRuleReport {
+        let _guard = self.sync_lock.lock();
+        let mut held = self.held_policies();
+        let mut report = DeviceRuleReport::default();
+        let drives = self.drives();
+
+        for rule in self.list_rules() {
+            let covered = drives.iter()
+                .filter(|drive| drive.device.as_ref().is_some_and(|device| rule.target.matches(device)));
+            for drive in covered {
+                if let Some(policy) = held.remove(&(rule.rule_id, drive.id)) {
+                    // Same drive node, but the medium may now sit behind another device name
+                    match self.policy_engine.repoint_policy(policy.policy_id, DEVICE_CONTROL_ACTOR) {
+                        Ok(Some(version)) => {
+                            println!("   🔌 Rule {} moved policy {} to drive {}'s new device (version {})",
+                                rule.rule_id, policy.policy_id, drive.id, version);
+                            report.repointed.push(policy);
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            println!("   ❌ Rule {} could not re-resolve drive {}: {}", rule.rule_id, drive.id, e);
+                            report.failed.push((drive.id, e));
+                        }
+                    }
+                    continue;
+                }
+                match self.policy_engine.apply_protection_with_assurance(rule.intent(drive.id), false) {
+                    Ok(ChangeOutcome::Applied { policy_id, .. }) => {
+                        println!("   🔌 Rule {} covers drive {} ({}) with policy {}", rule.rule_id, drive.id, drive.name, policy_id);
+                        report.applied.push(DevicePolicy { rule_id: rule.rule_id, node_id: drive.id, policy_id, action: rule.action });
+                    }
+                    Ok(ChangeOutcome::PendingApproval(request)) => {
+                        report.failed.push((drive.id, format!("Waiting for approval (request {})", request.request_id)));
+                    }
+                    Err(e) => {
+                        println!("   ❌ Rule {} could not protect drive {}: {}", rule.rule_id, drive.id, e);
+                        report.failed.push((drive.id, e));
+                    }
+                }
+            }
+        }
+
+        // Left over: the drive is gone, no longer matches, or its rule was deleted
+        for policy in held.into_values() {
+            match self.policy_engine.remove_protection(policy.policy_id, removed_by) {
+                Ok(()) => report.removed.push(policy),
+                Err(e) => println!("   ⚠️  Could not remove device policy {}: {}", policy.policy_id, e),
+            }
+        }
+
+        if !report.applied.is_empty() || !report.repointed.is_empty() || !report.removed.is_empty() {
+            println!("🔌 DeviceControl: {} drive policies applied, {} repointed, {} removed, {} failed",
+                report.applied.len(), report.repointed.len(), report.removed.len(), report.failed.len());
+        }
+        report
+    }
+
+    /// Write all rules to the rule file (tmp + rename)
+    fn save(&self, rules: &HashMap<u64, DeviceRule>) {
+        let path = &self.rules_file;
+        let mut stored: Vec<DeviceRule> = rules.values().cloned().collect();
+        stored.sort_by_key(|rule| rule.rule_id);
+        let file = DeviceRulesFile { next_rule_id: self.next_rule_id.load(Ordering::SeqCst), rules: stored };
+
+        let tmp_path = format!("{}.tmp", path);
+        let result = serde_json::to_vec_pretty(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&tmp_path, json).map_err(|e| e.to_string()))
+            .and_then(|_| std::fs::rename(&tmp_path, path).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("⚠️  DeviceControl: Failed to save rules to {}: {}", path, e);
+        }
+    }
+
+    /// Active policies generated by device rules, by (rule, drive node)
+    fn held_policies(&self) -> HashMap<(u64, u64), DevicePolicy> {
+        let store = self.policy_engine.policy_store();
+        store.get_policy_nodes().into_iter()
+            .filter_map(|(policy_id, node_id)| {
+                let intent = store.get_policy(policy_id)?.intent;
+                let rule_id = intent.device_rule_id?;
+                Some(((rule_id, node_id), DevicePolicy { rule_id, node_id, policy_id, action: intent.action }))
+            })
+            .collect()
+    }
+
+    /// Drive nodes currently in the tree
+    fn drives(&self) -> Vec<FileSystemNode> {
+        let index = self.policy_engine.path_resolver().index();
+        index.get_drives().into_iter()
+            .filter_map(|(_, node_id)| index.get_node(node_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_index::DriveKind;
+
+    fn temp_file(name: &str) -> String {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!("dlp_{}_{}_{}.json", name, std::process::id(), nanos))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn cleanup(path: &str) {
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(format!("{}.corrupt", path));
+    }
+
+    fn device(class: DeviceClass, serial: &str) -> DriveDevice {
+        DriveDevice { class, label: None, serial: Some(serial.to_string()) }
+    }
+
+    /// Drives are UNC roots so they resolve without the volume map
+    fn add_drive(engine: &PolicyEngine, share: &str, device: DriveDevice) -> u64 {
+        let root = format!("\\\\{}", share);
+        let nt_path = format!("\\Device\\Mup\\{}\\", share);
+        engine.path_resolver().index().add_drive(&root, share, &nt_path, DriveKind::Local, Some(device))
+    }
+
+    fn device_rule(target: serde_json::Value) -> DeviceRule {
+        serde_json::from_value(serde_json::json!({
+            "name": "USB exfiltration",
+            "target": target,
+            "action": "block",
+            "operations": { "read": false, "write": true, "delete": true, "rename": true, "create": true },
+            "created_by": "alice",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn class_rules_follow_drives_in_and_out() {
+        let engine = Arc::new(PolicyEngine::new_simulated());
+        let path = temp_file("device_rules_class");
+        let control = DeviceControlEngine::open(engine.clone(), &path);
+        let stick = add_drive(&engine, "usb01\\stick", device(DeviceClass::Removable, "1A2B-3C4D"));
+        add_drive(&engine, "cd01\\disc", device(DeviceClass::CdRom, "0000-0001"));
+
+        let rule = control.add_rule(device_rule(serde_json::json!({ "type": "class", "class": "removable" }))).unwrap();
+        let report = control.enforce();
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.applied[0].node_id, stick);
+        assert!(report.failed.is_empty());
+        assert_eq!(control.matching_drives(rule.rule_id).unwrap()[0].policy_id, Some(report.applied[0].policy_id));
+
+        // Nothing changed: nothing to do
+        let report = control.enforce();
+        assert!(report.applied.is_empty() && report.repointed.is_empty() && report.removed.is_empty());
+
+        // A second stick arrives, the first one leaves
+        let second = add_drive(&engine, "usb02\\stick", device(DeviceClass::Removable, "5E6F-7A8B"));
+        engine.path_resolver().index().remove_subtree(stick);
+        let report = control.enforce();
+        assert_eq!(report.applied.iter().map(|policy| policy.node_id).collect::<Vec<_>>(), vec![second]);
+        assert_eq!(report.removed.iter().map(|policy| policy.node_id).collect::<Vec<_>>(), vec![stick]);
+        assert_eq!(engine.get_active_policies().len(), 1);
+
+        // Deleting the rule removes what it holds
+        let (_, report) = control.remove_rule(rule.rule_id, "bob").unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert!(engine.get_active_policies().is_empty());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn serial_rules_cover_one_volume() {
+        let engine = Arc::new(PolicyEngine::new_simulated());
+        let path = temp_file("device_rules_serial");
+        let control = DeviceControlEngine::open(engine.clone(), &path);
+        add_drive(&engine, "usb01\\stick", device(DeviceClass::Removable, "1A2B-3C4D"));
+        let known = add_drive(&engine, "usb02\\stick", device(DeviceClass::Removable, "5E6F-7A8B"));
+
+        let rule = control.add_rule(device_rule(serde_json::json!({ "type": "serial", "serial": "5e6f7a8b" }))).unwrap();
+        assert_eq!(rule.target, DeviceTarget::Serial { serial: "5E6F-7A8B".to_string() });
+        let report = control.enforce();
+        assert_eq!(report.applied.iter().map(|policy| policy.node_id).collect::<Vec<_>>(), vec![known]);
+
+        assert!(control.add_rule(device_rule(serde_json::json!({ "type": "serial", "serial": "5E6F" }))).is_err());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn fixed_and_network_classes_are_refused() {
+        let engine = Arc::new(PolicyEngine::new_simulated());
+        let path = temp_file("device_rules_refused");
+        let control = DeviceControlEngine::open(engine, &path);
+        for class in ["fixed", "network"] {
+            let error = control.add_rule(device_rule(serde_json::json!({ "type": "class", "class": class }))).unwrap_err();
+            assert!(error.contains(class), "{}", error);
+        }
+        assert!(control.list_rules().is_empty());
+        cleanup(&path);
+    }
+
+    #[test]
+    fn held_policy_moves_when_the_drive_resolves_elsewhere() {
+        let engine = Arc::new(PolicyEngine::new_simulated());
+        let path = temp_file("device_rules_repoint");
+        let control = DeviceControlEngine::open(engine.clone(), &path);
+        let stick = add_drive(&engine, "usb01\\stick", device(DeviceClass::Removable, "1A2B-3C4D"));
+        control.add_rule(device_rule(serde_json::json!({ "type": "class", "class": "removable" }))).unwrap();
+        let policy_id = control.enforce().applied[0].policy_id;
+
+        // Same drive node, now reached through another device
+        let index = engine.path_resolver().index();
+        let mut node = index.get_node(stick).unwrap();
+        node.display_path = "\\\\usb09\\stick\\".to_string();
+        node.nt_path = "\\Device\\Mup\\usb09\\stick\\".to_string();
+        index.add_node(node);
+
+        let report = control.enforce();
+        assert_eq!(report.repointed.iter().map(|policy| policy.policy_id).collect::<Vec<_>>(), vec![policy_id]);
+        assert!(report.applied.is_empty() && report.removed.is_empty());
+        let policy = engine.get_policy_by_id(policy_id).unwrap();
+        assert!(policy.kernel_policies.iter().all(|kp| kp.nt_path.starts_with("\\Device\\Mup\\usb09\\stick")));
+        cleanup(&path);
+    }
+
+    #[test]
+    fn rules_survive_a_restart() {
+        let path = temp_file("device_rules_restart");
+        let engine = Arc::new(PolicyEngine::new_simulated());
+        let control = DeviceControlEngine::open(engine, &path);
+        let first = control.add_rule(device_rule(serde_json::json!({ "type": "class", "class": "removable" }))).unwrap();
+        let second = control.add_rule(device_rule(serde_json::json!({ "type": "class", "class": "cdrom" }))).unwrap();
+        control.remove_rule(second.rule_id, "bob").unwrap();
+        assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+
+        // A fresh agent loads the rule and covers the drives present once enforced
+        let engine = Arc::new(PolicyEngine::new_simulated());
+        let stick = add_drive(&engine, "usb01\\stick", device(DeviceClass::Removable, "1A2B-3C4D"));
+        let reopened = DeviceControlEngine::open(engine.clone(), &path);
+        let rules = reopened.list_rules();
+        assert_eq!(rules.len(), 1);
+        assert_eq!((rules[0].rule_id, rules[0].created_by.as_str()), (first.rule_id, "alice"));
+        assert!(engine.get_active_policies().is_empty());
+        assert_eq!(reopened.enforce().applied.iter().map(|policy| policy.node_id).collect::<Vec<_>>(), vec![stick]);
+
+        // Removed rule IDs are not handed out again
+        let third = reopened.add_rule(device_rule(serde_json::json!({ "type": "serial", "serial": "5E6F-7A8B" }))).unwrap();
+        assert_eq!(third.rule_id, second.rule_id + 1);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn corrupt_rule_file_is_set_aside() {
+        let path = temp_file("device_rules_corrupt");
+        std::fs::write(&path, "{not json").unwrap();
+
+        let control = DeviceControlEngine::open(Arc::new(PolicyEngine::new_simulated()), &path);
+        assert!(control.list_rules().is_empty());
+        assert!(std::path::Path::new(&format!("{}.corrupt", path)).exists());
+        cleanup(&path);
+    }
+}
@@ -0,0 +1,38 @@
+//! Break-Glass Enforcement Suspension (STEP 7.6)
+//! Core Principle: Emergency off-switch for the kernel, never for the policy store
+//! Suspending clears every kernel rule but keeps all policies; resuming
+//! re-pushes them. A suspension always ends, manually or by timeout.
+
+use serde::Serialize;
+
+/// Auto-resume timeout when the admin does not give one (15 minutes)
+pub const DEFAULT_SUSPEND_SECS: u64 = 15 * 60;
+
+/// Longest allowed suspension (4 hours)
+pub const MAX_SUSPEND_SECS: u64 = 4 * 60 * 60;
+
+/// Active break-glass suspension
+#[derive(Debug, Clone, Serialize)]
+pub struct EnforcementSuspension {
+    pub suspension_id: u64,
+    pub suspended_by: String,
+    pub reason: String,
+    pub suspended_at: u64,
+    pub resume_at: u64,                // Auto-resume deadline
+    pub policies_suspended: usize,
+    pub kernel_rules_cleared: usize,
+    pub kernel_rules_failed: usize,    // Rules the kernel refused to drop
+}
+
+/// Outcome of resuming enforcement
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumeReport {
+    pub suspension_id: u64,
+    pub resumed_by: String,
+    pub automatic: bool,
+    pub resumed_at: u64,
+    pub suspended_for_secs: u64,
+    pub policies_restored: usize,
+    pub kernel_rules_pushed: usize,
+    pub failed_policy_ids: Vec<u64>,   // Policies with at least one rule that failed to push
+}
@@ -0,0 +1,561 @@
+//! Fingerprint Registry for Protected Content
+//! Core Principle: A copy of a protected document is found by its content, not its name
+//! Files covered by a policy are hashed (SHA-256, plus optional content-defined
+//! chunk hashes for near-duplicates) and kept per policy_id. Crawled or newly
+//! created files are checked against the registry; matches are reported as
+//! node IDs only. Hashes never leave the agent.
+//!
+//! Sources are the files in the index: a folder policy is fingerprinted from
+//! the files loaded below it when the policy is first seen, and files written
+//! or crawled there later are added as they come. Crawl a protected folder to
+//! fingerprint all of it.
+
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::sync::{Arc, OnceLock};
+
+use crate::fs_index::{EntryType, FileSystemNode, FilesystemIndex};
+
+/// Environment variable enabling chunk hashing ("1", "true" or "on")
+pub const FINGERPRINT_CHUNKING_ENV: &str = "AGENT_FINGERPRINT_CHUNKING";
+
+/// Environment variable for the largest file hashed (MiB)
+pub const FINGERPRINT_MAX_FILE_MB_ENV: &str = "AGENT_FINGERPRINT_MAX_FILE_MB";
+
+const DEFAULT_MAX_FILE_MB: u64 = 256;
+
+/// Files fingerprinted per policy (folder policies beyond this are truncated)
+pub const MAX_FILES_PER_POLICY: usize = 1000;
+
+/// Shared chunks (percent of the larger chunk set) for a near-duplicate
+pub const SIMILARITY_THRESHOLD_PERCENT: u8 = 60;
+
+// Content-defined chunking: boundaries depend on content, so an insertion
+// only changes the chunks around it
+const MIN_CHUNK: usize = 2 * 1024;
+const MAX_CHUNK: usize = 64 * 1024;
+const CHUNK_MASK: u64 = (8 * 1024) - 1;    // ~8 KiB average
+
+const READ_BUFFER: usize = 64 * 1024;
+
+/// How a copy matched protected content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Exact,       // Same SHA-256
+    Similar,     // Shares most content-defined chunks
+}
+
+/// File outside a policy that carries its protected content (admin-safe)
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtectedCopy {
+    pub policy_id: u64,
+    pub source_node_id: u64,    // Protected file the content came from
+    pub node_id: u64,           // The copy
+    pub match_kind: MatchKind,
+    pub similarity: u8,         // Percent (100 for exact)
+}
+
+/// Registry state for one policy (admin-safe)
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyFingerprintSummary {
+    pub policy_id: u64,
+    pub node_id: u64,
+    pub files: usize,
+    pub chunked: bool,
+    pub truncated: bool,        // More files than MAX_FILES_PER_POLICY
+}
+
+/// Hashes of one protected file
+#[derive(Debug, Clone)]
+struct FileFingerprint {
+    node_id: u64,
+    size: u64,
+    modified_time: u64,
+    sha256: [u8; 32],
+    chunks: Option<HashSet<u64>>,
+}
+
+#[derive(Debug, Clone)]
+struct PolicyFingerprints {
+    node_id: u64,
+    files: Vec<FileFingerprint>,
+    truncated: bool,
+}
+
+/// Per-policy fingerprints of protected files
+pub struct FingerprintRegistry {
+    index: Arc<FilesystemIndex>,
+    policies: RwLock<HashMap<u64, PolicyFingerprints>>,
+    refresh_lock: Mutex<()>,      // One refresh hashes at a time
+    chunking: bool,
+    max_file_bytes: u64,
+}
+
+impl FingerprintRegistry {
+    pub fn new(index: Arc<FilesystemIndex>) -> Arc<Self> {
+        let chunking = std::env::var(FINGERPRINT_CHUNKING_ENV)
+            .is_ok_and(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "on"));
+        let max_file_mb = std::env::var(FINGERPRINT_MAX_FILE_MB_ENV)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_FILE_MB);
+
+        Self::with_options(index, chunking, max_file_mb * 1024 * 1024)
+    }
+
+    fn with_options(index: Arc<FilesystemIndex>, chunking: bool, max_file_bytes: u64) -> Arc<Self> {
+        Arc::new(FingerprintRegistry {
+            index,
+            policies: RwLock::new(HashMap::new()),
+            refresh_lock: Mutex::new(()),
+            chunking,
+            max_file_bytes,
+        })
+    }
+
+    pub fn chunking(&self) -> bool {
+        self.chunking
+    }
+
+    /// Bring the registry in line with the current policies: drop removed
+    /// policies and fingerprint new or retargeted ones. Policies already
+    /// registered are not walked again, so this is cheap when nothing changed.
+    pub fn sync_policies(&self, policy_nodes: &[(u64, u64)]) {
+        let _guard = self.refresh_lock.lock();
+
+        let current: HashSet<u64> = policy_nodes.iter().map(|&(policy_id, _)| policy_id).collect();
+        let pending: Vec<(u64, u64)> = {
+            let mut policies = self.policies.write();
+            policies.retain(|policy_id, _| current.contains(policy_id));
+            policy_nodes.iter()
+                .filter(|(policy_id, node_id)| policies.get(policy_id).is_none_or(|entry| entry.node_id != *node_id))
+                .copied()
+                .collect()
+        };
+
+        for (policy_id, node_id) in pending {
+            let mut sources = self.index.files_under(node_id);
+            let truncated = sources.len() > MAX_FILES_PER_POLICY;
+            sources.sort_by_key(|node| node.id);
+            sources.truncate(MAX_FILES_PER_POLICY);
+
+            let files: Vec<FileFingerprint> = sources.iter()
+                .filter(|node| Self::hashable(node, self.max_file_bytes))
+                .filter_map(|node| self.fingerprint(node, self.chunking))
+                .collect();
+
+            println!("🧬 Fingerprints: Policy {} - {} file(s) hashed", policy_id, files.len());
+            self.policies.write().insert(policy_id, PolicyFingerprints { node_id, files, truncated });
+        }
+    }
+
+    /// Hash files written or crawled inside a protected node: new files become
+    /// sources, rewritten ones replace their old hashes
+    pub fn update_sources(&self, node_ids: &[u64]) {
+        let _guard = self.refresh_lock.lock();
+
+        let roots: Vec<(u64, u64)> = self.policies.read().iter()
+            .map(|(&policy_id, entry)| (policy_id, entry.node_id))
+            .collect();
+        if roots.is_empty() {
+            return;
+        }
+
+        for &node_id in node_ids {
+            let covering: Vec<u64> = roots.iter()
+                .filter(|&&(_, root_id)| self.index.is_within(node_id, root_id))
+                .map(|&(policy_id, _)| policy_id)
+                .collect();
+            if covering.is_empty() {
+                continue;
+            }
+            let node = match self.index.get_node(node_id) {
+                Some(node) if Self::hashable(&node, self.max_file_bytes) => node,
+                _ => continue,
+            };
+            let unchanged = covering.iter().all(|policy_id| self.policies.read().get(policy_id)
+                .and_then(|entry| entry.files.iter().find(|file| file.node_id == node_id))
+                .is_some_and(|file| file.size == node.size.unwrap_or(0) && file.modified_time == node.modified_time));
+            if unchanged {
+                continue;
+            }
+            let fingerprint = match self.fingerprint(&node, self.chunking) {
+                Some(fingerprint) => fingerprint,
+                None => continue,
+            };
+
+            let mut policies = self.policies.write();
+            for policy_id in covering {
+                let entry = match policies.get_mut(&policy_id) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+                match entry.files.iter().position(|file| file.node_id == node_id) {
+                    Some(position) => entry.files[position] = fingerprint.clone(),
+                    None if entry.files.len() < MAX_FILES_PER_POLICY => entry.files.push(fingerprint.clone()),
+                    None => entry.truncated = true,
+                }
+            }
+        }
+    }
+
+    /// Check files against the registry (nodes that are not files, or are
+    /// covered by the matching policy themselves, are skipped)
+    pub fn find_copies(&self, node_ids: &[u64]) -> Vec<ProtectedCopy> {
+        let policies = self.policies.read();
+        if policies.values().all(|entry| entry.files.is_empty()) {
+            return Vec::new();
+        }
+
+        let sources: HashSet<u64> = policies.values()
+            .flat_map(|entry| entry.files.iter().map(|file| file.node_id))
+            .collect();
+
+        let mut copies = Vec::new();
+        for &node_id in node_ids {
+            let node = match self.index.get_node(node_id) {
+                Some(node) if !sources.contains(&node.id) && Self::hashable(&node, self.max_file_bytes) => node,
+                _ => continue,
+            };
+            copies.extend(self.match_node(&node, &policies));
+        }
+        copies
+    }
+
+    /// Current registry state (policy ID order)
+    pub fn summary(&self) -> Vec<PolicyFingerprintSummary> {
+        let mut summary: Vec<PolicyFingerprintSummary> = self.policies.read().iter()
+            .map(|(&policy_id, entry)| PolicyFingerprintSummary {
+                policy_id,
+                node_id: entry.node_id,
+                files: entry.files.len(),
+                chunked: entry.files.iter().any(|file| file.chunks.is_some()),
+                truncated: entry.truncated,
+            })
+            .collect();
+        summary.sort_by_key(|entry| entry.policy_id);
+        summary
+    }
+
+    /// Best match per policy for one candidate file
+    fn match_node(&self, node: &FileSystemNode, policies: &HashMap<u64, PolicyFingerprints>) -> Vec<ProtectedCopy> {
+        let size = node.size.unwrap_or(0);
+
+        // Only read the candidate when some source could match it
+        let exact_possible = policies.values().flat_map(|entry| &entry.files).any(|file| file.size == size);
+        let similar_possible = policies.values().flat_map(|entry| &entry.files)
+            .any(|file| file.chunks.is_some() && size <= file.size * 2 && file.size <= size * 2);
+        if !exact_possible && !similar_possible {
+            return Vec::new();
+        }
+        let candidate = match self.fingerprint(node, similar_possible) {
+            Some(candidate) => candidate,
+            None => return Vec::new(),
+        };
+
+        let mut copies = Vec::new();
+        for (&policy_id, entry) in policies {
+            // A file inside the protected folder is already covered by the policy
+            if self.index.is_within(node.id, entry.node_id) {
+                continue;
+            }
+            let best = entry.files.iter()
+                .filter_map(|file| Self::compare(&candidate, file).map(|(kind, similarity)| (file.node_id, kind, similarity)))
+                .max_by_key(|&(_, _, similarity)| similarity);
+            if let Some((source_node_id, match_kind, similarity)) = best {
+                copies.push(ProtectedCopy { policy_id, source_node_id, node_id: node.id, match_kind, similarity });
+            }
+        }
+        copies
+    }
+
+    fn compare(candidate: &FileFingerprint, source: &FileFingerprint) -> Option<(MatchKind, u8)> {
+        if candidate.size == source.size && candidate.sha256 == source.sha256 {
+            return Some((MatchKind::Exact, 100));
+        }
+        let (candidate_chunks, source_chunks) = match (&candidate.chunks, &source.chunks) {
+            (Some(candidate_chunks), Some(source_chunks)) => (candidate_chunks, source_chunks),
+            _ => return None,
+        };
+        let larger = candidate_chunks.len().max(source_chunks.len());
+        if larger == 0 {
+            return None;
+        }
+        let shared = candidate_chunks.intersection(source_chunks).count();
+        let similarity = (shared * 100 / larger) as u8;
+        (similarity >= SIMILARITY_THRESHOLD_PERCENT).then_some((MatchKind::Similar, similarity))
+    }
+
+    /// Empty files are all identical, so they never count as copies
+    fn hashable(node: &FileSystemNode, max_file_bytes: u64) -> bool {
+        node.entry_type == EntryType::File && node.size.is_some_and(|size| size > 0 && size <= max_file_bytes)
+    }
+
+    /// Hash a file in one pass (None if it cannot be read)
+    fn fingerprint(&self, node: &FileSystemNode, chunked: bool) -> Option<FileFingerprint> {
+        let mut file = File::open(&node.display_path).ok()?;
+        let mut sha = Sha256::new();
+        let mut chunker = chunked.then(Chunker::new);
+        let mut buffer = vec![0u8; READ_BUFFER];
+        let mut size = 0u64;
+
+        loop {
+            let read = file.read(&mut buffer).ok()?;
+            if read == 0 {
+                break;
+            }
+            sha.update(&buffer[..read]);
+            if let Some(chunker) = chunker.as_mut() {
+                chunker.update(&buffer[..read]);
+            }
+            size += read as u64;
+        }
+
+        Some(FileFingerprint {
+            node_id: node.id,
+            size,
+            modified_time: node.modified_time,
+            sha256: sha.finalize().into(),
+            chunks: chunker.map(Chunker::finish),
+        })
+    }
+}
+
+/// Gear-hash chunker; each chunk is identified by its FNV-1a hash
+struct Chunker {
+    rolling: u64,
+    chunk_hash: u64,
+    chunk_len: usize,
+    chunks: HashSet<u64>,
+}
+
+impl Chunker {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Chunker { rolling: 0, chunk_hash: Self::FNV_OFFSET, chunk_len: 0, chunks: HashSet::new() }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        let gear = gear_table();
+        for &byte in bytes {
+            self.rolling = (self.rolling << 1).wrapping_add(gear[byte as usize]);
+            self.chunk_hash = (self.chunk_hash ^ byte as u64).wrapping_mul(Self::FNV_PRIME);
+            self.chunk_len += 1;
+
+            let boundary = self.chunk_len >= MIN_CHUNK && self.rolling & CHUNK_MASK == 0;
+            if boundary || self.chunk_len >= MAX_CHUNK {
+                self.cut();
+            }
+        }
+    }
+
+    fn cut(&mut self) {
+        self.chunks.insert(self.chunk_hash);
+        self.chunk_hash = Self::FNV_OFFSET;
+        self.chunk_len = 0;
+    }
+
+    fn finish(mut self) -> HashSet<u64> {
+        if self.chunk_len > 0 {
+            self.cut();
+        }
+        self.chunks
+    }
+}
+
+/// Fixed pseudo-random value per byte (splitmix64), the same on every agent
+fn gear_table() -> &'static [u64; 256] {
+    static TABLE: OnceLock<[u64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0u64; 256];
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        for entry in table.iter_mut() {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *entry = z ^ (z >> 31);
+        }
+        table
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("dlp_{}_{}_{}", name, std::process::id(), nanos));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn pseudo_random(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    fn add(index: &FilesystemIndex, parent_id: u64, path: &std::path::Path, entry_type: EntryType) -> u64 {
+        let display_path = path.to_string_lossy().into_owned();
+        let id = index.id_for_path(&display_path);
+        index.add_node(FileSystemNode {
+            id,
+            name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            entry_type,
+            parent_id: Some(parent_id),
+            children_ids: Vec::new(),
+            nt_path: String::new(),
+            display_path,
+            size: std::fs::metadata(path).ok().filter(|metadata| metadata.is_file()).map(|metadata| metadata.len()),
+            modified_time: 0,
+            created_time: 0,
+            attributes: 0,
+            is_expanded: false,
+            is_accessible: true,
+            link_target: None,
+            classification: None,
+            content_type: None,
+            drive_kind: None,
+            device: None,
+        })
+    }
+
+    fn add_file(index: &FilesystemIndex, parent_id: u64, dir: &std::path::Path, name: &str, content: &[u8]) -> u64 {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        add(index, parent_id, &path, EntryType::File)
+    }
+
+    /// Protected and unprotected folders under a fresh temp directory
+    fn folders(index: &FilesystemIndex, name: &str) -> (PathBuf, u64, u64) {
+        let root = temp_dir(name);
+        std::fs::create_dir_all(root.join("protected")).unwrap();
+        std::fs::create_dir_all(root.join("public")).unwrap();
+        let protected = add(index, 1, &root.join("protected"), EntryType::Directory);
+        let public = add(index, 1, &root.join("public"), EntryType::Directory);
+        (root, protected, public)
+    }
+
+    fn chunks_of(content: &[u8]) -> HashSet<u64> {
+        let mut chunker = Chunker::new();
+        chunker.update(content);
+        chunker.finish()
+    }
+
+    #[test]
+    fn exact_copy_outside_the_policy_is_found() {
+        let index = Arc::new(FilesystemIndex::new());
+        let (root, protected, public) = folders(&index, "fingerprint_exact");
+        let secret = pseudo_random(10_000, 1);
+        let source = add_file(&index, protected, &root.join("protected"), "secret.docx", &secret);
+        add_file(&index, protected, &root.join("protected"), "empty.txt", b"");
+        let copy = add_file(&index, public, &root.join("public"), "copy.docx", &secret);
+        let same_size = add_file(&index, public, &root.join("public"), "other.docx", &pseudo_random(10_000, 2));
+        let empty = add_file(&index, public, &root.join("public"), "empty.txt", b"");
+
+        let registry = FingerprintRegistry::with_options(index.clone(), false, u64::MAX);
+        registry.sync_policies(&[(7, protected)]);
+        assert_eq!(registry.summary()[0].files, 1);
+
+        let copies = registry.find_copies(&[copy, same_size, empty, source]);
+        assert_eq!(copies.len(), 1);
+        assert_eq!((copies[0].policy_id, copies[0].source_node_id, copies[0].node_id), (7, source, copy));
+        assert_eq!((copies[0].match_kind, copies[0].similarity), (MatchKind::Exact, 100));
+
+        // A removed policy no longer matches
+        registry.sync_policies(&[]);
+        assert!(registry.find_copies(&[copy]).is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn edited_copy_matches_as_similar() {
+        let index = Arc::new(FilesystemIndex::new());
+        let (root, protected, public) = folders(&index, "fingerprint_similar");
+        let original = pseudo_random(256 * 1024, 3);
+        let mut edited = original.clone();
+        edited.splice(100_000..100_000, b"inserted paragraph".iter().copied());
+        edited[200_000] ^= 0xff;
+
+        add_file(&index, protected, &root.join("protected"), "plan.docx", &original);
+        let copy = add_file(&index, public, &root.join("public"), "plan (edited).docx", &edited);
+        let unrelated = add_file(&index, public, &root.join("public"), "notes.docx", &pseudo_random(256 * 1024, 4));
+
+        let registry = FingerprintRegistry::with_options(index.clone(), true, u64::MAX);
+        registry.sync_policies(&[(7, protected)]);
+        let copies = registry.find_copies(&[copy, unrelated]);
+        assert_eq!(copies.len(), 1);
+        assert_eq!((copies[0].node_id, copies[0].match_kind), (copy, MatchKind::Similar));
+        assert!(copies[0].similarity >= SIMILARITY_THRESHOLD_PERCENT && copies[0].similarity < 100);
+
+        // Without chunk hashes only exact copies are found
+        let exact_only = FingerprintRegistry::with_options(index.clone(), false, u64::MAX);
+        exact_only.sync_policies(&[(7, protected)]);
+        assert!(exact_only.find_copies(&[copy]).is_empty());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn chunker_only_changes_chunks_near_an_edit() {
+        let original = pseudo_random(512 * 1024, 5);
+        let chunks = chunks_of(&original);
+        assert!(chunks.len() > 10);
+
+        // Boundaries do not depend on how the content is read
+        let mut chunker = Chunker::new();
+        for piece in original.chunks(777) {
+            chunker.update(piece);
+        }
+        assert_eq!(chunker.finish(), chunks);
+
+        let mut edited = original.clone();
+        edited.splice(300_000..300_000, std::iter::repeat_n(0x41, 100));
+        let edited_chunks = chunks_of(&edited);
+        let shared = chunks.intersection(&edited_chunks).count();
+        assert!(shared + 3 >= chunks.len(), "{} of {} chunks shared", shared, chunks.len());
+
+        // Chunks are cut at MAX_CHUNK even without a content boundary
+        assert_eq!(chunks_of(&vec![0u8; MAX_CHUNK * 3]).len(), 1);
+    }
+
+    #[test]
+    fn written_files_in_the_policy_become_sources() {
+        let index = Arc::new(FilesystemIndex::new());
+        let (root, protected, public) = folders(&index, "fingerprint_sources");
+        let registry = FingerprintRegistry::with_options(index.clone(), false, u64::MAX);
+        registry.sync_policies(&[(7, protected)]);
+        assert_eq!(registry.summary()[0].files, 0);
+
+        let secret = pseudo_random(4_000, 6);
+        let source = add_file(&index, protected, &root.join("protected"), "new.xlsx", &secret);
+        let copy = add_file(&index, public, &root.join("public"), "new.xlsx", &secret);
+        registry.update_sources(&[source, copy]);
+        assert_eq!(registry.summary()[0].files, 1);
+
+        // Unchanged policies are not walked again
+        registry.sync_policies(&[(7, protected)]);
+        assert_eq!(registry.summary()[0].files, 1);
+
+        let copies = registry.find_copies(&[source, copy]);
+        assert_eq!(copies.len(), 1);
+        assert_eq!((copies[0].source_node_id, copies[0].node_id), (source, copy));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -8,7 +8,7 @@ use std::ptr;
 use super::kernel_policy::{KernelPolicy, KernelOperations};
 
 // Import fltlib from parent module
-use crate::{fltlib, kernel::KernelEvent, policy::PathMatchType};
+use crate::{fltlib, kernel::KernelEvent, nt_path_resolver::NtPathResolver, policy::PathMatchType};
 
 /// Kernel policy message (must match minifilter structure)
 #[repr(C)]
@@ -259,7 +259,7 @@ impl KernelAdapter {
     /// Send policy to kernel
     pub fn send_policy(&mut self, policy: &KernelPolicy) -> Result<u64, String> {
         println!("📤 KernelAdapter: Sending policy to kernel (ID: {})", policy.policy_id);
-        println!("   Path: {}", policy.nt_path);
+        println!("   Path: {}", NtPathResolver::describe_nt_path(&policy.nt_path));
         
         // Create kernel message
         let message = FilePolicy ::from_kernel_policy(policy);
@@ -291,7 +291,7 @@ impl KernelAdapter {
    /// Remove policy from kernel
 pub fn remove_policy(&mut self, policy_id: u64, nt_path: &str) -> Result<(), String> {
     println!("🗑️ KernelAdapter: Removing policy from kernel (ID: {})", policy_id);
-    println!("   Path: {}", nt_path);
+    println!("   Path: {}", NtPathResolver::describe_nt_path(nt_path));
 
     // -----------------------------
     // NT path → UTF-16
@@ -2,20 +2,23 @@
 //! Core Principle: Convert Admin intent to kernel-understandable rules
 //! IMPORTANT: Implements READ = BLOCK ALL enterprise DLP rule
 
+use serde::{Deserialize, Serialize};
+
+use crate::path_canonical;
 use crate::policy::{
     ProtectionScope,
     policy_intent::{ PolicyIntent, ProtectionAction, ProtectionOperations },
 };
 
 /// How kernel should match the path
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PathMatchType {
     Exact, // Exact NT path match (files)
     Prefix, // NT path prefix match (folders - recursive)
 }
 
 /// Kernel-ready policy
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KernelPolicy {
     pub policy_id: u64, // Unique policy ID
     pub nt_path: String, // NT path from PathResolver (INTERNAL ONLY)
@@ -31,7 +34,7 @@ pub struct KernelPolicy {
 }
 
 /// Kernel operations (binary flags for kernel)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct KernelOperations {
     pub write: bool,
     pub delete: bool,
@@ -329,6 +332,15 @@ impl KernelPolicy {
         }
     }
 
+    /// Whether the rule covers `nt_path`: the path itself (exact) or anything
+    /// below it (prefix), compared in canonical form
+    pub fn matches_nt_path(&self, nt_path: &str) -> bool {
+        match self.match_type {
+            PathMatchType::Exact => path_canonical::same_path(&self.nt_path, nt_path),
+            PathMatchType::Prefix => path_canonical::is_within(nt_path, &self.nt_path),
+        }
+    }
+
     // Get a description of what this kernel policy does
     // pub fn describe(&self) -> String {
     //     if self.is_block_all() {
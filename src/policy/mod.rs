@@ -11,6 +11,14 @@ mod policy_engine;
 pub mod policy_preview;
 mod policy_guard;
 mod policy_dry_run;
+pub mod policy_bundle;
+pub mod policy_history;
+pub mod policy_approval;
+pub mod enforcement_suspension;
+pub mod policy_retarget;
+pub mod auto_protection;
+pub mod fingerprint_registry;
+pub mod device_control;
 
 pub use policy_intent::{PolicyIntent, ProtectionScope, ProtectionAction, ProtectionOperations};
 pub use path_resolver::PathResolver;
@@ -20,6 +28,7 @@ pub use policy_store::{PolicyStore, ActivePolicy, PolicyStoreStats};
 pub use policy_engine::{PolicyEngine, PolicyEngineStats};
 pub use policy_preview::{PolicyPreviewService, PolicyPreview};
 pub use policy_store::HealthStatus;
+pub use policy_bundle::PolicyBundle;
 /// Initialize STEP 4 Policy Engine
 pub fn init_step4(
     index: std::sync::Arc<crate::fs_index::FilesystemIndex>,
@@ -389,6 +389,7 @@
 
 use crate::fs_index::{EntryType, FilesystemIndex};
 use crate::nt_path_resolver::NtPathResolver;
+use crate::path_canonical;
 use crate::policy::policy_intent::{PolicyIntent, ProtectionScope};
 use std::sync::Arc;
 
@@ -418,16 +419,19 @@ impl PathResolver {
         println!("   Node display path: {}", node.display_path);
         println!("   Node NT path: {}", node.nt_path);
         
-        // If node already has NT path in index, use it
-        if !node.nt_path.is_empty() && NtPathResolver::validate_nt_path(&node.nt_path) {
-            println!("   Using cached NT path");
-            return Ok(node.nt_path.clone());
+        // The scan could not map this node to a device; its display path is no NT path
+        if node.nt_path.is_empty() {
+            return Err(format!("Node {} has no resolved NT path; rescan it once its volume is available", node_id));
         }
         
-        println!("   Converting DOS to NT path...");
-        // Convert display path to NT path
+        // Always map through the current volumes: the cached path may name a
+        // device that left (or now belongs to another disk)
         let is_folder = matches!(node.entry_type, EntryType::Directory | EntryType::Drive);
-        NtPathResolver::dos_to_nt_path(&node.display_path, is_folder)
+        let nt_path = path_canonical::canonicalize(&NtPathResolver::dos_to_nt_path(&node.display_path, is_folder)?);
+        if NtPathResolver::validate_nt_path(&node.nt_path) && !path_canonical::same_path(&node.nt_path, &nt_path) {
+            println!("   ⚠️ Cached NT path is stale (volume changed), using current mapping");
+        }
+        Ok(nt_path)
     }
     
     /// Resolve policy intent to kernel-ready NT path(s)
@@ -435,6 +439,15 @@ impl PathResolver {
         println!("🔄 PathResolver: Resolving policy intent for ID {}", intent.node_id);
         println!("   Scope: {:?}, Action: {:?}", intent.scope, intent.action);
         
+        // The kernel sees the reparsed target path, never the link path
+        if let Some(link_id) = self.index.link_ancestor(intent.node_id) {
+            return Err(if link_id == intent.node_id {
+                format!("Node {} is a symbolic link or junction; protect its target instead", intent.node_id)
+            } else {
+                format!("Node {} is reached through link node {}; protect the real path instead", intent.node_id, link_id)
+            });
+        }
+        
         let base_nt_path = self.resolve_nt_path(intent.node_id)?;
         
         match intent.scope {
@@ -450,9 +463,7 @@ impl PathResolver {
 
                 for child in children {
                     if matches!(child.entry_type, EntryType::File) {
-                        if let Some(p) = self.index.resolve_nt_path(child.id) {
-                            paths.push(p);
-                        }
+                        paths.push(self.resolve_nt_path(child.id)?);
                     }
                 }
 
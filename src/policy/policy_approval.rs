@@ -0,0 +1,291 @@
+//! Two-Person Approval (STEP 7.5)
+//! Core Principle: High-risk policies need a second, different admin
+//! The requesting admin confirms, a second admin approves; only then does the
+//! change reach the kernel. Requests expire if nobody decides in time.
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::policy_intent::PolicyIntent;
+
+/// Default time a pending request stays open (1 hour)
+pub const DEFAULT_APPROVAL_TIMEOUT_SECS: u64 = 3600;
+
+/// Time decided (approved, rejected, expired, failed) requests stay listed (1 day)
+const DECIDED_RETENTION_SECS: u64 = 24 * 3600;
+
+/// Change waiting for approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PendingAction {
+    Apply,
+    Update { policy_id: u64 },
+    Revert { policy_id: u64, version: u32 },
+}
+
+/// Lifecycle of an approval request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,   // Second admin approved, change applied
+    Rejected,
+    Expired,
+    Failed,     // Approved, but applying the change failed
+}
+
+/// One approval request (admin-safe: node IDs only)
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingApproval {
+    pub request_id: u64,
+    pub action: PendingAction,
+    pub intent: PolicyIntent,
+    pub requested_by: String,
+    pub requested_at: u64,
+    pub expires_at: u64,
+    pub status: ApprovalStatus,
+    pub confirmation_message: Option<String>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<u64>,
+    pub reason: Option<String>,        // Rejection reason or failure message
+    pub policy_id: Option<u64>,        // Resulting policy once applied
+}
+
+/// Result of an assured policy change
+#[derive(Debug, Clone)]
+pub enum ChangeOutcome {
+    Applied { policy_id: u64, version: u32 },
+    PendingApproval(Box<PendingApproval>),
+}
+
+/// Pending approval queue
+pub struct ApprovalQueue {
+    requests: RwLock<HashMap<u64, PendingApproval>>,
+    next_id: AtomicU64,
+    timeout_secs: u64,
+    retention_secs: u64,
+}
+
+impl ApprovalQueue {
+    /// Create new queue with the given request timeout
+    pub fn new(timeout_secs: u64) -> Arc<Self> {
+        Arc::new(ApprovalQueue {
+            requests: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            timeout_secs,
+            retention_secs: DECIDED_RETENTION_SECS,
+        })
+    }
+
+    /// Open a new approval request
+    pub fn submit(
+        &self,
+        action: PendingAction,
+        intent: PolicyIntent,
+        requested_by: &str,
+        confirmation_message: Option<String>,
+    ) -> PendingApproval {
+        let request_id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let now = Self::now();
+
+        let request = PendingApproval {
+            request_id,
+            action,
+            intent,
+            requested_by: requested_by.to_string(),
+            requested_at: now,
+            expires_at: now + self.timeout_secs,
+            status: ApprovalStatus::Pending,
+            confirmation_message,
+            decided_by: None,
+            decided_at: None,
+            reason: None,
+            policy_id: None,
+        };
+
+        println!("⏳ ApprovalQueue: Request {} opened by {} ({:?}), expires in {}s",
+            request_id, requested_by, action, self.timeout_secs);
+
+        self.requests.write().insert(request_id, request.clone());
+        request
+    }
+
+    /// Claim a pending request for approval by `approved_by`
+    /// Marks it approved up front so two approvers cannot both apply it.
+    pub fn claim(&self, request_id: u64, approved_by: &str) -> Result<PendingApproval, String> {
+        self.expire_stale();
+
+        let mut requests = self.requests.write();
+        let request = requests.get_mut(&request_id)
+            .ok_or_else(|| format!("Approval request {} not found", request_id))?;
+
+        if request.status != ApprovalStatus::Pending {
+            return Err(format!("Approval request {} is {:?}", request_id, request.status));
+        }
+
+        if Self::same_admin(&request.requested_by, approved_by) {
+            return Err(format!(
+                "Approval request {} must be approved by a different admin than {}",
+                request_id, request.requested_by
+            ));
+        }
+
+        request.status = ApprovalStatus::Approved;
+        request.decided_by = Some(approved_by.to_string());
+        request.decided_at = Some(Self::now());
+        Ok(request.clone())
+    }
+
+    /// Record the result of applying a claimed request
+    pub fn complete(&self, request_id: u64, result: &Result<u64, String>) -> Option<PendingApproval> {
+        let mut requests = self.requests.write();
+        let request = requests.get_mut(&request_id)?;
+
+        match result {
+            Ok(policy_id) => request.policy_id = Some(*policy_id),
+            Err(e) => {
+                request.status = ApprovalStatus::Failed;
+                request.reason = Some(e.clone());
+            }
+        }
+        Some(request.clone())
+    }
+
+    /// Reject a pending request (requester may withdraw their own)
+    pub fn reject(&self, request_id: u64, rejected_by: &str, reason: &str) -> Result<PendingApproval, String> {
+        self.expire_stale();
+
+        let mut requests = self.requests.write();
+        let request = requests.get_mut(&request_id)
+            .ok_or_else(|| format!("Approval request {} not found", request_id))?;
+
+        if request.status != ApprovalStatus::Pending {
+            return Err(format!("Approval request {} is {:?}", request_id, request.status));
+        }
+
+        request.status = ApprovalStatus::Rejected;
+        request.decided_by = Some(rejected_by.to_string());
+        request.decided_at = Some(Self::now());
+        request.reason = Some(reason.to_string());
+
+        println!("🚫 ApprovalQueue: Request {} rejected by {}", request_id, rejected_by);
+        Ok(request.clone())
+    }
+
+    /// Get a request by ID
+    pub fn get(&self, request_id: u64) -> Option<PendingApproval> {
+        self.expire_stale();
+        self.requests.read().get(&request_id).cloned()
+    }
+
+    /// List all requests (newest first)
+    pub fn list(&self) -> Vec<PendingApproval> {
+        self.expire_stale();
+        let mut requests: Vec<_> = self.requests.read().values().cloned().collect();
+        requests.sort_by_key(|request| std::cmp::Reverse(request.request_id));
+        requests
+    }
+
+    /// Flip timed-out pending requests to expired, drop long-decided ones
+    fn expire_stale(&self) {
+        let now = Self::now();
+        let mut requests = self.requests.write();
+        for request in requests.values_mut() {
+            if request.status == ApprovalStatus::Pending && now >= request.expires_at {
+                request.status = ApprovalStatus::Expired;
+                request.decided_at = Some(now);
+                println!("⌛ ApprovalQueue: Request {} expired", request.request_id);
+            }
+        }
+
+        let before = requests.len();
+        requests.retain(|_, request| match request.decided_at {
+            Some(decided_at) => now < decided_at + self.retention_secs,
+            None => true,
+        });
+        if requests.len() < before {
+            println!("🧹 ApprovalQueue: Dropped {} decided requests", before - requests.len());
+        }
+    }
+
+    /// Admin identities compare case-insensitively, ignoring surrounding spaces
+    fn same_admin(a: &str, b: &str) -> bool {
+        a.trim().eq_ignore_ascii_case(b.trim())
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::policy_intent::{ProtectionAction, ProtectionOperations, ProtectionScope};
+
+    fn queue(timeout_secs: u64, retention_secs: u64) -> ApprovalQueue {
+        ApprovalQueue {
+            requests: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            timeout_secs,
+            retention_secs,
+        }
+    }
+
+    fn submit(queue: &ApprovalQueue, requested_by: &str) -> u64 {
+        let operations = ProtectionOperations { read: true, ..ProtectionOperations::default() };
+        let intent = PolicyIntent::new(42, ProtectionScope::FolderRecursive, ProtectionAction::Block, operations, requested_by, None);
+        queue.submit(PendingAction::Apply, intent, requested_by, None).request_id
+    }
+
+    #[test]
+    fn requester_cannot_approve_their_own_request() {
+        let queue = queue(3600, 3600);
+        let request_id = submit(&queue, "alice");
+
+        let error = queue.claim(request_id, " ALICE ").unwrap_err();
+        assert!(error.contains("different admin"));
+        assert_eq!(queue.get(request_id).unwrap().status, ApprovalStatus::Pending);
+
+        let approved = queue.claim(request_id, "bob").unwrap();
+        assert_eq!(approved.status, ApprovalStatus::Approved);
+        assert_eq!(approved.decided_by.as_deref(), Some("bob"));
+
+        // Claimed once: a second approver cannot apply it again
+        assert!(queue.claim(request_id, "carol").is_err());
+    }
+
+    #[test]
+    fn rejected_requests_cannot_be_approved() {
+        let queue = queue(3600, 3600);
+        let request_id = submit(&queue, "alice");
+
+        let rejected = queue.reject(request_id, "alice", "Wrong folder").unwrap();
+        assert_eq!(rejected.status, ApprovalStatus::Rejected);
+        assert_eq!(rejected.reason.as_deref(), Some("Wrong folder"));
+        assert!(queue.claim(request_id, "bob").is_err());
+        assert!(queue.reject(request_id, "bob", "Again").is_err());
+    }
+
+    #[test]
+    fn requests_expire_and_decided_ones_are_pruned() {
+        let expiring = queue(0, 3600);
+        let request_id = submit(&expiring, "alice");
+        assert_eq!(expiring.get(request_id).unwrap().status, ApprovalStatus::Expired);
+        assert!(expiring.claim(request_id, "bob").unwrap_err().contains("Expired"));
+
+        let pruning = queue(3600, 0);
+        let decided = submit(&pruning, "alice");
+        let open = submit(&pruning, "alice");
+        pruning.reject(decided, "bob", "No").unwrap();
+        let listed: Vec<u64> = pruning.list().iter().map(|request| request.request_id).collect();
+        assert_eq!(listed, vec![open]);
+    }
+}
@@ -0,0 +1,594 @@
+//! Policy Bundle Import/Export (STEP 8.1)
+//! Core Principle: Move reviewed policies between agents without leaking node IDs
+//! Node IDs only mean something inside one agent's index, so bundles carry
+//! display paths (+ volume GUID) and are re-resolved on the target machine.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::filesystem_scanner::FileSystemScanner;
+use crate::fs_index::EntryType;
+use crate::nt_path_resolver::NtPathResolver;
+use crate::volume_map::VolumeMap;
+
+use super::policy_approval::ChangeOutcome;
+use super::policy_engine::PolicyEngine;
+use super::policy_intent::{PolicyIntent, ProtectionAction, ProtectionOperations, ProtectionScope};
+
+/// Current bundle format version
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Environment variable holding the shared bundle signing key
+pub const BUNDLE_KEY_ENV: &str = "AGENT_POLICY_BUNDLE_KEY";
+
+/// One portable policy entry (no node IDs, no NT paths)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub display_path: String,          // e.g. "D:\Finance\"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drive_root: Option<String>,    // "D:\", "\\server\share\" or "\\?\volume{...}\" (None in older bundles)
+    pub volume_guid: Option<String>,   // \\?\Volume{...}\ on the source machine
+    pub is_folder: bool,
+    pub scope: ProtectionScope,
+    pub action: ProtectionAction,
+    pub operations: ProtectionOperations,
+    pub created_by: String,            // Original author on the source agent
+    pub comment: Option<String>,
+}
+
+/// Signed, portable policy bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyBundle {
+    pub format_version: u32,
+    pub exported_at: u64,
+    pub exported_by: String,
+    pub source_host: String,
+    pub entries: Vec<BundleEntry>,
+    pub signature: String,             // hex HMAC-SHA256 over everything above
+}
+
+/// Signed portion of a bundle (field order is the canonical order)
+#[derive(Serialize)]
+struct BundlePayload<'a> {
+    format_version: u32,
+    exported_at: u64,
+    exported_by: &'a str,
+    source_host: &'a str,
+    entries: &'a [BundleEntry],
+}
+
+/// Per-entry import outcome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportEntryStatus {
+    Resolved,   // Dry-run: path found, policy would apply
+    Applied,    // Policy applied on this agent
+    PendingApproval, // High-risk: waiting for a second admin
+    Failed,     // Could not resolve or apply
+}
+
+/// Per-entry resolution report
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportEntryReport {
+    pub index: usize,
+    pub display_path: String,
+    pub resolved_path: Option<String>,
+    pub matched_by: Option<String>,    // "volume_guid" or "display_path"
+    pub node_id: Option<u64>,
+    pub policy_id: Option<u64>,
+    pub status: ImportEntryStatus,
+    pub warnings: Vec<String>,
+    pub message: String,
+}
+
+/// Full import report
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub source_host: String,
+    pub total_entries: usize,
+    pub resolved: usize,
+    pub applied: usize,
+    pub pending_approval: usize,
+    pub failed: usize,
+    pub entries: Vec<ImportEntryReport>,
+}
+
+/// Policy bundle service
+pub struct PolicyBundleService;
+
+impl PolicyBundleService {
+    /// Export all active policies as a signed bundle
+    pub fn export(engine: &PolicyEngine, exported_by: &str) -> Result<PolicyBundle, String> {
+        println!("📦 PolicyBundleService: Exporting active policies");
+
+        if exported_by.trim().is_empty() {
+            return Err("Exporter name cannot be empty".to_string());
+        }
+
+        let key = Self::signing_key()?;
+        let index = engine.path_resolver().index();
+
+        let mut entries = Vec::new();
+        let mut unmapped = Vec::new();
+        let mut guid_cache: HashMap<String, Option<String>> = HashMap::new();
+
+        for policy in engine.get_active_policies() {
+            let node = match index.get_node(policy.intent.node_id) {
+                Some(node) => node,
+                None => {
+                    unmapped.push(policy.intent.node_id);
+                    continue;
+                }
+            };
+
+            let drive_root = match index.drive_root(node.id) {
+                Some(drive_root) => drive_root,
+                None => {
+                    unmapped.push(policy.intent.node_id);
+                    continue;
+                }
+            };
+            let volume_guid = guid_cache.entry(drive_root.to_lowercase())
+                .or_insert_with(|| Self::volume_guid_for_root(&drive_root))
+                .clone();
+
+            entries.push(BundleEntry {
+                display_path: node.display_path.clone(),
+                drive_root: Some(drive_root),
+                volume_guid,
+                is_folder: matches!(node.entry_type, EntryType::Directory | EntryType::Drive),
+                scope: policy.intent.scope,
+                action: policy.intent.action,
+                operations: policy.intent.operations,
+                created_by: policy.intent.created_by.clone(),
+                comment: policy.intent.comment.clone(),
+            });
+        }
+
+        if !unmapped.is_empty() {
+            return Err(format!(
+                "Cannot export: policies reference nodes no longer in the index: {:?}",
+                unmapped
+            ));
+        }
+
+        let mut bundle = PolicyBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            exported_by: exported_by.to_string(),
+            source_host: std::env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_string()),
+            entries,
+            signature: String::new(),
+        };
+        bundle.signature = Self::sign(&bundle, &key)?;
+
+        println!("   ✅ Exported {} policies", bundle.entries.len());
+        Ok(bundle)
+    }
+
+    /// Verify a bundle and re-resolve every entry on this machine
+    /// With `dry_run` nothing is applied, only the resolution report is built
+    pub fn import(
+        engine: &PolicyEngine,
+        scanner: &FileSystemScanner,
+        bundle: &PolicyBundle,
+        imported_by: &str,
+        dry_run: bool,
+        confirmed: bool,
+    ) -> Result<ImportReport, String> {
+        println!("📦 PolicyBundleService: Importing {} entries (dry_run={})",
+            bundle.entries.len(), dry_run);
+
+        if imported_by.trim().is_empty() {
+            return Err("Importer name cannot be empty".to_string());
+        }
+
+        if bundle.format_version != BUNDLE_FORMAT_VERSION {
+            return Err(format!("Unsupported bundle format version {}", bundle.format_version));
+        }
+
+        Self::verify(bundle)?;
+        println!("   ✅ Bundle signature verified");
+
+        // Volume GUID → drive root on THIS machine
+        let index = engine.path_resolver().index();
+        let local_volumes: HashMap<String, String> = index.get_drives()
+            .into_iter()
+            .filter_map(|(root, _)| {
+                let root = format!("{}\\", root);
+                Self::volume_guid_for_root(&root).map(|guid| (guid.to_lowercase(), root))
+            })
+            .collect();
+
+        let mut reports = Vec::new();
+
+        for (i, entry) in bundle.entries.iter().enumerate() {
+            let report = Self::import_entry(
+                engine, scanner, &local_volumes, i, entry, imported_by, dry_run, confirmed,
+            );
+            match report.status {
+                ImportEntryStatus::Failed => println!("   ❌ [{}] {}: {}", i, entry.display_path, report.message),
+                ImportEntryStatus::PendingApproval => println!("   ⏳ [{}] {}: {}", i, entry.display_path, report.message),
+                _ => println!("   ✅ [{}] {}: {}", i, entry.display_path, report.message),
+            }
+            reports.push(report);
+        }
+
+        let count = |status| reports.iter().filter(|r| r.status == status).count();
+
+        Ok(ImportReport {
+            dry_run,
+            source_host: bundle.source_host.clone(),
+            total_entries: bundle.entries.len(),
+            resolved: count(ImportEntryStatus::Resolved),
+            applied: count(ImportEntryStatus::Applied),
+            pending_approval: count(ImportEntryStatus::PendingApproval),
+            failed: count(ImportEntryStatus::Failed),
+            entries: reports,
+        })
+    }
+
+    /// Resolve (and optionally apply) a single bundle entry
+    #[allow(clippy::too_many_arguments)]
+    fn import_entry(
+        engine: &PolicyEngine,
+        scanner: &FileSystemScanner,
+        local_volumes: &HashMap<String, String>,
+        index: usize,
+        entry: &BundleEntry,
+        imported_by: &str,
+        dry_run: bool,
+        confirmed: bool,
+    ) -> ImportEntryReport {
+        let mut report = ImportEntryReport {
+            index,
+            display_path: entry.display_path.clone(),
+            resolved_path: None,
+            matched_by: None,
+            node_id: None,
+            policy_id: None,
+            status: ImportEntryStatus::Failed,
+            warnings: Vec::new(),
+            message: String::new(),
+        };
+
+        // 1. Pick the drive: same volume if it is mounted here, else same path
+        let relative = match Self::relative_path(entry) {
+            Some(relative) => relative,
+            None => {
+                report.message = format!("Cannot tell the drive of {}", entry.display_path);
+                return report;
+            }
+        };
+        let (target_path, matched_by) = match entry.volume_guid.as_ref()
+            .and_then(|guid| local_volumes.get(&guid.to_lowercase()))
+        {
+            Some(local_root) => (format!("{}{}", local_root, relative), "volume_guid"),
+            None => (entry.display_path.clone(), "display_path"),
+        };
+
+        if matched_by == "volume_guid" && !target_path.eq_ignore_ascii_case(&entry.display_path) {
+            report.warnings.push(format!("Volume is mounted at a different drive root here ({})", target_path));
+        }
+        report.resolved_path = Some(target_path.clone());
+        report.matched_by = Some(matched_by.to_string());
+
+        // 2. Re-resolve: through the scanner (expands directories as needed),
+        //    or for a dry run from the index and the disk so the tree stays as it is
+        let index = engine.path_resolver().index();
+        let resolved = if dry_run {
+            match index.get_node_by_path(&target_path) {
+                Some(node) => Ok((Some(node.id), matches!(node.entry_type, EntryType::Directory | EntryType::Drive))),
+                None => std::fs::metadata(&target_path)
+                    .map(|metadata| (None, metadata.is_dir()))
+                    .map_err(|e| format!("Path not found: {} ({})", target_path, e)),
+            }
+        } else {
+            scanner.resolve_display_path(&target_path).and_then(|node_id| {
+                index.get_node(node_id)
+                    .map(|node| (Some(node_id), matches!(node.entry_type, EntryType::Directory | EntryType::Drive)))
+                    .ok_or_else(|| format!("Node {} disappeared during import", node_id))
+            })
+        };
+        let (node_id, is_folder) = match resolved {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                report.message = e;
+                return report;
+            }
+        };
+        report.node_id = node_id;
+
+        if is_folder != entry.is_folder {
+            report.message = format!(
+                "Type mismatch: bundle expects a {}, target is a {}",
+                if entry.is_folder { "folder" } else { "file" },
+                if is_folder { "folder" } else { "file" },
+            );
+            return report;
+        }
+
+        // 3. Build intent for the target node (placeholder ID for a dry run on a path not loaded yet)
+        let intent = PolicyIntent::new(
+            node_id.unwrap_or(u64::MAX),
+            entry.scope,
+            entry.action,
+            entry.operations,
+            imported_by,
+            entry.comment.as_deref(),
+        );
+
+        if let Err(e) = intent.validate() {
+            report.message = e;
+            return report;
+        }
+
+        let safety = engine.validate_policy_safety(&intent);
+        report.warnings.extend(safety.warnings.iter().cloned());
+        if !safety.is_valid {
+            report.message = format!("Policy failed safety validation: {:?}", safety.errors);
+            return report;
+        }
+
+        if dry_run {
+            report.status = ImportEntryStatus::Resolved;
+            report.message = if safety.requires_confirmation && !confirmed {
+                format!("Would apply (requires confirmation): {}", intent.describe())
+            } else {
+                format!("Would apply: {}", intent.describe())
+            };
+            if node_id.is_none() {
+                report.warnings.push("Not in the index yet, loaded on import".to_string());
+            }
+            return report;
+        }
+
+        // 4. Apply
+        match engine.apply_protection_with_assurance(intent, confirmed) {
+            Ok(ChangeOutcome::Applied { policy_id, .. }) => {
+                report.policy_id = Some(policy_id);
+                report.status = ImportEntryStatus::Applied;
+                report.message = format!("Applied as policy {}", policy_id);
+            }
+            Ok(ChangeOutcome::PendingApproval(request)) => {
+                report.status = ImportEntryStatus::PendingApproval;
+                report.message = format!("Awaiting second admin approval (request {})", request.request_id);
+            }
+            Err(e) => report.message = e,
+        }
+
+        report
+    }
+
+    /// Path below the drive root ("Finance\" for "D:\Finance\")
+    /// Older bundles have no drive root and only know drive letters.
+    fn relative_path(entry: &BundleEntry) -> Option<&str> {
+        match &entry.drive_root {
+            Some(drive_root) => {
+                let prefix = entry.display_path.get(..drive_root.len())?;
+                prefix.eq_ignore_ascii_case(drive_root).then(|| &entry.display_path[drive_root.len()..])
+            }
+            None => {
+                let bytes = entry.display_path.as_bytes();
+                (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+                    .then(|| entry.display_path[2..].trim_start_matches('\\'))
+            }
+        }
+    }
+
+    /// Volume GUID behind a drive root (None for network roots)
+    fn volume_guid_for_root(drive_root: &str) -> Option<String> {
+        let root = drive_root.trim_end_matches('\\');
+        if root.len() == 2 {
+            NtPathResolver::volume_guid_for_drive(root).ok()
+        } else if root.to_lowercase().starts_with("\\\\?\\volume{") {
+            Some(VolumeMap::normalize_guid(root))
+        } else {
+            None
+        }
+    }
+
+    /// Verify bundle signature against the local signing key
+    pub fn verify(bundle: &PolicyBundle) -> Result<(), String> {
+        Self::verify_with(bundle, &Self::signing_key()?)
+    }
+
+    fn verify_with(bundle: &PolicyBundle, key: &[u8]) -> Result<(), String> {
+        let expected = Self::sign(bundle, key)?;
+
+        let matches = expected.len() == bundle.signature.len()
+            && expected.bytes()
+                .zip(bundle.signature.to_lowercase().bytes())
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+
+        if matches {
+            Ok(())
+        } else {
+            Err("Bundle signature is invalid (tampered or signed with a different key)".to_string())
+        }
+    }
+
+    /// Compute hex HMAC-SHA256 over the canonical payload
+    fn sign(bundle: &PolicyBundle, key: &[u8]) -> Result<String, String> {
+        let payload = BundlePayload {
+            format_version: bundle.format_version,
+            exported_at: bundle.exported_at,
+            exported_by: &bundle.exported_by,
+            source_host: &bundle.source_host,
+            entries: &bundle.entries,
+        };
+        let bytes = serde_json::to_vec(&payload)
+            .map_err(|e| format!("Failed to serialize bundle: {}", e))?;
+
+        Ok(format!("{:x}", hmac_sha256(key, &bytes)))
+    }
+
+    /// Load signing key from the environment
+    fn signing_key() -> Result<Vec<u8>, String> {
+        match std::env::var(BUNDLE_KEY_ENV) {
+            Ok(key) if !key.trim().is_empty() => Ok(key.into_bytes()),
+            _ => Err(format!("{} is not set - policy bundles cannot be signed or verified", BUNDLE_KEY_ENV)),
+        }
+    }
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> sha2::digest::Output<Sha256> {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut key_block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        key_block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        key_block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner_pad = [0x36u8; BLOCK_SIZE];
+    let mut outer_pad = [0x5cu8; BLOCK_SIZE];
+    for i in 0..BLOCK_SIZE {
+        inner_pad[i] ^= key_block[i];
+        outer_pad[i] ^= key_block[i];
+    }
+
+    let inner = Sha256::new()
+        .chain_update(inner_pad)
+        .chain_update(message)
+        .finalize();
+
+    Sha256::new()
+        .chain_update(outer_pad)
+        .chain_update(inner)
+        .finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn entry(display_path: &str, drive_root: Option<&str>) -> BundleEntry {
+        BundleEntry {
+            display_path: display_path.to_string(),
+            drive_root: drive_root.map(str::to_string),
+            volume_guid: None,
+            is_folder: true,
+            scope: ProtectionScope::FolderRecursive,
+            action: ProtectionAction::Block,
+            operations: ProtectionOperations::default(),
+            created_by: "alice".to_string(),
+            comment: None,
+        }
+    }
+
+    fn bundle(entries: Vec<BundleEntry>, key: &[u8]) -> PolicyBundle {
+        let mut bundle = PolicyBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: 1_700_000_000,
+            exported_by: "alice".to_string(),
+            source_host: "WS-01".to_string(),
+            entries,
+            signature: String::new(),
+        };
+        bundle.signature = PolicyBundleService::sign(&bundle, key).unwrap();
+        bundle
+    }
+
+    #[test]
+    fn hmac_matches_rfc4231_vectors() {
+        // Test case 1
+        assert_eq!(
+            hex(&hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        // Test case 2 (key shorter than the block)
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Test case 3 (50 bytes of 0xdd)
+        assert_eq!(
+            hex(&hmac_sha256(&[0xaa; 20], &[0xdd; 50])),
+            "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe"
+        );
+        // Test case 6 (key longer than the block is hashed first)
+        assert_eq!(
+            hex(&hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        // Test case 7 (long key and long message)
+        assert_eq!(
+            hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm."
+            )),
+            "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2"
+        );
+    }
+
+    #[test]
+    fn verify_rejects_tampered_bundles_and_other_keys() {
+        let key = b"shared-secret";
+        let signed = bundle(vec![entry("D:\\Finance", Some("D:\\"))], key);
+        assert!(PolicyBundleService::verify_with(&signed, key).is_ok());
+
+        let mut uppercase = signed.clone();
+        uppercase.signature = uppercase.signature.to_uppercase();
+        assert!(PolicyBundleService::verify_with(&uppercase, key).is_ok());
+
+        let mut tampered = signed.clone();
+        tampered.entries[0].action = ProtectionAction::Audit;
+        assert!(PolicyBundleService::verify_with(&tampered, key).is_err());
+
+        let mut retargeted = signed.clone();
+        retargeted.entries[0].drive_root = Some("E:\\".to_string());
+        assert!(PolicyBundleService::verify_with(&retargeted, key).is_err());
+
+        assert!(PolicyBundleService::verify_with(&signed, b"other-secret").is_err());
+    }
+
+    #[test]
+    fn bundles_without_drive_root_keep_their_signature() {
+        // Older bundles never had the field; skipping it keeps the signed payload identical
+        let legacy = bundle(vec![entry("D:\\Finance", None)], b"k");
+        let json = serde_json::to_string(&legacy).unwrap();
+        assert!(!json.contains("drive_root"));
+        let parsed: PolicyBundle = serde_json::from_str(&json).unwrap();
+        assert!(PolicyBundleService::verify_with(&parsed, b"k").is_ok());
+    }
+
+    #[test]
+    fn relative_path_strips_any_drive_root() {
+        let cases = [
+            ("D:\\Finance\\Q1", Some("D:\\"), Some("Finance\\Q1")),
+            ("D:\\", Some("D:\\"), Some("")),
+            ("\\\\fs01\\share\\Finance", Some("\\\\fs01\\share\\"), Some("Finance")),
+            ("\\\\?\\volume{0a1b}\\Data", Some("\\\\?\\Volume{0A1B}\\"), Some("Data")),
+            ("E:\\Finance", Some("D:\\"), None),
+            // Older bundles: drive letter only
+            ("D:\\Finance", None, Some("Finance")),
+            ("\\\\fs01\\share\\Finance", None, None),
+        ];
+        for (display_path, drive_root, expected) in cases {
+            let entry = entry(display_path, drive_root);
+            assert_eq!(PolicyBundleService::relative_path(&entry), expected, "{}", display_path);
+        }
+    }
+
+    #[test]
+    fn network_roots_have_no_volume_guid() {
+        assert_eq!(PolicyBundleService::volume_guid_for_root("\\\\fs01\\share\\"), None);
+        assert_eq!(
+            PolicyBundleService::volume_guid_for_root("\\\\?\\Volume{0A1B}\\"),
+            Some(VolumeMap::normalize_guid("\\\\?\\Volume{0A1B}"))
+        );
+    }
+}
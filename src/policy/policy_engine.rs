@@ -1,12 +1,16 @@
 //! Policy Engine (STEP 4)
 //! Core Principle: Bridge Admin intent → kernel enforcement securely
 
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use crate::fs_index::FilesystemIndex;
 use crate::policy::policy_dry_run::{DryRunEvaluation, DryRunEvaluator};
 use crate::policy::policy_guard::{PolicyGuard, SafetyValidation};
 use crate::policy::policy_preview::{PolicyPreview, PolicyPreviewService};
+use crate::policy::policy_bundle::{ImportReport, PolicyBundle, PolicyBundleService};
+use crate::filesystem_scanner::FileSystemScanner;
 use crate::policy::policy_store::{EnforcementStats, HealthStatus};
 
 use super::policy_intent::{PolicyIntent, ProtectionScope, ProtectionAction, ProtectionOperations};
@@ -14,12 +18,28 @@ use super::path_resolver::PathResolver;
 use super::kernel_policy::{KernelPolicy, PolicyNormalizer};
 use super::kernel_adapter::KernelAdapter;
 use super::policy_store::PolicyStore;
+use super::policy_history::{PolicyChange, PolicyChangeType, PolicyDiff, PolicyHistory, PolicyVersion};
+use super::enforcement_suspension::{
+    EnforcementSuspension, ResumeReport, DEFAULT_SUSPEND_SECS, MAX_SUSPEND_SECS,
+};
+use super::policy_approval::{
+    ApprovalQueue, ChangeOutcome, PendingAction, PendingApproval, DEFAULT_APPROVAL_TIMEOUT_SECS,
+};
+use super::policy_retarget::{PolicyOrphan, PolicyRetarget, RetargetMode};
+use super::fingerprint_registry::{FingerprintRegistry, PolicyFingerprintSummary, ProtectedCopy};
 
 /// Main policy engine
 pub struct PolicyEngine {
     path_resolver: Arc<PathResolver>,
     kernel_adapter: Arc<parking_lot::RwLock<Option<KernelAdapter>>>,
     policy_store: Arc<PolicyStore>,
+    policy_history: Arc<PolicyHistory>,
+    approvals: Arc<ApprovalQueue>,
+    suspension: parking_lot::RwLock<Option<EnforcementSuspension>>, // Break-glass state
+    next_suspension_id: AtomicU64,
+    retarget_mode: RetargetMode,                                    // Rename/move semantics
+    fingerprints: Arc<FingerprintRegistry>,                         // Content hashes of protected files
+    simulated_rules: parking_lot::Mutex<HashSet<(u64, String)>>,     // Kernel rules while disconnected
 }
 
 impl PolicyEngine {
@@ -32,6 +52,7 @@ impl PolicyEngine {
         println!("🚀 PolicyEngine: Initializing...");
         
         // Create path resolver
+        let fingerprints = FingerprintRegistry::new(index.clone());
         let path_resolver = Arc::new(PathResolver::new(index));
         
         // Create kernel adapter (might fail if kernel not running)
@@ -54,6 +75,13 @@ impl PolicyEngine {
             path_resolver,
             kernel_adapter,
             policy_store,
+            policy_history: PolicyHistory::open(&PolicyHistory::default_history_file()),
+            approvals: ApprovalQueue::new(DEFAULT_APPROVAL_TIMEOUT_SECS),
+            suspension: parking_lot::RwLock::new(None),
+            next_suspension_id: AtomicU64::new(1),
+            retarget_mode: RetargetMode::from_env(),
+            fingerprints,
+            simulated_rules: parking_lot::Mutex::new(HashSet::new()),
         });
         
         println!("✅ PolicyEngine: Ready");
@@ -85,12 +113,19 @@ impl PolicyEngine {
         println!("🔄 Creating simulated PolicyEngine for demo");
         
         // Create a minimal index for simulation
-        let index = FilesystemIndex::new();
+        let index = Arc::new(FilesystemIndex::new());
         
         PolicyEngine {
-            path_resolver: Arc::new(PathResolver::new(Arc::new(index))),
+            fingerprints: FingerprintRegistry::new(index.clone()),
+            path_resolver: Arc::new(PathResolver::new(index)),
             kernel_adapter: Arc::new(parking_lot::RwLock::new(None)),
             policy_store: PolicyStore::new(),
+            policy_history: PolicyHistory::new(),
+            approvals: ApprovalQueue::new(DEFAULT_APPROVAL_TIMEOUT_SECS),
+            suspension: parking_lot::RwLock::new(None),
+            next_suspension_id: AtomicU64::new(1),
+            retarget_mode: RetargetMode::from_env(),
+            simulated_rules: parking_lot::Mutex::new(HashSet::new()),
         }
     }
         
@@ -102,40 +137,387 @@ impl PolicyEngine {
         // 1. Validate intent
         intent.validate()?;
         
-        // 2. Validate node exists and is accessible
-        self.path_resolver.validate_node(intent.node_id)?;
-        
-        // 3. Resolve node ID → NT path(s)
-        let nt_paths = self.path_resolver.resolve_policy_intent(&intent)?;
+        // 2-3. Validate node and resolve node ID → NT path(s)
+        let nt_paths = self.resolve_intent_paths(&intent)?;
         
         // 4. Get policy ID
-        let policy_id = {
-            let mut adapter = self.kernel_adapter.write();
-            if let Some(adapter) = adapter.as_mut() {
-                adapter.get_next_policy_id()
-            } else {
-                // Simulation mode - generate fake ID
-                99990000 + std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs() as u64
+        let policy_id = self.allocate_policy_id();
+        
+        // 5. Normalize to kernel policies
+        let kernel_policies = Self::build_kernel_policies(&intent, nt_paths, policy_id)?;
+        
+        // 6. Send to kernel (if connected)
+        let kernel_policy_ids = self.push_kernel_policies(policy_id, &kernel_policies)?;
+        
+        // 7. Record history + store in policy store
+        self.policy_history.record(PolicyChange {
+            policy_id,
+            change_type: PolicyChangeType::Created,
+            changed_by: intent.created_by.clone(),
+            old_intent: None,
+            new_intent: Some(intent.clone()),
+            kernel_policies: kernel_policies.clone(),
+            reverted_from: None,
+        });
+        self.path_resolver.index().pin_node(intent.node_id);
+        self.policy_store.add_policy(
+            policy_id,
+            intent,
+            kernel_policies,
+            kernel_policy_ids,
+        );
+        
+        println!("✅ PolicyEngine: Protection applied successfully (Policy ID: {})", policy_id);
+        Ok(policy_id)
+    }
+    
+    /// Update an existing policy in place (same policy ID, new version)
+    pub fn update_protection(
+        &self,
+        policy_id: u64,
+        intent: PolicyIntent,
+        changed_by: &str,
+        confirmed: bool,
+    ) -> Result<ChangeOutcome, String> {
+        println!("✏️ PolicyEngine: Updating protection (Policy ID: {})", policy_id);
+        println!("   {}", intent.describe());
+        
+        if self.policy_store.get_policy(policy_id).is_none() {
+            return Err(format!("Policy ID {} not found", policy_id));
+        }
+        
+        intent.validate()?;
+        if self.check_assurance(&intent, confirmed)? {
+            return Ok(self.request_approval(PendingAction::Update { policy_id }, intent, changed_by));
+        }
+        
+        let version = self.replace_protection(policy_id, intent, changed_by, PolicyChangeType::Updated, None)?;
+        Ok(ChangeOutcome::Applied { policy_id, version })
+    }
+    
+    /// Revert a policy to the state recorded in `version`
+    /// Reverting to a removal version removes the policy; reverting a removed
+    /// policy re-applies it under the same policy ID.
+    pub fn revert_protection(
+        &self,
+        policy_id: u64,
+        version: u32,
+        reverted_by: &str,
+        confirmed: bool,
+    ) -> Result<ChangeOutcome, String> {
+        println!("⏪ PolicyEngine: Reverting policy {} to version {}", policy_id, version);
+        
+        let target = self.policy_history.get_version(policy_id, version)
+            .ok_or_else(|| format!("Policy {} has no version {}", policy_id, version))?;
+        
+        if let Some(intent) = &target.new_intent {
+            intent.validate()?;
+            if self.check_assurance(intent, confirmed)? {
+                return Ok(self.request_approval(
+                    PendingAction::Revert { policy_id, version }, intent.clone(), reverted_by,
+                ));
+            }
+        }
+        
+        let new_version = self.restore_version(policy_id, version, target.new_intent, reverted_by)?;
+        Ok(ChangeOutcome::Applied { policy_id, version: new_version })
+    }
+    
+    /// Bring a policy to the state recorded in `version` (no assurance checks)
+    fn restore_version(
+        &self,
+        policy_id: u64,
+        version: u32,
+        target_intent: Option<PolicyIntent>,
+        reverted_by: &str,
+    ) -> Result<u32, String> {
+        let current = self.policy_store.get_policy(policy_id);
+        
+        let intent = match target_intent {
+            Some(intent) => intent,
+            None => {
+                // Target state is "removed"
+                let current = current
+                    .ok_or_else(|| format!("Policy {} is already removed", policy_id))?;
+                self.pull_kernel_policies(policy_id, &current.kernel_policies);
+                self.policy_store.remove_policy(policy_id);
+                self.path_resolver.index().unpin_node(current.intent.node_id);
+                
+                return Ok(self.policy_history.record(PolicyChange {
+                    policy_id,
+                    change_type: PolicyChangeType::Reverted,
+                    changed_by: reverted_by.to_string(),
+                    old_intent: Some(current.intent),
+                    new_intent: None,
+                    kernel_policies: Vec::new(),
+                    reverted_from: Some(version),
+                }));
             }
         };
         
-        // 5. Normalize to kernel policies
-        let kernel_policies = PolicyNormalizer::normalize(&intent, nt_paths, policy_id);
+        if current.is_some() {
+            return self.replace_protection(
+                policy_id, intent, reverted_by, PolicyChangeType::Reverted, Some(version),
+            );
+        }
+        
+        // Policy was removed - restore it under its original ID
+        let nt_paths = self.resolve_intent_paths(&intent)?;
+        let kernel_policies = Self::build_kernel_policies(&intent, nt_paths, policy_id)?;
+        let kernel_policy_ids = self.push_kernel_policies(policy_id, &kernel_policies)?;
+        
+        let new_version = self.policy_history.record(PolicyChange {
+            policy_id,
+            change_type: PolicyChangeType::Reverted,
+            changed_by: reverted_by.to_string(),
+            old_intent: None,
+            new_intent: Some(intent.clone()),
+            kernel_policies: kernel_policies.clone(),
+            reverted_from: Some(version),
+        });
+        self.path_resolver.index().pin_node(intent.node_id);
+        self.policy_store.add_policy(policy_id, intent, kernel_policies, kernel_policy_ids);
+        
+        println!("✅ PolicyEngine: Policy {} restored from version {}", policy_id, version);
+        Ok(new_version)
+    }
+    
+    /// Remove protection policy
+    pub fn remove_protection(&self, policy_id: u64, removed_by: &str) -> Result<(), String> {
+        println!("🗑️ PolicyEngine: Removing protection (Policy ID: {})", policy_id);
+        
+        // 1. Get policy from store
+        let policy = match self.policy_store.get_policy(policy_id) {
+            Some(policy) => policy,
+            None => return Err(format!("Policy ID {} not found", policy_id)),
+        };
+        
+        // 2. Remove from kernel (if connected)
+        self.pull_kernel_policies(policy_id, &policy.kernel_policies);
+        
+        // 3. Remove from store, keep the record in history
+        self.policy_store.remove_policy(policy_id);
+        self.path_resolver.index().unpin_node(policy.intent.node_id);
+        self.policy_history.record(PolicyChange {
+            policy_id,
+            change_type: PolicyChangeType::Removed,
+            changed_by: removed_by.to_string(),
+            old_intent: Some(policy.intent),
+            new_intent: None,
+            kernel_policies: Vec::new(),
+            reverted_from: None,
+        });
+        
+        println!("✅ PolicyEngine: Protection removed successfully");
+        Ok(())
+    }
+    
+    /// Get full version history of a policy (oldest first)
+    pub fn get_policy_history(&self, policy_id: u64) -> Vec<PolicyVersion> {
+        self.policy_history.get_history(policy_id)
+    }
+    
+    /// Structural diff between two versions of a policy
+    pub fn diff_policy_versions(&self, policy_id: u64, from_version: u32, to_version: u32) -> Result<PolicyDiff, String> {
+        self.policy_history.diff(policy_id, from_version, to_version)
+    }
+    
+    /// Swap the kernel rules of an active policy for ones built from `intent`
+    fn replace_protection(
+        &self,
+        policy_id: u64,
+        intent: PolicyIntent,
+        changed_by: &str,
+        change_type: PolicyChangeType,
+        reverted_from: Option<u32>,
+    ) -> Result<u32, String> {
+        let existing = self.policy_store.get_policy(policy_id)
+            .ok_or_else(|| format!("Policy ID {} not found", policy_id))?;
+        
+        let nt_paths = self.resolve_intent_paths(&intent)?;
+        let kernel_policies = Self::build_kernel_policies(&intent, nt_paths, policy_id)?;
+        
+        // Swap kernel rules; on failure put the old ones back
+        self.pull_kernel_policies(policy_id, &existing.kernel_policies);
+        let kernel_policy_ids = match self.push_kernel_policies(policy_id, &kernel_policies) {
+            Ok(ids) => ids,
+            Err(e) => {
+                println!("❌ PolicyEngine: Update failed, restoring previous kernel rules");
+                self.pull_kernel_policies(policy_id, &kernel_policies);
+                if let Err(restore_err) = self.push_kernel_policies(policy_id, &existing.kernel_policies) {
+                    println!("❌ PolicyEngine: Restore failed: {}", restore_err);
+                }
+                return Err(e);
+            }
+        };
+        
+        if existing.intent.node_id != intent.node_id {
+            self.path_resolver.index().unpin_node(existing.intent.node_id);
+            self.path_resolver.index().pin_node(intent.node_id);
+        }
+        
+        let version = self.policy_history.record(PolicyChange {
+            policy_id,
+            change_type,
+            changed_by: changed_by.to_string(),
+            old_intent: Some(existing.intent),
+            new_intent: Some(intent.clone()),
+            kernel_policies: kernel_policies.clone(),
+            reverted_from,
+        });
+        self.policy_store.replace_policy(policy_id, intent, kernel_policies, kernel_policy_ids);
+        
+        println!("✅ PolicyEngine: Policy {} now at version {}", policy_id, version);
+        Ok(version)
+    }
+    
+    /// Rename/move semantics for protected nodes
+    pub fn retarget_mode(&self) -> RetargetMode {
+        self.retarget_mode
+    }
+    
+    /// Re-resolve policies on a renamed/moved node and everything below it
+    /// Follow mode swaps the kernel rules to the new NT paths (new history
+    /// version); stay mode leaves the rules on the old paths. Policies whose
+    /// paths did not change are skipped.
+    pub fn retarget_policies(&self, renamed_node_id: u64) -> Vec<PolicyRetarget> {
+        let index = self.path_resolver.index();
+        let mut retargets = Vec::new();
+        
+        for (policy_id, node_id) in self.policy_store.get_policy_nodes() {
+            if !index.is_within(node_id, renamed_node_id) {
+                continue;
+            }
+            let policy = match self.policy_store.get_policy(policy_id) {
+                Some(policy) => policy,
+                None => continue,
+            };
+            
+            let nt_paths = match self.resolve_intent_paths(&policy.intent) {
+                Ok(nt_paths) => nt_paths,
+                Err(e) => {
+                    println!("⚠️  PolicyEngine: Cannot re-resolve policy {} after rename: {}", policy_id, e);
+                    retargets.push(PolicyRetarget {
+                        policy_id, node_id, mode: self.retarget_mode, followed: false, version: None, failed: true,
+                    });
+                    continue;
+                }
+            };
+            if !Self::paths_changed(&policy.kernel_policies, &nt_paths) {
+                continue;
+            }
+            
+            let retarget = match self.retarget_mode {
+                RetargetMode::StayOnPath => {
+                    println!("📌 PolicyEngine: Policy {} stays on its original path (node {} moved)", policy_id, node_id);
+                    PolicyRetarget { policy_id, node_id, mode: self.retarget_mode, followed: false, version: None, failed: false }
+                }
+                RetargetMode::FollowFile => match self.replace_protection(
+                    policy_id, policy.intent, "agent (rename tracking)", PolicyChangeType::Retargeted, None,
+                ) {
+                    Ok(version) => {
+                        println!("🎯 PolicyEngine: Policy {} followed node {} (version {})", policy_id, node_id, version);
+                        PolicyRetarget { policy_id, node_id, mode: self.retarget_mode, followed: true, version: Some(version), failed: false }
+                    }
+                    Err(e) => {
+                        println!("❌ PolicyEngine: Policy {} could not follow node {}: {}", policy_id, node_id, e);
+                        PolicyRetarget { policy_id, node_id, mode: self.retarget_mode, followed: false, version: None, failed: true }
+                    }
+                },
+            };
+            retargets.push(retarget);
+        }
+        
+        retargets
+    }
+    
+    /// Re-resolve one policy and move its kernel rules when its NT paths
+    /// changed (e.g. a drive came back under another device name)
+    /// Returns the new history version, None when the paths are unchanged.
+    pub fn repoint_policy(&self, policy_id: u64, changed_by: &str) -> Result<Option<u32>, String> {
+        let policy = self.policy_store.get_policy(policy_id)
+            .ok_or_else(|| format!("Policy ID {} not found", policy_id))?;
+        let nt_paths = self.resolve_intent_paths(&policy.intent)?;
+        if !Self::paths_changed(&policy.kernel_policies, &nt_paths) {
+            return Ok(None);
+        }
+        self.replace_protection(policy_id, policy.intent, changed_by, PolicyChangeType::Retargeted, None)
+            .map(Some)
+    }
+    
+    fn paths_changed(kernel_policies: &[KernelPolicy], nt_paths: &[String]) -> bool {
+        nt_paths.len() != kernel_policies.len()
+            || !kernel_policies.iter().all(|kp| nt_paths.contains(&kp.nt_path))
+    }
+    
+    /// Flag policies whose protected node left the index (deleted or moved
+    /// out of view). Their rules stay on the old path; each policy is reported once.
+    pub fn orphan_missing_policies(&self) -> Vec<PolicyOrphan> {
+        let index = self.path_resolver.index();
+        self.policy_store.get_policy_nodes()
+            .into_iter()
+            .filter(|&(_, node_id)| index.get_node(node_id).is_none())
+            .filter(|&(policy_id, _)| self.policy_store.mark_orphaned(policy_id))
+            .map(|(policy_id, node_id)| {
+                println!("⚠️  PolicyEngine: Policy {} orphaned, node {} was deleted or moved out of view", policy_id, node_id);
+                PolicyOrphan { policy_id, node_id }
+            })
+            .collect()
+    }
+    
+    /// Validate node and resolve intent → NT path(s)
+    fn resolve_intent_paths(&self, intent: &PolicyIntent) -> Result<Vec<String>, String> {
+        self.path_resolver.validate_node(intent.node_id)?;
+        self.path_resolver.resolve_policy_intent(intent)
+    }
+    
+    /// Allocate a new policy ID
+    fn allocate_policy_id(&self) -> u64 {
+        let mut adapter = self.kernel_adapter.write();
+        let mut policy_id = if let Some(adapter) = adapter.as_mut() {
+            adapter.get_next_policy_id()
+        } else {
+            // Simulation mode - generate fake ID
+            99990000 + std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+        // IDs restart with the agent; never mix a new policy into an old one's history
+        while self.policy_history.contains(policy_id) {
+            policy_id = match adapter.as_mut() {
+                Some(adapter) => adapter.get_next_policy_id(),
+                None => policy_id + 1,
+            };
+        }
+        policy_id
+    }
+    
+    /// Normalize intent into validated kernel policies
+    fn build_kernel_policies(intent: &PolicyIntent, nt_paths: Vec<String>, policy_id: u64) -> Result<Vec<KernelPolicy>, String> {
+        let kernel_policies = PolicyNormalizer::normalize(intent, nt_paths, policy_id);
         
         // Validate each kernel policy
         for policy in &kernel_policies {
             PolicyNormalizer::validate(policy)?;
         }
         
-        // 6. Send to kernel (if connected)
+        Ok(kernel_policies)
+    }
+    
+    /// Send kernel policies to the minifilter (simulated IDs when disconnected)
+    fn push_kernel_policies(&self, policy_id: u64, kernel_policies: &[KernelPolicy]) -> Result<Vec<u64>, String> {
         let mut kernel_policy_ids = Vec::new();
         let mut adapter = self.kernel_adapter.write();
         
+        // Break glass: keep the store up to date, resume pushes the rules later
+        if self.is_enforcement_suspended() {
+            println!("🚨 Enforcement suspended - not sending to kernel");
+            return Ok(kernel_policies.iter().map(|policy| policy.policy_id).collect());
+        }
+        
         if let Some(adapter) = adapter.as_mut() {
-            for policy in &kernel_policies {
+            for policy in kernel_policies {
                 match adapter.send_policy(policy) {
                     Ok(id) => kernel_policy_ids.push(id),
                     Err(e) => {
@@ -148,38 +530,27 @@ impl PolicyEngine {
         } else {
             println!("⚠️  Running in simulation mode - not sending to kernel");
             // Generate fake kernel IDs for simulation
-            for (i, _) in kernel_policies.iter().enumerate() {
+            let mut simulated = self.simulated_rules.lock();
+            for (i, policy) in kernel_policies.iter().enumerate() {
+                simulated.insert((policy.policy_id, policy.nt_path.clone()));
                 kernel_policy_ids.push(policy_id + i as u64);
             }
         }
         
-        // 7. Store in policy store
-        self.policy_store.add_policy(
-            policy_id,
-            intent,
-            kernel_policies,
-            kernel_policy_ids,
-        );
-        
-        println!("✅ PolicyEngine: Protection applied successfully (Policy ID: {})", policy_id);
-        Ok(policy_id)
+        Ok(kernel_policy_ids)
     }
     
-    /// Remove protection policy
-    pub fn remove_protection(&self, policy_id: u64) -> Result<(), String> {
-        println!("🗑️ PolicyEngine: Removing protection (Policy ID: {})", policy_id);
-        
-        // 1. Get policy from store
-        let policy = match self.policy_store.get_policy(policy_id) {
-            Some(policy) => policy,
-            None => return Err(format!("Policy ID {} not found", policy_id)),
-        };
-        
-        // 2. Remove from kernel (if connected)
+    /// Remove kernel policies from the minifilter (best effort)
+    fn pull_kernel_policies(&self, policy_id: u64, kernel_policies: &[KernelPolicy]) {
         let mut adapter = self.kernel_adapter.write();
         
+        // Break glass: rules were already cleared from the kernel
+        if self.is_enforcement_suspended() {
+            return;
+        }
+        
         if let Some(adapter) = adapter.as_mut() {
-            for kernel_policy in &policy.kernel_policies {
+            for kernel_policy in kernel_policies {
                 if let Err(e) = adapter.remove_policy(policy_id, &kernel_policy.nt_path) {
                     println!("⚠️  Failed to remove from kernel: {}", e);
                     // Continue trying other paths
@@ -187,13 +558,162 @@ impl PolicyEngine {
             }
         } else {
             println!("⚠️  Running in simulation mode - not removing from kernel");
+            let mut simulated = self.simulated_rules.lock();
+            for kernel_policy in kernel_policies {
+                simulated.remove(&(kernel_policy.policy_id, kernel_policy.nt_path.clone()));
+            }
+        }
+    }
+    
+    /// Break glass: clear every kernel rule, keep the policy store intact
+    pub fn suspend_enforcement(
+        &self,
+        suspended_by: &str,
+        reason: &str,
+        duration_secs: Option<u64>,
+    ) -> Result<EnforcementSuspension, String> {
+        if reason.trim().is_empty() {
+            return Err("A reason is required to suspend enforcement".to_string());
+        }
+        let duration_secs = duration_secs.unwrap_or(DEFAULT_SUSPEND_SECS);
+        if duration_secs == 0 || duration_secs > MAX_SUSPEND_SECS {
+            return Err(format!("Suspension timeout must be between 1 and {} seconds", MAX_SUSPEND_SECS));
         }
         
-        // 3. Remove from store
-        self.policy_store.remove_policy(policy_id);
+        println!("🚨 PolicyEngine: BREAK GLASS - suspending enforcement ({} by {})", reason, suspended_by);
         
-        println!("✅ PolicyEngine: Protection removed successfully");
-        Ok(())
+        // Lock order (adapter → suspension) matches push/pull, so no rule slips in
+        let mut adapter = self.kernel_adapter.write();
+        let mut suspension = self.suspension.write();
+        if let Some(active) = suspension.as_ref() {
+            return Err(format!("Enforcement already suspended by {} (suspension {})",
+                active.suspended_by, active.suspension_id));
+        }
+        
+        let policies = self.policy_store.get_all_policies();
+        let mut cleared = 0;
+        let mut failed = 0;
+        for policy in &policies {
+            for kernel_policy in &policy.kernel_policies {
+                match adapter.as_mut() {
+                    Some(adapter) => match adapter.remove_policy(kernel_policy.policy_id, &kernel_policy.nt_path) {
+                        Ok(()) => cleared += 1,
+                        Err(e) => {
+                            println!("   ⚠️  Failed to clear kernel rule {}: {}", kernel_policy.policy_id, e);
+                            failed += 1;
+                        }
+                    },
+                    None => {
+                        // Simulation mode
+                        self.simulated_rules.lock().remove(&(kernel_policy.policy_id, kernel_policy.nt_path.clone()));
+                        cleared += 1;
+                    }
+                }
+            }
+        }
+        
+        let now = Self::now_secs();
+        let active = EnforcementSuspension {
+            suspension_id: self.next_suspension_id.fetch_add(1, Ordering::SeqCst),
+            suspended_by: suspended_by.to_string(),
+            reason: reason.trim().to_string(),
+            suspended_at: now,
+            resume_at: now + duration_secs,
+            policies_suspended: policies.len(),
+            kernel_rules_cleared: cleared,
+            kernel_rules_failed: failed,
+        };
+        *suspension = Some(active.clone());
+        
+        println!("🚨 PolicyEngine: {} policies suspended ({} kernel rules cleared, {} failed), auto-resume in {}s",
+            policies.len(), cleared, failed, duration_secs);
+        Ok(active)
+    }
+    
+    /// Resume enforcement: re-push every stored policy to the kernel
+    pub fn resume_enforcement(&self, resumed_by: &str) -> Result<ResumeReport, String> {
+        self.resume_enforcement_inner(resumed_by, None)
+            .ok_or_else(|| "Enforcement is not suspended".to_string())
+    }
+    
+    /// Timer-driven resume; no-op if `suspension_id` already ended
+    pub fn auto_resume_enforcement(&self, suspension_id: u64) -> Option<ResumeReport> {
+        self.resume_enforcement_inner("auto-resume", Some(suspension_id))
+    }
+    
+    /// Current break-glass suspension, if any
+    pub fn enforcement_suspension(&self) -> Option<EnforcementSuspension> {
+        self.suspension.read().clone()
+    }
+    
+    /// Check if enforcement is suspended (break glass)
+    pub fn is_enforcement_suspended(&self) -> bool {
+        self.suspension.read().is_some()
+    }
+    
+    fn resume_enforcement_inner(&self, resumed_by: &str, only_suspension: Option<u64>) -> Option<ResumeReport> {
+        let mut adapter = self.kernel_adapter.write();
+        let mut suspension = self.suspension.write();
+        
+        if let (Some(expected), Some(active)) = (only_suspension, suspension.as_ref()) {
+            if active.suspension_id != expected {
+                return None;
+            }
+        }
+        let active = suspension.take()?;
+        
+        println!("🔁 PolicyEngine: Resuming enforcement (suspension {} by {})", active.suspension_id, resumed_by);
+        
+        let policies = self.policy_store.get_all_policies();
+        let mut pushed = 0;
+        let mut failed_policy_ids = Vec::new();
+        for policy in &policies {
+            let mut policy_failed = false;
+            for kernel_policy in &policy.kernel_policies {
+                match adapter.as_mut() {
+                    Some(adapter) => match adapter.send_policy(kernel_policy) {
+                        Ok(_) => pushed += 1,
+                        Err(e) => {
+                            println!("   ❌ Failed to restore kernel rule {}: {}", kernel_policy.policy_id, e);
+                            policy_failed = true;
+                        }
+                    },
+                    None => {
+                        // Simulation mode
+                        self.simulated_rules.lock().insert((kernel_policy.policy_id, kernel_policy.nt_path.clone()));
+                        pushed += 1;
+                    }
+                }
+            }
+            if policy_failed {
+                if let Some(first) = policy.kernel_policies.first() {
+                    failed_policy_ids.push(first.policy_id);
+                }
+            }
+        }
+        
+        let now = Self::now_secs();
+        let report = ResumeReport {
+            suspension_id: active.suspension_id,
+            resumed_by: resumed_by.to_string(),
+            automatic: only_suspension.is_some(),
+            resumed_at: now,
+            suspended_for_secs: now.saturating_sub(active.suspended_at),
+            policies_restored: policies.len() - failed_policy_ids.len(),
+            kernel_rules_pushed: pushed,
+            failed_policy_ids,
+        };
+        
+        println!("✅ PolicyEngine: Enforcement resumed ({} policies, {} kernel rules, {} failed)",
+            report.policies_restored, report.kernel_rules_pushed, report.failed_policy_ids.len());
+        Some(report)
+    }
+    
+    fn now_secs() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
     }
     
     /// Get all active policies
@@ -206,6 +726,31 @@ impl PolicyEngine {
         self.policy_store.get_policies_for_node(node_id)
     }
     
+    /// Attribute a kernel-reported NT path to a node and its covering policy (INTERNAL)
+    /// Falls back to the deepest loaded ancestor when the path itself is not indexed.
+    pub fn attribute_nt_path(&self, nt_path: &str) -> Option<NtPathAttribution> {
+        let index = self.path_resolver.index();
+        let node_id = index.find_covering_node(nt_path)?;
+        let exact = index.get_id_by_nt_path(nt_path) == Some(node_id);
+        
+        // Walk up: a policy covers the path when one of its kernel rules matches
+        // it the way the minifilter does (any spelling of the path)
+        let mut current = Some(node_id);
+        while let Some(candidate) = current {
+            for policy_id in self.policy_store.get_policy_ids_for_node(candidate) {
+                let covers = self.policy_store.get_policy(policy_id).is_some_and(|policy| {
+                    policy.is_active && policy.kernel_policies.iter().any(|kp| kp.matches_nt_path(nt_path))
+                });
+                if covers {
+                    return Some(NtPathAttribution { node_id, exact, policy_id: Some(policy_id) });
+                }
+            }
+            current = index.get_node(candidate).and_then(|node| node.parent_id);
+        }
+        
+        Some(NtPathAttribution { node_id, exact, policy_id: None })
+    }
+    
     /// Get policy engine statistics
     pub fn get_stats(&self) -> PolicyEngineStats {
         let store_stats = self.policy_store.get_stats();
@@ -223,6 +768,26 @@ impl PolicyEngine {
         }
     }
     
+    /// Files among `node_ids` that carry content of a protected file
+    /// New policies are fingerprinted first, and files among `node_ids` inside
+    /// a protected node are taken in as sources rather than reported.
+    pub fn find_protected_copies(&self, node_ids: &[u64]) -> Vec<ProtectedCopy> {
+        self.fingerprints.sync_policies(&self.policy_store.get_policy_nodes());
+        self.fingerprints.update_sources(node_ids);
+        let copies = self.fingerprints.find_copies(node_ids);
+        for copy in &copies {
+            println!("🧬 Node {} carries content protected by policy {} ({:?}, {}%)",
+                copy.node_id, copy.policy_id, copy.match_kind, copy.similarity);
+        }
+        copies
+    }
+    
+    /// Fingerprint registry state per policy (new policies are fingerprinted first)
+    pub fn fingerprint_summary(&self) -> (bool, Vec<PolicyFingerprintSummary>) {
+        self.fingerprints.sync_policies(&self.policy_store.get_policy_nodes());
+        (self.fingerprints.chunking(), self.fingerprints.summary())
+    }
+    
     /// Get path resolver (for internal use)
     pub fn path_resolver(&self) -> &Arc<PathResolver> {
         &self.path_resolver
@@ -257,6 +822,23 @@ impl PolicyEngine {
         evaluator.evaluate(intent)
     }
 
+    /// Export active policies as a signed, portable bundle (STEP 8.1)
+    pub fn export_policy_bundle(&self, exported_by: &str) -> Result<PolicyBundle, String> {
+        PolicyBundleService::export(self, exported_by)
+    }
+
+    /// Import a policy bundle, re-resolving display paths on this machine (STEP 8.1)
+    pub fn import_policy_bundle(
+        &self,
+        scanner: &FileSystemScanner,
+        bundle: &PolicyBundle,
+        imported_by: &str,
+        dry_run: bool,
+        confirmed: bool,
+    ) -> Result<ImportReport, String> {
+        PolicyBundleService::import(self, scanner, bundle, imported_by, dry_run, confirmed)
+    }
+
      /// Validate policy safety (STEP 7.4)
     pub fn validate_policy_safety(&self, intent: &PolicyIntent) -> SafetyValidation {
         let kernel_connected = self.is_kernel_connected();
@@ -270,14 +852,79 @@ impl PolicyEngine {
     }
 
       /// Apply protection with assurance checks (enhanced version)
-    pub fn apply_protection_with_assurance(&self, intent: PolicyIntent, confirmed: bool) -> Result<u64, String> {
+    pub fn apply_protection_with_assurance(&self, intent: PolicyIntent, confirmed: bool) -> Result<ChangeOutcome, String> {
         println!("🛡️ PolicyEngine: Applying protection with assurance checks");
         
         // Step 1: Basic validation
         intent.validate()?;
         
+        // Steps 2-4: Safety validation, confirmation, warnings
+        if self.check_assurance(&intent, confirmed)? {
+            let requested_by = intent.created_by.clone();
+            return Ok(self.request_approval(PendingAction::Apply, intent, &requested_by));
+        }
+        
+        // Step 5: Apply protection (original method)
+        let policy_id = self.apply_protection(intent)?;
+        Ok(ChangeOutcome::Applied { policy_id, version: 1 })
+    }
+    
+    /// Open a two-person approval request for a high-risk change
+    fn request_approval(&self, action: PendingAction, intent: PolicyIntent, requested_by: &str) -> ChangeOutcome {
+        let message = PolicyGuard::get_confirmation_message(&intent);
+        println!("⏳ PolicyEngine: High-risk change needs a second admin");
+        ChangeOutcome::PendingApproval(Box::new(self.approvals.submit(action, intent, requested_by, message)))
+    }
+    
+    /// Approve a pending high-risk change and apply it
+    pub fn approve_pending(
+        &self,
+        request_id: u64,
+        approved_by: &str,
+        confirmation: Option<&str>,
+    ) -> Result<ChangeOutcome, String> {
+        println!("✅ PolicyEngine: {} approving request {}", approved_by, request_id);
+        
+        // Check the typed phrase before claiming, so a typo leaves the request open
+        let request = self.approvals.get(request_id)
+            .ok_or_else(|| format!("Approval request {} not found", request_id))?;
+        PolicyGuard::check_confirmation_phrase(&request.intent, confirmation)?;
+        
+        let request = self.approvals.claim(request_id, approved_by)?;
+        let changed_by = format!("{} (approved by {})", request.requested_by, approved_by);
+        
+        let result = match request.action {
+            PendingAction::Apply => self.apply_protection(request.intent.clone())
+                .map(|policy_id| (policy_id, 1)),
+            PendingAction::Update { policy_id } => self.replace_protection(
+                policy_id, request.intent.clone(), &changed_by, PolicyChangeType::Updated, None,
+            ).map(|version| (policy_id, version)),
+            PendingAction::Revert { policy_id, version } => self.restore_version(
+                policy_id, version, Some(request.intent.clone()), &changed_by,
+            ).map(|new_version| (policy_id, new_version)),
+        };
+        
+        let completed = result.as_ref().map(|(policy_id, _)| *policy_id).map_err(String::clone);
+        self.approvals.complete(request_id, &completed);
+        let (policy_id, version) = result?;
+        Ok(ChangeOutcome::Applied { policy_id, version })
+    }
+    
+    /// Reject a pending high-risk change
+    pub fn reject_pending(&self, request_id: u64, rejected_by: &str, reason: &str) -> Result<PendingApproval, String> {
+        self.approvals.reject(request_id, rejected_by, reason)
+    }
+    
+    /// List approval requests (newest first)
+    pub fn list_pending_approvals(&self) -> Vec<PendingApproval> {
+        self.approvals.list()
+    }
+    
+    /// Safety validation + confirmation check shared by apply/update/revert
+    /// Returns true when the change also needs a second admin's approval.
+    fn check_assurance(&self, intent: &PolicyIntent, confirmed: bool) -> Result<bool, String> {
         // Step 2: Safety validation
-        let safety = self.validate_policy_safety(&intent);
+        let safety = self.validate_policy_safety(intent);
         
         if !safety.is_valid {
             return Err(format!("Policy failed safety validation: {:?}", safety.errors));
@@ -296,8 +943,7 @@ impl PolicyEngine {
             }
         }
         
-        // Step 5: Apply protection (original method)
-        self.apply_protection(intent)
+        Ok(safety.requires_confirmation)
     }
 
       /// Get enforcement statistics
@@ -307,7 +953,7 @@ impl PolicyEngine {
         
         EnforcementStats {
             total_policies: stats.total_policies,
-            real_enforcement: if self.is_kernel_connected() { stats.active_policies } else { 0 },
+            real_enforcement: if self.is_kernel_connected() && !self.is_enforcement_suspended() { stats.active_policies } else { 0 },
             simulated: if !self.is_kernel_connected() { stats.active_policies } else { 0 },
             healthy: stats.active_policies, // Simplified for now
             warning: 0,
@@ -324,6 +970,11 @@ impl PolicyEngine {
                 
                 if !is_active {
                     Some((HealthStatus::Failed, "Policy is inactive".to_string()))
+                } else if let Some(suspension) = self.enforcement_suspension() {
+                    Some((HealthStatus::Warning, format!(
+                        "Enforcement suspended by {} until {} ({})",
+                        suspension.suspended_by, suspension.resume_at, suspension.reason
+                    )))
                 } else if !kernel_connected {
                     Some((HealthStatus::Warning, "Running in simulation mode".to_string()))
                 } else {
@@ -338,6 +989,14 @@ impl PolicyEngine {
 
 }
 
+/// Node and policy a kernel NT path belongs to
+#[derive(Debug, Clone, Copy)]
+pub struct NtPathAttribution {
+    pub node_id: u64,             // Exact node, or deepest loaded ancestor
+    pub exact: bool,
+    pub policy_id: Option<u64>,   // Nearest active policy covering the path
+}
+
 /// Policy engine statistics
 #[derive(Debug, Clone)]
 pub struct PolicyEngineStats {
@@ -345,4 +1004,211 @@ pub struct PolicyEngineStats {
     pub active_policies: usize,
     pub protected_nodes: usize,
     pub kernel_connected: bool,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_index::{DriveKind, EntryType, FileSystemNode};
+
+    // UNC roots resolve without the volume map
+    fn add_share(engine: &PolicyEngine, share: &str) -> u64 {
+        engine.path_resolver().index().add_drive(
+            &format!("\\\\fs01\\{}", share),
+            share,
+            &format!("\\Device\\Mup\\fs01\\{}\\", share),
+            DriveKind::Network,
+            None,
+        )
+    }
+
+    fn add_child(engine: &PolicyEngine, parent_id: u64, name: &str, entry_type: EntryType) -> u64 {
+        let index = engine.path_resolver().index();
+        let parent = index.get_node(parent_id).unwrap();
+        let display_path = format!("{}\\{}", parent.display_path.trim_end_matches('\\'), name);
+        index.add_node(FileSystemNode {
+            id: index.id_for_path(&display_path),
+            name: name.to_string(),
+            entry_type,
+            parent_id: Some(parent_id),
+            children_ids: Vec::new(),
+            nt_path: format!("{}\\{}", parent.nt_path.trim_end_matches('\\'), name),
+            display_path,
+            size: None,
+            modified_time: 0,
+            created_time: 0,
+            attributes: 0,
+            is_expanded: false,
+            is_accessible: true,
+            link_target: None,
+            classification: None,
+            content_type: None,
+            drive_kind: None,
+            device: None,
+        })
+    }
+
+    fn protect_node(engine: &PolicyEngine, node_id: u64, scope: ProtectionScope) -> u64 {
+        let operations = ProtectionOperations { create: scope != ProtectionScope::File, ..ProtectionOperations::default() };
+        let intent = PolicyIntent::new(node_id, scope, ProtectionAction::Block, operations, "alice", None);
+        engine.apply_protection(intent).unwrap()
+    }
+
+    fn protect(engine: &PolicyEngine, share: &str) -> u64 {
+        protect_node(engine, add_share(engine, share), ProtectionScope::FolderRecursive)
+    }
+
+    fn kernel_rules(engine: &PolicyEngine) -> usize {
+        engine.simulated_rules.lock().len()
+    }
+
+    fn kernel_paths(engine: &PolicyEngine) -> Vec<String> {
+        engine.simulated_rules.lock().iter().map(|(_, nt_path)| nt_path.clone()).collect()
+    }
+
+    /// Share with Finance\report.xlsx protected (file scope)
+    fn protected_report(engine: &PolicyEngine) -> (u64, u64, u64) {
+        let share = add_share(engine, "finance");
+        let folder = add_child(engine, share, "Finance", EntryType::Directory);
+        let report = add_child(engine, folder, "report.xlsx", EntryType::File);
+        let policy_id = protect_node(engine, report, ProtectionScope::File);
+        (folder, report, policy_id)
+    }
+
+    const REPORT_NT: &str = "\\Device\\Mup\\fs01\\finance\\Finance\\report.xlsx";
+
+    #[test]
+    fn suspend_clears_the_rules_and_refuses_a_second_suspend() {
+        let engine = PolicyEngine::new_simulated();
+        protect(&engine, "finance");
+        protect(&engine, "legal");
+        assert_eq!(kernel_rules(&engine), 2);
+
+        let active = engine.suspend_enforcement("alice", "outage", Some(60)).unwrap();
+        assert_eq!(active.policies_suspended, 2);
+        assert_eq!(active.kernel_rules_cleared, 2);
+        assert_eq!(kernel_rules(&engine), 0);
+        assert_eq!(engine.get_active_policies().len(), 2);
+
+        let err = engine.suspend_enforcement("bob", "again", Some(60)).unwrap_err();
+        assert!(err.contains("already suspended"));
+        assert_eq!(engine.enforcement_suspension().unwrap().suspended_by, "alice");
+    }
+
+    #[test]
+    fn kernel_pushes_and_pulls_are_skipped_while_suspended() {
+        let engine = PolicyEngine::new_simulated();
+        let removed = protect(&engine, "finance");
+        engine.suspend_enforcement("alice", "outage", Some(60)).unwrap();
+
+        // Changes land in the store but never reach the kernel
+        let added = protect(&engine, "legal");
+        assert!(engine.get_policy_by_id(added).is_some());
+        assert_eq!(kernel_rules(&engine), 0);
+
+        engine.remove_protection(removed, "alice").unwrap();
+        assert!(engine.get_policy_by_id(removed).is_none());
+        assert_eq!(kernel_rules(&engine), 0);
+    }
+
+    #[test]
+    fn resume_pushes_every_stored_policy() {
+        let engine = PolicyEngine::new_simulated();
+        protect(&engine, "finance");
+        engine.suspend_enforcement("alice", "outage", Some(60)).unwrap();
+        protect(&engine, "legal");
+
+        let report = engine.resume_enforcement("bob").unwrap();
+        assert_eq!(report.resumed_by, "bob");
+        assert!(!report.automatic);
+        assert_eq!(report.policies_restored, 2);
+        assert_eq!(report.kernel_rules_pushed, 2);
+        assert!(report.failed_policy_ids.is_empty());
+        assert_eq!(kernel_rules(&engine), 2);
+        assert!(!engine.is_enforcement_suspended());
+        assert!(engine.resume_enforcement("bob").is_err());
+    }
+
+    #[test]
+    fn auto_resume_ignores_a_stale_suspension() {
+        let engine = PolicyEngine::new_simulated();
+        protect(&engine, "finance");
+
+        let first = engine.suspend_enforcement("alice", "outage", Some(60)).unwrap();
+        engine.resume_enforcement("alice").unwrap();
+        let second = engine.suspend_enforcement("alice", "outage", Some(60)).unwrap();
+
+        // The first suspension's timer fires late
+        assert!(engine.auto_resume_enforcement(first.suspension_id).is_none());
+        assert!(engine.is_enforcement_suspended());
+        assert_eq!(kernel_rules(&engine), 0);
+
+        let report = engine.auto_resume_enforcement(second.suspension_id).unwrap();
+        assert!(report.automatic);
+        assert_eq!(kernel_rules(&engine), 1);
+    }
+
+    #[test]
+    fn follow_file_moves_the_kernel_rules_to_the_new_path() {
+        let mut engine = PolicyEngine::new_simulated();
+        engine.retarget_mode = RetargetMode::FollowFile;
+        let (folder, report, policy_id) = protected_report(&engine);
+        assert_eq!(kernel_paths(&engine), vec![REPORT_NT]);
+
+        // Renaming the parent folder moves the protected file with it
+        let index = engine.path_resolver().index();
+        let share = index.get_node(folder).unwrap().parent_id.unwrap();
+        index.rename_node(folder, share, "Accounts").unwrap();
+        let retargets = engine.retarget_policies(folder);
+        assert_eq!(retargets.len(), 1);
+        assert_eq!((retargets[0].policy_id, retargets[0].node_id), (policy_id, report));
+        assert!(retargets[0].followed && !retargets[0].failed);
+        assert_eq!(retargets[0].version, Some(2));
+        assert_eq!(kernel_paths(&engine), vec!["\\Device\\Mup\\fs01\\finance\\Accounts\\report.xlsx"]);
+
+        let versions = engine.get_policy_history(policy_id);
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[1].change_type, PolicyChangeType::Retargeted);
+        assert_eq!(versions[1].kernel_policies[0].nt_path, "\\Device\\Mup\\fs01\\finance\\Accounts\\report.xlsx");
+
+        // Nothing moved since: a second pass is a no-op
+        assert!(engine.retarget_policies(folder).is_empty());
+    }
+
+    #[test]
+    fn stay_on_path_leaves_the_kernel_rules_alone() {
+        let mut engine = PolicyEngine::new_simulated();
+        engine.retarget_mode = RetargetMode::StayOnPath;
+        let (folder, report, policy_id) = protected_report(&engine);
+
+        engine.path_resolver().index().rename_node(report, folder, "final.xlsx").unwrap();
+        let retargets = engine.retarget_policies(report);
+        assert_eq!(retargets.len(), 1);
+        assert_eq!(retargets[0].mode, RetargetMode::StayOnPath);
+        assert!(!retargets[0].followed && !retargets[0].failed);
+        assert_eq!(retargets[0].version, None);
+
+        assert_eq!(kernel_paths(&engine), vec![REPORT_NT]);
+        assert_eq!(engine.get_policy_by_id(policy_id).unwrap().kernel_policies[0].nt_path, REPORT_NT);
+        assert_eq!(engine.get_policy_history(policy_id).len(), 1);
+    }
+
+    #[test]
+    fn deleted_protected_node_is_orphaned_once() {
+        let engine = PolicyEngine::new_simulated();
+        let (_, report, policy_id) = protected_report(&engine);
+        let kept = protect(&engine, "legal");
+        assert!(engine.orphan_missing_policies().is_empty());
+
+        engine.path_resolver().index().remove_subtree(report);
+        let orphans = engine.orphan_missing_policies();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!((orphans[0].policy_id, orphans[0].node_id), (policy_id, report));
+        assert!(engine.orphan_missing_policies().is_empty());
+
+        // The rule keeps guarding the old path, the other policy is untouched
+        assert!(engine.get_policy_by_id(policy_id).is_some());
+        assert!(engine.get_policy_by_id(kept).is_some());
+        assert!(kernel_paths(&engine).contains(&REPORT_NT.to_string()));
+        assert_eq!(kernel_rules(&engine), 2);
+    }
+}
@@ -141,6 +141,19 @@ impl PolicyGuard {
         (matches!(intent.scope, ProtectionScope::FolderRecursive) && intent.operations.read)
     }
     
+    /// Phrase the approving admin must type for high-risk policies
+    pub fn confirmation_phrase(intent: &PolicyIntent) -> Option<&'static str> {
+        if !(intent.operations.read && intent.action == ProtectionAction::Block) {
+            return None;
+        }
+        
+        if matches!(intent.scope, ProtectionScope::FolderRecursive) {
+            Some("CONFIRM_RECURSIVE_BLOCK_ALL")
+        } else {
+            Some("CONFIRM_BLOCK_ALL")
+        }
+    }
+    
     /// Get confirmation message for high-risk policies
     pub fn get_confirmation_message(intent: &PolicyIntent) -> Option<String> {
         if !Self::requires_confirmation(intent) {
@@ -167,4 +180,13 @@ impl PolicyGuard {
             None
         }
     }
+    
+    /// Check the phrase typed by the approving admin
+    pub fn check_confirmation_phrase(intent: &PolicyIntent, typed: Option<&str>) -> Result<(), String> {
+        match Self::confirmation_phrase(intent) {
+            None => Ok(()),
+            Some(expected) if typed.map(str::trim) == Some(expected) => Ok(()),
+            Some(expected) => Err(format!("Type '{}' to approve this policy", expected)),
+        }
+    }
 }
\ No newline at end of file
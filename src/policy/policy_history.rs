@@ -0,0 +1,442 @@
+//! Policy History (STEP 8.2)
+//! Core Principle: Every policy version is kept, nothing is ever overwritten
+//! History is append-only: updates, removals and reverts add new versions.
+//! Versions are kept in a JSON-lines file so they survive agent restarts.
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Arc;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use super::kernel_policy::{KernelPolicy, PathMatchType};
+use super::policy_intent::PolicyIntent;
+
+/// Environment variable overriding the history file location
+pub const POLICY_HISTORY_FILE_ENV: &str = "AGENT_POLICY_HISTORY";
+
+/// Default history file (JSON lines, one version per line)
+const DEFAULT_POLICY_HISTORY_FILE: &str = "dlp_policy_history.jsonl";
+
+/// What kind of change produced a version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyChangeType {
+    Created,
+    Updated,
+    Removed,
+    Reverted,
+    Retargeted,   // Kernel rules followed a renamed/moved node
+}
+
+/// A change to append to a policy's history
+#[derive(Debug, Clone)]
+pub struct PolicyChange {
+    pub policy_id: u64,
+    pub change_type: PolicyChangeType,
+    pub changed_by: String,
+    pub old_intent: Option<PolicyIntent>,
+    pub new_intent: Option<PolicyIntent>,      // None once removed
+    pub kernel_policies: Vec<KernelPolicy>,    // Resulting kernel rules (INTERNAL)
+    pub reverted_from: Option<u32>,            // Source version for reverts
+}
+
+/// One immutable policy version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyVersion {
+    pub policy_id: u64,
+    pub version: u32,
+    pub change_type: PolicyChangeType,
+    pub changed_by: String,
+    pub changed_at: u64,
+    pub old_intent: Option<PolicyIntent>,
+    pub new_intent: Option<PolicyIntent>,      // None once removed
+    pub kernel_policies: Vec<KernelPolicy>,    // Resulting kernel rules (INTERNAL)
+    pub reverted_from: Option<u32>,            // Source version for reverts
+}
+
+/// Admin-safe view of a kernel rule (no NT path)
+#[derive(Debug, Clone, Serialize)]
+pub struct KernelRuleSummary {
+    pub match_type: String,
+    pub block_all: bool,
+    pub write: bool,
+    pub delete: bool,
+    pub rename: bool,
+    pub create: bool,
+}
+
+/// Admin-safe view of a version
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyVersionInfo {
+    pub policy_id: u64,
+    pub version: u32,
+    pub change_type: PolicyChangeType,
+    pub changed_by: String,
+    pub changed_at: u64,
+    pub old_intent: Option<PolicyIntent>,
+    pub new_intent: Option<PolicyIntent>,
+    pub kernel_rules: Vec<KernelRuleSummary>,
+    pub reverted_from: Option<u32>,
+}
+
+/// Single field difference between two versions
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: String,                 // Dotted path, e.g. "operations.read"
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+}
+
+/// Structural diff between two versions
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyDiff {
+    pub policy_id: u64,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub changes: Vec<FieldChange>,
+    pub kernel_rules_before: usize,
+    pub kernel_rules_after: usize,
+}
+
+impl PolicyVersion {
+    /// Convert to admin-safe view
+    pub fn to_info(&self) -> PolicyVersionInfo {
+        PolicyVersionInfo {
+            policy_id: self.policy_id,
+            version: self.version,
+            change_type: self.change_type,
+            changed_by: self.changed_by.clone(),
+            changed_at: self.changed_at,
+            old_intent: self.old_intent.clone(),
+            new_intent: self.new_intent.clone(),
+            kernel_rules: self.kernel_policies.iter().map(|kp| KernelRuleSummary {
+                match_type: match kp.match_type {
+                    PathMatchType::Exact => "exact".to_string(),
+                    PathMatchType::Prefix => "prefix".to_string(),
+                },
+                block_all: kp.block_all,
+                write: kp.blocked_ops.write,
+                delete: kp.blocked_ops.delete,
+                rename: kp.blocked_ops.rename,
+                create: kp.blocked_ops.create,
+            }).collect(),
+            reverted_from: self.reverted_from,
+        }
+    }
+}
+
+/// Append-only policy history
+pub struct PolicyHistory {
+    versions: RwLock<HashMap<u64, Vec<PolicyVersion>>>, // policy_id -> versions (oldest first)
+    history_file: Option<String>,                       // None = memory only
+}
+
+impl PolicyHistory {
+    /// Create new empty in-memory history
+    pub fn new() -> Arc<Self> {
+        Arc::new(PolicyHistory {
+            versions: RwLock::new(HashMap::new()),
+            history_file: None,
+        })
+    }
+
+    /// Default location of the history file
+    pub fn default_history_file() -> String {
+        std::env::var(POLICY_HISTORY_FILE_ENV).unwrap_or_else(|_| DEFAULT_POLICY_HISTORY_FILE.to_string())
+    }
+
+    /// Load history from `path` and append new versions there from now on
+    pub fn open(path: &str) -> Arc<Self> {
+        let mut versions: HashMap<u64, Vec<PolicyVersion>> = HashMap::new();
+
+        match Self::read_versions(path) {
+            Ok(loaded) => {
+                let count = loaded.len();
+                for version in loaded {
+                    versions.entry(version.policy_id).or_default().push(version);
+                }
+                if count > 0 {
+                    println!("📜 PolicyHistory: {} versions of {} policies loaded from {}", count, versions.len(), path);
+                }
+            }
+            Err(e) => {
+                // Keep the damaged file for inspection and start a fresh one
+                let backup = format!("{}.corrupt", path);
+                let _ = std::fs::rename(path, &backup);
+                println!("⚠️  PolicyHistory: Corrupt history moved to {}: {}", backup, e);
+            }
+        }
+
+        Arc::new(PolicyHistory {
+            versions: RwLock::new(versions),
+            history_file: Some(path.to_string()),
+        })
+    }
+
+    /// Append a new version, returns its version number
+    pub fn record(&self, change: PolicyChange) -> u32 {
+        let mut versions = self.versions.write();
+        let entries = versions.entry(change.policy_id).or_default();
+        let version = entries.len() as u32 + 1;
+
+        let entry = PolicyVersion {
+            policy_id: change.policy_id,
+            version,
+            change_type: change.change_type,
+            changed_by: change.changed_by,
+            changed_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            old_intent: change.old_intent,
+            new_intent: change.new_intent,
+            kernel_policies: change.kernel_policies,
+            reverted_from: change.reverted_from,
+        };
+
+        if let Err(e) = self.append_to_file(&entry) {
+            println!("⚠️  PolicyHistory: Failed to persist version {} of policy {}: {}", version, entry.policy_id, e);
+        }
+
+        println!("📜 PolicyHistory: Policy {} → version {} ({:?} by {})",
+            entry.policy_id, version, entry.change_type, entry.changed_by);
+        entries.push(entry);
+        version
+    }
+
+    /// Whether a policy ID has any recorded versions (also from earlier runs)
+    pub fn contains(&self, policy_id: u64) -> bool {
+        self.versions.read().contains_key(&policy_id)
+    }
+
+    /// Get all versions of a policy (oldest first)
+    pub fn get_history(&self, policy_id: u64) -> Vec<PolicyVersion> {
+        let versions = self.versions.read();
+        versions.get(&policy_id).cloned().unwrap_or_default()
+    }
+
+    /// Get a specific version
+    pub fn get_version(&self, policy_id: u64, version: u32) -> Option<PolicyVersion> {
+        let versions = self.versions.read();
+        versions.get(&policy_id)
+            .and_then(|entries| entries.iter().find(|v| v.version == version))
+            .cloned()
+    }
+
+    /// Structural diff between the resulting state of two versions
+    pub fn diff(&self, policy_id: u64, from_version: u32, to_version: u32) -> Result<PolicyDiff, String> {
+        let from = self.get_version(policy_id, from_version)
+            .ok_or_else(|| format!("Policy {} has no version {}", policy_id, from_version))?;
+        let to = self.get_version(policy_id, to_version)
+            .ok_or_else(|| format!("Policy {} has no version {}", policy_id, to_version))?;
+
+        let from_value = serde_json::to_value(&from.new_intent).unwrap_or_default();
+        let to_value = serde_json::to_value(&to.new_intent).unwrap_or_default();
+
+        let mut changes = Vec::new();
+        Self::diff_values("", &from_value, &to_value, &mut changes);
+
+        Ok(PolicyDiff {
+            policy_id,
+            from_version,
+            to_version,
+            changes,
+            kernel_rules_before: from.kernel_policies.len(),
+            kernel_rules_after: to.kernel_policies.len(),
+        })
+    }
+
+    /// Append one JSON line to the history file
+    fn append_to_file(&self, version: &PolicyVersion) -> Result<(), String> {
+        let path = match &self.history_file {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let line = serde_json::to_string(version)
+            .map_err(|e| format!("Failed to serialize version: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+
+    /// Read all versions from a history file (missing file = empty history)
+    fn read_versions(path: &str) -> Result<Vec<PolicyVersion>, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| serde_json::from_str(line)
+                .map_err(|e| format!("Line {} is not a valid version: {}", i + 1, e)))
+            .collect()
+    }
+
+    /// Recursively compare two JSON values, collecting leaf changes
+    fn diff_values(
+        path: &str,
+        old: &serde_json::Value,
+        new: &serde_json::Value,
+        changes: &mut Vec<FieldChange>,
+    ) {
+        use serde_json::Value;
+
+        match (old, new) {
+            (Value::Object(old_map), Value::Object(new_map)) => {
+                let mut keys: Vec<&String> = old_map.keys().chain(new_map.keys()).collect();
+                keys.sort();
+                keys.dedup();
+
+                for key in keys {
+                    // Timestamps change on every version and are not policy content
+                    if key == "timestamp" {
+                        continue;
+                    }
+                    let child_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                    Self::diff_values(
+                        &child_path,
+                        old_map.get(key).unwrap_or(&Value::Null),
+                        new_map.get(key).unwrap_or(&Value::Null),
+                        changes,
+                    );
+                }
+            }
+            _ if old != new => changes.push(FieldChange {
+                field: if path.is_empty() { "policy".to_string() } else { path.to_string() },
+                old_value: old.clone(),
+                new_value: new.clone(),
+            }),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_index::DriveKind;
+    use crate::policy::policy_engine::PolicyEngine;
+    use crate::policy::policy_intent::{ProtectionAction, ProtectionOperations, ProtectionScope};
+
+    fn intent(node_id: u64, action: ProtectionAction, delete: bool) -> PolicyIntent {
+        let operations = ProtectionOperations { delete, ..ProtectionOperations::default() };
+        PolicyIntent::new(node_id, ProtectionScope::FolderRecursive, action, operations, "alice", None)
+    }
+
+    fn change(policy_id: u64, change_type: PolicyChangeType, new_intent: Option<PolicyIntent>) -> PolicyChange {
+        PolicyChange {
+            policy_id,
+            change_type,
+            changed_by: "alice".to_string(),
+            old_intent: None,
+            new_intent,
+            kernel_policies: Vec::new(),
+            reverted_from: None,
+        }
+    }
+
+    fn temp_file(name: &str) -> String {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        std::env::temp_dir()
+            .join(format!("dlp_{}_{}_{}.jsonl", name, std::process::id(), nanos))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn diff_lists_changed_fields_only() {
+        let history = PolicyHistory::new();
+        history.record(change(7, PolicyChangeType::Created, Some(intent(42, ProtectionAction::Block, false))));
+        history.record(change(7, PolicyChangeType::Updated, Some(intent(42, ProtectionAction::Audit, true))));
+        history.record(change(7, PolicyChangeType::Removed, None));
+
+        let diff = history.diff(7, 1, 2).unwrap();
+        let mut fields: Vec<&str> = diff.changes.iter().map(|c| c.field.as_str()).collect();
+        fields.sort();
+        assert_eq!(fields, vec!["action", "operations.delete"]);
+        let action = diff.changes.iter().find(|c| c.field == "action").unwrap();
+        assert_eq!(action.old_value, serde_json::json!("block"));
+        assert_eq!(action.new_value, serde_json::json!("audit"));
+
+        // Timestamps differ between versions but are not content
+        assert!(history.diff(7, 1, 1).unwrap().changes.is_empty());
+
+        // Removal diffs against "no policy"
+        let removed = history.diff(7, 2, 3).unwrap();
+        assert_eq!(removed.changes.len(), 1);
+        assert_eq!(removed.changes[0].field, "policy");
+        assert!(removed.changes[0].new_value.is_null());
+
+        assert!(history.diff(7, 1, 9).is_err());
+    }
+
+    #[test]
+    fn history_survives_reopen() {
+        let path = temp_file("policy_history");
+        {
+            let history = PolicyHistory::open(&path);
+            history.record(change(7, PolicyChangeType::Created, Some(intent(42, ProtectionAction::Block, false))));
+            history.record(change(7, PolicyChangeType::Updated, Some(intent(42, ProtectionAction::Audit, false))));
+        }
+
+        let reopened = PolicyHistory::open(&path);
+        assert!(reopened.contains(7));
+        assert_eq!(reopened.get_history(7).len(), 2);
+        assert_eq!(reopened.record(change(7, PolicyChangeType::Removed, None)), 3);
+        assert_eq!(PolicyHistory::open(&path).get_history(7).len(), 3);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn corrupt_history_is_set_aside() {
+        let path = temp_file("policy_history_corrupt");
+        std::fs::write(&path, "{not json\n").unwrap();
+
+        let history = PolicyHistory::open(&path);
+        assert!(!history.contains(7));
+        let backup = format!("{}.corrupt", path);
+        assert!(std::path::Path::new(&backup).exists());
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&backup);
+    }
+
+    #[test]
+    fn revert_restores_an_earlier_version() {
+        // UNC roots resolve without the volume map
+        let engine = PolicyEngine::new_simulated();
+        let node_id = engine.path_resolver().index()
+            .add_drive("\\\\fs01\\finance", "finance", "\\Device\\Mup\\fs01\\finance\\", DriveKind::Network, None);
+
+        let policy_id = engine.apply_protection(intent(node_id, ProtectionAction::Block, false)).unwrap();
+        engine.update_protection(policy_id, intent(node_id, ProtectionAction::Block, true), "bob", false).unwrap();
+        engine.revert_protection(policy_id, 1, "carol", false).unwrap();
+
+        let versions = engine.get_policy_history(policy_id);
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[2].change_type, PolicyChangeType::Reverted);
+        assert_eq!(versions[2].reverted_from, Some(1));
+        assert_eq!(versions[2].changed_by, "carol");
+        assert!(engine.diff_policy_versions(policy_id, 1, 3).unwrap().changes.is_empty());
+        assert!(!engine.get_policy_by_id(policy_id).unwrap().intent.operations.delete);
+
+        // Reverting to the removal state removes the policy again
+        engine.remove_protection(policy_id, "bob").unwrap();
+        engine.revert_protection(policy_id, 3, "carol", false).unwrap();
+        assert!(engine.get_policy_by_id(policy_id).is_some());
+        engine.revert_protection(policy_id, 4, "carol", false).unwrap();
+        assert!(engine.get_policy_by_id(policy_id).is_none());
+    }
+}
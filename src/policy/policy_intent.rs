@@ -113,6 +113,10 @@ pub struct PolicyIntent {
     pub created_by: String,              // Admin username/ID
     pub timestamp: u64,                  // Unix timestamp
     pub comment: Option<String>,         // Optional admin comment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_rule_id: Option<u64>,       // Auto-protection rule that generated it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_rule_id: Option<u64>,     // Device rule that generated it (drive node policies)
 }
 
 impl PolicyIntent {
@@ -136,9 +140,23 @@ impl PolicyIntent {
                 .unwrap()
                 .as_secs(),
             comment: comment.map(|s| s.to_string()),
+            auto_rule_id: None,
+            device_rule_id: None,
         }
     }
 
+    /// Tag with the auto-protection rule that generated this intent
+    pub fn with_auto_rule(mut self, rule_id: u64) -> Self {
+        self.auto_rule_id = Some(rule_id);
+        self
+    }
+
+    /// Tag with the device rule that generated this intent
+    pub fn with_device_rule(mut self, rule_id: u64) -> Self {
+        self.device_rule_id = Some(rule_id);
+        self
+    }
+
     
     pub fn validate(&self) -> Result<(), String> {
 
@@ -0,0 +1,62 @@
+//! Policy Retargeting on Rename/Move
+//! Core Principle: A rename must never silently leave data unprotected
+//! Kernel rules are pinned to the NT path resolved at apply time. When a
+//! protected node (or one of its ancestors) is renamed, the agent either
+//! moves the rules to the new path (follow the file) or leaves them on the
+//! old path (stay on path). Either way the Admin is told.
+//! A protected node that disappears from view (deleted, or moved somewhere
+//! the agent does not watch) orphans its policy: the rules keep guarding the
+//! old path and the Admin is told to re-apply.
+
+use serde::Serialize;
+
+/// Environment variable selecting the rename semantics ("follow" or "stay")
+pub const RETARGET_MODE_ENV: &str = "AGENT_POLICY_RENAME_MODE";
+
+/// What happens to a policy when its node is renamed or moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetargetMode {
+    #[default]
+    FollowFile,   // Re-resolve and push kernel rules for the new path
+    StayOnPath,   // Keep guarding the old path
+}
+
+impl RetargetMode {
+    /// Mode from the environment (unknown values fall back to follow)
+    pub fn from_env() -> Self {
+        match std::env::var(RETARGET_MODE_ENV).as_deref().map(str::trim) {
+            Ok("stay") | Ok("stay_on_path") => RetargetMode::StayOnPath,
+            Ok("follow") | Ok("follow_file") | Err(_) => RetargetMode::FollowFile,
+            Ok(other) => {
+                println!("⚠️  {}='{}' not recognised, policies follow renamed files", RETARGET_MODE_ENV, other);
+                RetargetMode::FollowFile
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetargetMode::FollowFile => "follow_file",
+            RetargetMode::StayOnPath => "stay_on_path",
+        }
+    }
+}
+
+/// Outcome for one policy affected by a rename (admin-safe: IDs only)
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyRetarget {
+    pub policy_id: u64,
+    pub node_id: u64,              // Protected node (renamed itself or below the renamed node)
+    pub mode: RetargetMode,
+    pub followed: bool,            // Kernel rules now cover the new path
+    pub version: Option<u32>,      // History version recorded for the retarget
+    pub failed: bool,              // Follow failed; the old rules stay in place (details in agent log)
+}
+
+/// Policy whose protected node is gone from the index (admin-safe: IDs only)
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyOrphan {
+    pub policy_id: u64,
+    pub node_id: u64,              // Protected node that was deleted or moved out of view
+}
@@ -15,6 +15,7 @@ pub struct ActivePolicy {
     pub kernel_policies: Vec<KernelPolicy>, // Kernel-ready policies
     pub kernel_policy_ids: Vec<u64>,   // IDs returned by kernel
     pub is_active: bool,               // Is currently enforced?
+    pub orphaned: bool,                // Protected node deleted or moved out of view
     pub created_at: u64,               // When created
     pub last_updated: u64,             // When last updated
 }
@@ -110,6 +111,7 @@ impl PolicyStore {
             kernel_policies,
             kernel_policy_ids,
             is_active: true,
+            orphaned: false,
             created_at: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
@@ -130,7 +132,7 @@ impl PolicyStore {
         {
             let mut node_map = self.node_to_policies.write();
             node_map.entry(node_id)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(policy_id);
         }
         
@@ -154,6 +156,20 @@ impl PolicyStore {
             .collect()
     }
     
+    /// Get the IDs of all policies on a node
+    pub fn get_policy_ids_for_node(&self, node_id: u64) -> Vec<u64> {
+        let node_map = self.node_to_policies.read();
+        node_map.get(&node_id).cloned().unwrap_or_default()
+    }
+    
+    /// (policy_id, node_id) of every stored policy
+    pub fn get_policy_nodes(&self) -> Vec<(u64, u64)> {
+        let policies = self.policies.read();
+        policies.iter()
+            .map(|(&policy_id, policy)| (policy_id, policy.intent.node_id))
+            .collect()
+    }
+    
     /// Get all active policies
     pub fn get_all_policies(&self) -> Vec<ActivePolicy> {
         let policies = self.policies.read();
@@ -185,6 +201,74 @@ impl PolicyStore {
         removed_policy
     }
     
+    /// Replace intent and kernel rules of an existing policy (keeps created_at)
+    pub fn replace_policy(
+        &self,
+        policy_id: u64,
+        intent: PolicyIntent,
+        kernel_policies: Vec<KernelPolicy>,
+        kernel_policy_ids: Vec<u64>,
+    ) -> bool {
+        let new_node_id = intent.node_id;
+        
+        let old_node_id = {
+            let mut policies = self.policies.write();
+            let policy = match policies.get_mut(&policy_id) {
+                Some(policy) => policy,
+                None => {
+                    println!("❌ PolicyStore: Policy ID {} not found", policy_id);
+                    return false;
+                }
+            };
+            
+            let old_node_id = policy.intent.node_id;
+            policy.intent = intent;
+            policy.kernel_policies = kernel_policies;
+            policy.kernel_policy_ids = kernel_policy_ids;
+            policy.is_active = true;
+            policy.orphaned = false;
+            policy.last_updated = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            old_node_id
+        };
+        
+        // Re-link node-to-policies mapping if the target node changed
+        if old_node_id != new_node_id {
+            let mut node_map = self.node_to_policies.write();
+            if let Some(policy_ids) = node_map.get_mut(&old_node_id) {
+                policy_ids.retain(|&id| id != policy_id);
+                if policy_ids.is_empty() {
+                    node_map.remove(&old_node_id);
+                }
+            }
+            node_map.entry(new_node_id)
+                .or_default()
+                .push(policy_id);
+        }
+        
+        println!("📝 PolicyStore: Replaced policy ID {}", policy_id);
+        true
+    }
+    
+    /// Flag a policy whose node is gone (true if it was not flagged yet)
+    pub fn mark_orphaned(&self, policy_id: u64) -> bool {
+        let mut policies = self.policies.write();
+        match policies.get_mut(&policy_id) {
+            Some(policy) if !policy.orphaned => {
+                policy.orphaned = true;
+                policy.last_updated = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                println!("📝 PolicyStore: Policy ID {} orphaned", policy_id);
+                true
+            }
+            _ => false,
+        }
+    }
+    
     /// Update policy status
     pub fn update_policy_status(&self, policy_id: u64, is_active: bool) -> bool {
         let mut policies = self.policies.write();
@@ -1,7 +1,11 @@
 //! Query Interface - Safe, read-only API for Admin Server
 //! Core Principle: Admin only sees IDs, never NT paths
 
-use super::fs_index::{FilesystemIndex, FileSystemNode, EntryType};
+use super::file_type::ContentType;
+use super::fs_index::{ChildCursor, ChildSort, DriveKind, FilesystemIndex, FileSystemNode, EntryType};
+use super::index_search::{SearchMatcher, SearchRequest, DEFAULT_SEARCH_LIMIT};
+use super::volume_map::DriveDevice;
+use parking_lot::RwLock;
 use std::sync::Arc;
 
 /// Safe node information for Admin (no NT paths!)
@@ -17,6 +21,34 @@ pub struct SafeNodeInfo {
     pub is_expanded: bool,
     pub is_accessible: bool,
      pub display_path: String,
+    pub content_type: Option<ContentType>,
+}
+
+/// Default number of children per page
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// Largest page a client may request
+pub const MAX_PAGE_SIZE: usize = 5000;
+
+/// One sorted page of children (safe info only)
+#[derive(Debug, Clone)]
+pub struct SafeChildrenPage {
+    pub nodes: Vec<SafeNodeInfo>,
+    pub total: usize,
+    pub offset: usize,
+    pub has_more: bool,
+    pub next_cursor: Option<ChildCursor>,
+}
+
+/// One page of global search results (safe info only)
+#[derive(Debug, Clone)]
+pub struct SafeSearchPage {
+    pub nodes: Vec<SafeNodeInfo>,
+    pub total: usize,
+    pub offset: usize,
+    pub has_more: bool,
+    pub nodes_searched: usize,
+    pub total_indexed: usize,
 }
 
 /// Drive information for Admin
@@ -25,6 +57,8 @@ pub struct DriveInfo {
     pub drive_letter: String,
     pub display_name: String,
     pub node_id: u64,
+    pub kind: DriveKind,
+    pub device: Option<DriveDevice>,
 }
 
 /// System statistics - proper struct, not string parsing
@@ -33,7 +67,9 @@ pub struct SystemStats {
     pub total_nodes: usize,
     pub total_drives: usize,
     pub expanded_nodes: usize,
-    pub memory_usage_bytes: usize,
+    pub memory_usage_bytes: usize,     // Nodes + path maps (limited by the ceiling)
+    pub memory_ceiling_bytes: usize,   // 0 = unlimited
+    pub identity_memory_bytes: usize,  // Stable identities (not evicted)
     pub scan_state: ScanState,
 }
 
@@ -41,6 +77,7 @@ pub struct SystemStats {
 pub enum ScanState {
     Idle,
     Expanding,
+    Crawling { jobs: usize, entries_indexed: u64 },  // Background crawl, no expansion running
     Ready,
     Error(String),
 }
@@ -50,6 +87,7 @@ impl ScanState {
         match self {
             ScanState::Idle => "idle",
             ScanState::Expanding => "expanding",
+            ScanState::Crawling { .. } => "crawling",
             ScanState::Ready => "ready",
             ScanState::Error(_) => "error",
         }
@@ -61,6 +99,8 @@ impl ScanState {
 pub enum QueryResponse {
     Drives(Vec<DriveInfo>),
     Nodes(Vec<SafeNodeInfo>),
+    Page(SafeChildrenPage),
+    SearchPage(SafeSearchPage),
     Node(SafeNodeInfo),
     Stats(SystemStats),
     Error(String),
@@ -69,12 +109,19 @@ pub enum QueryResponse {
 /// Main query interface
 pub struct QueryInterface {
     index: Arc<FilesystemIndex>,
+    scan_state: Option<Arc<RwLock<ScanState>>>,  // Published by FileSystemScanner
 }
 
 impl QueryInterface {
     /// Create new query interface
     pub fn new(index: Arc<FilesystemIndex>) -> Self {
-        QueryInterface { index }
+        QueryInterface { index, scan_state: None }
+    }
+    
+    /// Report the scanner's scan state in stats
+    pub fn with_scan_state(mut self, scan_state: Arc<RwLock<ScanState>>) -> Self {
+        self.scan_state = Some(scan_state);
+        self
     }
     
     /// Get all drives
@@ -88,6 +135,8 @@ impl QueryInterface {
                     drive_letter,
                     display_name: node.name,
                     node_id,
+                    kind: node.drive_kind.unwrap_or(DriveKind::Local),
+                    device: node.device,
                 });
             }
         }
@@ -110,6 +159,29 @@ impl QueryInterface {
         QueryResponse::Nodes(safe_children)
     }
     
+    /// Get one sorted page of children (LOCAL ONLY - only expanded children)
+    pub fn list_children_page(
+        &self,
+        parent_id: u64,
+        sort: ChildSort,
+        descending: bool,
+        start_after: Option<&ChildCursor>,
+        offset: usize,
+        limit: usize,
+    ) -> QueryResponse {
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        match self.index.children_page(parent_id, sort, descending, start_after, offset, limit) {
+            Ok(page) => QueryResponse::Page(SafeChildrenPage {
+                nodes: page.nodes.iter().map(|node| self.convert_to_safe_info(node)).collect(),
+                total: page.total,
+                offset: page.offset,
+                has_more: page.has_more,
+                next_cursor: page.next_cursor,
+            }),
+            Err(e) => QueryResponse::Error(e),
+        }
+    }
+    
     /// Get specific node
     pub fn get_node(&self, node_id: u64) -> QueryResponse {
         match self.index.get_node(node_id) {
@@ -140,6 +212,27 @@ impl QueryInterface {
         QueryResponse::Nodes(matches)
     }
     
+    /// Search everything loaded in the index (expanded or crawled), ranked and paged
+    pub fn search(&self, request: &SearchRequest) -> QueryResponse {
+        let matcher = match SearchMatcher::compile(request) {
+            Ok(matcher) => matcher,
+            Err(e) => return QueryResponse::Error(e),
+        };
+        let limit = request.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_PAGE_SIZE);
+        
+        match self.index.search(&matcher, request.root_id, request.offset, limit) {
+            Ok(page) => QueryResponse::SearchPage(SafeSearchPage {
+                nodes: page.nodes.iter().map(|node| self.convert_to_safe_info(node)).collect(),
+                total: page.total,
+                offset: page.offset,
+                has_more: page.has_more,
+                nodes_searched: page.nodes_searched,
+                total_indexed: self.index.node_count(),
+            }),
+            Err(e) => QueryResponse::Error(e),
+        }
+    }
+    
     /// Resolve ID to NT path (INTERNAL USE ONLY)
     pub fn resolve_nt_path_internal(&self, node_id: u64) -> Result<String, String> {
         self.index.resolve_nt_path(node_id)
@@ -158,8 +251,12 @@ impl QueryInterface {
             total_nodes,
             total_drives: drives.len(),
             expanded_nodes,
-            memory_usage_bytes: 0, // Will be implemented later
-            scan_state: ScanState::Idle,
+            memory_usage_bytes: self.index.memory_usage_bytes(),
+            memory_ceiling_bytes: self.index.memory_ceiling_bytes(),
+            identity_memory_bytes: self.index.identity_memory_bytes(),
+            scan_state: self.scan_state.as_ref()
+                .map(|state| state.read().clone())
+                .unwrap_or(ScanState::Idle),
         };
         
         QueryResponse::Stats(stats)
@@ -175,13 +272,16 @@ impl QueryInterface {
     fn convert_to_safe_info(&self, node: &FileSystemNode) -> SafeNodeInfo {
         let has_children = !node.children_ids.is_empty() || 
             (node.entry_type == EntryType::Directory || 
-             node.entry_type == EntryType::Drive);
+             node.entry_type == EntryType::Drive ||
+             node.entry_type == EntryType::Junction);
         
         let entry_type_str = match node.entry_type {
             EntryType::VirtualRoot => "VirtualRoot",
             EntryType::Drive => "Drive",
             EntryType::Directory => "Directory",
             EntryType::File => "File",
+            EntryType::Symlink => "Symlink",
+            EntryType::Junction => "Junction",
         }.to_string();
         
         SafeNodeInfo {
@@ -195,6 +295,7 @@ impl QueryInterface {
             is_expanded: node.is_expanded,
             is_accessible: node.is_accessible,
             display_path: node.display_path.clone(),
+            content_type: node.content_type,
         }
     }
 }
\ No newline at end of file
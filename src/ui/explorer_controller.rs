@@ -3,7 +3,7 @@
 
 use super::explorer_state::{ExplorerState, SelectionInfo};
 use super::interaction_rules::InteractionEngine;
-use crate::comms::{QueryApiServer, AdminRequest, AgentResponse};
+use crate::comms::{QueryApiServer, AdminRequest, AgentResponse, PageRequest};
 use std::sync::Arc;
 
 /// Main Explorer Controller
@@ -79,38 +79,48 @@ impl ExplorerController {
     /// ========================================
     
     /// Load children of a node (called when node is expanded)
+    /// Follows the page cursor until every child is loaded.
     pub async fn load_children(&self, parent_id: u64) -> Result<Vec<SelectionInfo>, String> {
         println!("📂 UI: Loading children of node {}", parent_id);
         
-        match self.interaction_engine.api_server().handle_request(AdminRequest::ListChildren { node_id: parent_id }).await {
-            AgentResponse::Children { parent_id, parent_name, children, total_children } => {
-                println!("✅ UI: Loaded {} children for '{}'", total_children, parent_name);
-                
-                let selection_infos: Vec<SelectionInfo> = children.into_iter().map(|node| {
-                    SelectionInfo {
-                        node_id: node.id,
-                        node_type: node.node_type,
-                        name: node.name,
-                        is_accessible: node.is_accessible,
-                        has_children: node.has_children,
+        let mut selection_infos: Vec<SelectionInfo> = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = PageRequest { cursor: cursor.take(), ..PageRequest::default() };
+            match self.interaction_engine.api_server().handle_request(AdminRequest::ListChildren { node_id: parent_id, page }).await {
+                AgentResponse::Children { parent_name, children, total_children, next_cursor, has_more, .. } => {
+                    selection_infos.extend(children.into_iter().map(|node| {
+                        SelectionInfo {
+                            node_id: node.id,
+                            node_type: node.node_type,
+                            name: node.name,
+                            is_accessible: node.is_accessible,
+                            has_children: node.has_children,
+                        }
+                    }));
+                    
+                    match next_cursor {
+                        Some(next) if has_more => cursor = Some(next),
+                        _ => {
+                            println!("✅ UI: Loaded {} of {} children for '{}'", selection_infos.len(), total_children, parent_name);
+                            return Ok(selection_infos);
+                        }
                     }
-                }).collect();
+                }
                 
-                Ok(selection_infos)
-            }
-            
-            AgentResponse::Error { code, message, details } => {
-                let error = format!("Failed to load children: {}: {}", code, message);
-                println!("❌ UI: {}", error);
-                self.interaction_engine.ui_state().set_error(Some(error.clone()));
-                Err(error)
-            }
-            
-            _ => {
-                let error = "Unexpected response when loading children".to_string();
-                println!("❌ UI: {}", error);
-                self.interaction_engine.ui_state().set_error(Some(error.clone()));
-                Err(error)
+                AgentResponse::Error { code, message, .. } => {
+                    let error = format!("Failed to load children: {}: {}", code, message);
+                    println!("❌ UI: {}", error);
+                    self.interaction_engine.ui_state().set_error(Some(error.clone()));
+                    return Err(error);
+                }
+                
+                _ => {
+                    let error = "Unexpected response when loading children".to_string();
+                    println!("❌ UI: {}", error);
+                    self.interaction_engine.ui_state().set_error(Some(error.clone()));
+                    return Err(error);
+                }
             }
         }
     }
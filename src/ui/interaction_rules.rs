@@ -2,7 +2,7 @@
 //! Core Principle: Define how clicks behave, enforce STEP 2 API usage
 
 use super::explorer_state::{ExplorerState, SelectionInfo, PendingProtection};
-use crate::comms::{QueryApiServer, AdminRequest, AgentResponse};
+use crate::comms::{QueryApiServer, AdminRequest, AgentResponse, PageRequest};
 use std::sync::Arc;
 
 /// Interaction Rules Engine
@@ -106,11 +106,15 @@ impl InteractionEngine {
         println!("   → Sending ExpandNode request to Agent...");
         
         // Call STEP 2 API (async)
-        match self.api_server.handle_request(AdminRequest::ExpandNode { node_id }).await {
-            AgentResponse::Expanded { node_id: expanded_id, node_name, children, total_children } => {
+        match self.api_server.handle_request(AdminRequest::ExpandNode { node_id, scan_config: None, page: PageRequest::default() }).await {
+            AgentResponse::Expanded { node_id: expanded_id, node_name, children, total_children, has_more, .. } => {
                 println!("   ✅ Agent: Expanded '{}' (ID: {})", node_name, expanded_id);
                 println!("      • Children loaded: {}", children.len());
                 println!("      • Total children: {}", total_children);
+                if has_more {
+                    // Only the first page came back; the rest load through ListChildren
+                    println!("      • Showing first {} of {} (more pages available)", children.len(), total_children);
+                }
                 
                 // Mark as expanded
                 self.ui_state.mark_expanded(node_id);
@@ -0,0 +1,985 @@
+//! Volume Map - drive letters, mount folders, volume GUIDs and mapped
+//! network drives → NT device names
+//! Core Principle: Never guess a device. An unmapped volume is an error.
+//! IMPORTANT: Internal to Agent only (device names are NT paths)
+//! The map is filled by a VolumeMapper (Win32 on the agent, a table in tests)
+//! and refreshed when volumes arrive or leave.
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Environment variable for the volume refresh interval (0 disables periodic refresh)
+pub const VOLUME_REFRESH_MS_ENV: &str = "AGENT_VOLUME_REFRESH_MS";
+
+const DEFAULT_VOLUME_REFRESH_MS: u64 = 5000;
+
+/// Shortest gap between refreshes triggered by lookup misses
+const MISS_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// NT → DOS translations kept between volume changes
+const DOS_PATH_CACHE_SIZE: usize = 4096;
+
+/// Multiple UNC Provider: network files are \Device\Mup\server\share\...
+pub const MUP_DEVICE: &str = "\\Device\\Mup";
+
+/// One mounted volume
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeInfo {
+    pub volume_guid: String,        // \\?\Volume{...}\
+    pub device_path: String,        // \Device\HarddiskVolume3 (no trailing backslash)
+    pub mount_paths: Vec<String>,   // "C:\", folder mount points ("D:\Mounts\Data\")
+}
+
+/// Drive letter mapped to a network share
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkDrive {
+    pub drive: String,      // "Z:"
+    pub unc_path: String,   // \\server\share (no trailing backslash)
+}
+
+/// Hardware class of a drive (GetDriveType; disks on the USB bus count as removable)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceClass {
+    Fixed,
+    Removable,      // USB sticks and disks, card readers
+    Network,
+    #[serde(rename = "cdrom")]
+    CdRom,
+    RamDisk,
+    Unknown,
+}
+
+impl DeviceClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceClass::Fixed => "fixed",
+            DeviceClass::Removable => "removable",
+            DeviceClass::Network => "network",
+            DeviceClass::CdRom => "cdrom",
+            DeviceClass::RamDisk => "ram_disk",
+            DeviceClass::Unknown => "unknown",
+        }
+    }
+
+    /// Name Explorer shows for an unlabeled drive of this class
+    pub fn default_label(&self) -> &'static str {
+        match self {
+            DeviceClass::Fixed | DeviceClass::Unknown => "Local Disk",
+            DeviceClass::Removable => "Removable Disk",
+            DeviceClass::Network => "Network Drive",
+            DeviceClass::CdRom => "CD Drive",
+            DeviceClass::RamDisk => "RAM Disk",
+        }
+    }
+}
+
+/// Medium behind a drive root (admin-safe: no device names)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriveDevice {
+    pub class: DeviceClass,
+    pub label: Option<String>,      // Volume label, None when blank
+    pub serial: Option<String>,     // Volume serial number, "1A2B-3C4D"
+}
+
+impl DriveDevice {
+    /// "1A2B-3C4D" as shown by `vol`
+    pub fn format_serial(serial: u32) -> String {
+        format!("{:04X}-{:04X}", serial >> 16, serial & 0xFFFF)
+    }
+
+    /// Serial in the "1A2B-3C4D" form from "1a2b3c4d", "1A2B-3C4D" or "0x1A2B3C4D"
+    pub fn normalize_serial(serial: &str) -> Option<String> {
+        let hex: String = serial.trim().trim_start_matches("0x").trim_start_matches("0X")
+            .chars()
+            .filter(|c| *c != '-')
+            .collect();
+        if hex.len() != 8 {
+            return None;
+        }
+        u32::from_str_radix(&hex, 16).ok().map(Self::format_serial)
+    }
+}
+
+/// Source of the current volume list
+pub trait VolumeMapper: Send + Sync {
+    /// Mapper name for logs
+    fn name(&self) -> &'static str;
+
+    /// All volumes currently known to the system
+    fn enumerate(&self) -> Result<Vec<VolumeInfo>, String>;
+
+    /// Drive letters mapped to network shares (none by default)
+    fn network_drives(&self) -> Result<Vec<NetworkDrive>, String> {
+        Ok(Vec::new())
+    }
+
+    /// Class, label and serial of the medium behind a root ("E:", "\\server\share",
+    /// "\\?\Volume{GUID}"), None when the root does not exist
+    fn drive_device(&self, _root: &str) -> Option<DriveDevice> {
+        None
+    }
+}
+
+/// Volumes that appeared or disappeared in a refresh (volume GUIDs)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VolumeChanges {
+    pub arrived: Vec<String>,
+    pub removed: Vec<String>,
+    pub network_changed: bool,      // A network drive was mapped or disconnected
+}
+
+impl VolumeChanges {
+    pub fn is_empty(&self) -> bool {
+        self.arrived.is_empty() && self.removed.is_empty() && !self.network_changed
+    }
+}
+
+#[derive(Default)]
+struct MapState {
+    volumes: HashMap<String, VolumeInfo>,   // Normalized GUID → volume
+    drives: HashMap<String, String>,        // "C:" → normalized GUID
+    dos_roots: HashMap<String, String>,     // Lowercase device name → "D:\" (or mount/GUID path)
+    network: HashMap<String, String>,       // "Z:" → \\server\share
+    refreshed_at: Option<Instant>,
+}
+
+/// Drive letter / volume GUID → device name map
+pub struct VolumeMap {
+    mapper: Box<dyn VolumeMapper>,
+    state: RwLock<MapState>,
+    dos_paths: Mutex<HashMap<String, String>>,  // NT → DOS cache, cleared when volumes change
+    generation: AtomicU64,                      // Bumped by every refresh that saw a change
+}
+
+impl VolumeMap {
+    /// Empty map, filled on the first lookup or refresh
+    pub fn new(mapper: Box<dyn VolumeMapper>) -> Self {
+        VolumeMap {
+            mapper,
+            state: RwLock::new(MapState::default()),
+            dos_paths: Mutex::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Agent-wide map backed by the Win32 volume APIs
+    pub fn global() -> &'static VolumeMap {
+        static GLOBAL: OnceLock<VolumeMap> = OnceLock::new();
+        GLOBAL.get_or_init(|| VolumeMap::new(Box::new(Win32VolumeMapper)))
+    }
+
+    /// Periodic refresh interval from the environment (None = disabled)
+    pub fn configured_refresh_interval() -> Option<Duration> {
+        let millis = std::env::var(VOLUME_REFRESH_MS_ENV)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_VOLUME_REFRESH_MS);
+        (millis > 0).then(|| Duration::from_millis(millis))
+    }
+
+    pub fn mapper_name(&self) -> &'static str {
+        self.mapper.name()
+    }
+
+    /// Changes whenever volumes or network drives changed, including refreshes
+    /// triggered by lookup misses (whose VolumeChanges the caller never sees)
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Re-read the volume list and report what changed
+    pub fn refresh(&self) -> Result<VolumeChanges, String> {
+        let volumes = self.mapper.enumerate()?;
+
+        let mut state = self.state.write();
+        state.refreshed_at = Some(Instant::now());
+
+        let mut by_guid = HashMap::new();
+        let mut drives = HashMap::new();
+        for mut volume in volumes {
+            let guid = Self::normalize_guid(&volume.volume_guid);
+            volume.device_path = volume.device_path.trim_end_matches('\\').to_string();
+            if !volume.device_path.starts_with("\\Device\\") {
+                println!("⚠️  VolumeMap: {} has no device name, skipped", guid);
+                continue;
+            }
+            for mount in &volume.mount_paths {
+                if let Some(drive) = Self::normalize_drive(mount) {
+                    drives.insert(drive, guid.clone());
+                }
+            }
+            by_guid.insert(guid, volume);
+        }
+
+        let previous: HashSet<&String> = state.volumes.keys().collect();
+        let current: HashSet<&String> = by_guid.keys().collect();
+        let mut changes = VolumeChanges {
+            arrived: current.difference(&previous).map(|guid| guid.to_string()).collect(),
+            removed: previous.difference(&current).map(|guid| guid.to_string()).collect(),
+            network_changed: false,
+        };
+        changes.arrived.sort();
+        changes.removed.sort();
+
+        // A failing network query must not take local volumes down with it
+        let network: HashMap<String, String> = match self.mapper.network_drives() {
+            Ok(network_drives) => network_drives.into_iter()
+                .filter(|network_drive| !drives.contains_key(&network_drive.drive))
+                .filter_map(|network_drive| {
+                    let drive = Self::normalize_drive(&network_drive.drive)?;
+                    Some((drive, network_drive.unc_path.trim_end_matches('\\').to_string()))
+                })
+                .collect(),
+            Err(e) => {
+                println!("⚠️  VolumeMap: Network drives unavailable: {}", e);
+                state.network.clone()
+            }
+        };
+
+        let dos_roots = by_guid.values()
+            .map(|volume| (volume.device_path.to_lowercase(), Self::dos_root(volume)))
+            .collect();
+        changes.network_changed = network != state.network;
+        if by_guid != state.volumes || changes.network_changed {
+            self.dos_paths.lock().clear();
+        }
+
+        state.volumes = by_guid;
+        state.drives = drives;
+        state.dos_roots = dos_roots;
+        state.network = network;
+        if !changes.is_empty() {
+            self.generation.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(changes)
+    }
+
+    /// Device name for a volume GUID path
+    pub fn device_for_guid(&self, volume_guid: &str) -> Result<String, String> {
+        let key = Self::normalize_guid(volume_guid);
+        self.lookup(|state| state.volumes.get(&key).map(|volume| volume.device_path.clone()))
+            .ok_or_else(|| format!("Volume {} is not mounted", key))
+    }
+
+    /// Volume GUID path for a drive letter
+    pub fn guid_for_drive(&self, drive: &str) -> Result<String, String> {
+        let key = Self::normalize_drive(drive)
+            .ok_or_else(|| format!("Invalid drive letter: {}", drive))?;
+        self.lookup(|state| state.drives.get(&key).cloned())
+            .ok_or_else(|| format!("No mounted volume for drive {}", key))
+    }
+
+    /// Device name and remaining path for a drive-letter path: the volume
+    /// behind the longest covering mount ("C:\\Mounts\\Data" wins over "C:"),
+    /// else the share of a mapped network drive (under \\Device\\Mup)
+    pub fn device_for_path(&self, dos_path: &str) -> Result<(String, String), String> {
+        let drive = dos_path.get(0..2)
+            .and_then(Self::normalize_drive)
+            .ok_or_else(|| format!("Invalid DOS path: {}", dos_path))?;
+        let (device_path, prefix_len) = self.lookup(|state| {
+            let mounted = state.volumes.values()
+                .flat_map(|volume| volume.mount_paths.iter().map(move |mount| (mount.trim_end_matches('\\'), volume)))
+                .filter(|(mount, _)| Self::mount_covers(mount, dos_path))
+                .max_by_key(|(mount, _)| mount.len())
+                .map(|(mount, volume)| (volume.device_path.clone(), mount.len()));
+            mounted.or_else(|| {
+                state.network.get(&drive).map(|unc_path| (format!("{}{}", MUP_DEVICE, &unc_path[1..]), 2))
+            })
+        })
+        .ok_or_else(|| format!("No mounted volume or network share for drive {}", drive))?;
+        Ok((device_path, dos_path[prefix_len..].to_string()))
+    }
+
+    /// "\\?\Volume{GUID}\..." form of a path below a folder mount point
+    /// (None when the path is not under one)
+    pub fn volume_path_for(&self, dos_path: &str) -> Option<String> {
+        let state = self.state.read();
+        let (mount, volume) = state.volumes.values()
+            .flat_map(|volume| volume.mount_paths.iter().map(move |mount| (mount.trim_end_matches('\\'), volume)))
+            .filter(|(mount, _)| Self::mount_covers(mount, dos_path))
+            .max_by_key(|(mount, _)| mount.len())?;
+        if Self::normalize_drive(mount).is_some() {
+            return None;
+        }
+        let rest = dos_path[mount.len()..].trim_start_matches('\\');
+        Some(format!("{}{}", Self::normalize_guid(&volume.volume_guid), rest))
+    }
+
+    /// Share behind a mapped network drive
+    pub fn share_for_drive(&self, drive: &str) -> Result<String, String> {
+        let key = Self::normalize_drive(drive)
+            .ok_or_else(|| format!("Invalid drive letter: {}", drive))?;
+        self.lookup(|state| state.network.get(&key).cloned())
+            .ok_or_else(|| format!("Drive {} is not a mapped network drive", key))
+    }
+
+    /// Mapped drive letter for a share, if any (no refresh: an unmapped
+    /// share is normal)
+    pub fn drive_for_share(&self, unc_path: &str) -> Option<String> {
+        let unc_path = unc_path.trim_end_matches('\\');
+        self.state.read().network.iter()
+            .filter(|(_, share)| share.eq_ignore_ascii_case(unc_path))
+            .map(|(drive, _)| drive.clone())
+            .min()
+    }
+
+    /// Current mapped network drives
+    pub fn network_drives(&self) -> Vec<NetworkDrive> {
+        if self.state.read().refreshed_at.is_none() {
+            self.refresh_logged();
+        }
+        let mut network_drives: Vec<NetworkDrive> = self.state.read().network.iter()
+            .map(|(drive, unc_path)| NetworkDrive { drive: drive.clone(), unc_path: unc_path.clone() })
+            .collect();
+        network_drives.sort_by(|a, b| a.drive.cmp(&b.drive));
+        network_drives
+    }
+
+    /// Class, label and serial of a drive root (read on every call: media change)
+    pub fn drive_device(&self, root: &str) -> Option<DriveDevice> {
+        self.mapper.drive_device(root)
+    }
+
+    /// DOS root for a device name: drive letter, else folder mount, else
+    /// the volume GUID path (letterless volume)
+    pub fn dos_root_for_device(&self, device_path: &str) -> Result<String, String> {
+        let key = device_path.trim_end_matches('\\').to_lowercase();
+        self.lookup(|state| state.dos_roots.get(&key).cloned())
+            .ok_or_else(|| format!("No mounted volume for device {}", device_path))
+    }
+
+    /// Cached NT → DOS translation
+    pub fn cached_dos_path(&self, nt_path: &str) -> Option<String> {
+        self.dos_paths.lock().get(nt_path).cloned()
+    }
+
+    /// Remember an NT → DOS translation (until the volumes change)
+    pub fn cache_dos_path(&self, nt_path: &str, dos_path: &str) {
+        let mut cache = self.dos_paths.lock();
+        if cache.len() >= DOS_PATH_CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(nt_path.to_string(), dos_path.to_string());
+    }
+
+    /// Current volumes (refreshes first if the map was never filled)
+    pub fn volumes(&self) -> Vec<VolumeInfo> {
+        if self.state.read().refreshed_at.is_none() {
+            self.refresh_logged();
+        }
+        let mut volumes: Vec<VolumeInfo> = self.state.read().volumes.values().cloned().collect();
+        volumes.sort_by(|a, b| a.volume_guid.cmp(&b.volume_guid));
+        volumes
+    }
+
+    /// Look up in the current map; on a miss, refresh (rate limited) and retry
+    /// so a newly arrived volume is found without waiting for the next poll
+    fn lookup<T>(&self, find: impl Fn(&MapState) -> Option<T>) -> Option<T> {
+        {
+            let state = self.state.read();
+            if let Some(found) = find(&state) {
+                return Some(found);
+            }
+            if state.refreshed_at.is_some_and(|at| at.elapsed() < MISS_REFRESH_INTERVAL) {
+                return None;
+            }
+        }
+        self.refresh_logged();
+        find(&self.state.read())
+    }
+
+    fn refresh_logged(&self) {
+        match self.refresh() {
+            Ok(changes) => self.log_changes(&changes),
+            Err(e) => println!("⚠️  VolumeMap: Refresh failed ({}): {}", self.mapper.name(), e),
+        }
+    }
+
+    /// Print arrivals and removals (drive letters where known)
+    pub fn log_changes(&self, changes: &VolumeChanges) {
+        let state = self.state.read();
+        for guid in &changes.arrived {
+            let mounts = state.volumes.get(guid).map(|volume| volume.mount_paths.join(", ")).unwrap_or_default();
+            println!("💽 Volume arrived: {} [{}]", guid, mounts);
+        }
+        for guid in &changes.removed {
+            println!("💽 Volume removed: {}", guid);
+        }
+        if changes.network_changed {
+            println!("💽 Network drives changed: {} mapped", state.network.len());
+        }
+    }
+
+    /// Mount path (without trailing backslash) is the path or one of its ancestors
+    fn mount_covers(mount: &str, dos_path: &str) -> bool {
+        dos_path.len() >= mount.len()
+            && dos_path.as_bytes()[..mount.len()].eq_ignore_ascii_case(mount.as_bytes())
+            && dos_path.as_bytes().get(mount.len()).is_none_or(|&byte| byte == b'\\')
+    }
+
+    fn dos_root(volume: &VolumeInfo) -> String {
+        let mut drives: Vec<String> = volume.mount_paths.iter()
+            .filter_map(|mount| Self::normalize_drive(mount))
+            .collect();
+        drives.sort();
+        if let Some(drive) = drives.first() {
+            return format!("{}\\", drive);
+        }
+        match volume.mount_paths.iter().min_by_key(|mount| mount.len()) {
+            Some(mount) if mount.ends_with('\\') => mount.clone(),
+            Some(mount) => format!("{}\\", mount),
+            None => Self::normalize_guid(&volume.volume_guid),
+        }
+    }
+
+    /// "\\?\Volume{GUID}\" in lowercase (accepts "Volume{GUID}" forms too)
+    pub fn normalize_guid(volume_guid: &str) -> String {
+        let trimmed = volume_guid.trim().trim_start_matches("\\\\?\\").trim_end_matches('\\');
+        format!("\\\\?\\{}\\", trimmed.to_lowercase())
+    }
+
+    /// "C:" from "c", "C:" or "C:\" (None for anything longer)
+    fn normalize_drive(drive: &str) -> Option<String> {
+        let trimmed = drive.trim().trim_end_matches('\\');
+        let trimmed = trimmed.strip_suffix(':').unwrap_or(trimmed);
+        let mut chars = trimmed.chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), None) if letter.is_ascii_alphabetic() => Some(format!("{}:", letter.to_ascii_uppercase())),
+            _ => None,
+        }
+    }
+}
+
+/// Volume list from FindFirstVolume/QueryDosDevice/GetVolumePathNamesForVolumeName
+pub struct Win32VolumeMapper;
+
+impl Win32VolumeMapper {
+    fn wide_to_string(buffer: &[u16]) -> String {
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        String::from_utf16_lossy(&buffer[..len])
+    }
+
+    /// Disk behind a drive letter or volume GUID root sits on the USB bus
+    /// (IOCTL_STORAGE_QUERY_PROPERTY; no access rights needed for the query)
+    fn on_usb_bus(root: &str) -> bool {
+        use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+        use windows_sys::Win32::Storage::FileSystem::{BusTypeUsb, CreateFileW, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING};
+        use windows_sys::Win32::System::Ioctl::{
+            PropertyStandardQuery, StorageDeviceProperty, IOCTL_STORAGE_QUERY_PROPERTY, STORAGE_DEVICE_DESCRIPTOR,
+            STORAGE_PROPERTY_QUERY,
+        };
+        use windows_sys::Win32::System::IO::DeviceIoControl;
+
+        let root = root.trim_end_matches('\\');
+        let device = if root.len() == 2 && root.ends_with(':') {
+            format!("\\\\.\\{}", root)
+        } else if root.starts_with("\\\\?\\Volume{") {
+            root.to_string()
+        } else {
+            return false;   // UNC shares have no disk here
+        };
+        let wide: Vec<u16> = device.encode_utf16().chain(Some(0)).collect();
+
+        unsafe {
+            let handle = CreateFileW(
+                wide.as_ptr(),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                0,
+                0,
+            );
+            if handle == INVALID_HANDLE_VALUE {
+                return false;
+            }
+            let query = STORAGE_PROPERTY_QUERY {
+                PropertyId: StorageDeviceProperty,
+                QueryType: PropertyStandardQuery,
+                AdditionalParameters: [0],
+            };
+            // The descriptor is followed by vendor/product strings; only its header is read
+            let mut buffer = [0u64; 128];
+            let mut returned = 0u32;
+            let ok = DeviceIoControl(
+                handle,
+                IOCTL_STORAGE_QUERY_PROPERTY,
+                &query as *const STORAGE_PROPERTY_QUERY as *const std::ffi::c_void,
+                std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as u32,
+                buffer.as_mut_ptr() as *mut std::ffi::c_void,
+                std::mem::size_of_val(&buffer) as u32,
+                &mut returned,
+                std::ptr::null_mut(),
+            ) != 0;
+            CloseHandle(handle);
+
+            if !ok || (returned as usize) < std::mem::size_of::<STORAGE_DEVICE_DESCRIPTOR>() {
+                return false;
+            }
+            let descriptor = std::ptr::read_unaligned(buffer.as_ptr() as *const STORAGE_DEVICE_DESCRIPTOR);
+            descriptor.BusType == BusTypeUsb
+        }
+    }
+
+    /// Device name and mount paths of one volume (None if unreadable)
+    fn describe(volume_guid: &str) -> Option<VolumeInfo> {
+        // QueryDosDevice wants "Volume{GUID}" without prefix or trailing backslash
+        let dos_name = volume_guid.trim_start_matches("\\\\?\\").trim_end_matches('\\');
+        let device_path = crate::fltlib::query_dos_device(dos_name).ok()?;
+        Some(VolumeInfo {
+            volume_guid: volume_guid.to_string(),
+            device_path,
+            mount_paths: Self::mount_paths(volume_guid),
+        })
+    }
+
+    /// Mount paths of a volume (REG_MULTI_SZ style list)
+    fn mount_paths(volume_guid: &str) -> Vec<String> {
+        use windows_sys::Win32::Storage::FileSystem::GetVolumePathNamesForVolumeNameW;
+
+        let volume_wide: Vec<u16> = volume_guid.encode_utf16().chain(Some(0)).collect();
+        let mut buffer = vec![0u16; 1024];
+        let mut required = 0u32;
+        unsafe {
+            if GetVolumePathNamesForVolumeNameW(volume_wide.as_ptr(), buffer.as_mut_ptr(), buffer.len() as u32, &mut required) == 0 {
+                if required as usize <= buffer.len() {
+                    return Vec::new();
+                }
+                buffer = vec![0u16; required as usize];
+                if GetVolumePathNamesForVolumeNameW(volume_wide.as_ptr(), buffer.as_mut_ptr(), buffer.len() as u32, &mut required) == 0 {
+                    return Vec::new();
+                }
+            }
+        }
+        buffer.split(|&c| c == 0)
+            .filter(|path| !path.is_empty())
+            .map(String::from_utf16_lossy)
+            .collect()
+    }
+}
+
+impl VolumeMapper for Win32VolumeMapper {
+    fn name(&self) -> &'static str {
+        "win32"
+    }
+
+    fn enumerate(&self) -> Result<Vec<VolumeInfo>, String> {
+        use windows_sys::Win32::Foundation::{INVALID_HANDLE_VALUE, MAX_PATH};
+        use windows_sys::Win32::Storage::FileSystem::{FindFirstVolumeW, FindNextVolumeW, FindVolumeClose};
+
+        let mut name = vec![0u16; MAX_PATH as usize + 1];
+        let mut volumes = Vec::new();
+        unsafe {
+            let handle = FindFirstVolumeW(name.as_mut_ptr(), name.len() as u32);
+            if handle == INVALID_HANDLE_VALUE {
+                return Err("FindFirstVolume failed".to_string());
+            }
+            loop {
+                let volume_guid = Self::wide_to_string(&name);
+                if let Some(volume) = Self::describe(&volume_guid) {
+                    volumes.push(volume);
+                }
+                if FindNextVolumeW(handle, name.as_mut_ptr(), name.len() as u32) == 0 {
+                    break;
+                }
+            }
+            FindVolumeClose(handle);
+        }
+        Ok(volumes)
+    }
+
+    fn network_drives(&self) -> Result<Vec<NetworkDrive>, String> {
+        Ok((b'A'..=b'Z')
+            .filter_map(|letter| {
+                let drive = format!("{}:", letter as char);
+                let target = crate::fltlib::query_dos_device(&drive).ok()?;
+                let unc_path = share_from_redirector_target(&target)?;
+                Some(NetworkDrive { drive, unc_path })
+            })
+            .collect())
+    }
+
+    fn drive_device(&self, root: &str) -> Option<DriveDevice> {
+        use windows_sys::Win32::Foundation::MAX_PATH;
+        use windows_sys::Win32::Storage::FileSystem::{GetDriveTypeW, GetVolumeInformationW};
+        use windows_sys::Win32::System::WindowsProgramming::{
+            DRIVE_CDROM, DRIVE_FIXED, DRIVE_NO_ROOT_DIR, DRIVE_RAMDISK, DRIVE_REMOTE, DRIVE_REMOVABLE,
+        };
+
+        let root_path = format!("{}\\", root.trim_end_matches('\\'));
+        let root_wide: Vec<u16> = root_path.encode_utf16().chain(Some(0)).collect();
+        let mut label = vec![0u16; MAX_PATH as usize + 1];
+        let mut serial = 0u32;
+        unsafe {
+            let class = match GetDriveTypeW(root_wide.as_ptr()) {
+                DRIVE_NO_ROOT_DIR => return None,
+                // USB hard disks and some sticks report as fixed
+                DRIVE_FIXED if Self::on_usb_bus(root) => DeviceClass::Removable,
+                DRIVE_FIXED => DeviceClass::Fixed,
+                DRIVE_REMOVABLE => DeviceClass::Removable,
+                DRIVE_REMOTE => DeviceClass::Network,
+                DRIVE_CDROM => DeviceClass::CdRom,
+                DRIVE_RAMDISK => DeviceClass::RamDisk,
+                _ => DeviceClass::Unknown,
+            };
+            // Fails for an empty card reader or CD drive: class only
+            let has_medium = GetVolumeInformationW(
+                root_wide.as_ptr(),
+                label.as_mut_ptr(),
+                label.len() as u32,
+                &mut serial,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                0,
+            ) != 0;
+            let label = Self::wide_to_string(&label);
+            Some(DriveDevice {
+                class,
+                label: (has_medium && !label.trim().is_empty()).then(|| label.trim().to_string()),
+                serial: has_medium.then(|| DriveDevice::format_serial(serial)),
+            })
+        }
+    }
+}
+
+/// "\\server\share" from a redirector symlink target such as
+/// "\Device\LanmanRedirector\;Z:000000000001a2b3\server\share" or
+/// "\Device\Mup\;LanmanRedirector\;Z:0000000000012345\server\share"
+fn share_from_redirector_target(target: &str) -> Option<String> {
+    let components: Vec<&str> = target.split('\\').collect();
+    let last_session = components.iter().rposition(|component| component.starts_with(';'))?;
+    let share: Vec<&str> = components[last_session + 1..].iter()
+        .copied()
+        .filter(|component| !component.is_empty())
+        .collect();
+    (share.len() >= 2).then(|| format!("\\\\{}", share.join("\\")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nt_path_resolver::NtPathResolver;
+    use std::sync::{Arc, Mutex};
+
+    /// Table-backed mapper; tests edit the table to simulate arrival/removal
+    struct MockVolumeMapper {
+        table: Arc<Mutex<Vec<VolumeInfo>>>,
+        network: Vec<NetworkDrive>,
+        devices: Arc<Mutex<HashMap<String, DriveDevice>>>,     // Root → medium
+    }
+
+    impl VolumeMapper for MockVolumeMapper {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn enumerate(&self) -> Result<Vec<VolumeInfo>, String> {
+            Ok(self.table.lock().unwrap().clone())
+        }
+
+        fn network_drives(&self) -> Result<Vec<NetworkDrive>, String> {
+            Ok(self.network.clone())
+        }
+
+        fn drive_device(&self, root: &str) -> Option<DriveDevice> {
+            self.devices.lock().unwrap().get(root).cloned()
+        }
+    }
+
+    fn volume(guid: &str, device: &str, mounts: &[&str]) -> VolumeInfo {
+        VolumeInfo {
+            volume_guid: format!("\\\\?\\Volume{{{}}}\\", guid),
+            device_path: device.to_string(),
+            mount_paths: mounts.iter().map(|mount| mount.to_string()).collect(),
+        }
+    }
+
+    fn mock_map(volumes: Vec<VolumeInfo>) -> (VolumeMap, Arc<Mutex<Vec<VolumeInfo>>>) {
+        let table = Arc::new(Mutex::new(volumes));
+        let map = VolumeMap::new(Box::new(MockVolumeMapper { table: table.clone(), network: Vec::new(), devices: Default::default() }));
+        (map, table)
+    }
+
+    /// Device a drive letter ("C:") maps to
+    fn device_of(map: &VolumeMap, drive: &str) -> Result<String, String> {
+        map.device_for_path(&format!("{}\\", drive)).map(|(device_path, _)| device_path)
+    }
+
+    fn mock_map_with_network(volumes: Vec<VolumeInfo>, network: &[(&str, &str)]) -> VolumeMap {
+        let network = network.iter()
+            .map(|(drive, unc_path)| NetworkDrive { drive: drive.to_string(), unc_path: unc_path.to_string() })
+            .collect();
+        VolumeMap::new(Box::new(MockVolumeMapper { table: Arc::new(Mutex::new(volumes)), network, devices: Default::default() }))
+    }
+
+    #[test]
+    fn resolves_drive_letters_and_guids() {
+        let (map, _) = mock_map(vec![
+            volume("aaaa", "\\Device\\HarddiskVolume7", &["C:\\"]),
+            volume("BBBB", "\\Device\\HarddiskVolume2\\", &["E:\\", "C:\\Mounts\\Data\\"]),
+        ]);
+
+        assert_eq!(device_of(&map, "C:").unwrap(), "\\Device\\HarddiskVolume7");
+        assert_eq!(device_of(&map, "e:").unwrap(), "\\Device\\HarddiskVolume2");
+        assert_eq!(
+            map.device_for_path("C:\\Mounts\\Data\\q.txt").unwrap(),
+            ("\\Device\\HarddiskVolume2".to_string(), "\\q.txt".to_string())
+        );
+        assert_eq!(map.device_for_guid("Volume{bbbb}").unwrap(), "\\Device\\HarddiskVolume2");
+        assert_eq!(map.guid_for_drive("C:").unwrap(), "\\\\?\\volume{aaaa}\\");
+    }
+
+    #[test]
+    fn unknown_volume_fails_instead_of_guessing() {
+        let (map, _) = mock_map(vec![volume("aaaa", "\\Device\\HarddiskVolume7", &["C:\\"])]);
+
+        assert!(device_of(&map, "D:").is_err());
+        assert!(map.device_for_guid("\\\\?\\Volume{cccc}\\").is_err());
+        assert!(device_of(&map, "CD:").is_err());
+    }
+
+    #[test]
+    fn volumes_without_device_names_are_skipped() {
+        let (map, _) = mock_map(vec![volume("aaaa", "C:\\", &["C:\\"])]);
+
+        assert!(device_of(&map, "C:").is_err());
+        assert!(map.volumes().is_empty());
+    }
+
+    #[test]
+    fn refresh_reports_arrival_and_removal() {
+        let (map, table) = mock_map(vec![volume("aaaa", "\\Device\\HarddiskVolume7", &["C:\\"])]);
+        let changes = map.refresh().unwrap();
+        assert_eq!(changes.arrived, vec!["\\\\?\\volume{aaaa}\\".to_string()]);
+
+        table.lock().unwrap().push(volume("dddd", "\\Device\\HarddiskVolume9", &["F:\\"]));
+        let changes = map.refresh().unwrap();
+        assert_eq!(changes.arrived, vec!["\\\\?\\volume{dddd}\\".to_string()]);
+        assert!(changes.removed.is_empty());
+        assert_eq!(device_of(&map, "F:").unwrap(), "\\Device\\HarddiskVolume9");
+
+        table.lock().unwrap().retain(|volume| !volume.mount_paths.contains(&"F:\\".to_string()));
+        let changes = map.refresh().unwrap();
+        assert_eq!(changes.removed, vec!["\\\\?\\volume{dddd}\\".to_string()]);
+        assert!(device_of(&map, "F:").is_err());
+        assert!(map.refresh().unwrap().is_empty());
+    }
+
+    #[test]
+    fn generation_moves_only_when_something_changed() {
+        let (map, table) = mock_map(vec![volume("aaaa", "\\Device\\HarddiskVolume7", &["C:\\"])]);
+        map.refresh().unwrap();
+        let generation = map.generation();
+        map.refresh().unwrap();
+        assert_eq!(map.generation(), generation);
+
+        // A USB stick found by a lookup-miss refresh still moves the generation
+        table.lock().unwrap().push(volume("eeee", "\\Device\\HarddiskVolume12", &["E:\\"]));
+        map.refresh_logged();
+        assert_eq!(map.generation(), generation + 1);
+    }
+
+    #[test]
+    fn drive_devices_are_read_on_every_call() {
+        let devices = Arc::new(Mutex::new(HashMap::new()));
+        let map = VolumeMap::new(Box::new(MockVolumeMapper {
+            table: Arc::new(Mutex::new(Vec::new())),
+            network: Vec::new(),
+            devices: devices.clone(),
+        }));
+        assert!(map.drive_device("E:").is_none());
+
+        let stick = DriveDevice { class: DeviceClass::Removable, label: Some("BACKUP".to_string()), serial: Some("1A2B-3C4D".to_string()) };
+        devices.lock().unwrap().insert("E:".to_string(), stick.clone());
+        assert_eq!(map.drive_device("E:"), Some(stick));
+
+        // Another medium in the same slot: no refresh needed to see it
+        let other = DriveDevice { class: DeviceClass::Removable, label: None, serial: Some("5E6F-7A8B".to_string()) };
+        devices.lock().unwrap().insert("E:".to_string(), other.clone());
+        assert_eq!(map.drive_device("E:"), Some(other));
+    }
+
+    #[test]
+    fn volume_serials_use_the_vol_format() {
+        assert_eq!(DriveDevice::format_serial(0x1A2B_3C4D), "1A2B-3C4D");
+        assert_eq!(DriveDevice::format_serial(0x0000_00FF), "0000-00FF");
+        for serial in ["1a2b3c4d", "1A2B-3C4D", " 0x1A2B3C4D "] {
+            assert_eq!(DriveDevice::normalize_serial(serial).as_deref(), Some("1A2B-3C4D"));
+        }
+        assert!(DriveDevice::normalize_serial("1A2B-3C4").is_none());
+        assert!(DriveDevice::normalize_serial("WXYZ-3C4D").is_none());
+    }
+
+    #[test]
+    fn dos_paths_map_to_the_mapped_device() {
+        let (map, _) = mock_map(vec![volume("aaaa", "\\Device\\HarddiskVolume7", &["C:\\"])]);
+
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "C:\\Users\\a.txt", false).unwrap(),
+            "\\Device\\HarddiskVolume7\\Users\\a.txt"
+        );
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "c:/Users", true).unwrap(),
+            "\\Device\\HarddiskVolume7\\Users\\"
+        );
+        assert_eq!(NtPathResolver::dos_to_nt_path_with(&map, "C:", true).unwrap(), "\\Device\\HarddiskVolume7\\");
+        assert!(NtPathResolver::dos_to_nt_path_with(&map, "D:\\Data\\x.txt", false).is_err());
+        assert!(NtPathResolver::dos_to_nt_path_with(&map, "Users\\a.txt", false).is_err());
+    }
+
+    #[test]
+    fn lookup_miss_refresh_is_rate_limited() {
+        let (map, table) = mock_map(Vec::new());
+        assert!(device_of(&map, "C:").is_err());
+
+        // Arrived right after a refresh: found by the next explicit/periodic refresh
+        table.lock().unwrap().push(volume("aaaa", "\\Device\\HarddiskVolume7", &["C:\\"]));
+        assert!(device_of(&map, "C:").is_err());
+        map.refresh().unwrap();
+        assert_eq!(device_of(&map, "C:").unwrap(), "\\Device\\HarddiskVolume7");
+    }
+
+    #[test]
+    fn nt_paths_map_back_to_dos_paths() {
+        let (map, _) = mock_map(vec![
+            volume("aaaa", "\\Device\\HarddiskVolume7", &["c:\\"]),
+            volume("bbbb", "\\Device\\HarddiskVolume2", &["C:\\Mounts\\Data\\", "F:\\", "E:\\"]),
+            volume("cccc", "\\Device\\HarddiskVolume4", &["C:\\Mounts\\Archive\\"]),
+            volume("dddd", "\\Device\\HarddiskVolume5", &[]),
+        ]);
+
+        assert_eq!(
+            NtPathResolver::nt_to_dos_path_with(&map, "\\Device\\HarddiskVolume7\\Users\\a.txt").unwrap(),
+            "C:\\Users\\a.txt"
+        );
+        assert_eq!(
+            NtPathResolver::nt_to_dos_path_with(&map, "\\Device\\harddiskvolume2\\x").unwrap(),
+            "E:\\x"
+        );
+        assert_eq!(
+            NtPathResolver::nt_to_dos_path_with(&map, "\\Device\\HarddiskVolume4\\x").unwrap(),
+            "C:\\Mounts\\Archive\\x"
+        );
+        assert_eq!(
+            NtPathResolver::nt_to_dos_path_with(&map, "\\Device\\HarddiskVolume5\\x").unwrap(),
+            "\\\\?\\volume{dddd}\\x"
+        );
+        assert_eq!(NtPathResolver::nt_to_dos_path_with(&map, "\\Device\\HarddiskVolume7").unwrap(), "C:\\");
+        assert_eq!(NtPathResolver::nt_to_dos_path_with(&map, "\\??\\D:\\x").unwrap(), "D:\\x");
+        assert!(NtPathResolver::nt_to_dos_path_with(&map, "\\Device\\HarddiskVolume9\\x").is_err());
+        assert!(NtPathResolver::nt_to_dos_path_with(&map, "C:\\x").is_err());
+    }
+
+    #[test]
+    fn dos_path_cache_is_dropped_when_volumes_change() {
+        let (map, table) = mock_map(vec![volume("aaaa", "\\Device\\HarddiskVolume7", &["D:\\"])]);
+        let nt_path = "\\Device\\HarddiskVolume7\\x.txt";
+        assert_eq!(NtPathResolver::nt_to_dos_path_with(&map, nt_path).unwrap(), "D:\\x.txt");
+        assert_eq!(map.cached_dos_path(nt_path).as_deref(), Some("D:\\x.txt"));
+
+        map.refresh().unwrap();
+        assert_eq!(map.cached_dos_path(nt_path).as_deref(), Some("D:\\x.txt"));
+
+        // Same volume remounted under another letter
+        table.lock().unwrap()[0].mount_paths = vec!["G:\\".to_string()];
+        map.refresh().unwrap();
+        assert!(map.cached_dos_path(nt_path).is_none());
+        assert_eq!(NtPathResolver::nt_to_dos_path_with(&map, nt_path).unwrap(), "G:\\x.txt");
+    }
+
+    #[test]
+    fn folder_mounts_resolve_to_the_mounted_volume() {
+        let (map, _) = mock_map(vec![
+            volume("aaaa", "\\Device\\HarddiskVolume7", &["C:\\"]),
+            volume("bbbb", "\\Device\\HarddiskVolume2", &["C:\\Mounts\\Data\\"]),
+        ]);
+
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "C:\\Mounts\\Data\\q1.xlsx", false).unwrap(),
+            "\\Device\\HarddiskVolume2\\q1.xlsx"
+        );
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "c:\\mounts\\data", true).unwrap(),
+            "\\Device\\HarddiskVolume2\\"
+        );
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "C:\\Mounts\\Database\\x", false).unwrap(),
+            "\\Device\\HarddiskVolume7\\Mounts\\Database\\x"
+        );
+        assert_eq!(map.volume_path_for("C:\\Mounts\\Data\\x").as_deref(), Some("\\\\?\\volume{bbbb}\\x"));
+        assert!(map.volume_path_for("C:\\Users").is_none());
+    }
+
+    #[test]
+    fn volume_guid_paths_resolve_without_a_letter() {
+        let (map, _) = mock_map(vec![volume("cccc", "\\Device\\HarddiskVolume5", &[])]);
+
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "\\\\?\\Volume{CCCC}\\Logs\\a.txt", false).unwrap(),
+            "\\Device\\HarddiskVolume5\\Logs\\a.txt"
+        );
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "\\\\?\\Volume{cccc}", true).unwrap(),
+            "\\Device\\HarddiskVolume5\\"
+        );
+        assert!(NtPathResolver::dos_to_nt_path_with(&map, "\\\\?\\Volume{dddd}\\x", false).is_err());
+    }
+
+    #[test]
+    fn network_paths_go_through_mup() {
+        let map = mock_map_with_network(
+            vec![volume("aaaa", "\\Device\\HarddiskVolume7", &["C:\\"])],
+            &[("Z:", "\\\\fs01\\finance"), ("C:", "\\\\fs01\\shadowed")],
+        );
+
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "Z:\\Q1\\report.xlsx", false).unwrap(),
+            "\\Device\\Mup\\fs01\\finance\\Q1\\report.xlsx"
+        );
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "\\\\fs02\\hr\\people", true).unwrap(),
+            "\\Device\\Mup\\fs02\\hr\\people\\"
+        );
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "\\\\?\\UNC\\fs02\\hr\\x.txt", false).unwrap(),
+            "\\Device\\Mup\\fs02\\hr\\x.txt"
+        );
+        assert_eq!(
+            NtPathResolver::dos_to_nt_path_with(&map, "\\\\?\\C:\\x.txt", false).unwrap(),
+            "\\Device\\HarddiskVolume7\\x.txt"
+        );
+        assert!(NtPathResolver::dos_to_nt_path_with(&map, "\\\\fs02", true).is_err());
+
+        // A letter backed by a local volume is never treated as a share
+        assert!(map.share_for_drive("C:").is_err());
+
+        assert_eq!(
+            NtPathResolver::nt_to_dos_path_with(&map, "\\Device\\Mup\\FS01\\Finance\\Q1\\report.xlsx").unwrap(),
+            "Z:\\Q1\\report.xlsx"
+        );
+        assert_eq!(
+            NtPathResolver::nt_to_dos_path_with(&map, "\\Device\\Mup\\fs02\\hr\\x.txt").unwrap(),
+            "\\\\fs02\\hr\\x.txt"
+        );
+    }
+
+    #[test]
+    fn redirector_targets_name_their_share() {
+        assert_eq!(
+            share_from_redirector_target("\\Device\\LanmanRedirector\\;Z:000000000001a2b3\\fs01\\finance").as_deref(),
+            Some("\\\\fs01\\finance")
+        );
+        assert_eq!(
+            share_from_redirector_target("\\Device\\Mup\\;LanmanRedirector\\;Z:0000000000012345\\fs01\\finance\\q1").as_deref(),
+            Some("\\\\fs01\\finance\\q1")
+        );
+        assert!(share_from_redirector_target("\\Device\\HarddiskVolume3").is_none());
+        assert!(share_from_redirector_target("\\Device\\LanmanRedirector\\;Z:0\\fs01").is_none());
+    }
+}